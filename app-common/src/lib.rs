@@ -0,0 +1,335 @@
+//! Session keys, key rotation, random seeds and rulings of the app pallets
+//!
+//! Every pallet lets players delegate off-chain state signing to session
+//! keys, rotate their accounts in a live app, decide a random seed by
+//! commit-reveal and leave the outcome of a dispute to an arbiter. The
+//! payloads players sign for these, and for the single-signed moves of the
+//! gomoku pallets, are prefixed here once for all pallets, so that a payload
+//! signed for one purpose is never accepted as a signed app state or as a
+//! payload of another purpose. Checks return plain error strings, which the
+//! pallets turn into `DispatchError`s.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+#[cfg(test)]
+mod tests;
+
+use codec::{Decode, Encode};
+use sp_runtime::{traits::Hash as HashT, RuntimeDebug};
+use sp_std::{ops::Add, prelude::*, vec::Vec};
+
+pub const SESSION_KEY_DELEGATION_PREFIX: &[u8] = b"capps:session_key";
+pub const KEY_ROTATION_PREFIX: &[u8] = b"capps:key_rotation";
+pub const RELAYED_ACTION_PREFIX: &[u8] = b"capps:action";
+pub const SIGNED_MOVE_PREFIX: &[u8] = b"capps:move";
+
+/// Delegation of off-chain state signing from a player to a session key
+#[derive(Clone, Eq, PartialEq, PartialOrd, Ord, Encode, Decode, RuntimeDebug)]
+pub struct SessionKeyDelegation<AccountId, BlockNumber, Hash> {
+    pub app_id: Hash, // id of app, or of session in the multi session app
+    pub player: AccountId,
+    pub session_key: AccountId,
+    pub expires_at: BlockNumber, // block number from which the session key is no longer accepted
+}
+
+impl<AccountId: Encode, BlockNumber: Encode, Hash: Encode> SessionKeyDelegation<AccountId, BlockNumber, Hash> {
+    /// Encode delegation into the payload signed by the player
+    pub fn signing_payload(&self) -> Vec<u8> {
+        prefixed(SESSION_KEY_DELEGATION_PREFIX, self)
+    }
+}
+
+/// Replacement of the account of a player in a live app
+#[derive(Clone, Eq, PartialEq, PartialOrd, Ord, Encode, Decode, RuntimeDebug)]
+pub struct KeyRotation<AccountId, Hash> {
+    pub app_id: Hash, // id of app, or of session in the multi session app
+    pub old_player: AccountId,
+    pub new_player: AccountId,
+    pub nonce: u32, // number of rotations of the app before this one
+}
+
+impl<AccountId: Encode, Hash: Encode> KeyRotation<AccountId, Hash> {
+    /// Encode rotation into the payload signed for it
+    pub fn signing_payload(&self) -> Vec<u8> {
+        prefixed(KEY_ROTATION_PREFIX, self)
+    }
+}
+
+impl<AccountId: Eq, Hash> KeyRotation<AccountId, Hash> {
+    /// Check rotation against a live app, returning the index of the rotated
+    /// player; signatures are checked by the pallet
+    ///
+    /// Parameters:
+    /// `players`: AccountId of players
+    /// `arbiter`: AccountId of arbiter
+    /// `nonce`: Number of rotations of the app so far
+    pub fn rotated_index(
+        &self,
+        players: &[AccountId],
+        arbiter: Option<&AccountId>,
+        nonce: u32,
+    ) -> Result<usize, &'static str> {
+        let index = match players.iter().position(|player| player == &self.old_player) {
+            Some(index) => index,
+            None => return Err("not a player of app"),
+        };
+        if players.contains(&self.new_player) || arbiter == Some(&self.new_player) {
+            return Err("new account already joins app");
+        }
+        if self.nonce != nonce {
+            return Err("invalid rotation nonce");
+        }
+
+        Ok(index)
+    }
+}
+
+impl<AccountId: Ord, Hash> KeyRotation<AccountId, Hash> {
+    /// Whether the new account keeps players in ascending order, as the multi
+    /// pallets require
+    ///
+    /// Parameters:
+    /// `players`: AccountId of players
+    /// `index`: Index of the rotated player
+    pub fn keeps_order(&self, players: &[AccountId], index: usize) -> bool {
+        (index == 0 || players[index - 1] < self.new_player)
+            && (index + 1 == players.len() || self.new_player < players[index + 1])
+    }
+}
+
+/// Ruling of an arbiter finalizing an app
+#[derive(Clone, Eq, PartialEq, PartialOrd, Ord, Encode, Decode, RuntimeDebug)]
+pub struct Ruling<AccountId, BlockNumber> {
+    pub arbiter: AccountId,
+    pub outcome: u8, // state of a session app, or winner id or color of a gomoku app with 0 for a draw
+    pub block_number: BlockNumber,
+}
+
+/// Commit-reveal round deciding a random seed of an app
+#[derive(Clone, Eq, PartialEq, PartialOrd, Ord, Encode, Decode, RuntimeDebug)]
+pub struct SeedRound<BlockNumber, Hash> {
+    pub commits: Vec<Option<Hash>>, // commitment of each player
+    pub secrets: Vec<Option<Hash>>, // revealed secret of each player
+    pub timeout: BlockNumber, // period to commit and then to reveal
+    pub deadline: BlockNumber, // deadline of the current commit or reveal period
+    pub seed: Option<Hash>, // combined seed once every player revealed
+}
+
+impl<BlockNumber, Hash> SeedRound<BlockNumber, Hash>
+where
+    BlockNumber: Copy + PartialOrd + Add<Output = BlockNumber>,
+    Hash: Copy + Eq + Encode,
+{
+    /// Start a round whose commit period ends after `timeout`
+    ///
+    /// Parameters:
+    /// `player_num`: Number of players
+    /// `timeout`: Period to commit and then to reveal
+    /// `block_number`: Current block number
+    pub fn new(player_num: usize, timeout: BlockNumber, block_number: BlockNumber) -> Self {
+        let mut commits = Vec::new();
+        commits.resize(player_num, None);
+
+        SeedRound {
+            secrets: commits.clone(),
+            commits,
+            timeout,
+            deadline: block_number + timeout,
+            seed: None,
+        }
+    }
+
+    /// Check that the seed is undecided and the current period is open
+    ///
+    /// Parameter:
+    /// `block_number`: Current block number
+    pub fn check_live(&self, block_number: BlockNumber) -> Result<(), &'static str> {
+        if self.seed.is_some() {
+            return Err("random seed is decided");
+        }
+        if block_number > self.deadline {
+            return Err("seed deadline passed");
+        }
+
+        Ok(())
+    }
+
+    /// Record the commitment of a player, starting the reveal period once
+    /// every player committed
+    ///
+    /// Parameters:
+    /// `index`: Index of player
+    /// `commitment`: Commitment of the player, see `seed_commitment`
+    /// `block_number`: Current block number
+    pub fn commit(
+        &mut self,
+        index: usize,
+        commitment: Hash,
+        block_number: BlockNumber,
+    ) -> Result<(), &'static str> {
+        if self.commits[index].is_some() {
+            return Err("seed is already committed");
+        }
+
+        self.commits[index] = Some(commitment);
+        if self.commits.iter().all(Option::is_some) {
+            self.deadline = block_number + self.timeout;
+        }
+
+        Ok(())
+    }
+
+    /// Record the secret of a player, returning the seed once every player
+    /// revealed
+    ///
+    /// Parameters:
+    /// `app_id`: Id of app
+    /// `index`: Index of player
+    /// `player`: AccountId of player
+    /// `secret`: Secret of the commitment of the player
+    pub fn reveal<AccountId: Encode, Hashing: HashT<Output = Hash>>(
+        &mut self,
+        app_id: &Hash,
+        index: usize,
+        player: &AccountId,
+        secret: Hash,
+    ) -> Result<Option<Hash>, &'static str> {
+        if !self.commits.iter().all(Option::is_some) {
+            return Err("seeds are not all committed");
+        }
+        if self.secrets[index].is_some() {
+            return Err("seed is already revealed");
+        }
+        if self.commits[index] != Some(seed_commitment::<AccountId, Hashing>(app_id, player, &secret)) {
+            return Err("secret not match commitment");
+        }
+
+        self.secrets[index] = Some(secret);
+        let secrets: Option<Vec<Hash>> = self.secrets.iter().cloned().collect();
+        if let Some(secrets) = secrets {
+            self.seed = Some(combined_seed::<Hashing>(&secrets));
+        }
+
+        Ok(self.seed)
+    }
+
+    /// Check that players defaulted: the seed is undecided after the deadline
+    ///
+    /// Parameter:
+    /// `block_number`: Current block number
+    pub fn check_timeout(&self, block_number: BlockNumber) -> Result<(), &'static str> {
+        if self.seed.is_some() {
+            return Err("random seed is decided");
+        }
+        if block_number <= self.deadline {
+            return Err("seed deadline no passes");
+        }
+
+        Ok(())
+    }
+}
+
+impl<BlockNumber, Hash> SeedRound<BlockNumber, Hash> {
+    /// Whether each player failed to commit, or to reveal once every player committed
+    pub fn defaulted(&self) -> Vec<bool> {
+        if self.commits.iter().all(Option::is_some) {
+            self.secrets.iter().map(Option::is_none).collect()
+        } else {
+            self.commits.iter().map(Option::is_none).collect()
+        }
+    }
+}
+
+/// Get commitment of a player to a random seed, without a runtime
+///
+/// Parameters:
+/// `app_id`: Id of app
+/// `player`: AccountId of player
+/// `secret`: Secret revealed by the player
+pub fn seed_commitment<AccountId, Hashing>(
+    app_id: &Hashing::Output,
+    player: &AccountId,
+    secret: &Hashing::Output,
+) -> Hashing::Output
+where
+    AccountId: Encode,
+    Hashing: HashT,
+{
+    <Hashing as HashT>::hash(&(app_id, player, secret).encode())
+}
+
+/// Combine secrets revealed by players into the random seed, without a runtime
+///
+/// Parameter:
+/// `secrets`: Secrets in the order of players
+pub fn combined_seed<Hashing: HashT>(secrets: &[Hashing::Output]) -> Hashing::Output {
+    <Hashing as HashT>::hash(&secrets.encode())
+}
+
+/// Get accounts signing off-chain states of players, with the live session
+/// key of a player in place of its account
+///
+/// Parameters:
+/// `players`: AccountId of players
+/// `block_number`: Current block number
+/// `session_key`: Delegated session key of a player and its expiry
+pub fn signing_accounts<AccountId, BlockNumber, F>(
+    players: &[AccountId],
+    block_number: BlockNumber,
+    session_key: F,
+) -> Vec<AccountId>
+where
+    AccountId: Clone,
+    BlockNumber: PartialOrd,
+    F: Fn(&AccountId) -> Option<(AccountId, BlockNumber)>,
+{
+    players.iter()
+        .map(|player| match session_key(player) {
+            Some((key, expires_at)) if block_number < expires_at => key,
+            _ => player.clone(),
+        })
+        .collect()
+}
+
+/// Whether accounts are in strictly ascending order
+///
+/// Parameter:
+/// `players`: AccountId of players
+pub fn is_ordered<AccountId: Ord>(players: &[AccountId]) -> bool {
+    players.windows(2).all(|pair| pair[0] < pair[1])
+}
+
+/// Off-chain move of a gomoku app signed only by its mover
+#[derive(Clone, Eq, PartialEq, PartialOrd, Ord, Encode, Decode, RuntimeDebug)]
+pub struct SignedMove<Signature> {
+    pub action: Vec<u8>,
+    pub sig: Signature,
+}
+
+/// Get the payload of an off-chain move signed only by its mover
+///
+/// Parameters:
+/// `app_id`: Id of app
+/// `seq_num`: Sequence number of the state after the move
+/// `action`: Action data
+pub fn signed_move_payload<Hash: Encode>(app_id: &Hash, seq_num: u128, action: &[u8]) -> Vec<u8> {
+    prefixed(SIGNED_MOVE_PREFIX, &(app_id, seq_num, action))
+}
+
+/// Get the payload of an on-chain action signed by the player in turn, for
+/// any account to submit
+///
+/// Parameters:
+/// `app_id`: Id of app
+/// `seq_num`: Sequence number of the on-chain state
+/// `action`: Action data
+pub fn relayed_action_payload<Hash: Encode>(app_id: &Hash, seq_num: u128, action: &[u8]) -> Vec<u8> {
+    prefixed(RELAYED_ACTION_PREFIX, &(app_id, seq_num, action))
+}
+
+/// Encode a value after the prefix of its purpose
+fn prefixed<V: Encode>(prefix: &[u8], value: &V) -> Vec<u8> {
+    let mut encoded = prefix.to_vec();
+    value.encode_to(&mut encoded);
+    encoded
+}
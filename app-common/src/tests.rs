@@ -0,0 +1,98 @@
+use super::*;
+use sp_core::H256;
+use sp_runtime::traits::BlakeTwo256;
+
+type Round = SeedRound<u64, H256>;
+
+fn rotation(old_player: u64, new_player: u64, nonce: u32) -> KeyRotation<u64, H256> {
+    KeyRotation {
+        app_id: H256::from_low_u64_be(1),
+        old_player,
+        new_player,
+        nonce,
+    }
+}
+
+#[test]
+fn test_pass_payloads_are_prefixed_by_purpose() {
+    let app_id = H256::from_low_u64_be(1);
+    let delegation = SessionKeyDelegation { app_id, player: 1u64, session_key: 2u64, expires_at: 10u64 };
+    assert_eq!(delegation.signing_payload(), [SESSION_KEY_DELEGATION_PREFIX, &delegation.encode()[..]].concat());
+    let rotation = rotation(1, 2, 0);
+    assert_eq!(rotation.signing_payload(), [KEY_ROTATION_PREFIX, &rotation.encode()[..]].concat());
+
+    let action = vec![3u8, 4];
+    let encoded = [app_id.encode(), 5u128.encode(), action.encode()].concat();
+    assert_eq!(signed_move_payload(&app_id, 5, &action), [SIGNED_MOVE_PREFIX, &encoded[..]].concat());
+    assert_eq!(relayed_action_payload(&app_id, 5, &action), [RELAYED_ACTION_PREFIX, &encoded[..]].concat());
+    assert_ne!(signed_move_payload(&app_id, 5, &action), relayed_action_payload(&app_id, 5, &action));
+}
+
+#[test]
+fn test_pass_rotated_index() {
+    let players = vec![1u64, 3, 5];
+    assert_eq!(rotation(3, 4, 2).rotated_index(&players, Some(&7), 2), Ok(1));
+    assert_eq!(rotation(2, 4, 2).rotated_index(&players, Some(&7), 2), Err("not a player of app"));
+    assert_eq!(rotation(3, 5, 2).rotated_index(&players, Some(&7), 2), Err("new account already joins app"));
+    assert_eq!(rotation(3, 7, 2).rotated_index(&players, Some(&7), 2), Err("new account already joins app"));
+    assert_eq!(rotation(3, 4, 1).rotated_index(&players, Some(&7), 2), Err("invalid rotation nonce"));
+
+    assert!(rotation(3, 4, 0).keeps_order(&players, 1));
+    assert!(!rotation(3, 6, 0).keeps_order(&players, 1));
+    assert!(rotation(1, 0, 0).keeps_order(&players, 0));
+    assert!(rotation(5, 9, 0).keeps_order(&players, 2));
+    assert!(!rotation(5, 2, 0).keeps_order(&players, 2));
+}
+
+#[test]
+fn test_pass_signing_accounts_and_order() {
+    let players = vec![1u64, 2, 3];
+    let session_key = |player: &u64| match player {
+        1 => Some((11, 10u64)),
+        2 => Some((12, 5u64)),
+        _ => None,
+    };
+    assert_eq!(signing_accounts(&players, 5, session_key), vec![11, 2, 3]);
+    assert_eq!(signing_accounts(&players, 10, session_key), vec![1, 2, 3]);
+
+    assert!(is_ordered(&players));
+    assert!(is_ordered(&players[..1]));
+    assert!(!is_ordered(&[1u64, 3, 2]));
+    assert!(!is_ordered(&[1u64, 1]));
+}
+
+#[test]
+fn test_pass_seed_round_commit_and_reveal() {
+    let app_id = H256::from_low_u64_be(1);
+    let secrets = vec![H256::from_low_u64_be(2), H256::from_low_u64_be(3)];
+    let commitment = |player: u64| seed_commitment::<u64, BlakeTwo256>(&app_id, &player, &secrets[player as usize]);
+    let mut round = Round::new(2, 10, 1);
+    assert_eq!(round.deadline, 11);
+
+    assert_eq!(round.reveal::<u64, BlakeTwo256>(&app_id, 0, &0, secrets[0]), Err("seeds are not all committed"));
+    assert_eq!(round.commit(0, commitment(0), 3), Ok(()));
+    assert_eq!(round.commit(0, commitment(0), 3), Err("seed is already committed"));
+    assert_eq!(round.defaulted(), vec![false, true]);
+    assert_eq!(round.commit(1, commitment(1), 4), Ok(()));
+    // reveal period starts once every player committed
+    assert_eq!(round.deadline, 14);
+    assert_eq!(round.defaulted(), vec![true, true]);
+
+    assert_eq!(round.reveal::<u64, BlakeTwo256>(&app_id, 0, &0, secrets[1]), Err("secret not match commitment"));
+    assert_eq!(round.reveal::<u64, BlakeTwo256>(&app_id, 0, &0, secrets[0]), Ok(None));
+    assert_eq!(round.reveal::<u64, BlakeTwo256>(&app_id, 0, &0, secrets[0]), Err("seed is already revealed"));
+    assert_eq!(round.check_timeout(15), Ok(()));
+    let seed = combined_seed::<BlakeTwo256>(&secrets);
+    assert_eq!(round.reveal::<u64, BlakeTwo256>(&app_id, 1, &1, secrets[1]), Ok(Some(seed)));
+    assert_eq!(round.check_live(5), Err("random seed is decided"));
+    assert_eq!(round.check_timeout(15), Err("random seed is decided"));
+}
+
+#[test]
+fn test_pass_seed_round_deadline() {
+    let round = Round::new(2, 10, 1);
+    assert_eq!(round.check_live(11), Ok(()));
+    assert_eq!(round.check_live(12), Err("seed deadline passed"));
+    assert_eq!(round.check_timeout(11), Err("seed deadline no passes"));
+    assert_eq!(round.check_timeout(12), Ok(()));
+}
@@ -0,0 +1,56 @@
+//! Players signing off-chain states in benchmarks of the pallets
+//!
+//! Player keys are sr25519 keys generated in the keystore of the benchmark.
+//! Runtimes identify players either by the raw sr25519 types or by their
+//! multi-scheme wrappers, so accounts and signatures are converted through
+//! their SCALE encodings.
+
+use codec::{Decode, Encode};
+use sp_core::crypto::KeyTypeId;
+use sp_core::sr25519;
+use sp_runtime::MultiSignature;
+use sp_std::prelude::*;
+
+/// Key type of player keys generated in benchmarks
+pub const BENCHMARK_KEY_TYPE: KeyTypeId = KeyTypeId(*b"capb");
+
+/// Generate players in ascending order of their accounts
+///
+/// Parameter:
+/// `num`: Number of players
+pub fn sorted_players<AccountId: Decode + Ord>(num: u32) -> Vec<(AccountId, sr25519::Public)> {
+    let mut players: Vec<(AccountId, sr25519::Public)> = (0..num)
+        .map(|_| {
+            let public = sp_io::crypto::sr25519_generate(BENCHMARK_KEY_TYPE, None);
+            let account = decode_exact(&public.encode()).expect("runtime accounts are 32 bytes");
+            (account, public)
+        })
+        .collect();
+    players.sort_by(|a, b| a.0.cmp(&b.0));
+
+    players
+}
+
+/// Sign a message by a player
+///
+/// Parameters:
+/// `public`: Key of player
+/// `msg`: Message to sign
+pub fn sign<Signature: Decode>(public: &sr25519::Public, msg: &[u8]) -> Signature {
+    let signature = sp_io::crypto::sr25519_sign(BENCHMARK_KEY_TYPE, public, msg)
+        .expect("key is generated in the keystore");
+    decode_exact(&signature.encode())
+        .or_else(|| decode_exact(&MultiSignature::from(signature).encode()))
+        .expect("runtime accepts sr25519 signatures")
+}
+
+/// Decode a value which takes all of the input
+fn decode_exact<V: Decode>(encoded: &[u8]) -> Option<V> {
+    let mut input = encoded;
+    let value = V::decode(&mut input).ok()?;
+    if !input.is_empty() {
+        return None;
+    }
+
+    Some(value)
+}
@@ -0,0 +1,37 @@
+//! Verification of the co-signatures of off-chain states
+//!
+//! Signatures are verified one after another with `Verify`. The host batch
+//! verification (`start_batch_verify` and `finish_batch_verify`) can not be
+//! used inside a dispatch: block import in `frame-executive` already runs
+//! each block inside a batch, where a nested `start_batch_verify` panics and
+//! signatures pushed to the outer batch pass at once and only fail the whole
+//! block after dispatch. Failed disputes would then import differently from
+//! how they were authored, so every signature is checked before it is used.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+#[cfg(feature = "runtime-benchmarks")]
+pub mod benchmarking;
+#[cfg(test)]
+mod tests;
+
+use sp_runtime::traits::{IdentifyAccount, Verify};
+
+/// Account verifying a signature
+pub type SignerOf<S> = <<S as Verify>::Signer as IdentifyAccount>::AccountId;
+
+/// Verify signatures of all signers
+///
+/// Parameters:
+/// `signatures`: Signatures in the order of signers
+/// `msg`: Signed message
+/// `signers`: Accounts expected to sign
+pub fn verify_all<S: Verify>(signatures: &[S], msg: &[u8], signers: &[SignerOf<S>]) -> bool {
+    if signatures.len() != signers.len() {
+        return false;
+    }
+
+    signatures.iter()
+        .zip(signers)
+        .all(|(signature, signer)| signature.verify(msg, signer))
+}
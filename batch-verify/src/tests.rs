@@ -0,0 +1,66 @@
+use super::*;
+use sp_core::{ed25519, sr25519, Pair};
+use sp_runtime::{AccountId32, MultiSignature};
+
+fn sr25519_pairs() -> Vec<sr25519::Pair> {
+    vec![
+        sr25519::Pair::from_string("//Alice", None).unwrap(),
+        sr25519::Pair::from_string("//Bob", None).unwrap(),
+        sr25519::Pair::from_string("//Charlie", None).unwrap(),
+    ]
+}
+
+#[test]
+fn test_pass_verify_all_sr25519_signatures() {
+    sp_io::TestExternalities::default().execute_with(|| {
+        let pairs = sr25519_pairs();
+        let signers: Vec<sr25519::Public> = pairs.iter().map(|pair| pair.public()).collect();
+        let signatures: Vec<sr25519::Signature> = pairs.iter().map(|pair| pair.sign(b"state")).collect();
+        assert!(verify_all(&signatures, b"state", &signers));
+
+        assert!(!verify_all(&signatures, b"other state", &signers));
+        let mut swapped = signatures.clone();
+        swapped.swap(0, 1);
+        assert!(!verify_all(&swapped, b"state", &signers));
+        assert!(!verify_all(&signatures[..2], b"state", &signers));
+    })
+}
+
+#[test]
+fn test_pass_verify_all_multi_signatures() {
+    sp_io::TestExternalities::default().execute_with(|| {
+        let sr25519_pair = sr25519::Pair::from_string("//Alice", None).unwrap();
+        let ed25519_pair = ed25519::Pair::from_string("//Bob", None).unwrap();
+        let signers: Vec<AccountId32> = vec![
+            sr25519_pair.public().into(),
+            ed25519_pair.public().into(),
+        ];
+        let signatures: Vec<MultiSignature> = vec![
+            sr25519_pair.sign(b"state").into(),
+            ed25519_pair.sign(b"state").into(),
+        ];
+        assert!(verify_all(&signatures, b"state", &signers));
+
+        let signatures: Vec<MultiSignature> = vec![
+            sr25519_pair.sign(b"state").into(),
+            ed25519_pair.sign(b"other state").into(),
+        ];
+        assert!(!verify_all(&signatures, b"state", &signers));
+    })
+}
+
+#[test]
+fn test_pass_verify_all_inside_block_batch() {
+    sp_io::TestExternalities::default().execute_with(|| {
+        let pairs = sr25519_pairs();
+        let signers: Vec<sr25519::Public> = pairs.iter().map(|pair| pair.public()).collect();
+        let signatures: Vec<sr25519::Signature> = pairs.iter().map(|pair| pair.sign(b"state")).collect();
+
+        // block import runs dispatches inside a batch of the executive
+        sp_io::crypto::start_batch_verify();
+        assert!(verify_all(&signatures, b"state", &signers));
+        // invalid signatures fail right away instead of failing the block batch
+        assert!(!verify_all(&signatures, b"other state", &signers));
+        assert!(sp_io::crypto::finish_batch_verify());
+    })
+}
@@ -0,0 +1,155 @@
+//! Command-line tool for crafting and inspecting state proofs
+//!
+//! Works offline with the types of the dev runtime: sr25519 accounts and
+//! signatures, u64 block numbers and BlakeTwo256 hashes. Players are always
+//! sorted in ascending order, the order the pallets expect signatures in.
+
+mod proof;
+
+#[cfg(test)]
+mod tests;
+
+use proof::{
+    board_state_from_record, parse_hash, parse_hex, parse_pair, parse_players, AppKind, BlockNumber, Proof,
+};
+use sp_core::{crypto::Ss58Codec, Pair};
+use structopt::StructOpt;
+
+#[derive(Debug, StructOpt)]
+#[structopt(name = "capps", about = "Craft and inspect cApps state proofs")]
+enum Command {
+    /// Derive the id of an app or a session
+    AppId {
+        /// single-session-app, multi-session-app, single-gomoku or multi-gomoku
+        #[structopt(long)]
+        app: AppKind,
+        /// Nonce of app
+        #[structopt(long, default_value = "0")]
+        nonce: u128,
+        /// Secret URIs such as //Alice or SS58 addresses of players
+        #[structopt(long, required = true)]
+        players: Vec<String>,
+    },
+    /// Build an app state, sign it and print the hex encoded state proof
+    Sign {
+        /// single-session-app, multi-session-app, single-gomoku or multi-gomoku
+        #[structopt(long)]
+        app: AppKind,
+        /// Nonce of app
+        #[structopt(long, default_value = "0")]
+        nonce: u128,
+        /// Sequence number of app state
+        #[structopt(long)]
+        seq_num: u128,
+        /// Decimal state of session apps or hex board state of gomoku apps
+        #[structopt(long)]
+        state: String,
+        /// Timeout of settle and action period
+        #[structopt(long)]
+        timeout: BlockNumber,
+        /// Id of app, derived from nonce and signers if omitted
+        #[structopt(long)]
+        app_id: Option<String>,
+        /// Secret URIs such as //Alice of the signing players
+        #[structopt(long, required = true)]
+        signers: Vec<String>,
+    },
+    /// Decode a hex encoded state proof
+    Decode {
+        /// single-session-app, multi-session-app, single-gomoku or multi-gomoku
+        #[structopt(long)]
+        app: AppKind,
+        /// Hex encoded state proof
+        proof: String,
+    },
+    /// Replay a game record such as "h8 i9 j10" into a hex board state of a gomoku app
+    Record {
+        /// single-gomoku or multi-gomoku
+        #[structopt(long)]
+        app: AppKind,
+        /// Player playing black in single gomoku or black id in multi gomoku, 1 or 2
+        #[structopt(long, default_value = "1")]
+        black: u8,
+        /// Move list, compact RenjuNet record or RIF game
+        record: String,
+    },
+    /// Print polkadot.js type definitions of every pallet, checked in as types.json
+    Types,
+    /// Verify signatures of a hex encoded state proof against players
+    Verify {
+        /// single-session-app, multi-session-app, single-gomoku or multi-gomoku
+        #[structopt(long)]
+        app: AppKind,
+        /// Secret URIs such as //Alice or SS58 addresses of players
+        #[structopt(long, required = true)]
+        players: Vec<String>,
+        /// Hex encoded state proof
+        proof: String,
+    },
+}
+
+fn main() {
+    match run(Command::from_args()) {
+        Ok(output) => print!("{}", output),
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            std::process::exit(1);
+        },
+    }
+}
+
+fn run(command: Command) -> Result<String, String> {
+    match command {
+        Command::AppId { app, nonce, players } => {
+            let players = parse_players(&players)?;
+            Ok(format!("{:?}\n", app.app_id(nonce, &players)))
+        },
+        Command::Sign { app, nonce, seq_num, state, timeout, app_id, signers } => {
+            let mut pairs = signers.iter()
+                .map(|uri| parse_pair(uri))
+                .collect::<Result<Vec<_>, _>>()?;
+            pairs.sort_by_key(|pair| pair.public());
+            let app_id = match app_id {
+                Some(app_id) => parse_hash(&app_id)?,
+                None => {
+                    let players: Vec<_> = pairs.iter().map(|pair| pair.public()).collect();
+                    app.app_id(nonce, &players)
+                },
+            };
+            let proof = Proof::build(app, nonce, seq_num, &state, timeout, app_id, &pairs)?;
+            Ok(format!("0x{}\n", hex::encode(proof.encode())))
+        },
+        Command::Decode { app, proof } => {
+            let proof = Proof::decode(app, &parse_hex(&proof)?)?;
+            Ok(proof.describe())
+        },
+        Command::Record { app, black, record } => {
+            let board_state = board_state_from_record(app, black, &record)?;
+            Ok(format!("0x{}\n", hex::encode(board_state)))
+        },
+        Command::Types => Ok(capps_client::types::types_bundle()),
+        Command::Verify { app, players, proof } => {
+            let players = parse_players(&players)?;
+            let proof = Proof::decode(app, &parse_hex(&proof)?)?;
+            if proof.sigs().len() != players.len() {
+                return Err(format!(
+                    "proof has {} signatures for {} players",
+                    proof.sigs().len(),
+                    players.len()
+                ));
+            }
+
+            let results = proof.verify(&players);
+            let mut output = String::new();
+            for (index, (player, valid)) in players.iter().zip(results.iter()).enumerate() {
+                let result = if *valid { "valid" } else { "invalid" };
+                output.push_str(&format!("[{}] {} {}\n", index, player.to_ss58check(), result));
+            }
+            if results.iter().all(|valid| *valid) {
+                Ok(output)
+            } else {
+                Err(format!("{}invalid signatures", output))
+            }
+        },
+    }
+}
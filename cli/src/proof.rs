@@ -0,0 +1,289 @@
+//! State proofs of every app with the types of the dev runtime
+
+use std::str::FromStr;
+
+use capps_client::{multi_gomoku, multi_session_app, single_gomoku, single_session_app};
+use codec::{Decode, Encode};
+use gomoku_engine::{render_board_state, GameRecord};
+use sp_core::{crypto::Ss58Codec, sr25519, Pair, H256};
+use sp_runtime::traits::{BlakeTwo256, Verify};
+
+pub type AccountId = sr25519::Public;
+pub type Signature = sr25519::Signature;
+pub type BlockNumber = u64;
+
+/// App a state proof belongs to
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum AppKind {
+    SingleSessionApp,
+    MultiSessionApp,
+    SingleGomoku,
+    MultiGomoku,
+}
+
+impl FromStr for AppKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<AppKind, String> {
+        match s {
+            "single-session-app" => Ok(AppKind::SingleSessionApp),
+            "multi-session-app" => Ok(AppKind::MultiSessionApp),
+            "single-gomoku" => Ok(AppKind::SingleGomoku),
+            "multi-gomoku" => Ok(AppKind::MultiGomoku),
+            _ => Err(format!(
+                "unknown app `{}`, expected single-session-app, multi-session-app, single-gomoku or multi-gomoku",
+                s
+            )),
+        }
+    }
+}
+
+impl AppKind {
+    /// Get Id of app or session
+    ///
+    /// Parameters:
+    /// `nonce`: Nonce of app
+    /// `players`: AccountId of players in ascending order
+    pub fn app_id(&self, nonce: u128, players: &[AccountId]) -> H256 {
+        match self {
+            AppKind::SingleSessionApp => single_session_app::get_app_id::<_, BlakeTwo256>(nonce, players),
+            AppKind::MultiSessionApp => multi_session_app::get_session_id::<_, BlakeTwo256>(nonce, players),
+            AppKind::SingleGomoku => single_gomoku::get_app_id::<_, BlakeTwo256>(nonce, players),
+            AppKind::MultiGomoku => multi_gomoku::get_app_id::<_, BlakeTwo256>(nonce, players),
+        }
+    }
+}
+
+/// Signed app state of one of the apps
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Proof {
+    SingleSessionApp(single_session_app::StateProof<BlockNumber, H256, Signature>),
+    MultiSessionApp(multi_session_app::StateProof<BlockNumber, H256, Signature>),
+    SingleGomoku(single_gomoku::StateProof<BlockNumber, H256, Signature>),
+    MultiGomoku(multi_gomoku::StateProof<BlockNumber, H256, Signature>),
+}
+
+impl Proof {
+    /// Build app state and sign it by every signer
+    ///
+    /// Parameters:
+    /// `kind`: App of the state
+    /// `nonce`: Nonce of app, ignored by apps without nonce in app state
+    /// `seq_num`: Sequence number of app state
+    /// `state`: Decimal u8 state of session apps, hex board state of gomoku apps
+    /// `timeout`: Timeout of settle and action period
+    /// `app_id`: Id of app or session
+    /// `signers`: Key pairs in the order of players
+    pub fn build(
+        kind: AppKind,
+        nonce: u128,
+        seq_num: u128,
+        state: &str,
+        timeout: BlockNumber,
+        app_id: H256,
+        signers: &[sr25519::Pair],
+    ) -> Result<Proof, String> {
+        let proof = match kind {
+            AppKind::SingleSessionApp => {
+                let app_state = single_session_app::app_state(nonce, seq_num, parse_state(state)?, timeout, app_id);
+                Proof::SingleSessionApp(single_session_app::sign_app_state(app_state, signers))
+            },
+            AppKind::MultiSessionApp => {
+                let app_state = multi_session_app::app_state(seq_num, parse_state(state)?, timeout, app_id);
+                Proof::MultiSessionApp(multi_session_app::sign_app_state(app_state, signers))
+            },
+            AppKind::SingleGomoku => {
+                let app_state = single_gomoku::app_state(nonce, seq_num, parse_hex(state)?, timeout, app_id);
+                Proof::SingleGomoku(single_gomoku::sign_app_state(app_state, signers))
+            },
+            AppKind::MultiGomoku => {
+                let app_state = multi_gomoku::app_state(seq_num, parse_hex(state)?, timeout, app_id);
+                Proof::MultiGomoku(multi_gomoku::sign_app_state(app_state, signers))
+            },
+        };
+
+        Ok(proof)
+    }
+
+    /// Decode SCALE encoded state proof
+    ///
+    /// Parameters:
+    /// `kind`: App of the state proof
+    /// `encoded`: Encoded state proof
+    pub fn decode(kind: AppKind, encoded: &[u8]) -> Result<Proof, String> {
+        let input = &mut &encoded[..];
+        let proof = match kind {
+            AppKind::SingleSessionApp => Decode::decode(input).map(Proof::SingleSessionApp),
+            AppKind::MultiSessionApp => Decode::decode(input).map(Proof::MultiSessionApp),
+            AppKind::SingleGomoku => Decode::decode(input).map(Proof::SingleGomoku),
+            AppKind::MultiGomoku => Decode::decode(input).map(Proof::MultiGomoku),
+        }.map_err(|e| format!("invalid state proof: {}", e.what()))?;
+        if !input.is_empty() {
+            return Err(format!("{} trailing bytes after state proof", input.len()));
+        }
+
+        Ok(proof)
+    }
+
+    /// SCALE encode state proof
+    pub fn encode(&self) -> Vec<u8> {
+        match self {
+            Proof::SingleSessionApp(proof) => proof.encode(),
+            Proof::MultiSessionApp(proof) => proof.encode(),
+            Proof::SingleGomoku(proof) => proof.encode(),
+            Proof::MultiGomoku(proof) => proof.encode(),
+        }
+    }
+
+    /// Get the payload signed by players
+    pub fn payload(&self) -> Vec<u8> {
+        match self {
+            Proof::SingleSessionApp(proof) => proof.app_state.signing_payload(),
+            Proof::MultiSessionApp(proof) => proof.app_state.signing_payload(),
+            Proof::SingleGomoku(proof) => proof.app_state.signing_payload(),
+            Proof::MultiGomoku(proof) => proof.app_state.signing_payload(),
+        }
+    }
+
+    /// Get signatures of players
+    pub fn sigs(&self) -> &[Signature] {
+        match self {
+            Proof::SingleSessionApp(proof) => &proof.sigs,
+            Proof::MultiSessionApp(proof) => &proof.sigs,
+            Proof::SingleGomoku(proof) => &proof.sigs,
+            Proof::MultiGomoku(proof) => &proof.sigs,
+        }
+    }
+
+    /// Check every signature against the player of the same index
+    ///
+    /// Parameter:
+    /// `players`: AccountId of players in ascending order
+    pub fn verify(&self, players: &[AccountId]) -> Vec<bool> {
+        let payload = self.payload();
+        self.sigs().iter()
+            .zip(players.iter())
+            .map(|(sig, player)| sig.verify(&payload[..], player))
+            .collect()
+    }
+
+    /// Render state proof in readable form
+    pub fn describe(&self) -> String {
+        let mut lines = vec![];
+        match self {
+            Proof::SingleSessionApp(proof) => {
+                let app_state = &proof.app_state;
+                lines.push("app: single-session-app".to_string());
+                lines.push(format!("nonce: {}", app_state.nonce));
+                lines.push(format!("seq_num: {}", app_state.seq_num));
+                lines.push(format!("state: {}", app_state.state));
+                lines.push(format!("timeout: {}", app_state.timeout));
+                lines.push(format!("app_id: {:?}", app_state.app_id));
+            },
+            Proof::MultiSessionApp(proof) => {
+                let app_state = &proof.app_state;
+                lines.push("app: multi-session-app".to_string());
+                lines.push(format!("seq_num: {}", app_state.seq_num));
+                lines.push(format!("state: {}", app_state.state));
+                lines.push(format!("timeout: {}", app_state.timeout));
+                lines.push(format!("session_id: {:?}", app_state.session_id));
+            },
+            Proof::SingleGomoku(proof) => {
+                let app_state = &proof.app_state;
+                lines.push("app: single-gomoku".to_string());
+                lines.push(format!("nonce: {}", app_state.nonce));
+                lines.push(format!("seq_num: {}", app_state.seq_num));
+                lines.push(format!("timeout: {}", app_state.timeout));
+                lines.push(format!("app_id: {:?}", app_state.app_id));
+                describe_board(&mut lines, &app_state.board_state);
+            },
+            Proof::MultiGomoku(proof) => {
+                let app_state = &proof.app_state;
+                lines.push("app: multi-gomoku".to_string());
+                lines.push(format!("seq_num: {}", app_state.seq_num));
+                lines.push(format!("timeout: {}", app_state.timeout));
+                lines.push(format!("app_id: {:?}", app_state.app_id));
+                describe_board(&mut lines, &app_state.board_state);
+            },
+        }
+        lines.push(format!("sigs: {}", self.sigs().len()));
+        for (index, sig) in self.sigs().iter().enumerate() {
+            lines.push(format!("  [{}] 0x{}", index, hex::encode(AsRef::<[u8]>::as_ref(sig))));
+        }
+
+        lines.iter().map(|line| format!("{}\n", line)).collect()
+    }
+}
+
+/// Append the rendered board state of a gomoku app
+fn describe_board(lines: &mut Vec<String>, board_state: &[u8]) {
+    match render_board_state(board_state) {
+        Ok(text) => lines.extend(text.lines().map(str::to_string)),
+        Err(e) => lines.push(format!("board_state: 0x{} ({})", hex::encode(board_state), e)),
+    }
+}
+
+/// Replay a game record into a board state of a gomoku app
+///
+/// Parameters:
+/// `kind`: Gomoku app of the board state
+/// `black`: Player playing black in single gomoku or black id in multi gomoku
+/// `record`: Move list, compact RenjuNet record or RIF game
+pub fn board_state_from_record(kind: AppKind, black: u8, record: &str) -> Result<Vec<u8>, String> {
+    if black != 1 && black != 2 {
+        return Err(format!("invalid black player {}, expected 1 or 2", black));
+    }
+    let record = GameRecord::parse(record).map_err(|e| e.to_string())?;
+    match kind {
+        AppKind::SingleGomoku => single_gomoku::board_state_from_record(&record, black),
+        AppKind::MultiGomoku => multi_gomoku::board_state_from_record(&record, black),
+        _ => return Err("game records are only supported by gomoku apps".to_string()),
+    }.map_err(|e| e.to_string())
+}
+
+/// Parse accounts given as secret URIs such as `//Alice` or SS58 addresses
+/// and sort them in ascending order as the pallets expect
+pub fn parse_players(players: &[String]) -> Result<Vec<AccountId>, String> {
+    let mut accounts = players.iter()
+        .map(|player| parse_account(player))
+        .collect::<Result<Vec<_>, _>>()?;
+    accounts.sort();
+
+    Ok(accounts)
+}
+
+/// Parse account given as a secret URI such as `//Alice` or an SS58 address
+pub fn parse_account(account: &str) -> Result<AccountId, String> {
+    if account.starts_with("//") {
+        return parse_pair(account).map(|pair| pair.public());
+    }
+
+    AccountId::from_ss58check(account)
+        .map_err(|e| format!("invalid account `{}`: {:?}", account, e))
+}
+
+/// Get key pair from a secret URI such as `//Alice`
+pub fn parse_pair(uri: &str) -> Result<sr25519::Pair, String> {
+    capps_client::pair_from_uri(uri)
+        .map_err(|e| format!("invalid secret URI `{}`: {:?}", uri, e))
+}
+
+/// Parse 32 bytes hash in hex
+pub fn parse_hash(hash: &str) -> Result<H256, String> {
+    let bytes = parse_hex(hash)?;
+    if bytes.len() != 32 {
+        return Err(format!("invalid hash length {}, expected 32 bytes", bytes.len()));
+    }
+
+    Ok(H256::from_slice(&bytes))
+}
+
+/// Parse hex string with or without `0x` prefix
+pub fn parse_hex(hex_str: &str) -> Result<Vec<u8>, String> {
+    let digits = hex_str.trim().trim_start_matches("0x");
+    hex::decode(digits).map_err(|e| format!("invalid hex `{}`: {}", hex_str, e))
+}
+
+fn parse_state(state: &str) -> Result<u8, String> {
+    state.parse().map_err(|_| format!("invalid state `{}`, expected u8", state))
+}
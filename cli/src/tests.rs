@@ -0,0 +1,110 @@
+use super::*;
+use capps_client::single_gomoku;
+use sp_core::sr25519;
+use sp_runtime::traits::BlakeTwo256;
+
+fn players() -> Vec<String> {
+    vec!["//Bob".to_string(), "//Alice".to_string()]
+}
+
+fn sign(app: AppKind, state: &str) -> String {
+    run(Command::Sign {
+        app: app,
+        nonce: 0,
+        seq_num: 2,
+        state: state.to_string(),
+        timeout: 2,
+        app_id: None,
+        signers: players(),
+    }).unwrap().trim().to_string()
+}
+
+#[test]
+fn test_pass_app_id_sorts_players() {
+    let alice: sr25519::Pair = capps_client::pair_from_uri("//Alice").unwrap();
+    let bob: sr25519::Pair = capps_client::pair_from_uri("//Bob").unwrap();
+    let mut sorted = vec![alice.public(), bob.public()];
+    sorted.sort();
+
+    let output = run(Command::AppId {
+        app: AppKind::SingleGomoku,
+        nonce: 1,
+        players: vec![bob.public().to_ss58check(), "//Alice".to_string()],
+    }).unwrap();
+    let expected = single_gomoku::get_app_id::<_, BlakeTwo256>(1, &sorted);
+    assert_eq!(output, format!("{:?}\n", expected));
+}
+
+#[test]
+fn test_pass_sign_decode_and_verify_gomoku_proof() {
+    let mut board_state = vec![0u8; 227];
+    board_state[1] = 1;
+    board_state[2 + 15 * 7 + 7] = 2;
+    let proof = sign(AppKind::SingleGomoku, &hex::encode(&board_state));
+
+    let decoded = run(Command::Decode { app: AppKind::SingleGomoku, proof: proof.clone() }).unwrap();
+    assert!(decoded.contains("seq_num: 2"));
+    assert!(decoded.contains("turn: 1"));
+    assert!(decoded.contains("  7  .  .  .  .  .  .  .  O  .  .  .  .  .  .  ."));
+    assert!(decoded.contains("sigs: 2"));
+
+    assert_ok_verify(AppKind::SingleGomoku, &proof);
+}
+
+#[test]
+fn test_pass_verify_session_app_proof() {
+    let proof = sign(AppKind::MultiSessionApp, "5");
+    assert_ok_verify(AppKind::MultiSessionApp, &proof);
+
+    let decoded = run(Command::Decode { app: AppKind::MultiSessionApp, proof: proof }).unwrap();
+    assert!(decoded.contains("state: 5"));
+}
+
+#[test]
+fn test_fail_verify_with_other_players() {
+    let proof = sign(AppKind::SingleSessionApp, "2");
+    assert!(run(Command::Verify {
+        app: AppKind::SingleSessionApp,
+        players: vec!["//Alice".to_string(), "//Charlie".to_string()],
+        proof: proof.clone(),
+    }).is_err());
+    assert!(run(Command::Verify {
+        app: AppKind::SingleSessionApp,
+        players: vec!["//Alice".to_string()],
+        proof: proof,
+    }).is_err());
+}
+
+#[test]
+fn test_fail_decode_with_trailing_bytes() {
+    let proof = sign(AppKind::SingleSessionApp, "2");
+    assert!(run(Command::Decode { app: AppKind::SingleSessionApp, proof: format!("{}00", proof) }).is_err());
+    assert!(run(Command::Decode { app: AppKind::MultiGomoku, proof: "0x0102".to_string() }).is_err());
+}
+
+fn assert_ok_verify(app: AppKind, proof: &str) {
+    let output = run(Command::Verify {
+        app: app,
+        players: players(),
+        proof: proof.to_string(),
+    }).unwrap();
+    assert_eq!(output.matches(" valid\n").count(), 2);
+}
+
+#[test]
+fn test_pass_record_to_board_state() {
+    let output = run(Command::Record {
+        app: AppKind::MultiGomoku,
+        black: 2,
+        record: "h8 i9".to_string(),
+    }).unwrap();
+    let board_state = parse_hex(output.trim()).unwrap();
+    assert_eq!(&board_state[..3], &[0, 1, 2]);
+    assert_eq!(board_state[3 + 15 * 7 + 7], 1);
+
+    assert!(run(Command::Record {
+        app: AppKind::SingleSessionApp,
+        black: 1,
+        record: "h8".to_string(),
+    }).is_err());
+}
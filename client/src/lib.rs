@@ -0,0 +1,55 @@
+//! Off-chain client for cApps pallets
+//!
+//! Builds initiate requests and state proofs, derives app and session ids
+//! and signs app states without a runtime. Payloads are encoded by the
+//! pallets' own `AppState::payload`, in the encoding chosen at initiation,
+//! so signatures produced here verify on-chain byte for byte. `types` renders the polkadot.js type
+//! definitions of every pallet.
+
+pub mod single_session_app;
+pub mod multi_session_app;
+pub mod single_gomoku;
+pub mod multi_gomoku;
+pub mod types;
+
+#[cfg(test)]
+mod tests;
+
+use sp_core::Pair;
+
+pub use capps_state_encoding::ethereum::{EcdsaSignature, Pair as EthPair};
+pub use sp_core::crypto::SecretStringError;
+
+/// Get key pair from a secret URI
+///
+/// Parameter:
+/// `uri`: Secret URI such as `//Alice` or a mnemonic phrase
+pub fn pair_from_uri<P: Pair>(uri: &str) -> Result<P, SecretStringError> {
+    P::from_string(uri, None)
+}
+
+/// Sign a payload by every key pair
+///
+/// Parameters:
+/// `payload`: Signing payload
+/// `pairs`: Key pairs in the order of players
+pub fn sign_payload<P, Signature>(payload: &[u8], pairs: &[P]) -> Vec<Signature>
+where
+    P: Pair,
+    Signature: From<P::Signature>,
+{
+    pairs.iter()
+        .map(|pair| pair.sign(payload).into())
+        .collect()
+}
+
+/// Sign a payload by every Ethereum key pair as an Ethereum signed message
+///
+/// Parameters:
+/// `payload`: Signing payload
+/// `pairs`: Ethereum key pairs of players
+pub fn sign_eth_payload(payload: &[u8], pairs: &[EthPair]) -> Vec<EcdsaSignature> {
+    pairs.iter()
+        .map(|pair| pair.sign(payload))
+        .collect()
+}
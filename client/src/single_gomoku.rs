@@ -0,0 +1,419 @@
+//! Requests and state proofs of single gomoku app
+
+use codec::{Decode, Encode};
+use sp_core::{Pair, H160, H256};
+use sp_runtime::traits::{Hash as HashT, Zero};
+use std::convert::TryInto;
+use ::gomoku_engine::Outcome;
+
+pub use ::gomoku_engine::{GameRecord, Move, RecordError};
+pub use ::single_gomoku::{
+    AppInitiateRequest, AppState, MoveRecord, StateEncoding, EthStateProof, StateProof,
+    SessionKeyDelegation, KeyRotation, seed_commitment, combined_seed, first_mover, relayed_action_payload, SignedMove, signed_move_payload, SINGLE_GOMOKU_ID,
+};
+
+/// Builder of app initiate request
+pub struct AppInitiateRequestBuilder<AccountId, BlockNumber> {
+    request: AppInitiateRequest<AccountId, BlockNumber>,
+}
+
+impl<AccountId, BlockNumber: Zero> AppInitiateRequestBuilder<AccountId, BlockNumber> {
+    /// Start a request with no stone limits, no time control, the full board stored on-chain
+    /// and SCALE encoded app states
+    ///
+    /// Parameters:
+    /// `nonce`: Nonce of app
+    /// `players`: AccountId of 2 players
+    /// `timeout`: Timeout of settle and action period
+    pub fn new(nonce: u128, players: Vec<AccountId>, timeout: BlockNumber) -> Self {
+        AppInitiateRequestBuilder {
+            request: AppInitiateRequest {
+                nonce: nonce,
+                players: players,
+                timeout: timeout,
+                min_stone_offchain: 0,
+                max_stone_onchain: 225,
+                time_bank: Zero::zero(),
+                increment: Zero::zero(),
+                hashed_board: false,
+                encoding: StateEncoding::Scale,
+                eth_players: vec![],
+                arbiter: None,
+                seed_timeout: None,
+            },
+        }
+    }
+
+    /// Set minimal number of stones before going on-chain
+    pub fn min_stone_offchain(mut self, min_stone_offchain: u8) -> Self {
+        self.request.min_stone_offchain = min_stone_offchain;
+        self
+    }
+
+    /// Set maximal number of stones placed on-chain
+    pub fn max_stone_onchain(mut self, max_stone_onchain: u8) -> Self {
+        self.request.max_stone_onchain = max_stone_onchain;
+        self
+    }
+
+    /// Set time bank of each player and increment per on-chain move
+    pub fn time_control(mut self, time_bank: BlockNumber, increment: BlockNumber) -> Self {
+        self.request.time_bank = time_bank;
+        self.request.increment = increment;
+        self
+    }
+
+    /// Store only a hash of the board on-chain
+    pub fn hashed_board(mut self, hashed_board: bool) -> Self {
+        self.request.hashed_board = hashed_board;
+        self
+    }
+
+    /// Set encoding of app states signed by players
+    pub fn encoding(mut self, encoding: StateEncoding) -> Self {
+        self.request.encoding = encoding;
+        self
+    }
+
+    /// Sign app states by Ethereum accounts of players, in the Ethereum encoding
+    pub fn ethereum(mut self, eth_players: Vec<H160>) -> Self {
+        self.request.encoding = StateEncoding::Ethereum;
+        self.request.eth_players = eth_players;
+        self
+    }
+
+    /// Let an arbiter account rule the outcome while the app is in settle or action mode
+    pub fn arbiter(mut self, arbiter: AccountId) -> Self {
+        self.request.arbiter = Some(arbiter);
+        self
+    }
+
+    /// Decide who moves first by a commit-reveal random seed, with the period to commit
+    /// and then to reveal
+    pub fn seed_timeout(mut self, seed_timeout: BlockNumber) -> Self {
+        self.request.seed_timeout = Some(seed_timeout);
+        self
+    }
+
+    /// Build app initiate request
+    pub fn build(self) -> AppInitiateRequest<AccountId, BlockNumber> {
+        self.request
+    }
+}
+
+/// Build app state
+///
+/// Parameters:
+/// `nonce`: Nonce of app
+/// `seq_num`: Sequence number of app state
+/// `board_state`: u8 winner + u8 turn + 15*15 board
+/// `timeout`: Timeout of settle and action period
+/// `app_id`: Id of app
+pub fn app_state<BlockNumber, Hash>(
+    nonce: u128,
+    seq_num: u128,
+    board_state: Vec<u8>,
+    timeout: BlockNumber,
+    app_id: Hash,
+) -> AppState<BlockNumber, Hash> {
+    AppState {
+        nonce: nonce,
+        seq_num: seq_num,
+        board_state: board_state,
+        timeout: timeout,
+        app_id: app_id,
+    }
+}
+
+/// Replay a game record into a board state
+///
+/// Parameters:
+/// `record`: Moves of the game, black first
+/// `black_player`: Player playing black, 1 or 2
+pub fn board_state_from_record(record: &GameRecord, black_player: u8) -> Result<Vec<u8>, RecordError> {
+    let (board, outcome) = record.replay()?;
+    // stone of the player playing a color
+    let player = |color: u8| match color {
+        0 => 0,
+        1 => black_player,
+        _ => 3 - black_player,
+    };
+    let (winner, turn) = match outcome {
+        Outcome::Win(color) => (player(color), 0),
+        Outcome::Draw => (0, 0),
+        Outcome::InProgress(color) => (0, player(color)),
+    };
+
+    let mut slots = vec![];
+    board.extend_slots(&mut slots);
+    let mut board_state = vec![winner, turn];
+    board_state.extend(slots.into_iter().map(player));
+
+    Ok(board_state)
+}
+
+/// Export a game as a record
+///
+/// The off-chain moves lead to the board state the game resumed from on-chain.
+///
+/// Parameters:
+/// `offchain_moves`: Moves played off-chain, black first
+/// `move_history`: Moves recorded on-chain since the resume point
+pub fn game_record<BlockNumber>(
+    offchain_moves: &[Move],
+    move_history: &[MoveRecord<BlockNumber>],
+) -> Result<GameRecord, RecordError> {
+    let mut moves = offchain_moves.to_vec();
+    moves.extend(move_history.iter().map(|record| Move::new(record.x, record.y)));
+    let record = GameRecord::new(moves);
+    record.replay()?;
+
+    Ok(record)
+}
+
+/// Get Id of app
+///
+/// Parameters:
+/// `nonce`: Nonce of app
+/// `players`: AccountId of players
+pub fn get_app_id<AccountId, Hashing>(nonce: u128, players: &[AccountId]) -> Hashing::Output
+where
+    AccountId: Encode + Decode + Default,
+    Hashing: HashT,
+{
+    ::single_gomoku::app_id::<AccountId, Hashing>(nonce, players)
+}
+
+/// Encode app state into the payload signed by players
+///
+/// Parameter:
+/// `app_state`: App state
+pub fn encode_app_state<BlockNumber: Encode, Hash: Encode>(
+    app_state: &AppState<BlockNumber, Hash>,
+) -> Vec<u8> {
+    app_state.signing_payload()
+}
+
+/// Sign app state by every player
+///
+/// Parameters:
+/// `app_state`: App state
+/// `pairs`: Key pairs in the order of players
+pub fn sign_app_state<BlockNumber, Hash, P, Signature>(
+    app_state: AppState<BlockNumber, Hash>,
+    pairs: &[P],
+) -> StateProof<BlockNumber, Hash, Signature>
+where
+    BlockNumber: Encode,
+    Hash: Encode,
+    P: Pair,
+    Signature: From<P::Signature>,
+{
+    let sigs = crate::sign_payload(&encode_app_state(&app_state), pairs);
+    StateProof {
+        app_state: app_state,
+        sigs: sigs,
+    }
+}
+
+/// Encode app state into the payload signed by players under an encoding
+///
+/// Returns `None` if the app state does not fit in the encoding.
+///
+/// Parameters:
+/// `app_state`: App state
+/// `encoding`: Encoding of app chosen at initiation
+pub fn encode_app_state_with<BlockNumber, Hash>(
+    app_state: &AppState<BlockNumber, Hash>,
+    encoding: StateEncoding,
+) -> Option<Vec<u8>>
+where
+    BlockNumber: Encode + Clone + TryInto<u64>,
+    Hash: Encode,
+{
+    app_state.payload(encoding)
+}
+
+/// Sign app state by every player under an encoding
+///
+/// Returns `None` if the app state does not fit in the encoding.
+///
+/// Parameters:
+/// `app_state`: App state
+/// `encoding`: Encoding of app chosen at initiation
+/// `pairs`: Key pairs in the order of players
+pub fn sign_app_state_with<BlockNumber, Hash, P, Signature>(
+    app_state: AppState<BlockNumber, Hash>,
+    encoding: StateEncoding,
+    pairs: &[P],
+) -> Option<StateProof<BlockNumber, Hash, Signature>>
+where
+    BlockNumber: Encode + Clone + TryInto<u64>,
+    Hash: Encode,
+    P: Pair,
+    Signature: From<P::Signature>,
+{
+    let sigs = crate::sign_payload(&encode_app_state_with(&app_state, encoding)?, pairs);
+    Some(StateProof {
+        app_state: app_state,
+        sigs: sigs,
+    })
+}
+
+/// Get Id of app signed by Ethereum accounts
+///
+/// Parameters:
+/// `nonce`: Nonce of app
+/// `eth_players`: Ethereum addresses of players
+pub fn get_eth_app_id(nonce: u128, eth_players: &[H160]) -> H256 {
+    ::single_gomoku::eth_app_id(nonce, eth_players)
+}
+
+/// Sign app state by the Ethereum account of every player
+///
+/// Returns `None` if the timeout does not fit in a `uint64`.
+///
+/// Parameters:
+/// `app_state`: App state
+/// `pairs`: Ethereum key pairs of players
+pub fn sign_app_state_eth<BlockNumber, Hash>(
+    app_state: AppState<BlockNumber, Hash>,
+    pairs: &[crate::EthPair],
+) -> Option<EthStateProof<BlockNumber, Hash>>
+where
+    BlockNumber: Encode + Clone + TryInto<u64>,
+    Hash: Encode,
+{
+    let sigs = crate::sign_eth_payload(&app_state.ethereum_payload()?, pairs);
+    Some(StateProof {
+        app_state: app_state,
+        sigs: sigs,
+    })
+}
+
+/// Build delegation of off-chain state signing from a player to a session key
+///
+/// Parameters:
+/// `app_id`: Id of app
+/// `player`: AccountId of player
+/// `session_key`: AccountId of session key signing in place of the player
+/// `expires_at`: Block number from which the session key is no longer accepted
+pub fn session_key_delegation<AccountId, BlockNumber, Hash>(
+    app_id: Hash,
+    player: AccountId,
+    session_key: AccountId,
+    expires_at: BlockNumber,
+) -> SessionKeyDelegation<AccountId, BlockNumber, Hash> {
+    SessionKeyDelegation {
+        app_id: app_id,
+        player: player,
+        session_key: session_key,
+        expires_at: expires_at,
+    }
+}
+
+/// Sign delegation to a session key by the key pair of the player
+///
+/// Parameters:
+/// `delegation`: Delegation to a session key
+/// `pair`: Key pair of the player
+pub fn sign_session_key_delegation<AccountId, BlockNumber, Hash, P, Signature>(
+    delegation: &SessionKeyDelegation<AccountId, BlockNumber, Hash>,
+    pair: &P,
+) -> Signature
+where
+    AccountId: Encode,
+    BlockNumber: Encode,
+    Hash: Encode,
+    P: Pair,
+    Signature: From<P::Signature>,
+{
+    pair.sign(&delegation.signing_payload()).into()
+}
+
+/// Build replacement of the account of a player in a live app
+///
+/// Parameters:
+/// `app_id`: Id of app
+/// `old_player`: AccountId of player to replace
+/// `new_player`: AccountId taking the place of the player
+/// `nonce`: Number of rotations of the app before this one
+pub fn key_rotation<AccountId, Hash>(
+    app_id: Hash,
+    old_player: AccountId,
+    new_player: AccountId,
+    nonce: u32,
+) -> KeyRotation<AccountId, Hash> {
+    KeyRotation {
+        app_id: app_id,
+        old_player: old_player,
+        new_player: new_player,
+        nonce: nonce,
+    }
+}
+
+/// Sign replacement of the account of a player, by the old account or by
+/// the arbiter, and by the new account
+///
+/// Parameters:
+/// `rotation`: Replacement of the account of a player
+/// `pair`: Key pair of the old account, of the arbiter or of the new account
+pub fn sign_key_rotation<AccountId, Hash, P, Signature>(
+    rotation: &KeyRotation<AccountId, Hash>,
+    pair: &P,
+) -> Signature
+where
+    AccountId: Encode,
+    Hash: Encode,
+    P: Pair,
+    Signature: From<P::Signature>,
+{
+    pair.sign(&rotation.signing_payload()).into()
+}
+
+/// Sign an on-chain action by the player in turn, for any account to submit
+/// through `update_by_relayed_action`
+///
+/// Parameters:
+/// `app_id`: Id of app
+/// `seq_num`: Sequence number of the on-chain state
+/// `action`: Action data
+/// `pair`: Key pair of the player in turn or its session key
+pub fn sign_relayed_action<Hash, P, Signature>(
+    app_id: &Hash,
+    seq_num: u128,
+    action: &[u8],
+    pair: &P,
+) -> Signature
+where
+    Hash: Encode,
+    P: Pair,
+    Signature: From<P::Signature>,
+{
+    pair.sign(&relayed_action_payload(app_id, seq_num, action)).into()
+}
+
+/// Sign an off-chain move by its mover only, to follow a co-signed state in
+/// `update_by_state_with_moves`
+///
+/// Parameters:
+/// `app_id`: Id of app
+/// `seq_num`: Sequence number of the state after the move
+/// `action`: Action data
+/// `pair`: Key pair of the player in turn or its session key
+pub fn sign_move<Hash, P, Signature>(
+    app_id: &Hash,
+    seq_num: u128,
+    action: Vec<u8>,
+    pair: &P,
+) -> SignedMove<Signature>
+where
+    Hash: Encode,
+    P: Pair,
+    Signature: From<P::Signature>,
+{
+    SignedMove {
+        sig: pair.sign(&signed_move_payload(app_id, seq_num, &action)).into(),
+        action: action,
+    }
+}
@@ -0,0 +1,283 @@
+//! Requests and state proofs of single session app
+
+use codec::{Decode, Encode};
+use sp_core::{Pair, H160, H256};
+use sp_runtime::traits::Hash as HashT;
+use std::convert::TryInto;
+
+pub use ::single_session_app::{
+    AppInitiateRequest, AppState, StateEncoding, EthStateProof, StateProof,
+    SessionKeyDelegation, KeyRotation, seed_commitment, combined_seed, SINGLE_SESSION_APP_ID,
+};
+
+/// Build app initiate request
+///
+/// Parameters:
+/// `nonce`: Nonce of app
+/// `players`: AccountId of 2 players
+/// `timeout`: Timeout of settle and action period
+/// `encoding`: Encoding of app states signed by players
+pub fn app_initiate_request<AccountId, BlockNumber>(
+    nonce: u128,
+    players: Vec<AccountId>,
+    timeout: BlockNumber,
+    encoding: StateEncoding,
+) -> AppInitiateRequest<AccountId, BlockNumber> {
+    AppInitiateRequest {
+        nonce: nonce,
+        players: players,
+        timeout: timeout,
+        encoding: encoding,
+        eth_players: vec![],
+        arbiter: None,
+        seed_timeout: None,
+    }
+}
+
+/// Build initiate request of app signed by Ethereum accounts of players
+///
+/// Parameters:
+/// `nonce`: Nonce of app
+/// `players`: AccountId of 2 players
+/// `eth_players`: Ethereum addresses of players
+/// `timeout`: Timeout of settle and action period
+pub fn eth_app_initiate_request<AccountId, BlockNumber>(
+    nonce: u128,
+    players: Vec<AccountId>,
+    eth_players: Vec<H160>,
+    timeout: BlockNumber,
+) -> AppInitiateRequest<AccountId, BlockNumber> {
+    AppInitiateRequest {
+        nonce: nonce,
+        players: players,
+        timeout: timeout,
+        encoding: StateEncoding::Ethereum,
+        eth_players: eth_players,
+        arbiter: None,
+        seed_timeout: None,
+    }
+}
+
+/// Build app state
+///
+/// Parameters:
+/// `nonce`: Nonce of app
+/// `seq_num`: Sequence number of app state
+/// `state`: App state
+/// `timeout`: Timeout of settle and action period
+/// `app_id`: Id of app
+pub fn app_state<BlockNumber, Hash>(
+    nonce: u128,
+    seq_num: u128,
+    state: u8,
+    timeout: BlockNumber,
+    app_id: Hash,
+) -> AppState<BlockNumber, Hash> {
+    AppState {
+        nonce: nonce,
+        seq_num: seq_num,
+        state: state,
+        timeout: timeout,
+        app_id: app_id,
+    }
+}
+
+/// Get Id of app
+///
+/// Parameters:
+/// `nonce`: Nonce of app
+/// `players`: AccountId of players
+pub fn get_app_id<AccountId, Hashing>(nonce: u128, players: &[AccountId]) -> Hashing::Output
+where
+    AccountId: Encode + Decode + Default,
+    Hashing: HashT,
+{
+    ::single_session_app::app_id::<AccountId, Hashing>(nonce, players)
+}
+
+/// Encode app state into the payload signed by players
+///
+/// Parameter:
+/// `app_state`: App state
+pub fn encode_app_state<BlockNumber: Encode, Hash: Encode>(
+    app_state: &AppState<BlockNumber, Hash>,
+) -> Vec<u8> {
+    app_state.signing_payload()
+}
+
+/// Sign app state by every player
+///
+/// Parameters:
+/// `app_state`: App state
+/// `pairs`: Key pairs in the order of players
+pub fn sign_app_state<BlockNumber, Hash, P, Signature>(
+    app_state: AppState<BlockNumber, Hash>,
+    pairs: &[P],
+) -> StateProof<BlockNumber, Hash, Signature>
+where
+    BlockNumber: Encode,
+    Hash: Encode,
+    P: Pair,
+    Signature: From<P::Signature>,
+{
+    let sigs = crate::sign_payload(&encode_app_state(&app_state), pairs);
+    StateProof {
+        app_state: app_state,
+        sigs: sigs,
+    }
+}
+
+/// Encode app state into the payload signed by players under an encoding
+///
+/// Returns `None` if the app state does not fit in the encoding.
+///
+/// Parameters:
+/// `app_state`: App state
+/// `encoding`: Encoding of app chosen at initiation
+pub fn encode_app_state_with<BlockNumber, Hash>(
+    app_state: &AppState<BlockNumber, Hash>,
+    encoding: StateEncoding,
+) -> Option<Vec<u8>>
+where
+    BlockNumber: Encode + Clone + TryInto<u64>,
+    Hash: Encode,
+{
+    app_state.payload(encoding)
+}
+
+/// Sign app state by every player under an encoding
+///
+/// Returns `None` if the app state does not fit in the encoding.
+///
+/// Parameters:
+/// `app_state`: App state
+/// `encoding`: Encoding of app chosen at initiation
+/// `pairs`: Key pairs in the order of players
+pub fn sign_app_state_with<BlockNumber, Hash, P, Signature>(
+    app_state: AppState<BlockNumber, Hash>,
+    encoding: StateEncoding,
+    pairs: &[P],
+) -> Option<StateProof<BlockNumber, Hash, Signature>>
+where
+    BlockNumber: Encode + Clone + TryInto<u64>,
+    Hash: Encode,
+    P: Pair,
+    Signature: From<P::Signature>,
+{
+    let sigs = crate::sign_payload(&encode_app_state_with(&app_state, encoding)?, pairs);
+    Some(StateProof {
+        app_state: app_state,
+        sigs: sigs,
+    })
+}
+
+/// Get Id of app signed by Ethereum accounts
+///
+/// Parameters:
+/// `nonce`: Nonce of app
+/// `eth_players`: Ethereum addresses of players
+pub fn get_eth_app_id(nonce: u128, eth_players: &[H160]) -> H256 {
+    ::single_session_app::eth_app_id(nonce, eth_players)
+}
+
+/// Sign app state by the Ethereum account of every player
+///
+/// Returns `None` if the timeout does not fit in a `uint64`.
+///
+/// Parameters:
+/// `app_state`: App state
+/// `pairs`: Ethereum key pairs of players
+pub fn sign_app_state_eth<BlockNumber, Hash>(
+    app_state: AppState<BlockNumber, Hash>,
+    pairs: &[crate::EthPair],
+) -> Option<EthStateProof<BlockNumber, Hash>>
+where
+    BlockNumber: Encode + Clone + TryInto<u64>,
+    Hash: Encode,
+{
+    let sigs = crate::sign_eth_payload(&app_state.ethereum_payload()?, pairs);
+    Some(StateProof {
+        app_state: app_state,
+        sigs: sigs,
+    })
+}
+
+/// Build delegation of off-chain state signing from a player to a session key
+///
+/// Parameters:
+/// `app_id`: Id of app
+/// `player`: AccountId of player
+/// `session_key`: AccountId of session key signing in place of the player
+/// `expires_at`: Block number from which the session key is no longer accepted
+pub fn session_key_delegation<AccountId, BlockNumber, Hash>(
+    app_id: Hash,
+    player: AccountId,
+    session_key: AccountId,
+    expires_at: BlockNumber,
+) -> SessionKeyDelegation<AccountId, BlockNumber, Hash> {
+    SessionKeyDelegation {
+        app_id: app_id,
+        player: player,
+        session_key: session_key,
+        expires_at: expires_at,
+    }
+}
+
+/// Sign delegation to a session key by the key pair of the player
+///
+/// Parameters:
+/// `delegation`: Delegation to a session key
+/// `pair`: Key pair of the player
+pub fn sign_session_key_delegation<AccountId, BlockNumber, Hash, P, Signature>(
+    delegation: &SessionKeyDelegation<AccountId, BlockNumber, Hash>,
+    pair: &P,
+) -> Signature
+where
+    AccountId: Encode,
+    BlockNumber: Encode,
+    Hash: Encode,
+    P: Pair,
+    Signature: From<P::Signature>,
+{
+    pair.sign(&delegation.signing_payload()).into()
+}
+
+/// Build replacement of the account of a player in a live app
+///
+/// Parameters:
+/// `app_id`: Id of app
+/// `old_player`: AccountId of player to replace
+/// `new_player`: AccountId taking the place of the player
+/// `nonce`: Number of rotations of the app before this one
+pub fn key_rotation<AccountId, Hash>(
+    app_id: Hash,
+    old_player: AccountId,
+    new_player: AccountId,
+    nonce: u32,
+) -> KeyRotation<AccountId, Hash> {
+    KeyRotation {
+        app_id: app_id,
+        old_player: old_player,
+        new_player: new_player,
+        nonce: nonce,
+    }
+}
+
+/// Sign replacement of the account of a player, by the old account or by
+/// the arbiter, and by the new account
+///
+/// Parameters:
+/// `rotation`: Replacement of the account of a player
+/// `pair`: Key pair of the old account, of the arbiter or of the new account
+pub fn sign_key_rotation<AccountId, Hash, P, Signature>(
+    rotation: &KeyRotation<AccountId, Hash>,
+    pair: &P,
+) -> Signature
+where
+    AccountId: Encode,
+    Hash: Encode,
+    P: Pair,
+    Signature: From<P::Signature>,
+{
+    pair.sign(&rotation.signing_payload()).into()
+}
@@ -0,0 +1,304 @@
+use super::*;
+use codec::Encode;
+use sp_core::{sr25519, H160, H256};
+use sp_runtime::traits::{BlakeTwo256, Hash, Verify};
+
+fn get_sorted_pairs(uri_1: &str, uri_2: &str) -> (Vec<sr25519::Public>, Vec<sr25519::Pair>) {
+    let mut pairs: Vec<sr25519::Pair> = vec![
+        pair_from_uri(uri_1).unwrap(),
+        pair_from_uri(uri_2).unwrap(),
+    ];
+    pairs.sort_by_key(|pair| pair.public());
+    let players = pairs.iter().map(|pair| pair.public()).collect();
+
+    return (players, pairs);
+}
+
+#[test]
+fn test_pass_sign_single_session_app_state() {
+    let (players, pairs) = get_sorted_pairs("//Alice", "//Bob");
+    let app_id: H256 = single_session_app::get_app_id::<_, BlakeTwo256>(0, &players);
+
+    let app_state = single_session_app::app_state(0, 2, 5, 2u64, app_id);
+    let state_proof: single_session_app::StateProof<u64, H256, sr25519::Signature>
+        = single_session_app::sign_app_state(app_state.clone(), &pairs);
+
+    // same payload layout as the pallet signs
+    let mut expected = 0u128.encode();
+    expected.extend(2u128.encode());
+    expected.extend(5u8.encode());
+    expected.extend(2u64.encode());
+    expected.extend(app_id.encode());
+    assert_eq!(single_session_app::encode_app_state(&app_state), expected);
+
+    assert_eq!(state_proof.app_state, app_state);
+    for (sig, player) in state_proof.sigs.iter().zip(players.iter()) {
+        assert!(sig.verify(&expected[..], player));
+    }
+}
+
+#[test]
+fn test_pass_sign_protobuf_encoded_app_state() {
+    let (players, pairs) = get_sorted_pairs("//Alice", "//Bob");
+    let app_id: H256 = single_session_app::get_app_id::<_, BlakeTwo256>(0, &players);
+
+    let app_state = single_session_app::app_state(0, 2, 5, 2u64, app_id);
+    let state_proof: single_session_app::StateProof<u64, H256, sr25519::Signature>
+        = single_session_app::sign_app_state_with(
+            app_state.clone(), single_session_app::StateEncoding::Protobuf, &pairs
+        ).unwrap();
+
+    // seq_num, state, timeout and app_id fields, the nonce of 0 is omitted
+    let mut expected = vec![0x10, 2, 0x1a, 1, 5, 0x20, 2, 0x2a, 32];
+    expected.extend(app_id.as_bytes());
+    assert_eq!(
+        single_session_app::encode_app_state_with(&app_state, single_session_app::StateEncoding::Protobuf),
+        Some(expected.clone())
+    );
+    for (sig, player) in state_proof.sigs.iter().zip(players.iter()) {
+        assert!(sig.verify(&expected[..], player));
+    }
+
+    let app_state = single_session_app::app_state(0, u128::max_value(), 5, 2u64, app_id);
+    assert_eq!(
+        single_session_app::encode_app_state_with(&app_state, single_session_app::StateEncoding::Protobuf),
+        None
+    );
+
+    let request = single_gomoku::AppInitiateRequestBuilder::new(0, players, 2u64)
+        .encoding(single_gomoku::StateEncoding::Protobuf)
+        .build();
+    assert_eq!(request.encoding, single_gomoku::StateEncoding::Protobuf);
+}
+
+#[test]
+fn test_pass_sign_ethereum_app_state() {
+    let eth_pairs = vec![
+        EthPair::from_secret(&[1; 32]).unwrap(),
+        EthPair::from_secret(&[2; 32]).unwrap(),
+    ];
+    let eth_players: Vec<H160> = eth_pairs.iter().map(|pair| pair.address()).collect();
+    let app_id = single_gomoku::get_eth_app_id(0, &eth_players);
+
+    let app_state = single_gomoku::app_state(0, 3, vec![0; 227], 2u64, app_id);
+    let state_proof = single_gomoku::sign_app_state_eth(app_state.clone(), &eth_pairs).unwrap();
+    let payload = app_state.ethereum_payload().unwrap();
+    let signers: Vec<H160> = state_proof.sigs.iter()
+        .map(|sig| capps_state_encoding::ethereum::recover_signer(sig, &payload).unwrap())
+        .collect();
+    assert_eq!(signers, eth_players);
+
+    let (players, _) = get_sorted_pairs("//Alice", "//Bob");
+    let request = single_gomoku::AppInitiateRequestBuilder::new(0, players, 2u64)
+        .ethereum(eth_players.clone())
+        .build();
+    assert_eq!(request.encoding, single_gomoku::StateEncoding::Ethereum);
+    assert_eq!(request.eth_players, eth_players);
+}
+
+#[test]
+fn test_pass_get_multi_session_id() {
+    let (players, _) = get_sorted_pairs("//Alice", "//Bob");
+
+    let account: sr25519::Public = sp_runtime::traits::AccountIdConversion::into_account(
+        &multi_session_app::MULTI_SESSION_APP_ID
+    );
+    let mut encoded = account.encode();
+    encoded.extend(1u128.encode());
+    encoded.extend(players[0].encode());
+    encoded.extend(players[1].encode());
+    assert_eq!(
+        multi_session_app::get_session_id::<_, BlakeTwo256>(1, &players),
+        BlakeTwo256::hash(&encoded)
+    );
+
+    let request = multi_session_app::session_initiate_request(
+        1, players, 2u64, multi_session_app::StateEncoding::Scale
+    );
+    assert_eq!(request.player_num, 2);
+    assert_eq!(request.threshold, 2);
+}
+
+#[test]
+fn test_pass_sign_app_state_by_players() {
+    let (players, pairs) = get_sorted_pairs("//Alice", "//Bob");
+    let session_id = multi_session_app::get_session_id::<_, BlakeTwo256>(1, &players);
+    let app_state = multi_session_app::app_state(1, 2, 2u64, session_id);
+
+    let proof: multi_session_app::ThresholdStateProof<_, _, sr25519::Signature> =
+        multi_session_app::sign_app_state_by_players(app_state.clone(), &[(1, pairs[1].clone())]);
+    assert_eq!(proof.sigs.len(), 1);
+    assert_eq!(proof.sigs[0].player, 1);
+    let expected = multi_session_app::encode_app_state(&app_state);
+    assert!(proof.sigs[0].sig.verify(&expected[..], &players[1]));
+}
+
+#[test]
+fn test_pass_build_gomoku_initiate_request() {
+    let (players, pairs) = get_sorted_pairs("//Alice", "//Bob");
+
+    let request = single_gomoku::AppInitiateRequestBuilder::new(0, players.clone(), 2u64)
+        .min_stone_offchain(5)
+        .max_stone_onchain(5)
+        .build();
+    assert_eq!(request.min_stone_offchain, 5);
+    assert_eq!(request.max_stone_onchain, 5);
+    assert_eq!(request.time_bank, 0);
+    assert_eq!(request.hashed_board, false);
+    assert_eq!(request.encoding, single_gomoku::StateEncoding::Scale);
+    assert_eq!(request.arbiter, None);
+
+    let arbiter = pair_from_uri::<sr25519::Pair>("//Carl").unwrap().public();
+    let request = multi_gomoku::AppInitiateRequestBuilder::new(0, players.clone(), 2u64)
+        .time_control(10, 1)
+        .hashed_board(true)
+        .arbiter(arbiter)
+        .build();
+    assert_eq!(request.player_num, 2);
+    assert_eq!(request.time_bank, 10);
+    assert_eq!(request.increment, 1);
+    assert_eq!(request.hashed_board, true);
+    assert_eq!(request.arbiter, Some(arbiter));
+
+    let app_id: H256 = multi_gomoku::get_app_id::<_, BlakeTwo256>(0, &players);
+    let app_state = multi_gomoku::app_state(1, vec![0; 228], 2u64, app_id);
+    let state_proof: multi_gomoku::StateProof<u64, H256, sr25519::Signature>
+        = multi_gomoku::sign_app_state(app_state.clone(), &pairs);
+    let payload = multi_gomoku::encode_app_state(&app_state);
+    for (sig, player) in state_proof.sigs.iter().zip(players.iter()) {
+        assert!(sig.verify(&payload[..], player));
+    }
+}
+
+#[test]
+fn test_pass_board_state_from_game_record() {
+    let record = single_gomoku::GameRecord::parse("h8 i9 h9").unwrap();
+
+    // player 2 plays black
+    let board_state = single_gomoku::board_state_from_record(&record, 2).unwrap();
+    assert_eq!(board_state.len(), 227);
+    assert_eq!(board_state[0], 0);
+    assert_eq!(board_state[1], 1);
+    assert_eq!(board_state[2 + 15 * 7 + 7], 2);
+    assert_eq!(board_state[2 + 15 * 6 + 8], 1);
+    assert_eq!(board_state[2 + 15 * 6 + 7], 2);
+
+    let board_state = multi_gomoku::board_state_from_record(&record, 2).unwrap();
+    assert_eq!(board_state.len(), 228);
+    assert_eq!(&board_state[..3], &[0, 2, 2]);
+    assert_eq!(board_state[3 + 15 * 7 + 7], 1);
+    assert_eq!(board_state[3 + 15 * 6 + 8], 2);
+}
+
+#[test]
+fn test_pass_export_game_record_with_move_history() {
+    let offchain_moves = single_gomoku::GameRecord::parse("h8 a1 i8 a2 j8 a3").unwrap().moves;
+    let move_history: Vec<single_gomoku::MoveRecord<u64>> = vec![
+        single_gomoku::MoveRecord { player_id: 1, x: 7, y: 10, block_number: 3, seq_num: 4 },
+        single_gomoku::MoveRecord { player_id: 2, x: 11, y: 0, block_number: 4, seq_num: 5 },
+        single_gomoku::MoveRecord { player_id: 1, x: 7, y: 11, block_number: 5, seq_num: 6 },
+    ];
+
+    let record = single_gomoku::game_record(&offchain_moves, &move_history).unwrap();
+    assert_eq!(record.to_move_list(), "h8 a1 i8 a2 j8 a3 k8 a4 l8");
+    assert_eq!(
+        record.to_rif().unwrap(),
+        "<game bresult=\"1\"><move>h8 a1 i8 a2 j8 a3 k8 a4 l8</move></game>"
+    );
+}
+
+#[test]
+fn test_pass_sign_session_key_delegation() {
+    let (players, pairs) = get_sorted_pairs("//Alice", "//Bob");
+    let session_key = pair_from_uri::<sr25519::Pair>("//Carl").unwrap().public();
+    let app_id: H256 = single_session_app::get_app_id::<_, BlakeTwo256>(0, &players);
+
+    let delegation = single_session_app::session_key_delegation(app_id, players[0], session_key, 10u64);
+    let sig: sr25519::Signature = single_session_app::sign_session_key_delegation(&delegation, &pairs[0]);
+
+    let mut expected = b"capps:session_key".to_vec();
+    expected.extend((app_id, players[0], session_key, 10u64).encode());
+    assert_eq!(delegation.signing_payload(), expected);
+    assert!(sig.verify(&expected[..], &players[0]));
+}
+
+#[test]
+fn test_pass_sign_key_rotation() {
+    let (players, pairs) = get_sorted_pairs("//Alice", "//Bob");
+    let new_player = pair_from_uri::<sr25519::Pair>("//Carl").unwrap().public();
+    let session_id: H256 = multi_session_app::get_session_id::<_, BlakeTwo256>(0, &players);
+
+    let rotation = multi_session_app::key_rotation(session_id, players[0], new_player, 0);
+    let sig: sr25519::Signature = multi_session_app::sign_key_rotation(&rotation, &pairs[0]);
+
+    let mut expected = b"capps:key_rotation".to_vec();
+    expected.extend((session_id, players[0], new_player, 0u32).encode());
+    assert_eq!(rotation.signing_payload(), expected);
+    assert!(sig.verify(&expected[..], &players[0]));
+}
+
+#[test]
+fn test_pass_seed_commitment() {
+    let (players, _) = get_sorted_pairs("//Alice", "//Bob");
+    let app_id: H256 = multi_gomoku::get_app_id::<_, BlakeTwo256>(0, &players);
+    let secrets = vec![H256::from_low_u64_be(1), H256::from_low_u64_be(2)];
+
+    let commitment = multi_gomoku::seed_commitment::<_, BlakeTwo256>(&app_id, &players[0], &secrets[0]);
+    assert_eq!(commitment, BlakeTwo256::hash(&(app_id, players[0], secrets[0]).encode()));
+
+    let seed = multi_gomoku::combined_seed::<BlakeTwo256>(&secrets);
+    assert_eq!(seed, BlakeTwo256::hash(&secrets.encode()));
+    let black_id = multi_gomoku::seeded_black_id(seed.as_ref());
+    assert_eq!(black_id, seed.as_ref()[0] % 2 + 1);
+    assert_eq!(single_gomoku::first_mover(seed.as_ref()), black_id);
+
+    let request = single_gomoku::AppInitiateRequestBuilder::new(0, players.clone(), 2u64)
+        .seed_timeout(5)
+        .build();
+    assert_eq!(request.seed_timeout, Some(5));
+}
+
+#[test]
+fn test_pass_sign_relayed_action() {
+    let (players, pairs) = get_sorted_pairs("//Alice", "//Bob");
+    let app_id: H256 = single_gomoku::get_app_id::<_, BlakeTwo256>(0, &players);
+
+    let sig: sr25519::Signature = single_gomoku::sign_relayed_action(&app_id, 1, &[3, 12], &pairs[1]);
+
+    let mut expected = b"capps:action".to_vec();
+    expected.extend((app_id, 1u128, vec![3u8, 12]).encode());
+    assert!(sig.verify(&expected[..], &players[0]));
+}
+
+#[test]
+fn test_pass_sign_move() {
+    let (players, pairs) = get_sorted_pairs("//Alice", "//Bob");
+    let app_id: H256 = multi_gomoku::get_app_id::<_, BlakeTwo256>(0, &players);
+
+    let signed_move: multi_gomoku::SignedMove<sr25519::Signature> =
+        multi_gomoku::sign_move(&app_id, 3, vec![6, 12], &pairs[0]);
+
+    let mut expected = b"capps:move".to_vec();
+    expected.extend((app_id, 3u128, vec![6u8, 12]).encode());
+    assert_eq!(signed_move.action, vec![6, 12]);
+    assert!(signed_move.sig.verify(&expected[..], &players[0]));
+}
+
+#[test]
+fn test_pass_types_bundle_is_up_to_date() {
+    let bundle = types::types_bundle();
+    assert!(bundle.contains("\"board_state\": \"Option<SingleGomokuBoardState>\""));
+    assert!(bundle.contains("\"MultiGomokuBoard\": \"[u32; 15]\""));
+    assert!(bundle.contains("\"StateProofOf\": \"MultiSessionAppStateProofOf\""));
+    for (_, prefix, defs) in types::PALLETS.iter() {
+        for def in defs.iter() {
+            if let Some(encodes_in_order) = def.encodes_in_order {
+                assert!(encodes_in_order(), "fields of {}{} are not listed in encoding order", prefix, def.name);
+            }
+        }
+    }
+    assert!(
+        bundle == include_str!("../../types.json"),
+        "types.json is stale, regenerate it with `cargo run -p capps-cli -- types > types.json`"
+    );
+}
@@ -0,0 +1,136 @@
+//! polkadot.js type definitions of every pallet
+//!
+//! The pallets share type names such as `AppState` with different layouts,
+//! so every type is defined under a name prefixed by its pallet, as in
+//! `SingleGomokuAppState`, and `typesAlias` maps the names each pallet uses
+//! in its metadata to the prefixed ones. Alias keys assume the pallets are
+//! named `SingleSessionApp`, `MultiSessionApp`, `SingleGomoku` and
+//! `MultiGomoku` in the runtime. The bundle is checked in as `types.json` at
+//! the root of the repository and is regenerated by `capps types`.
+
+use capps_type_defs::{TypeDef, TypeKind};
+
+/// Module name, type name prefix and types of every pallet
+pub(crate) const PALLETS: [(&str, &str, &[TypeDef]); 4] = [
+    ("singleSessionApp", "SingleSessionApp", ::single_session_app::types::TYPE_DEFS),
+    ("multiSessionApp", "MultiSessionApp", ::multi_session_app::types::TYPE_DEFS),
+    ("singleGomoku", "SingleGomoku", ::single_gomoku::types::TYPE_DEFS),
+    ("multiGomoku", "MultiGomoku", ::multi_gomoku::types::TYPE_DEFS),
+];
+
+/// Generic types polkadot.js knows, kept with their converted parameter
+const WRAPPERS: [&str; 3] = ["Option", "Vec", "Compact"];
+
+enum Json {
+    Str(String),
+    Num(u8),
+    Array(Vec<Json>),
+    Object(Vec<(String, Json)>),
+}
+
+impl Json {
+    fn render(&self, indent: usize, out: &mut String) {
+        let pad = "  ".repeat(indent + 1);
+        match self {
+            Json::Str(s) => out.push_str(&format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""))),
+            Json::Num(n) => out.push_str(&n.to_string()),
+            Json::Array(items) => {
+                out.push_str("[\n");
+                for (index, item) in items.iter().enumerate() {
+                    out.push_str(&pad);
+                    item.render(indent + 1, out);
+                    out.push_str(if index + 1 < items.len() { ",\n" } else { "\n" });
+                }
+                out.push_str(&format!("{}]", "  ".repeat(indent)));
+            },
+            Json::Object(entries) => {
+                out.push_str("{\n");
+                for (index, (key, value)) in entries.iter().enumerate() {
+                    out.push_str(&format!("{}\"{}\": ", pad, key));
+                    value.render(indent + 1, out);
+                    out.push_str(if index + 1 < entries.len() { ",\n" } else { "\n" });
+                }
+                out.push_str(&format!("{}}}", "  ".repeat(indent)));
+            },
+        }
+    }
+}
+
+/// Render the types bundle of every pallet as polkadot.js `types` and `typesAlias`
+pub fn types_bundle() -> String {
+    let mut types = vec![];
+    let mut aliases = vec![];
+    for (module, prefix, defs) in PALLETS.iter() {
+        let mut module_aliases = vec![];
+        for def in defs.iter() {
+            let name = format!("{}{}", prefix, def.name);
+            types.push((name.clone(), type_json(def, prefix, defs)));
+            module_aliases.push((def.name.to_string(), Json::Str(name)));
+        }
+        aliases.push((module.to_string(), Json::Object(module_aliases)));
+    }
+
+    let bundle = Json::Object(vec![
+        ("types".to_string(), Json::Object(types)),
+        ("typesAlias".to_string(), Json::Object(aliases)),
+    ]);
+    let mut out = String::new();
+    bundle.render(0, &mut out);
+    out.push('\n');
+
+    out
+}
+
+/// Definition of a type in polkadot.js form
+fn type_json(def: &TypeDef, prefix: &str, defs: &[TypeDef]) -> Json {
+    let js = |rust_type: &str| Json::Str(js_type(rust_type, prefix, defs));
+    match def.kind {
+        TypeKind::Struct(fields) => Json::Object(
+            fields.iter().map(|(name, ty)| (name.to_string(), js(ty))).collect()
+        ),
+        TypeKind::Enum(variants) => {
+            // variants indexed from 0 are listed, others are given their index
+            let indexed = variants.iter().enumerate().all(|(index, (_, value))| index == *value as usize);
+            let variants = if indexed {
+                Json::Array(variants.iter().map(|(name, _)| Json::Str(name.to_string())).collect())
+            } else {
+                Json::Object(variants.iter().map(|(name, value)| (name.to_string(), Json::Num(*value))).collect())
+            };
+            Json::Object(vec![("_enum".to_string(), variants)])
+        },
+        TypeKind::DataEnum(variants) => Json::Object(vec![(
+            "_enum".to_string(),
+            Json::Object(variants.iter().map(|(name, ty)| (name.to_string(), js(ty))).collect()),
+        )]),
+        TypeKind::Alias(target) => js(target),
+    }
+}
+
+/// Convert a Rust type to polkadot.js, dropping generic parameters of
+/// pallet types and prefixing them
+///
+/// Parameters:
+/// `rust_type`: Type as written in Rust
+/// `prefix`: Type name prefix of the pallet
+/// `defs`: Types of the pallet
+fn js_type(rust_type: &str, prefix: &str, defs: &[TypeDef]) -> String {
+    let ty: String = rust_type.chars().filter(|c| !c.is_whitespace()).collect();
+    if ty.starts_with('[') && ty.ends_with(']') {
+        if let Some(semicolon) = ty.rfind(';') {
+            let element = js_type(&ty[1..semicolon], prefix, defs);
+            return format!("[{}; {}]", element, &ty[semicolon + 1..ty.len() - 1]);
+        }
+    }
+
+    let (name, parameter) = match ty.find('<') {
+        Some(start) if ty.ends_with('>') => (&ty[..start], Some(&ty[start + 1..ty.len() - 1])),
+        _ => (&ty[..], None),
+    };
+    match parameter {
+        Some(parameter) if WRAPPERS.contains(&name) => {
+            format!("{}<{}>", name, js_type(parameter, prefix, defs))
+        },
+        _ if defs.iter().any(|def| def.name == name) => format!("{}{}", prefix, name),
+        _ => name.to_string(),
+    }
+}
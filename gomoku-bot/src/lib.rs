@@ -0,0 +1,178 @@
+//! Heuristic gomoku players for automated end-to-end play
+//!
+//! Bots read boards in the byte layout of the pallets, 15*15 slots indexed by
+//! `15 * x + y` holding 0 (empty), 1 or 2, and pick moves by the threats a
+//! stone makes and blocks. Randomness comes from a seeded `Rng`, so every
+//! game can be replayed from its seed. The `referee` module judges games
+//! independently of `gomoku_engine`, for checking pallet end states.
+
+mod rng;
+pub mod referee;
+
+#[cfg(test)]
+mod tests;
+
+use gomoku_engine::{Move, BOARD_DIMENSION, SLOT_NUM};
+
+pub use rng::Rng;
+
+// scores of the line a stone makes, by length and number of open ends
+const FIVE: u32 = 1_000_000;
+const OPEN_FOUR: u32 = 100_000;
+const FOUR: u32 = 10_000;
+const OPEN_THREE: u32 = 5_000;
+const THREE: u32 = 500;
+const OPEN_TWO: u32 = 200;
+const TWO: u32 = 50;
+const ONE: u32 = 10;
+
+// (xdir, ydir) of horizontal, vertical, main-diagonal and anti-diagonal lines
+const DIRECTIONS: [(i8, i8); 4] = [(1, 0), (0, 1), (1, 1), (1, -1)];
+
+/// Threat-based gomoku player of one stone
+pub struct Bot {
+    stone: u8,
+    noise: u32,
+    rng: Rng,
+}
+
+impl Bot {
+    /// Create a bot playing `stone` that picks one of its best moves at random
+    ///
+    /// Parameters:
+    /// `stone`: Stone of the bot, 1 or 2
+    /// `seed`: Seed of the random choices
+    pub fn new(stone: u8, seed: u64) -> Bot {
+        Bot {
+            stone: stone,
+            noise: 0,
+            rng: Rng::new(seed),
+        }
+    }
+
+    /// Play one of the three best moves instead of the best in `percent` of moves
+    pub fn with_noise(mut self, percent: u32) -> Bot {
+        self.noise = percent;
+        self
+    }
+
+    /// Stone of the bot
+    pub fn stone(&self) -> u8 {
+        self.stone
+    }
+
+    /// Pick the next move on a board
+    ///
+    /// Returns `None` if the board is full or not 15*15 slots.
+    ///
+    /// Parameter:
+    /// `slots`: 15*15 slots indexed by `15 * x + y`
+    pub fn next_move(&mut self, slots: &[u8]) -> Option<Move> {
+        if slots.len() != SLOT_NUM {
+            return None;
+        }
+        let mut candidates: Vec<(u32, Move)> = candidates(slots)
+            .into_iter()
+            .map(|mv| (self.score(slots, mv), mv))
+            .collect();
+        if candidates.is_empty() {
+            return None;
+        }
+        // best first, ties in board order
+        candidates.sort_by(|a, b| b.0.cmp(&a.0));
+
+        let best = candidates[0].0;
+        let pool = if self.rng.chance(self.noise, 100) && best < FOUR {
+            candidates.len().min(3)
+        } else {
+            candidates.iter().take_while(|(score, _)| *score == best).count()
+        };
+
+        Some(candidates[self.rng.below(pool as u64) as usize].1)
+    }
+
+    /// Score a move by the lines it makes for the bot and blocks for the opponent
+    fn score(&self, slots: &[u8], mv: Move) -> u32 {
+        let attack = stone_score(slots, mv, self.stone);
+        let defense = stone_score(slots, mv, 3 - self.stone);
+
+        attack + defense / 10 * 9
+    }
+}
+
+/// Empty slots within two slots of a stone, or the center of an empty board
+fn candidates(slots: &[u8]) -> Vec<Move> {
+    let mut moves = vec![];
+    for x in 0..BOARD_DIMENSION {
+        for y in 0..BOARD_DIMENSION {
+            if slot(slots, x as i8, y as i8) == Some(0) && has_neighbor(slots, x, y) {
+                moves.push(Move::new(x, y));
+            }
+        }
+    }
+    if moves.is_empty() && slots.iter().all(|slot| *slot == 0) {
+        moves.push(Move::new(BOARD_DIMENSION / 2, BOARD_DIMENSION / 2));
+    } else if moves.is_empty() {
+        // only far away slots are left
+        moves = (0..SLOT_NUM)
+            .filter(|index| slots[*index] == 0)
+            .map(|index| Move::new((index / 15) as u8, (index % 15) as u8))
+            .collect();
+    }
+
+    moves
+}
+
+fn has_neighbor(slots: &[u8], x: u8, y: u8) -> bool {
+    (-2..=2i8).any(|dx| {
+        (-2..=2i8).any(|dy| match slot(slots, x as i8 + dx, y as i8 + dy) {
+            Some(stone) => stone != 0,
+            None => false,
+        })
+    })
+}
+
+/// Sum of the scores of the lines a stone at `mv` would be part of
+fn stone_score(slots: &[u8], mv: Move, stone: u8) -> u32 {
+    DIRECTIONS.iter()
+        .map(|(xdir, ydir)| {
+            let (forward, forward_open) = run(slots, mv, *xdir, *ydir, stone);
+            let (backward, backward_open) = run(slots, mv, -xdir, -ydir, stone);
+            let open_ends = forward_open as u8 + backward_open as u8;
+            match (1 + forward + backward, open_ends) {
+                (len, _) if len >= 5 => FIVE,
+                (_, 0) => 0,
+                (4, 2) => OPEN_FOUR,
+                (4, _) => FOUR,
+                (3, 2) => OPEN_THREE,
+                (3, _) => THREE,
+                (2, 2) => OPEN_TWO,
+                (2, _) => TWO,
+                _ => ONE,
+            }
+        })
+        .sum()
+}
+
+/// Count stones from `mv` in a direction and check if the slot after them is empty
+fn run(slots: &[u8], mv: Move, xdir: i8, ydir: i8, stone: u8) -> (u8, bool) {
+    let mut count = 0;
+    loop {
+        let x = mv.x as i8 + xdir * (count + 1);
+        let y = mv.y as i8 + ydir * (count + 1);
+        match slot(slots, x, y) {
+            Some(value) if value == stone => count += 1,
+            Some(0) => return (count as u8, true),
+            _ => return (count as u8, false),
+        }
+    }
+}
+
+fn slot(slots: &[u8], x: i8, y: i8) -> Option<u8> {
+    let dimension = BOARD_DIMENSION as i8;
+    if x < 0 || y < 0 || x >= dimension || y >= dimension {
+        return None;
+    }
+
+    Some(slots[x as usize * 15 + y as usize])
+}
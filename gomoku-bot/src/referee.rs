@@ -0,0 +1,46 @@
+//! Gomoku rules written independently of `gomoku_engine`
+//!
+//! Scans the byte layout directly, slot by slot, so a bug shared with the
+//! packed board of the engine does not go unnoticed when the two disagree.
+
+/// Stones with five or more in a row anywhere on the board, in ascending order
+///
+/// Parameter:
+/// `slots`: 15*15 slots indexed by `15 * x + y`
+pub fn five_in_a_row(slots: &[u8]) -> Vec<u8> {
+    let mut stones = vec![];
+    for stone in 1..=2 {
+        if has_five(slots, stone) {
+            stones.push(stone);
+        }
+    }
+
+    stones
+}
+
+/// Check if every slot has a stone
+pub fn is_full(slots: &[u8]) -> bool {
+    slots.iter().all(|slot| *slot != 0)
+}
+
+/// Count the stones of a player or color
+pub fn stone_num(slots: &[u8], stone: u8) -> usize {
+    slots.iter().filter(|slot| **slot == stone).count()
+}
+
+fn has_five(slots: &[u8], stone: u8) -> bool {
+    let at = |x: i32, y: i32| -> bool {
+        x >= 0 && y >= 0 && x < 15 && y < 15 && slots[(x * 15 + y) as usize] == stone
+    };
+    for x in 0..15 {
+        for y in 0..15 {
+            for (dx, dy) in &[(0, 1), (1, 0), (1, 1), (1, -1)] {
+                if (0..5).all(|i| at(x + dx * i, y + dy * i)) {
+                    return true;
+                }
+            }
+        }
+    }
+
+    false
+}
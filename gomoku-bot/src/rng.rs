@@ -0,0 +1,40 @@
+/// Seeded xorshift64* generator, the same seed always gives the same numbers
+#[derive(Clone, Debug)]
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    /// Create a generator from a seed, any seed including 0 is valid
+    pub fn new(seed: u64) -> Rng {
+        // splitmix64 step, so close seeds give unrelated sequences
+        let mut z = seed.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^= z >> 31;
+
+        Rng { state: if z == 0 { 1 } else { z } }
+    }
+
+    /// Next random number
+    pub fn next_u64(&mut self) -> u64 {
+        self.state ^= self.state >> 12;
+        self.state ^= self.state << 25;
+        self.state ^= self.state >> 27;
+        self.state.wrapping_mul(0x2545_f491_4f6c_dd1d)
+    }
+
+    /// Random number below `bound`, 0 if `bound` is 0
+    pub fn below(&mut self, bound: u64) -> u64 {
+        if bound == 0 {
+            return 0;
+        }
+
+        self.next_u64() % bound
+    }
+
+    /// Whether an event of probability `numerator / denominator` happens
+    pub fn chance(&mut self, numerator: u32, denominator: u32) -> bool {
+        self.below(denominator as u64) < numerator as u64
+    }
+}
@@ -0,0 +1,79 @@
+use super::*;
+
+fn slots_with(stones: &[(u8, u8, u8)]) -> Vec<u8> {
+    let mut slots = vec![0; SLOT_NUM];
+    for (x, y, stone) in stones {
+        slots[*x as usize * 15 + *y as usize] = *stone;
+    }
+
+    return slots;
+}
+
+/// Play two bots from the empty board until a five or a full board
+fn play_game(seed: u64) -> (Vec<Move>, Vec<u8>) {
+    let mut bots = [Bot::new(1, seed).with_noise(20), Bot::new(2, seed + 1).with_noise(20)];
+    let mut slots = vec![0; SLOT_NUM];
+    let mut moves = vec![];
+    let mut stone = 1;
+    while referee::five_in_a_row(&slots).is_empty() && !referee::is_full(&slots) {
+        let mv = bots[stone as usize - 1].next_move(&slots).unwrap();
+        assert_eq!(slots[mv.x as usize * 15 + mv.y as usize], 0);
+        slots[mv.x as usize * 15 + mv.y as usize] = stone;
+        moves.push(mv);
+        stone = 3 - stone;
+    }
+
+    return (moves, slots);
+}
+
+#[test]
+fn test_pass_bot_completes_five() {
+    let slots = slots_with(&[
+        (7, 3, 1), (7, 4, 1), (7, 5, 1), (7, 6, 1),
+        (3, 3, 2), (3, 4, 2), (3, 5, 2), (3, 6, 2),
+    ]);
+    let mv = Bot::new(1, 0).next_move(&slots).unwrap();
+    assert!(mv == Move::new(7, 2) || mv == Move::new(7, 7));
+}
+
+#[test]
+fn test_pass_bot_blocks_four_and_open_three() {
+    let slots = slots_with(&[(2, 2, 2), (3, 3, 2), (4, 4, 2), (5, 5, 2), (6, 6, 1), (9, 9, 1)]);
+    assert_eq!(Bot::new(1, 0).next_move(&slots), Some(Move::new(1, 1)));
+
+    let slots = slots_with(&[(7, 6, 2), (7, 7, 2), (7, 8, 2), (8, 8, 1)]);
+    let mv = Bot::new(1, 0).next_move(&slots).unwrap();
+    assert!(mv == Move::new(7, 5) || mv == Move::new(7, 9));
+}
+
+#[test]
+fn test_pass_bots_play_reproducible_games() {
+    for seed in 0..20 {
+        let (moves, slots) = play_game(seed);
+        assert_eq!(play_game(seed).0, moves);
+        let winners = referee::five_in_a_row(&slots);
+        assert!(winners.len() == 1 || referee::is_full(&slots));
+        // the last stone decides the game
+        if let Some(winner) = winners.first() {
+            assert_eq!(*winner, 2 - (moves.len() % 2) as u8);
+        }
+    }
+    assert!(play_game(0).0 != play_game(1).0);
+    assert_eq!(Bot::new(1, 0).next_move(&vec![1; SLOT_NUM]), None);
+}
+
+#[test]
+fn test_pass_referee_finds_five_in_every_direction() {
+    let lines: [[(u8, u8); 5]; 4] = [
+        [(0, 10), (0, 11), (0, 12), (0, 13), (0, 14)],
+        [(10, 0), (11, 0), (12, 0), (13, 0), (14, 0)],
+        [(10, 10), (11, 11), (12, 12), (13, 13), (14, 14)],
+        [(10, 4), (11, 3), (12, 2), (13, 1), (14, 0)],
+    ];
+    for line in lines.iter() {
+        let stones: Vec<(u8, u8, u8)> = line.iter().map(|(x, y)| (*x, *y, 2)).collect();
+        assert_eq!(referee::five_in_a_row(&slots_with(&stones)), vec![2]);
+        assert!(referee::five_in_a_row(&slots_with(&stones[1..])).is_empty());
+    }
+    assert_eq!(referee::stone_num(&slots_with(&[(0, 0, 1), (1, 1, 2), (2, 2, 1)]), 1), 2);
+}
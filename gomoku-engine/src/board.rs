@@ -0,0 +1,161 @@
+use codec::{Decode, Encode};
+use sp_std::{prelude::*, vec::Vec};
+use crate::{opponent, Move, MoveError, Outcome};
+
+/// Board dimension is 15*15
+pub const BOARD_DIMENSION: u8 = 15;
+
+/// Number of slots on the board
+pub const SLOT_NUM: usize = 225;
+
+// low bit of each 2-bit slot in a row
+const LOW_BITS: u32 = 0x1555_5555;
+
+// (xdir, ydir) of horizontal, vertical, main-diagonal and anti-diagonal lines
+const DIRECTIONS: [(i8, i8); 4] = [(1, 0), (0, 1), (1, 1), (1, -1)];
+
+/// 15*15 gomoku board packed into 2 bits per slot
+///
+/// Slot (x, y) is stored in bits `2y` and `2y + 1` of `rows[x]` and holds
+/// 0 (empty), 1 or 2 (stone of player or color 1 or 2).
+#[derive(Clone, Copy, Default, Eq, PartialEq, PartialOrd, Ord, Encode, Decode)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct Board {
+    rows: [u32; 15],
+}
+
+impl Board {
+    /// Decode a board from 15*15 slots indexed by `15 * x + y`
+    ///
+    /// Returns `None` if the length is invalid or a slot is not 0, 1 or 2.
+    pub fn from_slots(slots: &[u8]) -> Option<Board> {
+        if slots.len() != SLOT_NUM {
+            return None;
+        }
+        let mut board = Board::default();
+        for (index, slot) in slots.iter().enumerate() {
+            if *slot > 2 {
+                return None;
+            }
+            board.rows[index / 15] |= (*slot as u32) << (2 * (index % 15));
+        }
+
+        Some(board)
+    }
+
+    /// Append 15*15 slots indexed by `15 * x + y` to `encoded`
+    pub fn extend_slots(&self, encoded: &mut Vec<u8>) {
+        for x in 0..BOARD_DIMENSION {
+            for y in 0..BOARD_DIMENSION {
+                encoded.push(self.get(x, y));
+            }
+        }
+    }
+
+    /// Check if coordinate (x, y) is on the board
+    pub fn check_boundary(x: u8, y: u8) -> bool {
+        x < BOARD_DIMENSION && y < BOARD_DIMENSION
+    }
+
+    /// Get the slot value at (x, y)
+    pub fn get(&self, x: u8, y: u8) -> u8 {
+        ((self.rows[x as usize] >> (2 * y as u32)) & 0b11) as u8
+    }
+
+    /// Set the slot value at (x, y)
+    pub fn set(&mut self, x: u8, y: u8, stone: u8) {
+        let shift = 2 * y as u32;
+        let row = &mut self.rows[x as usize];
+        *row = (*row & !(0b11 << shift)) | ((stone as u32 & 0b11) << shift);
+    }
+
+    /// Place a stone and decide the outcome of the game
+    ///
+    /// The game is won by five or more in a row including the new stone and
+    /// drawn once every slot is occupied. Otherwise the opponent is in turn.
+    pub fn play(&mut self, mv: Move, stone: u8) -> Result<Outcome, MoveError> {
+        if stone != 1 && stone != 2 {
+            return Err(MoveError::InvalidStone);
+        }
+        if !Self::check_boundary(mv.x, mv.y) {
+            return Err(MoveError::OutOfBoundary);
+        }
+        if self.get(mv.x, mv.y) != 0 {
+            return Err(MoveError::Occupied);
+        }
+
+        self.set(mv.x, mv.y, stone);
+        if self.check_five(mv.x, mv.y) {
+            Ok(Outcome::Win(stone))
+        } else if self.stone_num(1) + self.stone_num(2) == SLOT_NUM as u16 {
+            Ok(Outcome::Draw)
+        } else {
+            Ok(Outcome::InProgress(opponent(stone)))
+        }
+    }
+
+    /// Count the stones of a player or color
+    pub fn stone_num(&self, stone: u8) -> u16 {
+        self.rows.iter()
+            .map(|row| Self::stone_mask(*row, stone).count_ones() as u16)
+            .sum()
+    }
+
+    /// Check if the stone at (x, y) is part of five or more in a row
+    pub fn check_five(&self, x: u8, y: u8) -> bool {
+        let stone = self.get(x, y);
+        if stone == 0 {
+            return false;
+        }
+        DIRECTIONS.iter().any(|(xdir, ydir)| {
+            // the stone at (x, y) is counted in both directions
+            self.count_stone(x, y, *xdir, *ydir, stone)
+                + self.count_stone(x, y, -xdir, -ydir, stone) > 5
+        })
+    }
+
+    /// Check if a player or color has five in a row anywhere on the board
+    pub fn has_five(&self, stone: u8) -> bool {
+        let mut masks = [0u32; 15];
+        for (mask, row) in masks.iter_mut().zip(self.rows.iter()) {
+            *mask = Self::stone_mask(*row, stone);
+        }
+
+        // horizontal, one row at a time
+        if masks.iter().any(|m| m & (m >> 2) & (m >> 4) & (m >> 6) & (m >> 8) != 0) {
+            return true;
+        }
+        // vertical and diagonals, five consecutive rows at a time
+        masks.windows(5).any(|m| {
+            m[0] & m[1] & m[2] & m[3] & m[4] != 0
+                || m[0] & (m[1] >> 2) & (m[2] >> 4) & (m[3] >> 6) & (m[4] >> 8) != 0
+                || m[0] & (m[1] << 2) & (m[2] << 4) & (m[3] << 6) & (m[4] << 8) != 0
+        })
+    }
+
+    /// Count consecutive stones from (x, y) in a given direction, at most 5
+    fn count_stone(&self, x: u8, y: u8, xdir: i8, ydir: i8, stone: u8) -> u8 {
+        let mut count: u8 = 1;
+        while count < 5 {
+            let next_x = (x as i8 + xdir * count as i8) as u8;
+            let next_y = (y as i8 + ydir * count as i8) as u8;
+            if !Self::check_boundary(next_x, next_y) || self.get(next_x, next_y) != stone {
+                break;
+            }
+            count += 1;
+        }
+
+        count
+    }
+
+    /// Mask of the low bit of every slot holding `stone` in a packed row
+    fn stone_mask(row: u32, stone: u8) -> u32 {
+        let low = row & LOW_BITS;
+        let high = (row >> 1) & LOW_BITS;
+        match stone {
+            1 => low & !high,
+            2 => high & !low,
+            _ => 0,
+        }
+    }
+}
@@ -0,0 +1,130 @@
+//! Gomoku rules shared by the gomoku pallets and off-chain clients
+//!
+//! Players or colors are identified by stone values 1 and 2, and 0 marks an
+//! empty slot. Slot (x, y) of the 15*15 board is slot `15 * x + y` in the
+//! byte layout of off-chain board states.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+mod board;
+#[cfg(feature = "std")]
+mod record;
+#[cfg(feature = "std")]
+mod text;
+
+#[cfg(test)]
+mod tests;
+
+use codec::{Decode, Encode};
+
+pub use board::{Board, BOARD_DIMENSION, SLOT_NUM};
+#[cfg(feature = "std")]
+pub use record::{format_coordinate, parse_coordinate, GameRecord, RecordError};
+#[cfg(feature = "std")]
+pub use text::{parse_board_state, render_board, render_board_state, TextError};
+
+/// Coordinate of a stone to place
+#[derive(Clone, Copy, Eq, PartialEq, PartialOrd, Ord, Encode, Decode)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct Move {
+    pub x: u8,
+    pub y: u8,
+}
+
+impl Move {
+    /// Create a move at (x, y)
+    pub fn new(x: u8, y: u8) -> Move {
+        Move { x: x, y: y }
+    }
+
+    /// Decode a move from on-chain action data `[x, y]`
+    pub fn from_action(action: &[u8]) -> Option<Move> {
+        if action.len() != 2 {
+            return None;
+        }
+
+        Some(Move::new(action[0], action[1]))
+    }
+}
+
+/// Result of placing a stone
+#[derive(Clone, Copy, Eq, PartialEq, PartialOrd, Ord, Encode, Decode)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub enum Outcome {
+    /// Game goes on with the stone in turn
+    InProgress(u8),
+    /// Game is won by the stone
+    Win(u8),
+    /// Game is over with no winner
+    Draw,
+}
+
+impl Outcome {
+    /// End a game in progress in a draw once more than `max_stone_num` stones are placed
+    ///
+    /// Parameters:
+    /// `stone_num`: Number of stones placed
+    /// `max_stone_num`: Maximal number of stones to place
+    pub fn limit(self, stone_num: u16, max_stone_num: u16) -> Outcome {
+        match self {
+            Outcome::InProgress(_) if stone_num > max_stone_num => Outcome::Draw,
+            _ => self,
+        }
+    }
+
+    /// Record the outcome in the winner and turn of a board state, returning
+    /// whether the game is over
+    ///
+    /// Parameters:
+    /// `winner`: Winner stone, set once the game is won
+    /// `turn`: Stone in turn, 0 once the game is over
+    pub fn record(self, winner: &mut u8, turn: &mut u8) -> bool {
+        match self {
+            Outcome::InProgress(next) => {
+                *turn = next;
+                false
+            },
+            Outcome::Win(stone) => {
+                *winner = stone;
+                *turn = 0;
+                true
+            },
+            Outcome::Draw => {
+                *turn = 0;
+                true
+            },
+        }
+    }
+}
+
+/// Reason a stone can not be placed
+#[derive(Clone, Copy, Eq, PartialEq, PartialOrd, Ord, Encode, Decode)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub enum MoveError {
+    /// Stone is not 1 or 2
+    InvalidStone,
+    /// Coordinate is not on the board
+    OutOfBoundary,
+    /// Slot already has a stone
+    Occupied,
+}
+
+impl MoveError {
+    /// Error message used by the pallets
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            MoveError::InvalidStone => "invalid stone",
+            MoveError::OutOfBoundary => "out of boundary",
+            MoveError::Occupied => "slot is occupied",
+        }
+    }
+}
+
+/// Get the stone of the opponent
+pub fn opponent(stone: u8) -> u8 {
+    if stone == 1 {
+        2
+    } else {
+        1
+    }
+}
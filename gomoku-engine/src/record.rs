@@ -0,0 +1,160 @@
+//! Standard gomoku game records
+//!
+//! Moves use the usual notation of a column `a` to `o` from left to right
+//! followed by a row `1` to `15` from bottom to top. Column is `y` and row
+//! 15 is `x = 0`, so `h8` is the center slot (7, 7) and `a15` is slot (0, 0).
+//! Black, stone 1, moves first and colors alternate.
+
+use std::fmt;
+
+use crate::{Board, Move, MoveError, Outcome, BOARD_DIMENSION};
+
+/// Reason a game record can not be read or replayed
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum RecordError {
+    /// Text is not a move in standard notation
+    InvalidCoordinate(String),
+    /// Move of the index can not be placed
+    InvalidMove(usize, MoveError),
+    /// Move of the index is played after the game is over
+    MoveAfterGameOver(usize),
+}
+
+impl fmt::Display for RecordError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RecordError::InvalidCoordinate(text) => write!(f, "invalid coordinate `{}`", text),
+            RecordError::InvalidMove(index, e) => write!(f, "move {}: {}", index + 1, e.as_str()),
+            RecordError::MoveAfterGameOver(index) => write!(f, "move {}: game is over", index + 1),
+        }
+    }
+}
+
+impl std::error::Error for RecordError {}
+
+/// Moves of a game from the empty board, black first
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct GameRecord {
+    pub moves: Vec<Move>,
+}
+
+impl GameRecord {
+    /// Create a record from moves
+    pub fn new(moves: Vec<Move>) -> GameRecord {
+        GameRecord { moves: moves }
+    }
+
+    /// Parse a record
+    ///
+    /// Accepts a move list such as `h8 i9 j10`, optionally numbered as in
+    /// `1. h8 2. i9`, the compact RenjuNet form `h8i9j10`, and RIF games
+    /// whose moves are in a `<move>` element.
+    ///
+    /// Parameter:
+    /// `text`: Game record
+    pub fn parse(text: &str) -> Result<GameRecord, RecordError> {
+        let text = match (text.find("<move>"), text.find("</move>")) {
+            (Some(start), Some(end)) if start < end => &text[start + "<move>".len()..end],
+            _ => text,
+        };
+
+        let chars: Vec<char> = text.chars().collect();
+        let mut moves = vec![];
+        let mut i = 0;
+        while i < chars.len() {
+            let c = chars[i];
+            if c.is_whitespace() || c == ',' || c == ';' || c == '.' {
+                i += 1;
+            } else if c.is_ascii_digit() {
+                // move number
+                while i < chars.len() && chars[i].is_ascii_digit() {
+                    i += 1;
+                }
+            } else if c.is_ascii_alphabetic() {
+                let start = i;
+                i += 1;
+                let mut row = 0u32;
+                let mut digits = 0;
+                while i < chars.len() && digits < 2 && chars[i].is_ascii_digit() {
+                    let next = row * 10 + chars[i].to_digit(10).unwrap_or(0);
+                    if digits > 0 && next > BOARD_DIMENSION as u32 {
+                        break;
+                    }
+                    row = next;
+                    digits += 1;
+                    i += 1;
+                }
+                let token: String = chars[start..i].iter().collect();
+                let mv = parse_coordinate(&token).ok_or(RecordError::InvalidCoordinate(token))?;
+                moves.push(mv);
+            } else {
+                return Err(RecordError::InvalidCoordinate(c.to_string()));
+            }
+        }
+
+        Ok(GameRecord::new(moves))
+    }
+
+    /// Replay the moves on the empty board
+    ///
+    /// Returns the board and the outcome after the last move. A game with no
+    /// moves is in progress with black in turn.
+    pub fn replay(&self) -> Result<(Board, Outcome), RecordError> {
+        let mut board = Board::default();
+        let mut outcome = Outcome::InProgress(1);
+        for (index, mv) in self.moves.iter().enumerate() {
+            let stone = match outcome {
+                Outcome::InProgress(stone) => stone,
+                _ => return Err(RecordError::MoveAfterGameOver(index)),
+            };
+            outcome = board.play(*mv, stone).map_err(|e| RecordError::InvalidMove(index, e))?;
+        }
+
+        Ok((board, outcome))
+    }
+
+    /// Write the moves as a space separated move list such as `h8 i9 j10`
+    pub fn to_move_list(&self) -> String {
+        self.moves.iter()
+            .map(|mv| format_coordinate(*mv))
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    /// Write the game as a RIF game element
+    ///
+    /// `bresult` is the result of black, 1, 0 or 0.5, and is left out while
+    /// the game is in progress.
+    pub fn to_rif(&self) -> Result<String, RecordError> {
+        let (_, outcome) = self.replay()?;
+        let result = match outcome {
+            Outcome::Win(1) => " bresult=\"1\"",
+            Outcome::Win(_) => " bresult=\"0\"",
+            Outcome::Draw => " bresult=\"0.5\"",
+            Outcome::InProgress(_) => "",
+        };
+
+        Ok(format!("<game{}><move>{}</move></game>", result, self.to_move_list()))
+    }
+}
+
+/// Parse a move in standard notation such as `h8`
+pub fn parse_coordinate(text: &str) -> Option<Move> {
+    let mut chars = text.chars();
+    let column = chars.next()?.to_ascii_lowercase();
+    if !column.is_ascii_lowercase() {
+        return None;
+    }
+    let row: u8 = chars.as_str().parse().ok()?;
+    let y = column as u8 - b'a';
+    if y >= BOARD_DIMENSION || row == 0 || row > BOARD_DIMENSION {
+        return None;
+    }
+
+    Some(Move::new(BOARD_DIMENSION - row, y))
+}
+
+/// Write a move in standard notation such as `h8`
+pub fn format_coordinate(mv: Move) -> String {
+    format!("{}{}", (b'a' + mv.y) as char, BOARD_DIMENSION - mv.x)
+}
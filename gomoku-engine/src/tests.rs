@@ -0,0 +1,217 @@
+use super::*;
+
+fn board_with(stones: &[(u8, u8, u8)]) -> Board {
+    let mut board = Board::default();
+    for (x, y, stone) in stones {
+        board.set(*x, *y, *stone);
+    }
+
+    return board;
+}
+
+#[test]
+fn test_pass_slots_round_trip() {
+    let mut slots = vec![0; SLOT_NUM];
+    slots[0] = 1;
+    slots[16] = 2;
+    slots[224] = 1;
+    let board = Board::from_slots(&slots).unwrap();
+    assert_eq!(board.get(0, 0), 1);
+    assert_eq!(board.get(1, 1), 2);
+    assert_eq!(board.get(14, 14), 1);
+    assert_eq!(board.stone_num(1), 2);
+    assert_eq!(board.stone_num(2), 1);
+
+    let mut encoded = vec![];
+    board.extend_slots(&mut encoded);
+    assert_eq!(encoded, slots);
+}
+
+#[test]
+fn test_fail_from_invalid_slots() {
+    assert_eq!(Board::from_slots(&vec![0; SLOT_NUM - 1]), None);
+    let mut slots = vec![0; SLOT_NUM];
+    slots[7] = 3;
+    assert_eq!(Board::from_slots(&slots), None);
+}
+
+#[test]
+fn test_pass_five_in_every_direction() {
+    let lines: [[(u8, u8); 5]; 4] = [
+        [(3, 10), (3, 11), (3, 12), (3, 13), (3, 14)], // along y
+        [(10, 3), (11, 3), (12, 3), (13, 3), (14, 3)], // along x
+        [(2, 2), (3, 3), (4, 4), (5, 5), (6, 6)], // main diagonal
+        [(0, 14), (1, 13), (2, 12), (3, 11), (4, 10)], // anti diagonal
+    ];
+    for line in lines.iter() {
+        let mut board = Board::default();
+        for index in [0, 1, 3, 4].iter() {
+            board.set(line[*index].0, line[*index].1, 2);
+        }
+        assert!(!board.has_five(2));
+
+        // the middle stone completes the line
+        assert_eq!(board.play(Move::new(line[2].0, line[2].1), 2), Ok(Outcome::Win(2)));
+        assert!(board.has_five(2));
+        assert!(!board.has_five(1));
+    }
+}
+
+#[test]
+fn test_pass_play_toggles_turn() {
+    let mut board = board_with(&[(7, 7, 1)]);
+    assert_eq!(board.play(Move::new(7, 8), 2), Ok(Outcome::InProgress(1)));
+    assert_eq!(board.play(Move::new(7, 8), 1), Err(MoveError::Occupied));
+    assert_eq!(board.play(Move::new(15, 0), 1), Err(MoveError::OutOfBoundary));
+    assert_eq!(board.play(Move::new(0, 0), 3), Err(MoveError::InvalidStone));
+    assert_eq!(board.stone_num(1) + board.stone_num(2), 2);
+}
+
+#[test]
+fn test_pass_draw_on_full_board_or_stone_limit() {
+    // fill the board without five in a row, leaving (14, 14) empty
+    let mut board = Board::default();
+    for x in 0..BOARD_DIMENSION {
+        for y in 0..BOARD_DIMENSION {
+            if (x, y) != (14, 14) {
+                board.set(x, y, if (y / 2 + x) % 2 == 0 { 1 } else { 2 });
+            }
+        }
+    }
+    assert!(!board.has_five(1) && !board.has_five(2));
+    assert_eq!(board.play(Move::new(14, 14), 2), Ok(Outcome::Draw));
+
+    assert_eq!(Outcome::InProgress(1).limit(6, 5), Outcome::Draw);
+    assert_eq!(Outcome::InProgress(1).limit(5, 5), Outcome::InProgress(1));
+    assert_eq!(Outcome::Win(2).limit(6, 5), Outcome::Win(2));
+}
+
+#[test]
+fn test_pass_record_outcome() {
+    let (mut winner, mut turn) = (0, 1);
+    assert!(!Outcome::InProgress(2).record(&mut winner, &mut turn));
+    assert_eq!((winner, turn), (0, 2));
+    assert!(Outcome::Win(2).record(&mut winner, &mut turn));
+    assert_eq!((winner, turn), (2, 0));
+
+    let (mut winner, mut turn) = (0, 1);
+    assert!(Outcome::Draw.record(&mut winner, &mut turn));
+    assert_eq!((winner, turn), (0, 0));
+}
+
+#[test]
+fn test_pass_move_from_action() {
+    assert_eq!(Move::from_action(&[3, 12]), Some(Move::new(3, 12)));
+    assert_eq!(Move::from_action(&[3]), None);
+}
+
+#[test]
+fn test_pass_coordinate_notation() {
+    assert_eq!(parse_coordinate("h8"), Some(Move::new(7, 7)));
+    assert_eq!(parse_coordinate("A15"), Some(Move::new(0, 0)));
+    assert_eq!(parse_coordinate("o1"), Some(Move::new(14, 14)));
+    assert_eq!(parse_coordinate("p1"), None);
+    assert_eq!(parse_coordinate("a16"), None);
+    assert_eq!(parse_coordinate("a0"), None);
+    assert_eq!(format_coordinate(Move::new(5, 9)), "j10");
+}
+
+#[test]
+fn test_pass_parse_record_forms() {
+    let expected = GameRecord::new(vec![Move::new(7, 7), Move::new(6, 8), Move::new(5, 9)]);
+    assert_eq!(GameRecord::parse("h8 i9 j10").unwrap(), expected);
+    assert_eq!(GameRecord::parse("1. h8 2. i9 3. j10").unwrap(), expected);
+    assert_eq!(GameRecord::parse("h8i9j10").unwrap(), expected);
+    assert_eq!(
+        GameRecord::parse("<game id=\"1\" bresult=\"1\"><move>h8 i9 j10</move></game>").unwrap(),
+        expected
+    );
+    assert_eq!(expected.to_move_list(), "h8 i9 j10");
+    assert_eq!(
+        GameRecord::parse("h8 z9"),
+        Err(RecordError::InvalidCoordinate("z9".to_string()))
+    );
+}
+
+#[test]
+fn test_pass_replay_record_and_export_rif() {
+    let record = GameRecord::parse("h8 a1 i8 a2 j8 a3 k8 a4").unwrap();
+    let (board, outcome) = record.replay().unwrap();
+    assert_eq!(outcome, Outcome::InProgress(1));
+    assert_eq!(board.get(7, 7), 1);
+    assert_eq!(board.get(14, 0), 2);
+    assert_eq!(record.to_rif().unwrap(), "<game><move>h8 a1 i8 a2 j8 a3 k8 a4</move></game>");
+
+    let record = GameRecord::parse("h8 a1 i8 a2 j8 a3 k8 a4 l8").unwrap();
+    assert_eq!(record.replay().unwrap().1, Outcome::Win(1));
+    assert_eq!(record.to_rif().unwrap(), "<game bresult=\"1\"><move>h8 a1 i8 a2 j8 a3 k8 a4 l8</move></game>");
+
+    let record = GameRecord::parse("h8 a1 i8 a2 j8 a3 k8 a4 l8 a5").unwrap();
+    assert_eq!(record.replay(), Err(RecordError::MoveAfterGameOver(9)));
+    let record = GameRecord::parse("h8 h8").unwrap();
+    assert_eq!(record.replay(), Err(RecordError::InvalidMove(1, MoveError::Occupied)));
+}
+
+#[test]
+fn test_pass_render_and_parse_single_board_state() {
+    let text = "\
+winner: 0
+turn: 2
+     0  1  2  3  4  5  6  7  8  9 10 11 12 13 14
+  0  X  X  X  X  .  .  .  .  .  .  .  .  .  .  .
+  1  .  .  .  .  .  .  .  .  .  .  .  .  .  .  .
+  2  .  .  .  .  .  .  .  .  .  .  .  .  .  .  .
+  3  .  .  .  .  .  .  .  .  .  .  .  .  .  .  .
+  4  .  .  .  .  .  .  .  .  .  .  .  .  .  .  .
+  5  .  .  .  .  .  .  .  .  .  .  .  .  .  .  .
+  6  .  .  .  .  .  .  .  .  .  O  O  O  .  .  .
+  7  .  .  .  .  .  .  .  .  .  .  .  .  .  .  .
+  8  .  .  .  .  .  .  .  .  .  .  .  .  .  .  .
+  9  .  .  .  .  .  .  .  .  .  .  .  .  .  .  .
+ 10  .  .  .  .  .  .  .  .  .  .  .  .  .  .  .
+ 11  .  .  .  .  .  .  .  .  .  .  .  .  .  .  .
+ 12  .  .  .  .  .  .  .  .  .  .  .  .  .  .  .
+ 13  .  .  .  .  .  .  .  .  .  .  .  .  .  .  .
+ 14  .  .  .  .  .  .  .  .  .  .  .  .  .  .  .
+";
+    let board_state = parse_board_state(text).unwrap();
+    let mut expected = vec![0; 227];
+    expected[1] = 2;
+    expected[2..6].copy_from_slice(&[1, 1, 1, 1]);
+    expected[101..104].copy_from_slice(&[2, 2, 2]);
+    assert_eq!(board_state, expected);
+    assert_eq!(render_board_state(&board_state).unwrap(), text);
+}
+
+#[test]
+fn test_pass_parse_multi_board_state_without_column_line() {
+    let mut text = String::from("winner color: 1\nturn color: 0\nblack id: 2\n");
+    for x in 0..15 {
+        let row: Vec<&str> = (0..15).map(|y| if x == 7 && y < 5 { "●" } else if x == 8 && y < 4 { "○" } else { "+" }).collect();
+        text.push_str(&format!("{} {}\n", x, row.join(" ")));
+    }
+    let board_state = parse_board_state(&text).unwrap();
+    assert_eq!(board_state.len(), 228);
+    assert_eq!(&board_state[..3], &[1, 0, 2]);
+    assert_eq!(board_state[3 + 15 * 7 + 4], 1);
+    assert_eq!(board_state[3 + 15 * 8 + 3], 2);
+    assert_eq!(parse_board_state(&render_board_state(&board_state).unwrap()).unwrap(), board_state);
+}
+
+#[test]
+fn test_fail_parse_invalid_board_state() {
+    let rendered = render_board_state(&vec![0; 227]).unwrap();
+    assert_eq!(
+        parse_board_state(&rendered.replace("turn: 0\n", "")),
+        Err(TextError::MissingHeader("turn"))
+    );
+    assert_eq!(
+        parse_board_state(&rendered.replace(" 14  .", " 14  #")),
+        Err(TextError::InvalidValue("#".to_string()))
+    );
+    assert_eq!(
+        parse_board_state(&rendered.replace(" 14  .", " 13  .")),
+        Err(TextError::InvalidLine(format!(" 13{}", "  .".repeat(15)).trim().to_string()))
+    );
+    assert_eq!(render_board_state(&[0; 10]), Err(TextError::InvalidLength(10)));
+}
@@ -0,0 +1,160 @@
+//! Text form of gomoku board states
+//!
+//! A board state is written as header lines followed by the grid, row `x`
+//! from top and column `y` from left, with `X` for stone 1, `O` for stone 2
+//! and `.` for an empty slot:
+//!
+//! ```text
+//! winner: 0
+//! turn: 2
+//!      0  1  2  3  4  5  6  7  8  9 10 11 12 13 14
+//!   0  X  X  X  X  .  .  .  .  .  .  .  .  .  .  .
+//!   1  .  .  .  .  .  .  .  .  .  .  .  .  .  .  .
+//! ...
+//! ```
+//!
+//! Single gomoku board states (227 bytes) have `winner` and `turn` headers,
+//! multi gomoku board states (228 bytes) have `winner color`, `turn color`
+//! and `black id` headers. Parsing also accepts `●`/`○` and `1`/`2` for
+//! stones and `+`/`0` for empty slots.
+
+use std::fmt;
+
+use crate::{Board, BOARD_DIMENSION, SLOT_NUM};
+
+const SINGLE_HEADER: [&str; 2] = ["winner", "turn"];
+const MULTI_HEADER: [&str; 3] = ["winner color", "turn color", "black id"];
+
+/// Reason a board state can not be rendered or parsed
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum TextError {
+    /// Board state is neither 227 nor 228 bytes
+    InvalidLength(usize),
+    /// Header or slot value is out of range
+    InvalidValue(String),
+    /// Line is neither a header nor a board row
+    InvalidLine(String),
+    /// Header is missing
+    MissingHeader(&'static str),
+    /// Board row is missing
+    MissingRow(u8),
+}
+
+impl fmt::Display for TextError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TextError::InvalidLength(len) => write!(f, "invalid board state length {}", len),
+            TextError::InvalidValue(value) => write!(f, "invalid value `{}`", value),
+            TextError::InvalidLine(line) => write!(f, "invalid line `{}`", line),
+            TextError::MissingHeader(name) => write!(f, "missing header `{}`", name),
+            TextError::MissingRow(x) => write!(f, "missing row {}", x),
+        }
+    }
+}
+
+impl std::error::Error for TextError {}
+
+/// Render a single or multi gomoku board state
+pub fn render_board_state(board_state: &[u8]) -> Result<String, TextError> {
+    let header: &[&str] = match board_state.len() {
+        len if len == SINGLE_HEADER.len() + SLOT_NUM => &SINGLE_HEADER,
+        len if len == MULTI_HEADER.len() + SLOT_NUM => &MULTI_HEADER,
+        len => return Err(TextError::InvalidLength(len)),
+    };
+    let slots = &board_state[header.len()..];
+    if let Some(slot) = slots.iter().find(|slot| **slot > 2) {
+        return Err(TextError::InvalidValue(slot.to_string()));
+    }
+    let board = Board::from_slots(slots).ok_or(TextError::InvalidLength(board_state.len()))?;
+
+    let mut lines: Vec<String> = header.iter()
+        .zip(board_state.iter())
+        .map(|(name, value)| format!("{}: {}", name, value))
+        .collect();
+    lines.extend(render_board(&board));
+
+    Ok(lines.iter().map(|line| format!("{}\n", line)).collect())
+}
+
+/// Render the grid of a board, one line per row
+pub fn render_board(board: &Board) -> Vec<String> {
+    let columns: String = (0..BOARD_DIMENSION).map(|y| format!("{:>3}", y)).collect();
+    let mut lines = vec![format!("   {}", columns)];
+    for x in 0..BOARD_DIMENSION {
+        let row: String = (0..BOARD_DIMENSION)
+            .map(|y| match board.get(x, y) {
+                1 => "  X",
+                2 => "  O",
+                _ => "  .",
+            })
+            .collect();
+        lines.push(format!("{:>3}{}", x, row));
+    }
+
+    lines
+}
+
+/// Parse a single or multi gomoku board state from its text form
+///
+/// The column line is optional and blank lines are ignored.
+pub fn parse_board_state(text: &str) -> Result<Vec<u8>, TextError> {
+    let mut headers: Vec<(String, u8)> = vec![];
+    let mut rows: Vec<Option<Vec<u8>>> = vec![None; BOARD_DIMENSION as usize];
+    for line in text.lines().map(str::trim).filter(|line| !line.is_empty()) {
+        if let Some(colon) = line.find(':') {
+            let value = line[colon + 1..].trim();
+            let value = value.parse::<u8>().map_err(|_| TextError::InvalidValue(value.to_string()))?;
+            headers.push((line[..colon].trim().to_string(), value));
+            continue;
+        }
+
+        let tokens: Vec<&str> = line.split_whitespace().collect();
+        let is_column_line = tokens.len() == BOARD_DIMENSION as usize
+            && tokens.iter().enumerate().all(|(y, token)| *token == y.to_string());
+        if is_column_line {
+            continue;
+        }
+        if tokens.len() != BOARD_DIMENSION as usize + 1 {
+            return Err(TextError::InvalidLine(line.to_string()));
+        }
+        let x = match tokens[0].parse::<u8>() {
+            Ok(x) if x < BOARD_DIMENSION && rows[x as usize].is_none() => x,
+            _ => return Err(TextError::InvalidLine(line.to_string())),
+        };
+        let slots = tokens[1..].iter()
+            .map(|token| parse_slot(token))
+            .collect::<Result<Vec<u8>, TextError>>()?;
+        rows[x as usize] = Some(slots);
+    }
+
+    let header: &[&str] = if headers.iter().any(|(name, _)| name == MULTI_HEADER[2]) {
+        &MULTI_HEADER
+    } else {
+        &SINGLE_HEADER
+    };
+    let mut board_state = vec![];
+    for name in header {
+        let value = headers.iter()
+            .find(|(header_name, _)| header_name == name)
+            .map(|(_, value)| *value)
+            .ok_or(TextError::MissingHeader(name))?;
+        if value > 2 || (*name == MULTI_HEADER[2] && value == 0) {
+            return Err(TextError::InvalidValue(format!("{}: {}", name, value)));
+        }
+        board_state.push(value);
+    }
+    for (x, row) in rows.into_iter().enumerate() {
+        board_state.extend(row.ok_or(TextError::MissingRow(x as u8))?);
+    }
+
+    Ok(board_state)
+}
+
+fn parse_slot(token: &str) -> Result<u8, TextError> {
+    match token {
+        "." | "+" | "0" => Ok(0),
+        "X" | "x" | "●" | "1" => Ok(1),
+        "O" | "o" | "○" | "2" => Ok(2),
+        _ => Err(TextError::InvalidValue(token.to_string())),
+    }
+}
@@ -0,0 +1,110 @@
+//! Benchmarks of multi gomoku app
+
+use super::*;
+use capps_batch_verify::benchmarking::{sign, sorted_players};
+use frame_benchmarking::{account, benchmarks};
+use frame_system::RawOrigin;
+
+const SEED: u32 = 0;
+const MAX_PLAYERS: u32 = 100;
+
+benchmarks! {
+    _ { }
+
+    // Dispute with an empty board co-signed by every player of the app
+    update_by_state {
+        let n in 2 .. MAX_PLAYERS;
+        let caller: T::AccountId = account("caller", 0, SEED);
+        let (players, keys): (Vec<T::AccountId>, Vec<_>) = sorted_players::<T::AccountId>(n)
+            .into_iter()
+            .unzip();
+        let timeout = T::BlockNumber::from(10u32);
+        let initiate_request = AppInitiateRequestOf::<T> {
+            nonce: 0,
+            player_num: n as u8,
+            players: players.clone(),
+            timeout: timeout,
+            min_stone_offchain: 0,
+            max_stone_onchain: 225,
+            time_bank: Zero::zero(),
+            increment: Zero::zero(),
+            hashed_board: false,
+            encoding: StateEncoding::Scale,
+            eth_players: vec![],
+            arbiter: None,
+            seed_timeout: None,
+        };
+        Module::<T>::app_initiate(RawOrigin::Signed(caller.clone()).into(), initiate_request)?;
+
+        let app_id = Module::<T>::get_app_id(0, players);
+        // black in turn, played by player 1
+        let mut board_state = vec![0; 228];
+        board_state[1] = 1;
+        board_state[2] = 1;
+        let app_state = AppStateOf::<T> {
+            seq_num: 1,
+            board_state: board_state,
+            timeout: timeout,
+            app_id: app_id,
+        };
+        let encoded = app_state.signing_payload();
+        let state_proof = StateProofOf::<T> {
+            app_state: app_state,
+            sigs: keys.iter().map(|key| sign(key, &encoded)).collect(),
+        };
+    }: _(RawOrigin::Signed(caller), state_proof)
+    verify {
+        assert_eq!(Module::<T>::get_seq_num(app_id), Some(1));
+    }
+
+    // On-chain move of the player in turn once the settle period passes
+    update_by_action {
+        let caller: T::AccountId = account("caller", 0, SEED);
+        let (players, keys): (Vec<T::AccountId>, Vec<_>) = sorted_players::<T::AccountId>(2)
+            .into_iter()
+            .unzip();
+        let timeout = T::BlockNumber::from(10u32);
+        let initiate_request = AppInitiateRequestOf::<T> {
+            nonce: 0,
+            player_num: 2,
+            players: players.clone(),
+            timeout: timeout,
+            min_stone_offchain: 0,
+            max_stone_onchain: 225,
+            time_bank: Zero::zero(),
+            increment: Zero::zero(),
+            hashed_board: false,
+            encoding: StateEncoding::Scale,
+            eth_players: vec![],
+            arbiter: None,
+            seed_timeout: None,
+        };
+        Module::<T>::app_initiate(RawOrigin::Signed(caller.clone()).into(), initiate_request)?;
+
+        let app_id = Module::<T>::get_app_id(0, players.clone());
+        // black in turn, played by player 1, with stones of both colors next to the move
+        let mut board_state = vec![0; 228];
+        board_state[1] = 1;
+        board_state[2] = 1;
+        board_state[3 + 15 * 7 + 6] = 1;
+        board_state[3 + 15 * 8 + 7] = 2;
+        let app_state = AppStateOf::<T> {
+            seq_num: 1,
+            board_state: board_state,
+            timeout: timeout,
+            app_id: app_id,
+        };
+        let encoded = app_state.signing_payload();
+        let state_proof = StateProofOf::<T> {
+            app_state: app_state,
+            sigs: keys.iter().map(|key| sign(key, &encoded)).collect(),
+        };
+        Module::<T>::update_by_state(RawOrigin::Signed(caller).into(), state_proof)?;
+
+        let settle_finalized_time = Module::<T>::get_settle_finalized_time(app_id).unwrap();
+        frame_system::Module::<T>::set_block_number(settle_finalized_time + T::BlockNumber::from(1u32));
+    }: _(RawOrigin::Signed(players[0].clone()), app_id, vec![7, 7])
+    verify {
+        assert_eq!(Module::<T>::get_seq_num(app_id), Some(2));
+    }
+}
@@ -1,6 +1,10 @@
 #![cfg_attr(not(feature = "std"), no_std)]
 
 mod mock;
+mod watchtower;
+pub mod types;
+#[cfg(feature = "runtime-benchmarks")]
+mod benchmarking;
 
 #[cfg(test)]
 mod tests;
@@ -8,25 +12,44 @@ mod tests;
 use codec::{Decode, Encode};
 use frame_support::{
     decl_module, decl_storage, decl_event, decl_error, ensure,
-    storage::StorageMap,
+    storage::{StorageDoubleMap, StorageMap},
     traits::Get,
 };
 use frame_system::{self as system, ensure_signed};
 use sp_runtime::traits::{
-    Hash, IdentifyAccount, 
+    Hash, IdentifyAccount, UniqueSaturatedInto, 
     Member, Verify, Zero, AccountIdConversion, 
 };
 use sp_runtime::{ModuleId, RuntimeDebug, DispatchResult, DispatchError};
-use sp_std::{prelude::*, vec::Vec};
+use sp_std::{convert::TryInto, prelude::*, vec::Vec};
+use sp_core::{H160, H256};
+use capps_batch_verify::verify_all;
+use capps_state_encoding::ethereum;
+
+pub use capps_app_common::{
+    combined_seed, relayed_action_payload, seed_commitment, signed_move_payload, KeyRotation,
+    Ruling, SeedRound, SessionKeyDelegation, SignedMove, KEY_ROTATION_PREFIX,
+    RELAYED_ACTION_PREFIX, SESSION_KEY_DELEGATION_PREFIX, SIGNED_MOVE_PREFIX,
+};
+pub use capps_state_encoding::{ethereum::EcdsaSignature, StateEncoding};
+pub use watchtower::{crypto, watched_proof_key, SignedWatchtower, WatchedProofOf, KEY_TYPE, WATCHED_APPS_KEY};
+use gomoku_engine::{Board, Move};
 
 #[derive(Clone, Eq, PartialEq, PartialOrd, Ord, Encode, Decode, RuntimeDebug)]
 pub struct AppInitiateRequest<AccountId, BlockNumber> {
-    nonce: u128,
-    player_num: u8,
-    players: Vec<AccountId>,
-    timeout: BlockNumber,
-    min_stone_offchain: u8,
-    max_stone_onchain: u8,
+    pub nonce: u128,
+    pub player_num: u8,
+    pub players: Vec<AccountId>,
+    pub timeout: BlockNumber,
+    pub min_stone_offchain: u8,
+    pub max_stone_onchain: u8,
+    pub time_bank: BlockNumber, // time bank of each player in action mode, 0 for no time control
+    pub increment: BlockNumber, // time added to the time bank after each on-chain move
+    pub hashed_board: bool, // store only a hash of the board on-chain
+    pub encoding: StateEncoding, // encoding of app states signed by players
+    pub eth_players: Vec<H160>, // Ethereum addresses of players in Ethereum encoding, empty otherwise
+    pub arbiter: Option<AccountId>, // account ruling the winner in settle or action mode
+    pub seed_timeout: Option<BlockNumber>, // period to commit and then to reveal a random seed, None for no random seed
 }
 
 pub type AppInitiateRequestOf<T> = AppInitiateRequest<
@@ -36,10 +59,10 @@ pub type AppInitiateRequestOf<T> = AppInitiateRequest<
 
 #[derive(Clone, Eq, PartialEq, PartialOrd, Ord, Encode, Decode, RuntimeDebug)]
 pub struct AppState<BlockNumber, Hash> {
-    seq_num: u128,
-    board_state: Vec<u8>,
-    timeout: BlockNumber,
-    app_id: Hash,
+    pub seq_num: u128,
+    pub board_state: Vec<u8>,
+    pub timeout: BlockNumber,
+    pub app_id: Hash,
 }
 
 pub type AppStateOf<T> = AppState<
@@ -49,8 +72,8 @@ pub type AppStateOf<T> = AppState<
 
 #[derive(Clone, Eq, PartialEq, PartialOrd, Ord, Encode, Decode, RuntimeDebug)]
 pub struct StateProof<BlockNumber, Hash, Signature> {
-    app_state: AppState<BlockNumber, Hash>,
-    sigs: Vec<Signature>,
+    pub app_state: AppState<BlockNumber, Hash>,
+    pub sigs: Vec<Signature>,
 }
 
 pub type StateProofOf<T> = StateProof<
@@ -59,6 +82,98 @@ pub type StateProofOf<T> = StateProof<
     <T as Trait>::Signature,
 >;
 
+/// Signature of the player at an index of app players
+#[derive(Clone, Eq, PartialEq, PartialOrd, Ord, Encode, Decode, RuntimeDebug)]
+pub struct PlayerSig<Signature> {
+    pub player: u8,
+    pub sig: Signature,
+}
+
+/// State proof signed by every player in any order
+pub type IndexedStateProof<BlockNumber, Hash, Signature> = StateProof<BlockNumber, Hash, PlayerSig<Signature>>;
+
+pub type IndexedStateProofOf<T> = IndexedStateProof<
+    <T as system::Trait>::BlockNumber,
+    <T as system::Trait>::Hash,
+    <T as Trait>::Signature,
+>;
+
+pub type SessionKeyDelegationOf<T> = SessionKeyDelegation<
+    <T as system::Trait>::AccountId,
+    <T as system::Trait>::BlockNumber,
+    <T as system::Trait>::Hash,
+>;
+
+pub type KeyRotationOf<T> = KeyRotation<
+    <T as system::Trait>::AccountId,
+    <T as system::Trait>::Hash,
+>;
+
+pub type SignedMoveOf<T> = SignedMove<<T as Trait>::Signature>;
+
+/// State proof signed by Ethereum accounts of players
+pub type EthStateProof<BlockNumber, Hash> = StateProof<BlockNumber, Hash, EcdsaSignature>;
+
+pub type EthStateProofOf<T> = EthStateProof<
+    <T as system::Trait>::BlockNumber,
+    <T as system::Trait>::Hash,
+>;
+
+impl<BlockNumber: Encode, Hash: Encode> AppState<BlockNumber, Hash> {
+    /// Encode app state into the payload signed by players
+    pub fn signing_payload(&self) -> Vec<u8> {
+        let mut encoded = self.seq_num.encode();
+        self.board_state.iter()
+            .for_each(|state| { encoded.extend(state.encode()); });
+        encoded.extend(self.timeout.encode());
+        encoded.extend(self.app_id.encode());
+
+        return encoded;
+    }
+}
+
+impl<BlockNumber: Encode + Clone + TryInto<u64>, Hash: Encode> AppState<BlockNumber, Hash> {
+    /// Encode app state into the protobuf `AppState` message signed by Celer's clients,
+    /// with the board state as state and no nonce
+    ///
+    /// Returns `None` if a number does not fit in a `uint64` field.
+    pub fn protobuf_payload(&self) -> Option<Vec<u8>> {
+        capps_state_encoding::protobuf::app_state(
+            0,
+            self.seq_num,
+            &self.board_state,
+            self.timeout.clone().try_into().ok()?,
+            &self.app_id.encode(),
+        )
+    }
+
+    /// Encode app state as `abi.encodePacked` signed by Ethereum accounts of players,
+    /// with the board state as state and no nonce
+    ///
+    /// Returns `None` if the timeout does not fit in a `uint64`.
+    pub fn ethereum_payload(&self) -> Option<Vec<u8>> {
+        Some(ethereum::app_state(
+            None,
+            self.seq_num,
+            &self.board_state,
+            self.timeout.clone().try_into().ok()?,
+            &self.app_id.encode(),
+        ))
+    }
+
+    /// Encode app state into the payload signed by players under an encoding
+    ///
+    /// Parameter:
+    /// `encoding`: Encoding chosen at initiation
+    pub fn payload(&self, encoding: StateEncoding) -> Option<Vec<u8>> {
+        match encoding {
+            StateEncoding::Scale => Some(self.signing_payload()),
+            StateEncoding::Protobuf => self.protobuf_payload(),
+            StateEncoding::Ethereum => self.ethereum_payload(),
+        }
+    }
+}
+
 #[derive(Clone, Eq, PartialEq, PartialOrd, Ord, Encode, Decode, RuntimeDebug)]
 pub enum AppStatus {
     Idle = 0,
@@ -68,19 +183,23 @@ pub enum AppStatus {
 }
 
 #[derive(Clone, Eq, PartialEq, PartialOrd, Ord, Encode, Decode, RuntimeDebug)]
-pub struct GomokuInfo<AccountId, BlockNumber> {
+pub struct GomokuInfo<AccountId, BlockNumber, Hash> {
     players: Vec<AccountId>,
     player_num: u8,
     seq_num: u128,
     timeout: BlockNumber,
     deadline: BlockNumber,
     status: AppStatus,
-    gomoku_state: GomokuState,
+    gomoku_state: GomokuState<BlockNumber, Hash>,
+    encoding: StateEncoding,
+    eth_players: Vec<H160>,
+    arbiter: Option<AccountId>,
 }
 
 pub type GomokuInfoOf<T> = GomokuInfo<
     <T as system::Trait>::AccountId,
     <T as system::Trait>::BlockNumber,
+    <T as system::Trait>::Hash,
 >;
 
 #[derive(Clone, Eq, PartialEq, PartialOrd, Ord, Encode, Decode, RuntimeDebug)]
@@ -91,13 +210,75 @@ pub enum StateKey {
 }
 
 #[derive(Clone, Eq, PartialEq, PartialOrd, Ord, Encode, Decode, RuntimeDebug)]
-struct GomokuState {
-    board_state: Option<Vec<u8>>, // 228 length: u8 winner color + u8 turn color + u8 black id + 15*15 board
+struct GomokuState<BlockNumber, Hash> {
+    board_state: Option<BoardState<BoardRecord<Hash>>>, // u8 winner color + u8 turn color + u8 black id + packed or hashed 15*15 board
     stone_num: Option<u16>, // number of stones
     stone_num_onchain: Option<u16>, // number of stones placed on-chain
     state_key: Option<StateKey>, // key of turn_color, winner_color, full_state
     min_stone_offchain: u8, // minimal number of stones before go onchain
     max_stone_onchain: u8, // maximal number of stones after go onchain
+    time_banks: Vec<BlockNumber>, // remaining time bank of each player, empty for no time control
+    increment: BlockNumber, // time added to the time bank after each on-chain move
+    hashed_board: bool, // store only a hash of the board on-chain
+}
+
+#[derive(Clone, Copy, Default, Eq, PartialEq, PartialOrd, Ord, Encode, Decode, RuntimeDebug)]
+struct BoardState<B = Board> {
+    winner_color: u8, // winner color
+    turn_color: u8, // color in turn
+    black_id: u8, // black player index, smaller (=1) or larger(=2) addr
+    board: B, // 15*15 board
+}
+
+#[derive(Clone, Copy, Eq, PartialEq, PartialOrd, Ord, Encode, Decode, RuntimeDebug)]
+enum BoardRecord<Hash> {
+    Packed(Board), // 15*15 board, 2 bits per slot
+    Hashed(Hash), // hash of the 225 board slots, board state is given as a witness
+}
+
+impl BoardState {
+    /// Decode board state from the 228 length layout of off-chain states
+    ///
+    /// Parameter:
+    /// `state`: u8 winner color + u8 turn color + u8 black id + 15*15 board
+    fn from_bytes(state: &[u8]) -> Option<BoardState> {
+        if state.len() != 228 {
+            return None;
+        }
+        let board = Board::from_slots(&state[3..])?;
+
+        Some(BoardState {
+            winner_color: state[0],
+            turn_color: state[1],
+            black_id: state[2],
+            board: board,
+        })
+    }
+
+    /// Encode board state into the 228 length layout of off-chain states
+    fn to_bytes(&self) -> Vec<u8> {
+        let mut state = Vec::with_capacity(228);
+        state.push(self.winner_color);
+        state.push(self.turn_color);
+        state.push(self.black_id);
+        self.board.extend_slots(&mut state);
+
+        return state;
+    }
+}
+
+impl<B> BoardState<B> {
+    /// Index of the player of a color
+    ///
+    /// Parameter:
+    /// `color`: stone color (1 or 2)
+    fn player_index(&self, color: u8) -> usize {
+        if self.black_id == 1 {
+            color as usize - 1
+        } else {
+            2 - color as usize
+        }
+    }
 }
 
 #[derive(Eq, PartialEq)]
@@ -106,18 +287,120 @@ pub enum Color {
     White = 2,
 }
 
+#[derive(Clone, Eq, PartialEq, PartialOrd, Ord, Encode, Decode, RuntimeDebug)]
+pub struct MoveRecord<BlockNumber> {
+    pub player_id: u8, // id of the moving player, kept across key rotations
+    pub x: u8,
+    pub y: u8,
+    pub block_number: BlockNumber,
+    pub seq_num: u128,
+}
+
+pub type MoveRecordOf<T> = MoveRecord<<T as system::Trait>::BlockNumber>;
+
+#[derive(Clone, Eq, PartialEq, PartialOrd, Ord, Encode, Decode, RuntimeDebug)]
+pub struct ResumePoint<Hash> {
+    seq_num: u128, // sequence number of the co-signed state
+    state_hash: Hash, // hash of the co-signed board state
+}
+
+pub type ResumePointOf<T> = ResumePoint<<T as system::Trait>::Hash>;
+
+pub type RulingOf<T> = Ruling<
+    <T as system::Trait>::AccountId,
+    <T as system::Trait>::BlockNumber,
+>;
+
+pub type SeedRoundOf<T> = SeedRound<
+    <T as system::Trait>::BlockNumber,
+    <T as system::Trait>::Hash,
+>;
+
 pub const MULTI_GOMOKU_ID: ModuleId = ModuleId(*b"m_gomoku");
 
+/// Get Id of app from its nonce and players, without a runtime
+///
+/// Parameters:
+/// `nonce`: Nonce of app
+/// `players`: AccountId of players
+pub fn app_id<AccountId, Hashing>(
+    nonce: u128,
+    players: &[AccountId],
+) -> Hashing::Output
+where
+    AccountId: Encode + Decode + Default,
+    Hashing: Hash,
+{
+    let app_account: AccountId = MULTI_GOMOKU_ID.into_account();
+    let mut encoded = app_account.encode();
+    encoded.extend(nonce.encode());
+    players.iter()
+        .for_each(|player| { encoded.extend(player.encode()); });
+
+    Hashing::hash(&encoded)
+}
+
+/// Get Id of app signed by Ethereum accounts, hashed with keccak256 as
+/// Ethereum contracts do
+///
+/// Parameters:
+/// `nonce`: Nonce of app
+/// `eth_players`: Ethereum addresses of players
+pub fn eth_app_id(nonce: u128, eth_players: &[H160]) -> H256 {
+    ethereum::app_id(MULTI_GOMOKU_ID.0, nonce, eth_players)
+}
+
+/// Get id of the player playing black, decided by the random seed
+///
+/// Parameter:
+/// `seed`: Random seed of app
+pub fn seeded_black_id(seed: &[u8]) -> u8 {
+    seed[0] % 2 + 1
+}
+
+/// Authorization of an on-chain action
+enum ActionAuth<AccountId, Signature> {
+    /// Action submitted by the player in turn
+    Caller(AccountId),
+    /// Action signed by the player in turn and submitted by any account
+    Signed(Signature),
+}
+
+/// Signatures of an off-chain state proof
+enum StateSigs<Signature> {
+    /// Signatures of player accounts
+    Players(Vec<Signature>),
+    /// Signatures of Ethereum accounts of players
+    Ethereum(Vec<EcdsaSignature>),
+    /// Signatures of player accounts tagged with their player index
+    Indexed(Vec<PlayerSig<Signature>>),
+}
+
 pub trait Trait: system::Trait {
     type Event: From<Event<Self>> + Into<<Self as frame_system::Trait>::Event>;
     type Public: IdentifyAccount<AccountId = Self::AccountId>;
-    type Signature: Verify<Signer = <Self as Trait>::Public> + Member + Decode + Encode; 
+    type Signature: Verify<Signer = <Self as Trait>::Public> + Member + Decode + Encode;
+    /// Watchtower submitting state proofs from the off-chain worker, `()` for none
+    type Watchtower: capps_watchtower::SubmitProof<WatchedProofOf<Self>>;
 }
 
 decl_storage! {
     trait Store for Module<T: Trait> as MultiGomoku {
         pub MultiGomokuInfoMap get(fn gmoku_info):
             map hasher(blake2_128_concat) T::Hash => Option<GomokuInfoOf<T>>;
+        pub MoveHistoryMap get(fn move_history):
+            map hasher(blake2_128_concat) T::Hash => Vec<MoveRecordOf<T>>;
+        pub ResumePointMap get(fn resume_point):
+            map hasher(blake2_128_concat) T::Hash => Option<ResumePointOf<T>>;
+        pub RulingMap get(fn ruling):
+            map hasher(blake2_128_concat) T::Hash => Option<RulingOf<T>>;
+        pub SeedRoundMap get(fn seed_round):
+            map hasher(blake2_128_concat) T::Hash => Option<SeedRoundOf<T>>;
+        pub SessionKeyMap get(fn session_key):
+            double_map hasher(blake2_128_concat) T::Hash, hasher(blake2_128_concat) T::AccountId
+            => Option<(T::AccountId, T::BlockNumber)>;
+        pub KeyRotationNonceMap get(fn key_rotation_nonce):
+            map hasher(blake2_128_concat) T::Hash => u32;
     }
 }
 
@@ -128,6 +411,11 @@ decl_module! {
 
         fn deposit_event() = default;
 
+        /// Answer stale settlements of apps watched by this node
+        fn offchain_worker(block_number: T::BlockNumber) {
+            capps_watchtower::answer_stale_settles::<Self>(block_number.unique_saturated_into());
+        }
+
         /// Initate multi gomoku app
         ///
         /// Parameters:
@@ -140,17 +428,18 @@ decl_module! {
         /// - DB:
         ///   - 1 storage insertion `GomokuInfoMap`
         ///   - 1 storage reads `GomokuxInfoMap`
+        ///   - 1 storage insertion `SeedRoundMap` with a seed timeout
         /// - Based on benchmark;
         ///     18.59　µs
         /// # </weight>
-        #[weight = 19_000_000 + T::DbWeight::get().reads_writes(1, 1)]
+        #[weight = 19_000_000 + T::DbWeight::get().reads_writes(1, 2)]
         fn app_initiate(
             origin,
             initiate_request: AppInitiateRequestOf<T>
         ) -> DispatchResult {
             ensure_signed(origin)?;
 
-            let app_id = Self::get_app_id(initiate_request.nonce, initiate_request.players.clone());
+            let app_id = Self::get_initiate_app_id(&initiate_request)?;
             ensure!(
                 MultiGomokuInfoMap::<T>::contains_key(&app_id) == false,
                 "AppId already exists"
@@ -158,6 +447,26 @@ decl_module! {
 
             // check whether account is asscending order
             Self::is_ordered_account(initiate_request.players.clone())?;
+            if let Some(arbiter) = &initiate_request.arbiter {
+                ensure!(
+                    !initiate_request.players.contains(arbiter),
+                    "arbiter is a player"
+                );
+            }
+            let seed_round = match initiate_request.seed_timeout {
+                Some(seed_timeout) => {
+                    ensure!(
+                        !seed_timeout.is_zero(),
+                        "invalid seed timeout"
+                    );
+                    Some(SeedRoundOf::<T>::new(
+                        initiate_request.players.len(),
+                        seed_timeout,
+                        frame_system::Module::<T>::block_number(),
+                    ))
+                },
+                None => None,
+            };
 
             let gomoku_state = GomokuState {
                 board_state: None,
@@ -166,6 +475,13 @@ decl_module! {
                 state_key: None,
                 min_stone_offchain: initiate_request.min_stone_offchain,
                 max_stone_onchain: initiate_request.max_stone_onchain,
+                time_banks: if initiate_request.time_bank.is_zero() {
+                    vec![]
+                } else {
+                    vec![initiate_request.time_bank; initiate_request.players.len()]
+                },
+                increment: initiate_request.increment,
+                hashed_board: initiate_request.hashed_board,
             };
             let gomoku_info = GomokuInfoOf::<T> {
                 players: initiate_request.players,
@@ -175,8 +491,15 @@ decl_module! {
                 deadline: Zero::zero(),
                 status: AppStatus::Idle,
                 gomoku_state: gomoku_state,
+                encoding: initiate_request.encoding,
+                eth_players: initiate_request.eth_players,
+                arbiter: initiate_request.arbiter,
             };
             MultiGomokuInfoMap::<T>::insert(app_id, gomoku_info);
+            // players commit to a random seed before the first settle
+            if let Some(seed_round) = seed_round {
+                SeedRoundMap::<T>::insert(app_id, seed_round);
+            }
 
             Ok(())
         }
@@ -188,326 +511,654 @@ decl_module! {
         ///
         /// # <weight>
         /// ## Weight
-        /// - Complexity: `O(1)`
-        ///      - `N` player number
+        /// - Complexity: `O(N)`
+        ///   - N: Number of signatures
         ///   - 1 storage mutation `GomokuInfoMap`
         ///   - 1 storage read `GomokuInfoMap`
-        /// - Based on benchmark;
-        ///     49.04　µs
+        ///   - 1 storage insertion `ResumePointMap`
+        ///   - 1 storage removal `MoveHistoryMap`
+        ///   - 1 storage read `SessionKeyMap` per player
+        ///   - 1 storage read `SeedRoundMap`
+        /// - Based on the 2-player `update_by_state` benchmark and 1 signature
+        ///     verification of 50　µs per further signature, as signatures are
+        ///     verified one by one; the benchmark runs over 2 to 100 players
+        ///     49.04　µs + 50　µs per signature beyond 2
         /// # </weight>
-        #[weight = 49_000_000 + T::DbWeight::get().reads_writes(1, 1)]
+        #[weight = 49_040_000 + 50_000_000 * (state_proof.sigs.len() as u64).saturating_sub(2)
+            + T::DbWeight::get().reads_writes(2 + state_proof.sigs.len() as u64, 3)]
         fn update_by_state(
             origin,
             state_proof: StateProofOf<T>
         ) -> DispatchResult {
             ensure_signed(origin)?;
-            
-            // submit and settle off-chain state
-            let mut gomoku_info: GomokuInfoOf<T> = Self::intend_settle(state_proof.clone())?;
-
-            let _state = state_proof.app_state.board_state;
-            // u8 winner color + u8 turn color + u8 black ud + 15*15 board state
-            ensure!(
-                _state.len() == 228,
-                "invalid state length"
-            );
-
-            let count = 0;
-            if _state[0] != 0 {
-                gomoku_info = Self::win_game(_state[0], gomoku_info.clone())?;
-            } else {
-                // advance to _state[3]
-                let mut _state_iter = _state.iter();
-                for _i in 0..4 {
-                    _state_iter.next();
-                }
-                // load other states only if winner color is not BLACK or WHITE
-                let count = _state_iter.filter(|&x| *x != 0).count() as u8;
-
-                ensure!(
-                    count >= gomoku_info.gomoku_state.min_stone_offchain,
-                    "not enough offchain stones"
-                );
-            }
 
-            let new_gomoku_state = GomokuState {
-                board_state: Some(_state),
-                stone_num: Some(count),
-                stone_num_onchain: gomoku_info.gomoku_state.stone_num_onchain,
-                state_key: gomoku_info.gomoku_state.state_key,
-                min_stone_offchain: gomoku_info.gomoku_state.min_stone_offchain,
-                max_stone_onchain: gomoku_info.gomoku_state.max_stone_onchain,
-            };
-            let new_gomoku_info = GomokuInfoOf::<T> {
-                players: gomoku_info.players,
-                player_num: gomoku_info.player_num,
-                seq_num: gomoku_info.seq_num,
-                timeout: gomoku_info.timeout,
-                deadline: gomoku_info.deadline,
-                status: gomoku_info.status,
-                gomoku_state: new_gomoku_state,
-            };
-            let app_id = state_proof.app_state.app_id;
-            MultiGomokuInfoMap::<T>::mutate(app_id, |info| *info = Some(new_gomoku_info.clone()));
+            // submit and settle off-chain state
+            Self::update_state(state_proof.app_state, StateSigs::Players(state_proof.sigs), Vec::new())
+        }
 
-            Self::deposit_event(RawEvent::IntendSettle(app_id, new_gomoku_info.seq_num));
+        /// Update on-chain state according to an off-chain state proof
+        /// followed by moves signed only by their movers
+        ///
+        /// The i-th move is signed by the player in turn on
+        /// `signed_move_payload` with sequence number `seq_num + i + 1` of the
+        /// state proof, and is checked by the gomoku rules.
+        ///
+        /// Parameters:
+        /// - `state_proof`: Signed off-chain app state
+        /// - `moves`: Moves played after the app state, in order
+        ///
+        /// # <weight>
+        /// ## Weight
+        /// - Complexity: `O(N + M)`
+        ///   - N: Number of signatures
+        ///   - M: Number of moves
+        ///   - 1 storage mutation `GomokuInfoMap`
+        ///   - 1 storage read `GomokuInfoMap`
+        ///   - 1 storage insertion `ResumePointMap`
+        ///   - 1 storage removal `MoveHistoryMap`
+        ///   - 1 storage read `SessionKeyMap` per player, shared by the state proof and the moves
+        ///   - 1 storage read `SeedRoundMap`
+        /// - Based on `update_by_state` and 55　µs per move to verify its
+        ///     signature and place its stone
+        /// # </weight>
+        #[weight = 49_040_000 + 50_000_000 * (state_proof.sigs.len() as u64).saturating_sub(2)
+            + 55_000_000 * moves.len() as u64
+            + T::DbWeight::get().reads_writes(2 + state_proof.sigs.len() as u64, 3)]
+        fn update_by_state_with_moves(
+            origin,
+            state_proof: StateProofOf<T>,
+            moves: Vec<SignedMoveOf<T>>
+        ) -> DispatchResult {
+            ensure_signed(origin)?;
+            Self::update_state(state_proof.app_state, StateSigs::Players(state_proof.sigs), moves)
+        }
 
-            Ok(())
+        /// Update state according to an off-chain state proof whose
+        /// signatures name the index of their player and may come in any order
+        ///
+        /// Parameters:
+        /// - `state_proof`: Off-chain app state signed by indexed players
+        ///
+        /// # <weight>
+        /// ## Weight
+        /// - Complexity: `O(N)`
+        ///   - N: Number of signatures
+        ///   - 1 storage mutation `GomokuInfoMap`
+        ///   - 1 storage read `GomokuInfoMap`
+        ///   - 1 storage insertion `ResumePointMap`
+        ///   - 1 storage removal `MoveHistoryMap`
+        ///   - 1 storage read `SessionKeyMap` per player
+        ///   - 1 storage read `SeedRoundMap`
+        /// - Based on `update_by_state` with the same number of signatures
+        /// # </weight>
+        #[weight = 49_040_000 + 50_000_000 * (state_proof.sigs.len() as u64).saturating_sub(2)
+            + T::DbWeight::get().reads_writes(2 + state_proof.sigs.len() as u64, 3)]
+        fn update_by_indexed_state(
+            origin,
+            state_proof: IndexedStateProofOf<T>
+        ) -> DispatchResult {
+            ensure_signed(origin)?;
+            Self::update_state(state_proof.app_state, StateSigs::Indexed(state_proof.sigs), Vec::new())
         }
 
-        /// Update state according to an on-chain action
+        /// Update state according to an off-chain state proof signed by
+        /// Ethereum accounts of players
         ///
         /// Parameters:
-        /// - `app_id`: Id of app
-        /// - `action`: Action data
+        /// - `state_proof`: Off-chain app state signed by Ethereum accounts
         ///
         /// # <weight>
         /// ## Weight
         /// - Complexity: `O(1)`
-        ///   - 2 storage mutation `GomokuInfoMap`
+        ///   - 1 storage mutation `GomokuInfoMap`
         ///   - 1 storage read `GomokuInfoMap`
-        /// - Based on benchmark;
-        ///     46.07　µs
+        ///   - 1 storage insertion `ResumePointMap`
+        ///   - 1 storage removal `MoveHistoryMap`
+        ///   - 1 storage read `SeedRoundMap`
+        /// - Based on `update_by_state` and 2 secp256k1 recoveries of
+        ///     50　µs
         /// # </weight>
-        #[weight = 46_000_000 + T::DbWeight::get().reads_writes(1, 2)]
-        fn update_by_action(
+        #[weight = 149_000_000 + T::DbWeight::get().reads_writes(2, 3)]
+        fn update_by_eth_state(
             origin,
-            app_id: T::Hash,
-            action: Vec<u8>
+            state_proof: EthStateProofOf<T>
         ) -> DispatchResult {
-            let caller = ensure_signed(origin)?;
-
-            // apply an action to the on-chain state
-            let gomoku_info = Self::apply_action(app_id)?;
-            let gomoku_state = gomoku_info.gomoku_state.clone();
-            let mut board_state = match gomoku_state.board_state {
-                Some(state) => state,
-                None => Err(Error::<T>::EmptyBoardState)?,
-            };
-            let turn_color: usize = board_state[1] as usize;
-            // black player index, smaller (=1) or larger(=2) addr
-            let black_id = board_state[2];
-            if black_id == 1 {
-                ensure!(
-                    caller == gomoku_info.players[turn_color - 1],
-                    "Not your turn"
-                );
-            } else if black_id == 2 {
-                ensure!(
-                    caller == gomoku_info.players[2 - turn_color],
-                    "Not your turn"
-                )
-            } else {
-                Err(Error::<T>::InvalidBlackId)?
-            }
-            ensure!(
-                action.len() == 2,
-                "invalid action length"
-            );
-            let x = action[0];
-            let y = action[1];
-            ensure!(
-                Self::check_boundary(x, y),
-                "out of boundary"
-            );
-            let index: usize = Self::state_index(x, y);
-            ensure!(
-                board_state[index] == 0,
-                "slot is occupied"
-            );
-
-            // place the stone
-            board_state[index] = turn_color as u8;
-            let new_stone_num = gomoku_state.stone_num.unwrap_or(0) + 1;
-            let new_stone_num_onchain = gomoku_state.stone_num_onchain.unwrap_or(0) + 1;
-            let new_gomoku_state_1 = GomokuState {
-                board_state: Some(board_state.clone()),
-                stone_num: Some(new_stone_num),
-                stone_num_onchain: Some(new_stone_num_onchain),
-                state_key: gomoku_state.state_key.clone(),
-                min_stone_offchain: gomoku_state.min_stone_offchain,
-                max_stone_onchain: gomoku_state.max_stone_onchain,
-            };
-            let mut new_gomoku_info_1 = GomokuInfoOf::<T> {
-                players: gomoku_info.players.clone(),
-                player_num: gomoku_info.player_num,
-                seq_num: gomoku_info.seq_num,
-                timeout: gomoku_info.timeout,
-                deadline: gomoku_info.deadline,
-                status: gomoku_info.status.clone(),
-                gomoku_state: new_gomoku_state_1,
-            };
-            MultiGomokuInfoMap::<T>::mutate(app_id, |info| *info = Some(new_gomoku_info_1.clone()));
-
-            // check if there is five-in-a-row including this new stone
-            if Self::check_five(board_state.clone(), x, y, 1, 0) // horizontal bidirection
-                || Self::check_five(board_state.clone(), x, y, 0, 1) // vertical bidirection
-                || Self::check_five(board_state.clone(), x, y, 1, 1) // main-diagonal bidirection
-                || Self::check_five(board_state.clone(), x, y, 1, -1) // anti-diagonal bidirection
-            {
-                new_gomoku_info_1 = Self::win_game(turn_color as u8, new_gomoku_info_1)?;
-                MultiGomokuInfoMap::<T>::mutate(app_id, |info| *info = Some(new_gomoku_info_1));
-                return Ok(());
-            }
-
-            if new_stone_num == 225 
-                || new_stone_num_onchain as u8 > gomoku_state.max_stone_onchain {
-                    // all slots occupied, game is over with no winner
-                    board_state[1] = 0;
-                    let new_gomoku_state_2 = GomokuState {
-                        board_state: Some(board_state),
-                        stone_num: Some(new_stone_num),
-                        stone_num_onchain: Some(new_stone_num_onchain),
-                        state_key: gomoku_state.state_key,
-                        min_stone_offchain: gomoku_state.min_stone_offchain,
-                        max_stone_onchain: gomoku_state.max_stone_onchain,
-                    };
-                    let new_gomoku_info_2 = GomokuInfoOf::<T> {
-                        players: gomoku_info.players,
-                        player_num: gomoku_info.player_num,
-                        seq_num: gomoku_info.seq_num,
-                        timeout: gomoku_info.timeout,
-                        deadline: gomoku_info.deadline,
-                        status: AppStatus::Finalized,
-                        gomoku_state: new_gomoku_state_2,
-                    };
-                    MultiGomokuInfoMap::<T>::mutate(app_id, |info| *info = Some(new_gomoku_info_2.clone()));
-            } else {
-                // toggle turn and update game phase
-                if turn_color == Color::Black as usize {
-                    // set turn color white
-                    board_state[1] = 2;
-                } else {
-                    // set turn color black
-                    board_state[1] = 1;
-                }
-                let new_gomoku_state_2 = GomokuState {
-                    board_state: Some(board_state),
-                    stone_num: Some(new_stone_num),
-                    stone_num_onchain: Some(new_stone_num_onchain),
-                    state_key: gomoku_state.state_key,
-                    min_stone_offchain: gomoku_state.min_stone_offchain,
-                    max_stone_onchain: gomoku_state.max_stone_onchain,
-                };
-                let new_gomoku_info_2 = GomokuInfoOf::<T> {
-                    players: gomoku_info.players,
-                    player_num: gomoku_info.player_num,
-                    seq_num: gomoku_info.seq_num,
-                    timeout: gomoku_info.timeout,
-                    deadline: gomoku_info.deadline,
-                    status: gomoku_info.status,
-                    gomoku_state: new_gomoku_state_2,
-                };
-                MultiGomokuInfoMap::<T>::mutate(app_id, |info| *info = Some(new_gomoku_info_2.clone()));
-            }
-
-            Ok(())
+            ensure_signed(origin)?;
+            Self::update_state(state_proof.app_state, StateSigs::Ethereum(state_proof.sigs), Vec::new())
         }
 
-        /// Finalize the app based on current state in case of on-chain action timeout
+        /// Delegate off-chain state signing of a player to a session key
+        ///
+        /// The delegation is signed by the player, so any account may submit
+        /// it. A delegation only replaces one which expires earlier.
         ///
         /// Parameters:
-        /// - `app_id`: Id of app
+        /// - `delegation`: Session key of a player and its expiry
+        /// - `signature`: Signature of the player on the delegation
         ///
         /// # <weight>
         /// ## Weight
         /// - Complexity: `O(1)`
-        ///   - 1 storage mutation `GomokuInfoMap`
-        ///   - 1 storage read `GomokuInfoMapp`
-        /// - Based on benchmark;
-        ///     29.36 µs
+        ///   - 1 storage read `GomokuInfoMap`
+        ///   - 1 storage read `SessionKeyMap`
+        ///   - 1 storage insertion `SessionKeyMap`
+        /// - Based on 1 signature verification of 50　µs
         /// # </weight>
-        #[weight = 30_000_000 + T::DbWeight::get().reads_writes(1, 1)]
-        fn finalize_on_action_timeout(
+        #[weight = 60_000_000 + T::DbWeight::get().reads_writes(2, 1)]
+        fn delegate_session_key(
             origin,
-            app_id: T::Hash
+            delegation: SessionKeyDelegationOf<T>,
+            signature: <T as Trait>::Signature
         ) -> DispatchResult {
             ensure_signed(origin)?;
-            let gomoku_info = match MultiGomokuInfoMap::<T>::get(app_id) {
+            let gomoku_info = match MultiGomokuInfoMap::<T>::get(delegation.app_id) {
                 Some(info) => info,
                 None => Err(Error::<T>::MultiGomokuInfoNotExist)?,
             };
-
-            let block_number = frame_system::Module::<T>::block_number();
-            if gomoku_info.status == AppStatus::Action {
-                ensure!(
-                    block_number >  gomoku_info.deadline,
-                    "deadline does not passes"
-                );
-            } else if gomoku_info.status == AppStatus::Settle {
+            ensure!(
+                gomoku_info.encoding != StateEncoding::Ethereum,
+                "app is signed by Ethereum accounts"
+            );
+            ensure!(
+                gomoku_info.players.contains(&delegation.player),
+                "not a player of app"
+            );
+            ensure!(
+                delegation.expires_at > frame_system::Module::<T>::block_number(),
+                "session key is expired"
+            );
+            if let Some((_, expires_at)) = SessionKeyMap::<T>::get(delegation.app_id, &delegation.player) {
                 ensure!(
-                    block_number > gomoku_info.deadline + gomoku_info.timeout,
-                    "while setting"
+                    delegation.expires_at > expires_at,
+                    "session key expires earlier than current one"
                 );
-            } else {
-                return Ok(());
             }
+            ensure!(
+                signature.verify(&delegation.signing_payload()[..], &delegation.player),
+                "invalid delegation signature"
+            );
 
-            let board_state = match gomoku_info.clone().gomoku_state.board_state {
-                Some(state) => state,
-                None => Err(Error::<T>::EmptyBoardState)?,
-            };
+            SessionKeyMap::<T>::insert(
+                delegation.app_id,
+                &delegation.player,
+                (delegation.session_key.clone(), delegation.expires_at)
+            );
 
-            if board_state[1] == Color::Black as u8 {
-                let new_gomoku_info = Self::win_game(2, gomoku_info)?;
-                MultiGomokuInfoMap::<T>::mutate(app_id, |info| *info = Some(new_gomoku_info));
-            } else if board_state[1] == Color::White as u8 {
-                let new_gomoku_info = Self::win_game(1, gomoku_info)?;
-                MultiGomokuInfoMap::<T>::mutate(app_id, |info| *info = Some(new_gomoku_info));
-            } else {
-                return Ok(());
-            }
+            // emit SessionKeyDelegated event
+            Self::deposit_event(RawEvent::SessionKeyDelegated(
+                delegation.app_id,
+                delegation.player,
+                delegation.session_key,
+                delegation.expires_at,
+            ));
 
             Ok(())
         }
 
-        /// Check whether app is finalized
+        /// Replace the account of a player in a live app
+        ///
+        /// The rotation is signed by the old account of the player or by the
+        /// arbiter, and by the new account, so any account may submit it. The
+        /// other player cannot take over the seat alone, and no account joins
+        /// without its own consent. The new account takes the place of the old
+        /// one, keeping the player id used by app states, and must sort
+        /// between the neighbouring players.
         ///
         /// Parameters:
-        /// - `app_id`: Id of app
+        /// - `rotation`: Old and new account of a player
+        /// - `signature`: Signature of the old account or of the arbiter
+        /// - `new_signature`: Signature of the new account
         ///
         /// # <weight>
         /// ## Weight
         /// - Complexity: `O(1)`
+        ///   - 1 storage mutation `GomokuInfoMap`
         ///   - 1 storage read `GomokuInfoMap`
-        /// - Based on benchmark;
-        ///     12.06　µs
+        ///   - 1 storage mutation `KeyRotationNonceMap`
+        ///   - 1 storage read `KeyRotationNonceMap`
+        ///   - 1 storage removal `SessionKeyMap`
+        /// - Based on 3 signature verifications of 50　µs
         /// # </weight>
-        #[weight = 12_000_000 + T::DbWeight::get().reads(1)]
-        pub fn is_finalized(
-           origin,
-           app_id: T::Hash
+        #[weight = 160_000_000 + T::DbWeight::get().reads_writes(2, 3)]
+        fn rotate_player_key(
+            origin,
+            rotation: KeyRotationOf<T>,
+            signature: <T as Trait>::Signature,
+            new_signature: <T as Trait>::Signature
         ) -> DispatchResult {
             ensure_signed(origin)?;
-            let gomoku_info = match MultiGomokuInfoMap::<T>::get(app_id) {
+            let mut gomoku_info = match MultiGomokuInfoMap::<T>::get(rotation.app_id) {
                 Some(info) => info,
                 None => Err(Error::<T>::MultiGomokuInfoNotExist)?,
             };
-
-            // If app is not finalized, return DispatchError::Other("NotFinalized")
             ensure!(
-                gomoku_info.status == AppStatus::Finalized,
-                "NotFinalized"
+                gomoku_info.status != AppStatus::Finalized,
+                "app state is finalized"
             );
+            let index = Self::valid_rotation(
+                &rotation,
+                signature,
+                new_signature,
+                &gomoku_info.players,
+                gomoku_info.arbiter.as_ref(),
+            )?;
+
+            gomoku_info.players[index] = rotation.new_player.clone();
+            MultiGomokuInfoMap::<T>::insert(rotation.app_id, gomoku_info);
+            KeyRotationNonceMap::<T>::insert(rotation.app_id, rotation.nonce + 1);
+            // a session key delegated by the old account is no longer accepted
+            SessionKeyMap::<T>::remove(rotation.app_id, &rotation.old_player);
+
+            // emit PlayerKeyRotated event
+            Self::deposit_event(RawEvent::PlayerKeyRotated(
+                rotation.app_id,
+                rotation.old_player,
+                rotation.new_player,
+            ));
 
-            // If app is finalized, return Ok(())
             Ok(())
         }
 
-        /// Get the app outcome
-        /// 
+        /// Update state according to an on-chain action
+        ///
+        /// In hash-committed mode, use `update_by_action_with_witness` instead.
+        ///
         /// Parameters:
         /// - `app_id`: Id of app
-        /// - `query`: query param
+        /// - `action`: Action data
         ///
         /// # <weight>
         /// ## Weight
         /// - Complexity: `O(1)`
+        ///   - 1 storage mutation `GomokuInfoMap`
         ///   - 1 storage read `GomokuInfoMap`
-        /// - Based on benchmark;
-        ///     11.88　µs
+        ///   - 1 storage mutation `MoveHistoryMap`
+        /// - Based on benchmark of the board vector on reference hardware, which
+        ///   the bitboard does not exceed as it places and scans stones without
+        ///   allocating; `update_by_action` in `benchmarking.rs` measures the bitboard
+        ///     46.07　µs
         /// # </weight>
-        #[weight = 12_000_000 + T::DbWeight::get().reads(1)]
+        #[weight = 46_000_000 + T::DbWeight::get().reads_writes(2, 2)]
+        fn update_by_action(
+            origin,
+            app_id: T::Hash,
+            action: Vec<u8>
+        ) -> DispatchResult {
+            let caller = ensure_signed(origin)?;
+            Self::play_action(app_id, action, None, ActionAuth::Caller(caller))
+        }
+
+        /// Update state according to an on-chain action, with the current board
+        /// as a witness of the board hash in hash-committed mode
+        ///
+        /// Parameters:
+        /// - `app_id`: Id of app
+        /// - `action`: Action data
+        /// - `board_witness`: Current board state, ignored when the full board is on-chain
+        ///
+        /// # <weight>
+        /// ## Weight
+        /// - Complexity: `O(1)`
+        ///   - 1 storage mutation `GomokuInfoMap`
+        ///   - 1 storage read `GomokuInfoMap`
+        ///   - 1 storage mutation `MoveHistoryMap`
+        /// - Based on benchmark of the board vector on reference hardware, which
+        ///   the bitboard does not exceed as it places and scans stones without
+        ///   allocating; `update_by_action` in `benchmarking.rs` measures the bitboard
+        ///     46.07　µs
+        /// # </weight>
+        #[weight = 46_000_000 + T::DbWeight::get().reads_writes(2, 2)]
+        fn update_by_action_with_witness(
+            origin,
+            app_id: T::Hash,
+            action: Vec<u8>,
+            board_witness: Vec<u8>
+        ) -> DispatchResult {
+            let caller = ensure_signed(origin)?;
+            Self::play_action(app_id, action, Some(board_witness), ActionAuth::Caller(caller))
+        }
+
+        /// Update state according to an on-chain action signed by the player
+        /// in turn and submitted by any account
+        ///
+        /// The signature on `relayed_action_payload` takes the place of the
+        /// origin check. It is bound to the current seq_num, so it authorizes
+        /// exactly one move and can not be replayed after that move.
+        ///
+        /// Parameters:
+        /// - `app_id`: Id of app
+        /// - `action`: Action data
+        /// - `signature`: Signature of the player in turn or its session key
+        /// - `board_witness`: Current board state in hash-committed mode, ignored otherwise
+        ///
+        /// # <weight>
+        /// ## Weight
+        /// - Complexity: `O(1)`
+        ///   - 1 storage mutation `GomokuInfoMap`
+        ///   - 1 storage read `GomokuInfoMap`
+        ///   - 1 storage mutation `MoveHistoryMap`
+        ///   - 1 storage read `SessionKeyMap`
+        /// - Based on `update_by_action` and 1 signature verification of 50　µs
+        /// # </weight>
+        #[weight = 96_000_000 + T::DbWeight::get().reads_writes(3, 2)]
+        fn update_by_relayed_action(
+            origin,
+            app_id: T::Hash,
+            action: Vec<u8>,
+            signature: <T as Trait>::Signature,
+            board_witness: Option<Vec<u8>>
+        ) -> DispatchResult {
+            ensure_signed(origin)?;
+            Self::play_action(app_id, action, board_witness, ActionAuth::Signed(signature))
+        }
+
+        /// Finalize the app based on current state in case of on-chain action timeout
+        ///
+        /// With time control, the player in turn loses once the time bank runs out.
+        ///
+        /// Parameters:
+        /// - `app_id`: Id of app
+        ///
+        /// # <weight>
+        /// ## Weight
+        /// - Complexity: `O(1)`
+        ///   - 1 storage mutation `GomokuInfoMap`
+        ///   - 1 storage read `GomokuInfoMapp`
+        /// - Based on benchmark;
+        ///     29.36 µs
+        /// # </weight>
+        #[weight = 30_000_000 + T::DbWeight::get().reads_writes(1, 1)]
+        fn finalize_on_action_timeout(
+            origin,
+            app_id: T::Hash
+        ) -> DispatchResult {
+            ensure_signed(origin)?;
+            let mut gomoku_info = match MultiGomokuInfoMap::<T>::get(app_id) {
+                Some(info) => info,
+                None => Err(Error::<T>::MultiGomokuInfoNotExist)?,
+            };
+
+            let block_number = frame_system::Module::<T>::block_number();
+            if gomoku_info.status == AppStatus::Action {
+                ensure!(
+                    block_number >  gomoku_info.deadline,
+                    "deadline does not passes"
+                );
+            } else if gomoku_info.status == AppStatus::Settle {
+                ensure!(
+                    block_number > Self::first_action_deadline(&gomoku_info),
+                    "while setting"
+                );
+            } else {
+                return Ok(());
+            }
+
+            let board_state = match gomoku_info.gomoku_state.board_state {
+                Some(state) => state,
+                None => Err(Error::<T>::EmptyBoardState)?,
+            };
+
+            if board_state.turn_color == Color::Black as u8 {
+                Self::win_game(Color::White as u8, &mut gomoku_info)?;
+            } else if board_state.turn_color == Color::White as u8 {
+                Self::win_game(Color::Black as u8, &mut gomoku_info)?;
+            } else {
+                return Ok(());
+            }
+            MultiGomokuInfoMap::<T>::insert(app_id, gomoku_info);
+
+            Ok(())
+        }
+
+        /// Commit to a random seed
+        ///
+        /// Every player commits before the commit deadline, which runs from
+        /// the initiation of the app. Once every player committed, the reveal
+        /// period starts. Commitments are not taken in the initiate request:
+        /// any account submits it and players do not sign it, so the submitter
+        /// could put in a commitment to a secret a player never knew and make
+        /// the player forfeit. Each player commits by itself.
+        ///
+        /// Parameters:
+        /// - `app_id`: Id of app
+        /// - `commitment`: Commitment of the player, see `seed_commitment`
+        ///
+        /// # <weight>
+        /// ## Weight
+        /// - Complexity: `O(N)`
+        ///   - N: Number of players
+        ///   - 1 storage read `GomokuInfoMap`
+        ///   - 1 storage mutation `SeedRoundMap`
+        ///   - 1 storage read `SeedRoundMap`
+        /// # </weight>
+        #[weight = 20_000_000 + T::DbWeight::get().reads_writes(2, 1)]
+        fn commit_seed(
+            origin,
+            app_id: T::Hash,
+            commitment: T::Hash
+        ) -> DispatchResult {
+            let caller = ensure_signed(origin)?;
+            let (index, mut seed_round) = Self::live_seed_round(app_id, &caller)?;
+            seed_round.commit(index, commitment, frame_system::Module::<T>::block_number())?;
+            SeedRoundMap::<T>::insert(app_id, seed_round);
+
+            // emit SeedCommitted event
+            Self::deposit_event(RawEvent::SeedCommitted(app_id, caller));
+
+            Ok(())
+        }
+
+        /// Reveal the secret committed to a random seed
+        ///
+        /// The random seed is decided once every player revealed.
+        ///
+        /// Parameters:
+        /// - `app_id`: Id of app
+        /// - `secret`: Secret of the commitment of the player
+        ///
+        /// # <weight>
+        /// ## Weight
+        /// - Complexity: `O(N)`
+        ///   - N: Number of players
+        ///   - 1 storage read `GomokuInfoMap`
+        ///   - 1 storage mutation `SeedRoundMap`
+        ///   - 1 storage read `SeedRoundMap`
+        /// # </weight>
+        #[weight = 25_000_000 + T::DbWeight::get().reads_writes(2, 1)]
+        fn reveal_seed(
+            origin,
+            app_id: T::Hash,
+            secret: T::Hash
+        ) -> DispatchResult {
+            let caller = ensure_signed(origin)?;
+            let (index, mut seed_round) = Self::live_seed_round(app_id, &caller)?;
+            let seed = seed_round.reveal::<T::AccountId, T::Hashing>(&app_id, index, &caller, secret)?;
+            SeedRoundMap::<T>::insert(app_id, seed_round);
+
+            // emit SeedRevealed and SeedDecided events
+            Self::deposit_event(RawEvent::SeedRevealed(app_id, caller));
+            if let Some(seed) = seed {
+                Self::deposit_event(RawEvent::SeedDecided(app_id, seed));
+            }
+
+            Ok(())
+        }
+
+        /// Finalize app against players who did not commit or reveal a random
+        /// seed in time
+        ///
+        /// The first player who did not default plays black and wins the game,
+        /// nobody wins if every player defaulted.
+        ///
+        /// Parameters:
+        /// - `app_id`: Id of app
+        ///
+        /// # <weight>
+        /// ## Weight
+        /// - Complexity: `O(N)`
+        ///   - N: Number of players
+        ///   - 1 storage mutation `GomokuInfoMap`
+        ///   - 1 storage read `GomokuInfoMap`
+        ///   - 1 storage read `SeedRoundMap`
+        /// # </weight>
+        #[weight = 25_000_000 + T::DbWeight::get().reads_writes(2, 1)]
+        fn finalize_on_seed_timeout(
+            origin,
+            app_id: T::Hash
+        ) -> DispatchResult {
+            ensure_signed(origin)?;
+            let mut gomoku_info = match MultiGomokuInfoMap::<T>::get(app_id) {
+                Some(info) => info,
+                None => Err(Error::<T>::MultiGomokuInfoNotExist)?,
+            };
+            ensure!(
+                gomoku_info.status != AppStatus::Finalized,
+                "app state is finalized"
+            );
+            let seed_round = match SeedRoundMap::<T>::get(app_id) {
+                Some(seed_round) => seed_round,
+                None => Err("app has no random seed")?,
+            };
+            seed_round.check_timeout(frame_system::Module::<T>::block_number())?;
+
+            let defaulted = seed_round.defaulted();
+            let defaulters: Vec<T::AccountId> = gomoku_info.players.iter()
+                .zip(defaulted.iter())
+                .filter(|(_, defaulted)| **defaulted)
+                .map(|(player, _)| player.clone())
+                .collect();
+            let winner = defaulted.iter()
+                .position(|defaulted| !defaulted)
+                .map_or(0, |index| index as u8 + 1);
+            // the winner plays black
+            let board_state = BoardState {
+                winner_color: if winner == 0 { 0 } else { Color::Black as u8 },
+                turn_color: 0,
+                black_id: if winner == 0 { 1 } else { winner },
+                board: Board::default(),
+            };
+            let hashed_board = gomoku_info.gomoku_state.hashed_board;
+            gomoku_info.gomoku_state.board_state = Some(Self::record_board(&board_state, hashed_board));
+            gomoku_info.status = AppStatus::Finalized;
+            MultiGomokuInfoMap::<T>::insert(app_id, gomoku_info);
+
+            // emit SeedForfeited event
+            Self::deposit_event(RawEvent::SeedForfeited(app_id, defaulters));
+
+            Ok(())
+        }
+
+        /// Finalize app with the winner ruled by its arbiter
+        ///
+        /// The ruling overrides the settle and action timeouts and is recorded
+        /// in `RulingMap`.
+        ///
+        /// Parameters:
+        /// - `app_id`: Id of app
+        /// - `winner_color`: Winner color, 0 for a draw
+        ///
+        /// # <weight>
+        /// ## Weight
+        /// - Complexity: `O(1)`
+        ///   - 1 storage mutation `GomokuInfoMap`
+        ///   - 1 storage read `GomokuInfoMap`
+        ///   - 1 storage insertion `RulingMap`
+        /// - Based on `finalize_on_action_timeout`
+        /// # </weight>
+        #[weight = 30_000_000 + T::DbWeight::get().reads_writes(1, 2)]
+        fn arbitrate(
+            origin,
+            app_id: T::Hash,
+            winner_color: u8
+        ) -> DispatchResult {
+            let caller = ensure_signed(origin)?;
+            let mut gomoku_info = match MultiGomokuInfoMap::<T>::get(app_id) {
+                Some(info) => info,
+                None => Err(Error::<T>::MultiGomokuInfoNotExist)?,
+            };
+            ensure!(
+                gomoku_info.arbiter.as_ref() == Some(&caller),
+                Error::<T>::NotArbiter
+            );
+            ensure!(
+                gomoku_info.status == AppStatus::Settle || gomoku_info.status == AppStatus::Action,
+                "app not in settle or action mode"
+            );
+
+            if winner_color == 0 {
+                // draw
+                let board_state = match gomoku_info.gomoku_state.board_state.as_mut() {
+                    Some(state) => state,
+                    None => Err(Error::<T>::EmptyBoardState)?,
+                };
+                board_state.winner_color = 0;
+                board_state.turn_color = 0;
+                gomoku_info.status = AppStatus::Finalized;
+            } else {
+                Self::win_game(winner_color, &mut gomoku_info)?;
+            }
+            MultiGomokuInfoMap::<T>::insert(app_id, gomoku_info);
+
+            let block_number = frame_system::Module::<T>::block_number();
+            RulingMap::<T>::insert(&app_id, RulingOf::<T> {
+                arbiter: caller.clone(),
+                outcome: winner_color,
+                block_number: block_number,
+            });
+
+            // emit Arbitrated event
+            Self::deposit_event(RawEvent::Arbitrated(app_id, caller, winner_color));
+
+            Ok(())
+        }
+
+        /// Check whether app is finalized
+        ///
+        /// Parameters:
+        /// - `app_id`: Id of app
+        ///
+        /// # <weight>
+        /// ## Weight
+        /// - Complexity: `O(1)`
+        ///   - 1 storage read `GomokuInfoMap`
+        /// - Based on benchmark;
+        ///     12.06　µs
+        /// # </weight>
+        #[weight = 12_000_000 + T::DbWeight::get().reads(1)]
+        pub fn is_finalized(
+           origin,
+           app_id: T::Hash
+        ) -> DispatchResult {
+            ensure_signed(origin)?;
+            let gomoku_info = match MultiGomokuInfoMap::<T>::get(app_id) {
+                Some(info) => info,
+                None => Err(Error::<T>::MultiGomokuInfoNotExist)?,
+            };
+
+            // If app is not finalized, return DispatchError::Other("NotFinalized")
+            ensure!(
+                gomoku_info.status == AppStatus::Finalized,
+                "NotFinalized"
+            );
+
+            // If app is finalized, return Ok(())
+            Ok(())
+        }
+
+        /// Get the app outcome
+        /// 
+        /// Parameters:
+        /// - `app_id`: Id of app
+        /// - `query`: query param
+        ///
+        /// # <weight>
+        /// ## Weight
+        /// - Complexity: `O(1)`
+        ///   - 1 storage read `GomokuInfoMap`
+        /// - Based on benchmark;
+        ///     11.88　µs
+        /// # </weight>
+        #[weight = 12_000_000 + T::DbWeight::get().reads(1)]
         pub fn get_outcome(
             origin,
             app_id: T::Hash,
@@ -525,7 +1176,7 @@ decl_module! {
 
             // If outcome is false, return DispatchError::Other("FalseOutcome")
             ensure!(
-                board_state[0] == query,
+                board_state.winner_color == query,
                 "FalseOutcome"
             );
 
@@ -537,10 +1188,28 @@ decl_module! {
 
 decl_event! (
     pub enum Event<T> where
-        <T as system::Trait>::Hash
+        <T as system::Trait>::Hash,
+        <T as system::Trait>::AccountId,
+        <T as system::Trait>::BlockNumber
     {
+        /// SessionKeyDelegated(app_id, player, session_key, expires_at)
+        SessionKeyDelegated(Hash, AccountId, AccountId, BlockNumber),
+        /// PlayerKeyRotated(app_id, old_player, new_player)
+        PlayerKeyRotated(Hash, AccountId, AccountId),
         /// IntendSettle(app_id, seq_num)
         IntendSettle(Hash, u128),
+        /// BoardUpdated(app_id, seq_num, board_state) in hash-committed mode
+        BoardUpdated(Hash, u128, Vec<u8>),
+        /// Arbitrated(app_id, arbiter, winner_color)
+        Arbitrated(Hash, AccountId, u8),
+        /// SeedCommitted(app_id, player)
+        SeedCommitted(Hash, AccountId),
+        /// SeedRevealed(app_id, player)
+        SeedRevealed(Hash, AccountId),
+        /// SeedDecided(app_id, seed)
+        SeedDecided(Hash, Hash),
+        /// SeedForfeited(app_id, defaulters)
+        SeedForfeited(Hash, Vec<AccountId>),
     }
 );
 
@@ -552,6 +1221,20 @@ decl_error! {
         EmptyBoardState,
         // BlackId is invalid
         InvalidBlackId,
+        // ResumePoint is not exist
+        ResumePointNotExist,
+        // MoveRecord is not played by player 1 or 2
+        InvalidMoveRecord,
+        // App state does not fit in the encoding of app
+        AppStateNotEncodable,
+        // Caller is not the arbiter of app
+        NotArbiter,
+        // Number of signatures does not match the players
+        InvalidSignatureNum,
+        // Player index of a signature is out of range
+        InvalidPlayerIndex,
+        // A player signed more than once
+        DuplicateSigner,
     }
 }
 
@@ -565,13 +1248,47 @@ impl<T: Trait> Module<T> {
        nonce:  u128,
        players: Vec<T::AccountId>,
     ) -> T::Hash {
-        let multi_gomoku_app_account = Self::app_account();
-        let mut encoded = multi_gomoku_app_account.encode();
-        encoded.extend(nonce.encode());
-        players.into_iter()
-            .for_each(|players| { encoded.extend(players.encode()); });
-        let app_id = T::Hashing::hash(&encoded);
-        return app_id;
+        app_id::<T::AccountId, T::Hashing>(nonce, &players)
+    }
+
+    /// Get Id of app signed by Ethereum accounts
+    ///
+    /// Returns `None` if the runtime hash is not 32 bytes.
+    ///
+    /// Parameters:
+    /// `nonce`: Nonce of app
+    /// `eth_players`: Ethereum addresses of players
+    pub fn get_eth_app_id(
+        nonce: u128,
+        eth_players: Vec<H160>,
+    ) -> Option<T::Hash> {
+        let app_id = eth_app_id(nonce, &eth_players);
+        T::Hash::decode(&mut app_id.as_bytes()).ok()
+    }
+
+    /// Get Id of app to initiate, from Ethereum addresses of players in Ethereum encoding
+    ///
+    /// Parameter:
+    /// `initiate_request`: App initiate request message
+    fn get_initiate_app_id(
+        initiate_request: &AppInitiateRequestOf<T>,
+    ) -> Result<T::Hash, DispatchError> {
+        if initiate_request.encoding != StateEncoding::Ethereum {
+            ensure!(
+                initiate_request.eth_players.is_empty(),
+                "Ethereum players without Ethereum encoding"
+            );
+            return Ok(Self::get_app_id(initiate_request.nonce, initiate_request.players.clone()));
+        }
+
+        ensure!(
+            initiate_request.eth_players.len() == initiate_request.players.len(),
+            "invalid number of Ethereum players"
+        );
+        let app_id = Self::get_eth_app_id(initiate_request.nonce, initiate_request.eth_players.clone())
+            .ok_or("runtime hash is not 32 bytes")?;
+
+        Ok(app_id)
     }
 
     /// Get app state
@@ -584,15 +1301,25 @@ impl<T: Trait> Module<T> {
             Some(info) => info,
             None => return None
         };
-        let board_state = gomoku_info.gomoku_state.board_state.unwrap();
+        let board_state = match gomoku_info.gomoku_state.board_state {
+            Some(state) => state,
+            None => return None
+        };
         if key == StateKey::WinnerColor as u8 {
-            let state = vec![board_state[0]];
-            return Some(state);
+            return Some(vec![board_state.winner_color]);
         } else if key == StateKey::TurnColor as u8 {
-            let state = vec![board_state[1]];
-            return Some(state);
+            return Some(vec![board_state.turn_color]);
         } else if key == StateKey::FullState as u8 {
-            return Some(board_state);
+            // only the hash of the board is on-chain in hash-committed mode
+            return match board_state.board {
+                BoardRecord::Packed(board) => Some(BoardState {
+                    winner_color: board_state.winner_color,
+                    turn_color: board_state.turn_color,
+                    black_id: board_state.black_id,
+                    board: board,
+                }.to_bytes()),
+                BoardRecord::Hashed(_) => None,
+            };
         } else {
             return None;
         }
@@ -641,7 +1368,7 @@ impl<T: Trait> Module<T> {
         if gomoku_info.status == AppStatus::Action {
             return Some(gomoku_info.deadline);
         } else if gomoku_info.status == AppStatus::Settle {
-            return Some(gomoku_info.deadline + gomoku_info.timeout);
+            return Some(Self::first_action_deadline(&gomoku_info));
         } else {
             return None;
         }
@@ -660,25 +1387,255 @@ impl<T: Trait> Module<T> {
         return Some(gomoku_info.seq_num);
     }
 
+    /// Get the undecided random seed round of app and the index of a player
+    ///
+    /// Parameters:
+    /// `app_id`: Id of app
+    /// `player`: AccountId of player
+    fn live_seed_round(
+        app_id: T::Hash,
+        player: &T::AccountId,
+    ) -> Result<(usize, SeedRoundOf<T>), DispatchError> {
+        let gomoku_info = match MultiGomokuInfoMap::<T>::get(app_id) {
+            Some(info) => info,
+            None => Err(Error::<T>::MultiGomokuInfoNotExist)?,
+        };
+        ensure!(
+            gomoku_info.status != AppStatus::Finalized,
+            "app state is finalized"
+        );
+        let index = match gomoku_info.players.iter().position(|account| account == player) {
+            Some(index) => index,
+            None => Err("not a player of app")?,
+        };
+        let seed_round = match SeedRoundMap::<T>::get(app_id) {
+            Some(seed_round) => seed_round,
+            None => Err("app has no random seed")?,
+        };
+        seed_round.check_live(frame_system::Module::<T>::block_number())?;
+
+        Ok((index, seed_round))
+    }
+
+    /// Get random seed of app, which must be decided before the first settle
+    ///
+    /// Parameter:
+    /// `app_id`: Id of app
+    fn decided_seed(app_id: T::Hash) -> Result<Option<T::Hash>, DispatchError> {
+        match SeedRoundMap::<T>::get(app_id) {
+            Some(seed_round) => match seed_round.seed {
+                Some(seed) => Ok(Some(seed)),
+                None => Err("random seed is not decided")?,
+            },
+            None => Ok(None),
+        }
+    }
+
+    /// Get random seed of app, None if it is not decided
+    ///
+    /// Parameter:
+    /// `app_id`: Id of app
+    pub fn get_seed(app_id: T::Hash) -> Option<T::Hash> {
+        SeedRoundMap::<T>::get(app_id).and_then(|seed_round| seed_round.seed)
+    }
+
+    /// Replay on-chain moves from the co-signed state the game resumed from
+    ///
+    /// Parameters:
+    /// `app_id`: Id of app
+    /// `base_state`: Board state of the co-signed state proof the game resumed from
+    pub fn replay_moves(
+        app_id: T::Hash,
+        base_state: Vec<u8>,
+    ) -> Result<Vec<u8>, DispatchError> {
+        let gomoku_info = match MultiGomokuInfoMap::<T>::get(app_id) {
+            Some(info) => info,
+            None => Err(Error::<T>::MultiGomokuInfoNotExist)?,
+        };
+        let resume_point = match ResumePointMap::<T>::get(app_id) {
+            Some(point) => point,
+            None => Err(Error::<T>::ResumePointNotExist)?,
+        };
+        ensure!(
+            T::Hashing::hash(&base_state) == resume_point.state_hash,
+            "base state not match"
+        );
+
+        let mut board_state = BoardState::from_bytes(&base_state).ok_or("invalid slot state")?;
+        let mut stone_num_onchain: u16 = 0;
+        for record in MoveHistoryMap::<T>::get(app_id) {
+            ensure!(
+                record.player_id == 1 || record.player_id == 2,
+                Error::<T>::InvalidMoveRecord
+            );
+            let turn_color = if board_state.black_id == 1 {
+                record.player_id
+            } else {
+                3 - record.player_id
+            };
+            let outcome = board_state.board.play(Move::new(record.x, record.y), turn_color)
+                .map_err(|_| Error::<T>::InvalidMoveRecord)?;
+            stone_num_onchain += 1;
+
+            outcome.limit(stone_num_onchain, gomoku_info.gomoku_state.max_stone_onchain as u16)
+                .record(&mut board_state.winner_color, &mut board_state.turn_color);
+        }
+
+        Ok(board_state.to_bytes())
+    }
+
     /// Get multi gomoku app account id
     pub fn app_account() -> T::AccountId {
         MULTI_GOMOKU_ID.into_account()
     }
 
-    /// Submit and settle offchain state
+    /// Settle a co-signed board state and finalize app if it has a winner
     ///
-    /// Parameter:
-    /// `state_proof`: Signed off-chain app state
+    /// Parameters:
+    /// `app_state`: Off-chain app state
+    /// `sigs`: Signatures of app state
+    fn update_state(
+        app_state: AppStateOf<T>,
+        sigs: StateSigs<<T as Trait>::Signature>,
+        moves: Vec<SignedMoveOf<T>>,
+    ) -> DispatchResult {
+        let (mut gomoku_info, signers) = Self::intend_settle(app_state.clone(), sigs)?;
+
+        let mut _state = app_state.board_state;
+        // u8 winner color + u8 turn color + u8 black ud + 15*15 board state
+        ensure!(
+            _state.len() == 228,
+            "invalid state length"
+        );
+        let mut board_state = BoardState::from_bytes(&_state).ok_or("invalid slot state")?;
+
+        // check consistency of the co-signed board and count its stones
+        let mut count = Self::valid_board_state(&board_state)?;
+        // the player picked by the random seed plays black
+        if let Some(seed) = Self::decided_seed(app_state.app_id)? {
+            ensure!(
+                board_state.black_id == seeded_black_id(seed.as_ref()),
+                "black id not match seed"
+            );
+        }
+        // moves signed only by their movers continue the co-signed board
+        if !moves.is_empty() {
+            let move_num = moves.len();
+            // moves only follow states signed by players, whose signing accounts are already read
+            let signers = signers.ok_or("app is signed by Ethereum accounts")?;
+            Self::apply_signed_moves(app_state.app_id, &signers, &mut board_state, app_state.seq_num, moves)?;
+            count += move_num as u16;
+            gomoku_info.seq_num = app_state.seq_num + move_num as u128;
+            _state = board_state.to_bytes();
+        }
+        let state_hash = T::Hashing::hash(&_state);
+        let hashed_board = gomoku_info.gomoku_state.hashed_board;
+        gomoku_info.gomoku_state.board_state = Some(Self::record_board(&board_state, hashed_board));
+        gomoku_info.gomoku_state.stone_num = Some(count);
+        if board_state.winner_color != 0 {
+            Self::win_game(board_state.winner_color, &mut gomoku_info)?;
+        } else {
+            ensure!(
+                count >= gomoku_info.gomoku_state.min_stone_offchain as u16,
+                "not enough offchain stones"
+            );
+        }
+
+        let app_id = app_state.app_id;
+        let seq_num = gomoku_info.seq_num;
+        MultiGomokuInfoMap::<T>::insert(app_id, gomoku_info);
+
+        // on-chain moves are replayed from the latest co-signed state
+        let resume_point = ResumePointOf::<T> {
+            seq_num: seq_num,
+            state_hash: state_hash,
+        };
+        ResumePointMap::<T>::insert(app_id, resume_point);
+        MoveHistoryMap::<T>::remove(app_id);
+
+        Self::deposit_event(RawEvent::IntendSettle(app_id, seq_num));
+        if hashed_board {
+            Self::deposit_event(RawEvent::BoardUpdated(app_id, seq_num, _state));
+        }
+
+        Ok(())
+    }
+
+    /// Apply moves signed only by their movers to a co-signed board
+    ///
+    /// Parameters:
+    /// `app_id`: Id of app
+    /// `signers`: Accounts signing off-chain states of players
+    /// `board_state`: Co-signed board state
+    /// `seq_num`: Sequence number of the co-signed state
+    /// `moves`: Moves played after the co-signed state, in order
+    fn apply_signed_moves(
+        app_id: T::Hash,
+        signers: &[T::AccountId],
+        board_state: &mut BoardState,
+        seq_num: u128,
+        moves: Vec<SignedMoveOf<T>>,
+    ) -> DispatchResult {
+        for (i, signed_move) in moves.into_iter().enumerate() {
+            let turn = board_state.turn_color;
+            ensure!(turn != 0, "game is over");
+            let payload = signed_move_payload(&app_id, seq_num + i as u128 + 1, &signed_move.action);
+            ensure!(
+                signed_move.sig.verify(&payload[..], &signers[board_state.player_index(turn)]),
+                "invalid move signature"
+            );
+            let mv = Move::from_action(&signed_move.action).ok_or("invalid action length")?;
+            board_state.board.play(mv, turn)
+                .map_err(|e| e.as_str())?
+                .record(&mut board_state.winner_color, &mut board_state.turn_color);
+        }
+
+        Ok(())
+    }
+
+    /// Submit and settle off-chain state, returning the app info and the
+    /// accounts signing off-chain states of players, None for Ethereum accounts
+    ///
+    /// Parameters:
+    /// `app_state`: Off-chain app state
+    /// `sigs`: Signatures of app state
     fn intend_settle(
-        state_proof: StateProofOf<T>
-    ) -> Result<GomokuInfoOf<T>, DispatchError> {
-        let app_state = state_proof.app_state;
-        let gomoku_info = match MultiGomokuInfoMap::<T>::get(app_state.app_id) {
+        app_state: AppStateOf<T>,
+        sigs: StateSigs<<T as Trait>::Signature>,
+    ) -> Result<(GomokuInfoOf<T>, Option<Vec<T::AccountId>>), DispatchError> {
+        let mut gomoku_info = match MultiGomokuInfoMap::<T>::get(app_state.app_id) {
             Some(info) => info,
             None => Err(Error::<T>::MultiGomokuInfoNotExist)?,
         };
-        let encoded = Self::encode_app_state(app_state.clone());
-        Self::valid_signers(state_proof.sigs, &encoded, gomoku_info.players.clone())?;
+        let encoded = Self::encode_app_state(app_state.clone(), gomoku_info.encoding)?;
+        let signers = match sigs {
+            StateSigs::Players(sigs) => {
+                ensure!(
+                    gomoku_info.encoding != StateEncoding::Ethereum,
+                    "app is signed by Ethereum accounts"
+                );
+                let signers = Self::signing_accounts(app_state.app_id, &gomoku_info.players);
+                Self::valid_signers(sigs, &encoded, signers.clone())?;
+                Some(signers)
+            },
+            StateSigs::Ethereum(sigs) => {
+                ensure!(
+                    gomoku_info.encoding == StateEncoding::Ethereum,
+                    "app is not signed by Ethereum accounts"
+                );
+                Self::valid_eth_signers(&sigs, &encoded, &gomoku_info.eth_players)?;
+                None
+            },
+            StateSigs::Indexed(sigs) => {
+                ensure!(
+                    gomoku_info.encoding != StateEncoding::Ethereum,
+                    "app is signed by Ethereum accounts"
+                );
+                let signers = Self::signing_accounts(app_state.app_id, &gomoku_info.players);
+                Self::valid_indexed_signers(sigs, &encoded, &signers)?;
+                Some(signers)
+            },
+        };
         ensure!(
             gomoku_info.status != AppStatus::Finalized,
             "app state is finalized"
@@ -690,17 +1647,87 @@ impl<T: Trait> Module<T> {
         );
 
         let block_number = frame_system::Module::<T>::block_number();
-        let new_gomoku_info = GomokuInfoOf::<T> {
-            players: gomoku_info.players,
-            player_num: gomoku_info.player_num,
+        gomoku_info.seq_num = app_state.seq_num;
+        gomoku_info.deadline = block_number + gomoku_info.timeout;
+        gomoku_info.status = AppStatus::Settle;
+
+        Ok((gomoku_info, signers))
+    }
+
+    /// Apply an action of the player in turn to the on-chain state
+    ///
+    /// Parameters:
+    /// `app_id`: Id of app
+    /// `action`: Action data
+    /// `board_witness`: Current board state in hash-committed mode, ignored otherwise
+    /// `auth`: Submitter or signature of the action
+    fn play_action(
+        app_id: T::Hash,
+        action: Vec<u8>,
+        board_witness: Option<Vec<u8>>,
+        auth: ActionAuth<T::AccountId, <T as Trait>::Signature>,
+    ) -> DispatchResult {
+        // apply an action to the on-chain state
+        let mut gomoku_info = Self::apply_action(app_id)?;
+        let mut board_state = Self::load_board(&gomoku_info, board_witness)?;
+        let turn_color = board_state.turn_color;
+        ensure!(
+            board_state.black_id == 1 || board_state.black_id == 2,
+            Error::<T>::InvalidBlackId
+        );
+        ensure!(turn_color != 0, "Not your turn");
+        let player = gomoku_info.players[board_state.player_index(turn_color)].clone();
+        match auth {
+            ActionAuth::Caller(caller) => ensure!(caller == player, "Not your turn"),
+            ActionAuth::Signed(signature) => {
+                let payload = relayed_action_payload(&app_id, gomoku_info.seq_num, &action);
+                let signer = Self::signing_accounts(app_id, &[player.clone()]).remove(0);
+                ensure!(
+                    signature.verify(&payload[..], &signer),
+                    "invalid action signature"
+                );
+            },
+        }
+        let mv = Move::from_action(&action).ok_or("invalid action length")?;
+
+        // place the stone
+        let outcome = board_state.board.play(mv, turn_color).map_err(|e| e.as_str())?;
+        let block_number = frame_system::Module::<T>::block_number();
+        let move_record = MoveRecordOf::<T> {
+            player_id: board_state.player_index(turn_color) as u8 + 1,
+            x: mv.x,
+            y: mv.y,
+            block_number: block_number,
             seq_num: gomoku_info.seq_num,
-            timeout: gomoku_info.timeout,
-            deadline: block_number + gomoku_info.deadline,
-            status: AppStatus::Settle,
-            gomoku_state: gomoku_info.gomoku_state,
         };
+        MoveHistoryMap::<T>::mutate(app_id, |history| history.push(move_record));
+
+        let gomoku_state = &mut gomoku_info.gomoku_state;
+        let new_stone_num = gomoku_state.stone_num.unwrap_or(0) + 1;
+        let new_stone_num_onchain = gomoku_state.stone_num_onchain.unwrap_or(0) + 1;
+        gomoku_state.stone_num = Some(new_stone_num);
+        gomoku_state.stone_num_onchain = Some(new_stone_num_onchain);
+
+        let over = outcome.limit(new_stone_num_onchain, gomoku_state.max_stone_onchain as u16)
+            .record(&mut board_state.winner_color, &mut board_state.turn_color);
+        if over {
+            // five-in-a-row including this new stone, all slots occupied or
+            // on-chain stones run out
+            gomoku_info.status = AppStatus::Finalized;
+        }
+        let hashed_board = gomoku_info.gomoku_state.hashed_board;
+        gomoku_info.gomoku_state.board_state = Some(Self::record_board(&board_state, hashed_board));
+        // start the clock of the next player
+        gomoku_info.deadline = Self::turn_deadline(&gomoku_info, block_number);
+        let seq_num = gomoku_info.seq_num;
+        MultiGomokuInfoMap::<T>::insert(app_id, gomoku_info);
+
+        // new board is the witness of the next action
+        if hashed_board {
+            Self::deposit_event(RawEvent::BoardUpdated(app_id, seq_num, board_state.to_bytes()));
+        }
 
-        Ok(new_gomoku_info)
+        Ok(())
     }
 
     /// Apply an action to the on-chain state
@@ -710,7 +1737,7 @@ impl<T: Trait> Module<T> {
     fn apply_action(
         app_id: T::Hash
     ) -> Result<GomokuInfoOf<T>, DispatchError> {
-        let gomoku_info = match MultiGomokuInfoMap::<T>::get(app_id) {
+        let mut gomoku_info = match MultiGomokuInfoMap::<T>::get(app_id) {
             Some(info) => info,
             None => Err(Error::<T>::MultiGomokuInfoNotExist)?,
         };
@@ -720,36 +1747,193 @@ impl<T: Trait> Module<T> {
         );
 
         let block_number =  frame_system::Module::<T>::block_number();
-        let new_gomoku_info: GomokuInfoOf<T>;
-        if gomoku_info.status == AppStatus::Settle && block_number > gomoku_info.deadline {
-            new_gomoku_info = GomokuInfoOf::<T> {
-                players: gomoku_info.players,
-                player_num: gomoku_info.player_num,
-                seq_num:  gomoku_info.seq_num + 1,
-                timeout: gomoku_info.timeout,
-                deadline: block_number + gomoku_info.timeout,
-                status: AppStatus::Action,
-                gomoku_state: gomoku_info.gomoku_state
-            };
-        } else {
+        // app turns into action mode once settle period passes
+        if gomoku_info.status != AppStatus::Settle || block_number <= gomoku_info.deadline {
             ensure!(
                 gomoku_info.status ==  AppStatus::Action,
                 "app not in action mode"
             );
-            new_gomoku_info = GomokuInfoOf::<T> {
-                players: gomoku_info.players,
-                player_num: gomoku_info.player_num,
-                seq_num:  gomoku_info.seq_num + 1,
-                timeout: gomoku_info.timeout,
-                deadline: block_number + gomoku_info.timeout,
-                status: AppStatus::Action,
-                gomoku_state: gomoku_info.gomoku_state
-            };
         }
+        Self::charge_clock(&mut gomoku_info, block_number)?;
+        gomoku_info.seq_num = gomoku_info.seq_num + 1;
+        gomoku_info.status = AppStatus::Action;
+
+        Ok(gomoku_info) 
+    }
+
+    /// Charge the time spent on an on-chain move to the time bank of the player in turn
+    ///
+    /// Parameters:
+    /// `gomoku_info`: Info of gomoku app before applying the action
+    /// `block_number`: Current block number
+    fn charge_clock(
+        gomoku_info: &mut GomokuInfoOf<T>,
+        block_number: T::BlockNumber,
+    ) -> DispatchResult {
+        let board_state = match gomoku_info.gomoku_state.board_state {
+            Some(state) => state,
+            None => return Ok(()),
+        };
+        if gomoku_info.gomoku_state.time_banks.is_empty() || board_state.turn_color == 0 {
+            return Ok(());
+        }
+
+        // the clock of the first on-chain move starts when settle period passes
+        let flag_fall = if gomoku_info.status == AppStatus::Settle {
+            Self::first_action_deadline(gomoku_info)
+        } else {
+            gomoku_info.deadline
+        };
+        ensure!(
+            block_number <= flag_fall,
+            "time bank exhausted"
+        );
+        let gomoku_state = &mut gomoku_info.gomoku_state;
+        let player_index = board_state.player_index(board_state.turn_color);
+        gomoku_state.time_banks[player_index] = flag_fall - block_number + gomoku_state.increment;
+
+        Ok(())
+    }
+
+    /// Get the deadline of the player in turn, starting from a given block
+    ///
+    /// Parameters:
+    /// `gomoku_info`: Info of gomoku app
+    /// `block_number`: Block number the turn starts at
+    fn turn_deadline(
+        gomoku_info: &GomokuInfoOf<T>,
+        block_number: T::BlockNumber,
+    ) -> T::BlockNumber {
+        let board_state = match gomoku_info.gomoku_state.board_state {
+            Some(state) => state,
+            None => return block_number + gomoku_info.timeout,
+        };
+        if gomoku_info.gomoku_state.time_banks.is_empty() || board_state.turn_color == 0 {
+            return block_number + gomoku_info.timeout;
+        }
+
+        let player_index = board_state.player_index(board_state.turn_color);
+        return block_number + gomoku_info.gomoku_state.time_banks[player_index];
+    }
+
+    /// Get the deadline of the first on-chain action after settle period
+    ///
+    /// Parameter:
+    /// `gomoku_info`: Info of gomoku app in settle mode
+    fn first_action_deadline(gomoku_info: &GomokuInfoOf<T>) -> T::BlockNumber {
+        Self::turn_deadline(gomoku_info, gomoku_info.deadline)
+    }
+
+    /// Load the on-chain board state
+    ///
+    /// Parameters:
+    /// `gomoku_info`: Info of gomoku app
+    /// `board_witness`: Board state committed to by the hash in hash-committed mode
+    fn load_board(
+        gomoku_info: &GomokuInfoOf<T>,
+        board_witness: Option<Vec<u8>>,
+    ) -> Result<BoardState, DispatchError> {
+        let board_state = match gomoku_info.gomoku_state.board_state {
+            Some(state) => state,
+            None => Err(Error::<T>::EmptyBoardState)?,
+        };
+        match board_state.board {
+            BoardRecord::Packed(board) => Ok(BoardState {
+                winner_color: board_state.winner_color,
+                turn_color: board_state.turn_color,
+                black_id: board_state.black_id,
+                board: board,
+            }),
+            BoardRecord::Hashed(board_hash) => {
+                let witness = match board_witness {
+                    Some(witness) => witness,
+                    None => Err("empty board witness")?,
+                };
+                let witness_state = BoardState::from_bytes(&witness).ok_or("invalid slot state")?;
+                // only the slots are hashed, the rest of the witness must match the record
+                ensure!(
+                    T::Hashing::hash(&witness[3..]) == board_hash
+                        && witness_state.winner_color == board_state.winner_color
+                        && witness_state.turn_color == board_state.turn_color
+                        && witness_state.black_id == board_state.black_id,
+                    "board witness not match"
+                );
+
+                Ok(witness_state)
+            }
+        }
+    }
+
+    /// Build the on-chain record of a board state
+    ///
+    /// Parameters:
+    /// `board_state`: Board state
+    /// `hashed_board`: Whether to store only a hash of the board
+    ///
+    /// Only the slots are hashed, so the winner and turn kept in the record
+    /// can change without the hash going stale.
+    fn record_board(
+        board_state: &BoardState,
+        hashed_board: bool,
+    ) -> BoardState<BoardRecord<T::Hash>> {
+        let board = if hashed_board {
+            let mut slots = Vec::with_capacity(225);
+            board_state.board.extend_slots(&mut slots);
+            BoardRecord::Hashed(T::Hashing::hash(&slots))
+        } else {
+            BoardRecord::Packed(board_state.board)
+        };
 
-        Ok(new_gomoku_info) 
+        BoardState {
+            winner_color: board_state.winner_color,
+            turn_color: board_state.turn_color,
+            black_id: board_state.black_id,
+            board: board,
+        }
     }
     
+    /// Check a key rotation of a live app and its signatures, returning
+    /// the index of the rotated player
+    ///
+    /// The new account keeps players in ascending order.
+    ///
+    /// Parameters:
+    /// `rotation`: Old and new account of a player
+    /// `signature`: Signature of the old account or of the arbiter
+    /// `new_signature`: Signature of the new account
+    /// `players`: AccountId of players
+    /// `arbiter`: AccountId of arbiter
+    fn valid_rotation(
+        rotation: &KeyRotationOf<T>,
+        signature: <T as Trait>::Signature,
+        new_signature: <T as Trait>::Signature,
+        players: &[T::AccountId],
+        arbiter: Option<&T::AccountId>,
+    ) -> Result<usize, DispatchError> {
+        let index = rotation.rotated_index(
+            players,
+            arbiter,
+            KeyRotationNonceMap::<T>::get(rotation.app_id),
+        )?;
+        ensure!(
+            rotation.keeps_order(players, index),
+            "player is not ascending order"
+        );
+
+        let payload = rotation.signing_payload();
+        ensure!(
+            signature.verify(&payload[..], &rotation.old_player)
+                || arbiter.iter().any(|arbiter| signature.verify(&payload[..], arbiter)),
+            "invalid rotation signature"
+        );
+        ensure!(
+            new_signature.verify(&payload[..], &rotation.new_player),
+            "invalid new account signature"
+        );
+
+        Ok(index)
+    }
+
     /// Check whether account is assceding order
     /// 
     /// Parameter:
@@ -757,18 +1941,28 @@ impl<T: Trait> Module<T> {
     fn is_ordered_account(
         players: Vec<T::AccountId>
     ) -> Result<(), DispatchError> {
-        let mut prev = &players[0];
-        for i in 1..players.len() {
-            ensure!(
-                prev < &players[1],
-                "player is not ascending order"
-            );
-            prev = &players[i];
-        }
+        ensure!(
+            capps_app_common::is_ordered(&players),
+            "player is not ascending order"
+        );
 
         Ok(())
     }
 
+    /// Get accounts signing off-chain states of players, with the live
+    /// session key of a player in place of its account
+    ///
+    /// Parameters:
+    /// `app_id`: Id of app
+    /// `players`: AccountId of players
+    fn signing_accounts(app_id: T::Hash, players: &[T::AccountId]) -> Vec<T::AccountId> {
+        capps_app_common::signing_accounts(
+            players,
+            frame_system::Module::<T>::block_number(),
+            |player| SessionKeyMap::<T>::get(app_id, player),
+        )
+    }
+
     /// Verify off-chain state signatures
     ///
     /// Parameters:
@@ -780,13 +1974,69 @@ impl<T: Trait> Module<T> {
         encoded: &[u8],
         signers: Vec<T::AccountId>,
     ) -> Result<(), DispatchError> {
-        for i in 0..signers.len() {
-            let signature = &signatures[i];
-            ensure!(
-                signature.verify(encoded, &signers[i]),
-                "Check co-sigs failed"
-            );
+        ensure!(
+            signatures.len() == signers.len(),
+            Error::<T>::InvalidSignatureNum
+        );
+        // every signature is checked before the state is used
+        ensure!(
+            verify_all(&signatures, encoded, &signers),
+            "Check co-sigs failed"
+        );
+
+        Ok(())
+    }
+
+    /// Verify off-chain state signatures of every player given in any order
+    ///
+    /// Parameters:
+    /// `signatures`: Signatures with the index of their player
+    /// `encoded`: Encoded app state
+    /// `players`: AccountId of players
+    fn valid_indexed_signers(
+        signatures: Vec<PlayerSig<<T as Trait>::Signature>>,
+        encoded: &[u8],
+        players: &[T::AccountId],
+    ) -> Result<(), DispatchError> {
+        ensure!(
+            signatures.len() == players.len(),
+            Error::<T>::InvalidSignatureNum
+        );
+        // place every signature at the index of its player
+        let mut player_sigs: Vec<Option<<T as Trait>::Signature>> = Vec::new();
+        player_sigs.resize(players.len(), None);
+        for signature in signatures {
+            let player_sig = player_sigs.get_mut(signature.player as usize)
+                .ok_or(Error::<T>::InvalidPlayerIndex)?;
+            ensure!(player_sig.is_none(), Error::<T>::DuplicateSigner);
+            *player_sig = Some(signature.sig);
         }
+        // as many signatures as players without duplicates, so every player signed
+        let sigs: Vec<<T as Trait>::Signature> = player_sigs.into_iter().flatten().collect();
+
+        Self::valid_signers(sigs, encoded, players.to_vec())
+    }
+
+    /// Verify off-chain state signatures of Ethereum accounts, in any order
+    ///
+    /// Parameters:
+    /// `signatures`: Signatures from Ethereum accounts of players
+    /// `encoded`: Packed app state
+    /// `signers`: Ethereum addresses of players
+    fn valid_eth_signers(
+        signatures: &[EcdsaSignature],
+        encoded: &[u8],
+        signers: &[H160],
+    ) -> Result<(), DispatchError> {
+        ensure!(signatures.len() == signers.len(), "Check co-sigs failed");
+        let mut recovered = signatures.iter()
+            .map(|signature| ethereum::recover_signer(signature, encoded))
+            .collect::<Option<Vec<H160>>>()
+            .ok_or("Check co-sigs failed")?;
+        let mut expected = signers.to_vec();
+        recovered.sort();
+        expected.sort();
+        ensure!(recovered == expected, "Check co-sigs failed");
 
         Ok(())
     }
@@ -798,156 +2048,90 @@ impl<T: Trait> Module<T> {
     /// `gomoku_info`: Info of gomoku state
     fn win_game(
         winner: u8,
-        gomoku_info: GomokuInfoOf<T>
-    ) -> Result<GomokuInfoOf<T>, DispatchError> {
+        gomoku_info: &mut GomokuInfoOf<T>
+    ) -> DispatchResult {
         ensure!(
             u8::min_value() <= winner && winner <= 2,
             "invalid winner state"
         );
-        let gomoku_state = gomoku_info.gomoku_state;
-        let mut new_board_state = gomoku_state.board_state.unwrap_or(vec![0; 228]);
-        // set winner color
-        new_board_state[0] = winner;
 
-        let new_gomoku_info: GomokuInfoOf<T>;
+        let board_state = match gomoku_info.gomoku_state.board_state.as_mut() {
+            Some(state) => state,
+            None => Err(Error::<T>::EmptyBoardState)?,
+        };
+        // set winner color
+        board_state.winner_color = winner;
         if winner != 0 {// Game over
             // set turn color 0
-            new_board_state[1] = 0; 
-
-            let new_gomoku_state = GomokuState {
-                board_state: Some(new_board_state),
-                stone_num: gomoku_state.stone_num,
-                stone_num_onchain: gomoku_state.stone_num_onchain,
-                state_key: gomoku_state.state_key,
-                min_stone_offchain: gomoku_state.min_stone_offchain,
-                max_stone_onchain: gomoku_state.max_stone_onchain
-            };
-            
-            new_gomoku_info = GomokuInfoOf::<T> {
-                players: gomoku_info.players,
-                player_num: gomoku_info.player_num,
-                seq_num: gomoku_info.seq_num,
-                timeout: gomoku_info.timeout,
-                deadline: gomoku_info.deadline,
-                status: AppStatus::Finalized,
-                gomoku_state: new_gomoku_state,
-            };
-        } else {
-            let new_gomoku_state = GomokuState {
-                board_state: Some(new_board_state),
-                stone_num: gomoku_state.stone_num,
-                stone_num_onchain: gomoku_state.stone_num_onchain,
-                state_key: gomoku_state.state_key,
-                min_stone_offchain: gomoku_state.min_stone_offchain,
-                max_stone_onchain: gomoku_state.max_stone_onchain
-            };
-            new_gomoku_info = GomokuInfoOf::<T> {
-                players: gomoku_info.players,
-                player_num: gomoku_info.player_num,
-                seq_num: gomoku_info.seq_num,
-                timeout: gomoku_info.timeout,
-                deadline: gomoku_info.deadline,
-                status: gomoku_info.status,
-                gomoku_state: new_gomoku_state,
-            };
+            board_state.turn_color = 0;
+            gomoku_info.status = AppStatus::Finalized;
         }
-        
-        return Ok(new_gomoku_info);
-    }
 
-    /// Check if there is five in a row in agiven direction
-    ///
-    /// Parameters:
-    /// `_x`: x coordinate on the board
-    /// `_y`: y coordinate on the board
-    /// `_xdir`: direction (-1 or 0 or 1) in x axis
-    /// `_ydir`: direction (-1 or 0 or 1) in y axis
-    fn check_five(
-        _board_state: Vec<u8>,
-        _x: u8,
-        _y: u8,
-        _xdir: i8,
-        _ydir: i8,
-    ) -> bool {
-        let mut count: u8 = 0;
-        count += Self::count_stone(_board_state.clone(), _x, _y, _xdir, _ydir).unwrap();
-        count += Self::count_stone(_board_state, _x, _y, -1 * _xdir, -1 * _ydir).unwrap() - 1; // reverse direction
-        if count >= 5 {
-            return true
-        } else {
-            return false;
-        }
+        Ok(())
     }
 
-    /// Count the maximum consecutive stones in a given direction
+    /// Check consistency of a co-signed board state and count its stones
     ///
-    /// Parameters:
-    /// `_x`: x coordinate on the board
-    /// `_y`: y coordinate on the board
-    /// `_xdir`: direction (-1 or 0 or 1) in x axis
-    /// `_ydir`: direction (-1 or 0 or 1) in y axis
-    fn count_stone(
-        _board_state: Vec<u8>, 
-        _x: u8, 
-        _y: u8, 
-        _xdir: i8, 
-        _ydir: i8
-    ) -> Option<u8> {
-        let mut count: u8 = 1;
-        while count <= 5 {
-            let x = (_x as i8 + _xdir * count as i8) as u8;
-            let y = (_y as i8 + _ydir * count as i8) as u8;
-            if Self::check_boundary(x, y) 
-                && (_board_state[Self::state_index(x, y)] == _board_state[Self::state_index(_x, _y)]) {
-                    count += 1;
-            } else {
-                return Some(count);
-            }
-        }
-
-        return None;
-    }
+    /// Parameter:
+    /// `board_state`: Decoded off-chain board state
+    fn valid_board_state(board_state: &BoardState) -> Result<u16, DispatchError> {
+        let winner_color = board_state.winner_color;
+        let turn_color = board_state.turn_color;
+        ensure!(
+            winner_color <= Color::White as u8,
+            "invalid winner state"
+        );
+        ensure!(
+            turn_color <= Color::White as u8,
+            "invalid turn state"
+        );
+        // no color is in turn once the game has a winner
+        ensure!(
+            winner_color == 0 || turn_color == 0,
+            "invalid turn state"
+        );
+        ensure!(
+            board_state.black_id == 1 || board_state.black_id == 2,
+            Error::<T>::InvalidBlackId
+        );
 
-    /// Check if coordinate (x, y) is valid
-    ///
-    /// Parameters:
-    /// `_x`: x coordinate on the board
-    /// `_y`: y coordinate on the board
-    fn check_boundary(x: u8, y: u8) -> bool {
-        // board dimention is 15*15
-        let board_dimention = 15;
-        if x < board_dimention && y < board_dimention {
-            return true;
-        } else {
-            return false;
+        // number of stones of each color, indexed by slot value
+        let stone_nums: [u16; 3] = [
+            0,
+            board_state.board.stone_num(Color::Black as u8),
+            board_state.board.stone_num(Color::White as u8),
+        ];
+        // colors place stones alternately
+        ensure!(
+            stone_nums[1] <= stone_nums[2] + 1 && stone_nums[2] <= stone_nums[1] + 1,
+            "invalid stone number"
+        );
+        // color in turn can not have placed more stones than the other color
+        if turn_color != 0 {
+            ensure!(
+                stone_nums[turn_color as usize] <= stone_nums[3 - turn_color as usize],
+                "invalid turn state"
+            );
         }
-    }
+        // only the winner color has five in a row
+        ensure!(
+            board_state.board.has_five(Color::Black as u8) == (winner_color == Color::Black as u8)
+                && board_state.board.has_five(Color::White as u8) == (winner_color == Color::White as u8),
+            "winner not match board"
+        );
 
-    /// Check if coordinate (x, y) is valid
-    ///
-    /// Parameters:
-    /// `_x`: x coordinate on the board
-    /// `_y`: y coordinate on the board
-    fn state_index(x: u8, y: u8) -> usize {
-        // board dimention is 15*15
-        let board_dimention = 15;
-        let index = (3 + board_dimention * x + y) as usize;
-        return index;
+        Ok(stone_nums[1] + stone_nums[2])
     }
 
     /// Encode app state
     ///
-    /// Parameter:
+    /// Parameters:
     /// `app_state`: app state
+    /// `encoding`: Encoding of app
     fn encode_app_state(
-        app_state: AppStateOf<T>
-    ) -> Vec<u8> {
-        let mut encoded = app_state.seq_num.encode();
-        app_state.board_state.iter()
-            .for_each(|state| { encoded.extend(state.encode()); });
-        encoded.extend(app_state.timeout.encode());
-        encoded.extend(app_state.app_id.encode());
-
-        return encoded;
+        app_state: AppStateOf<T>,
+        encoding: StateEncoding,
+    ) -> Result<Vec<u8>, DispatchError> {
+        app_state.payload(encoding).ok_or_else(|| Error::<T>::AppStateNotEncodable.into())
     }
 }
\ No newline at end of file
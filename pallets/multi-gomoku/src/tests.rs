@@ -1,6 +1,7 @@
 use super::*;
 use mock::*;
-use sp_core::{sr25519, Pair, H256};
+use sp_core::{sr25519, Pair, H160, H256};
+use sp_runtime::traits::{BlakeTwo256, OffchainWorker};
 use frame_support::{assert_ok, assert_noop};
 
 #[test]
@@ -18,6 +19,13 @@ fn test_pass_initiate() {
             timeout: 2,
             min_stone_offchain: 5,
             max_stone_onchain: 5,
+            time_bank: 0,
+            increment: 0,
+            hashed_board: false,
+            encoding: StateEncoding::Scale,
+            eth_players: vec![],
+            arbiter: None,
+            seed_timeout: None,
         };
 
         assert_ok!(MultiGomoku::app_initiate(
@@ -75,6 +83,163 @@ fn test_pass_player1_submits_state_proof() {
     })
 }
 
+#[test]
+fn test_pass_update_by_state_with_protobuf_encoding() {
+    ExtBuilder::build().execute_with(|| {
+        let black: u8 = 1;
+        let white: u8 = 2;
+        let black_player_id1 = 2;
+
+        let alice_pair = account_pair("Alice");
+        let bob_pair = account_pair("Bob");
+        let (players, players_pair)
+            = get_sorted_peer(alice_pair, bob_pair);
+
+        let initiate_request = AppInitiateRequest {
+            nonce: 1,
+            players: players.clone(),
+            player_num: 2,
+            timeout: 2,
+            min_stone_offchain: 5,
+            max_stone_onchain: 5,
+            time_bank: 0,
+            increment: 0,
+            hashed_board: false,
+            encoding: StateEncoding::Protobuf,
+            eth_players: vec![],
+            arbiter: None,
+            seed_timeout: None,
+        };
+        assert_ok!(MultiGomoku::app_initiate(
+            Origin::signed(players[0]),
+            initiate_request.clone())
+        );
+        let app_id = MultiGomoku::get_app_id(initiate_request.nonce, initiate_request.players);
+
+        let mut board_state = vec![0; 228];
+        board_state[1] = black; // turn color
+        board_state[2] = black_player_id1;
+        board_state[3] = white;
+        board_state[4] = white;
+        board_state[5] = black;
+        board_state[6] = black;
+        board_state[7] = white;
+        board_state[8] = white;
+        board_state[9] = black;
+
+        // states signed in the SCALE encoding are not accepted
+        let state_proof = get_state_proof(3, board_state.clone(), 2, app_id, players_pair.clone());
+        assert_noop!(
+            MultiGomoku::update_by_state(
+                Origin::signed(players[0]),
+                state_proof
+            ),
+            "Check co-sigs failed"
+        );
+
+        let state_proof = get_encoded_state_proof(
+            u64::max_value() as u128 + 1, board_state.clone(), 2, app_id, players_pair.clone(), StateEncoding::Scale
+        );
+        assert_noop!(
+            MultiGomoku::update_by_state(
+                Origin::signed(players[0]),
+                state_proof
+            ),
+            Error::<TestRuntime>::AppStateNotEncodable
+        );
+
+        let state_proof = get_encoded_state_proof(
+            3, board_state.clone(), 2, app_id, players_pair, StateEncoding::Protobuf
+        );
+        assert_ok!(
+            MultiGomoku::update_by_state(
+                Origin::signed(players[0]),
+                state_proof
+            )
+        );
+        assert_eq!(MultiGomoku::get_seq_num(app_id), Some(3));
+        assert_eq!(MultiGomoku::get_state(app_id, 2).unwrap()[3..10], board_state[3..10]);
+    })
+}
+
+#[test]
+fn test_pass_update_by_eth_state() {
+    ExtBuilder::build().execute_with(|| {
+        let black: u8 = 1;
+        let white: u8 = 2;
+        let black_player_id1 = 2;
+
+        let alice_pair = account_pair("Alice");
+        let bob_pair = account_pair("Bob");
+        let (players, players_pair)
+            = get_sorted_peer(alice_pair, bob_pair);
+        let eth_players: Vec<H160> = vec![eth_pair(1).address(), eth_pair(2).address()];
+
+        let initiate_request = AppInitiateRequest {
+            nonce: 1,
+            players: players.clone(),
+            player_num: 2,
+            timeout: 2,
+            min_stone_offchain: 5,
+            max_stone_onchain: 5,
+            time_bank: 0,
+            increment: 0,
+            hashed_board: false,
+            encoding: StateEncoding::Ethereum,
+            eth_players: eth_players.clone(),
+            arbiter: None,
+            seed_timeout: None,
+        };
+        assert_ok!(MultiGomoku::app_initiate(
+            Origin::signed(players[0]),
+            initiate_request.clone())
+        );
+        let app_id = MultiGomoku::get_eth_app_id(initiate_request.nonce, eth_players).unwrap();
+
+        let mut board_state = vec![0; 228];
+        board_state[1] = black; // turn color
+        board_state[2] = black_player_id1;
+        board_state[3] = white;
+        board_state[4] = white;
+        board_state[5] = black;
+        board_state[6] = black;
+        board_state[7] = white;
+        board_state[8] = white;
+        board_state[9] = black;
+
+        // states signed by substrate accounts are not accepted
+        let state_proof = get_encoded_state_proof(
+            3, board_state.clone(), 2, app_id, players_pair, StateEncoding::Ethereum
+        );
+        assert_noop!(
+            MultiGomoku::update_by_state(
+                Origin::signed(players[0]),
+                state_proof
+            ),
+            "app is signed by Ethereum accounts"
+        );
+
+        let state_proof = get_eth_state_proof(3, board_state.clone(), 2, app_id, vec![eth_pair(3), eth_pair(2)]);
+        assert_noop!(
+            MultiGomoku::update_by_eth_state(
+                Origin::signed(players[0]),
+                state_proof
+            ),
+            "Check co-sigs failed"
+        );
+
+        let state_proof = get_eth_state_proof(3, board_state.clone(), 2, app_id, vec![eth_pair(2), eth_pair(1)]);
+        assert_ok!(
+            MultiGomoku::update_by_eth_state(
+                Origin::signed(players[0]),
+                state_proof
+            )
+        );
+        assert_eq!(MultiGomoku::get_seq_num(app_id), Some(3));
+        assert_eq!(MultiGomoku::get_state(app_id, 2).unwrap()[3..10], board_state[3..10]);
+    })
+}
+
 #[test]
 fn test_fail_update_by_state_with_invalid_seq() {
     ExtBuilder::build().execute_with(|| {
@@ -100,6 +265,273 @@ fn test_fail_update_by_state_with_invalid_seq() {
     })
 }
 
+#[test]
+fn test_fail_update_by_state_with_missing_sig() {
+    ExtBuilder::build().execute_with(|| {
+        let alice_pair = account_pair("Alice");
+        let bob_pair = account_pair("Bob");
+        let (players, players_pair)
+            = get_sorted_peer(alice_pair, bob_pair);
+
+        let app_id = app_initiate(2, players.clone(), 2, 2, 0, 5);
+
+        let mut board_state = vec![0; 228];
+        board_state[1] = 1; // turn color
+        board_state[2] = 1; // black player id
+        let mut state_proof = get_state_proof(1, board_state, 2, app_id, players_pair);
+        state_proof.sigs.pop();
+        assert_noop!(
+            MultiGomoku::update_by_state(
+                Origin::signed(players[0]),
+                state_proof
+            ),
+            Error::<TestRuntime>::InvalidSignatureNum
+        );
+    })
+}
+
+#[test]
+fn test_pass_delegate_session_key() {
+    ExtBuilder::build().execute_with(|| {
+        System::set_block_number(1);
+        let alice_pair = account_pair("Alice");
+        let bob_pair = account_pair("Bob");
+        let (players, players_pair)
+            = get_sorted_peer(alice_pair, bob_pair);
+
+        let app_id = app_initiate(2, players.clone(), 2, 2, 0, 5);
+        let mut board_state = vec![0; 228];
+        board_state[1] = 1; // turn color
+        board_state[2] = 1; // black player id
+
+        let session_pair = account_pair("Carl");
+        let mut delegation = SessionKeyDelegation {
+            app_id: app_id,
+            player: players[0],
+            session_key: session_pair.public(),
+            expires_at: 1,
+        };
+        assert_noop!(
+            MultiGomoku::delegate_session_key(
+                Origin::signed(players[1]),
+                delegation.clone(),
+                players_pair[0].sign(&delegation.signing_payload())
+            ),
+            "session key is expired"
+        );
+
+        // only the player signs its delegation, anyone submits it
+        delegation.expires_at = 10;
+        assert_noop!(
+            MultiGomoku::delegate_session_key(
+                Origin::signed(players[1]),
+                delegation.clone(),
+                players_pair[1].sign(&delegation.signing_payload())
+            ),
+            "invalid delegation signature"
+        );
+        assert_ok!(
+            MultiGomoku::delegate_session_key(
+                Origin::signed(players[1]),
+                delegation.clone(),
+                players_pair[0].sign(&delegation.signing_payload())
+            )
+        );
+        let expected_event = TestEvent::multi_gomoku(RawEvent::SessionKeyDelegated(app_id, players[0], session_pair.public(), 10));
+        assert!(System::events().iter().any(|a| a.event == expected_event));
+        assert_eq!(MultiGomoku::session_key(app_id, players[0]), Some((session_pair.public(), 10)));
+
+        // a replayed delegation does not extend the session key
+        assert_noop!(
+            MultiGomoku::delegate_session_key(
+                Origin::signed(players[1]),
+                delegation.clone(),
+                players_pair[0].sign(&delegation.signing_payload())
+            ),
+            "session key expires earlier than current one"
+        );
+
+        // the session key signs in place of the player
+        let state_proof = get_state_proof(1, board_state.clone(), 2, app_id, vec![session_pair.clone(), players_pair[1].clone()]);
+        assert_ok!(
+            MultiGomoku::update_by_state(
+                Origin::signed(players[1]),
+                state_proof
+            )
+        );
+
+        // the expired session key is not accepted
+        System::set_block_number(10);
+        let state_proof = get_state_proof(2, board_state.clone(), 2, app_id, vec![session_pair, players_pair[1].clone()]);
+        assert_noop!(
+            MultiGomoku::update_by_state(
+                Origin::signed(players[1]),
+                state_proof
+            ),
+            "Check co-sigs failed"
+        );
+    })
+}
+
+#[test]
+fn test_pass_rotate_player_key() {
+    ExtBuilder::build().execute_with(|| {
+        let alice_pair = account_pair("Alice");
+        let bob_pair = account_pair("Bob");
+        let (players, players_pair)
+            = get_sorted_peer(alice_pair, bob_pair);
+
+        let app_id = app_initiate(1, players.clone(), 2, 2, 5, 5);
+        let new_pair = account_pair("Carl");
+        let mut rotation = KeyRotation {
+            app_id: app_id,
+            old_player: players[1],
+            new_player: new_pair.public(),
+            nonce: 1,
+        };
+        assert_noop!(
+            MultiGomoku::rotate_player_key(
+                Origin::signed(players[0]),
+                rotation.clone(),
+                players_pair[1].sign(&rotation.signing_payload()),
+                new_pair.sign(&rotation.signing_payload())
+            ),
+            "invalid rotation nonce"
+        );
+
+        rotation.nonce = 0;
+        // the other player cannot take over the seat of a player
+        assert_noop!(
+            MultiGomoku::rotate_player_key(
+                Origin::signed(players[0]),
+                rotation.clone(),
+                players_pair[0].sign(&rotation.signing_payload()),
+                new_pair.sign(&rotation.signing_payload())
+            ),
+            "invalid rotation signature"
+        );
+        assert_noop!(
+            MultiGomoku::rotate_player_key(
+                Origin::signed(players[0]),
+                rotation.clone(),
+                players_pair[1].sign(&rotation.signing_payload()),
+                players_pair[1].sign(&rotation.signing_payload())
+            ),
+            "invalid new account signature"
+        );
+        let misplaced_rotation = KeyRotation {
+            app_id: app_id,
+            old_player: players[1],
+            new_player: account_key("Dave"),
+            nonce: 0,
+        };
+        assert_noop!(
+            MultiGomoku::rotate_player_key(
+                Origin::signed(players[0]),
+                misplaced_rotation.clone(),
+                players_pair[1].sign(&misplaced_rotation.signing_payload()),
+                account_pair("Dave").sign(&misplaced_rotation.signing_payload())
+            ),
+            "player is not ascending order"
+        );
+
+        // the old account authorizes the rotation, the player keeps its slot
+        assert_ok!(
+            MultiGomoku::rotate_player_key(
+                Origin::signed(players[0]),
+                rotation.clone(),
+                players_pair[1].sign(&rotation.signing_payload()),
+                new_pair.sign(&rotation.signing_payload())
+            )
+        );
+        assert_eq!(MultiGomoku::gmoku_info(app_id).unwrap().players, vec![players[0], new_pair.public()]);
+        assert_eq!(MultiGomoku::key_rotation_nonce(app_id), 1);
+        let expected_event = TestEvent::multi_gomoku(RawEvent::PlayerKeyRotated(app_id, players[1], new_pair.public()));
+        assert!(System::events().iter().any(|a| a.event == expected_event));
+
+        // black id 2 still refers to the rotated player
+        place_stone_and_update_by_state(app_id, players.clone(), vec![players_pair[0].clone(), new_pair]);
+        assert_eq!(MultiGomoku::get_seq_num(app_id), Some(3));
+    })
+}
+
+#[test]
+fn test_pass_update_by_indexed_state() {
+    ExtBuilder::build().execute_with(|| {
+        let alice_pair = account_pair("Alice");
+        let bob_pair = account_pair("Bob");
+        let (players, players_pair)
+            = get_sorted_peer(alice_pair, bob_pair);
+
+        let app_id = app_initiate(2, players.clone(), 2, 2, 0, 5);
+
+        let mut board_state = vec![0; 228];
+        board_state[1] = 1; // turn color
+        board_state[2] = 1; // black player id
+        let state_proof = get_state_proof(1, board_state, 2, app_id, players_pair);
+        let indexed_proof = |indexes: Vec<u8>| StateProof {
+            app_state: state_proof.app_state.clone(),
+            sigs: indexes.into_iter()
+                .map(|player| PlayerSig {
+                    player: player,
+                    sig: state_proof.sigs[player as usize % 2].clone(),
+                })
+                .collect::<Vec<PlayerSig<Signature>>>(),
+        };
+
+        assert_noop!(
+            MultiGomoku::update_by_indexed_state(
+                Origin::signed(players[0]),
+                indexed_proof(vec![1])
+            ),
+            Error::<TestRuntime>::InvalidSignatureNum
+        );
+        assert_noop!(
+            MultiGomoku::update_by_indexed_state(
+                Origin::signed(players[0]),
+                indexed_proof(vec![1, 1])
+            ),
+            Error::<TestRuntime>::DuplicateSigner
+        );
+        assert_noop!(
+            MultiGomoku::update_by_indexed_state(
+                Origin::signed(players[0]),
+                indexed_proof(vec![1, 2])
+            ),
+            Error::<TestRuntime>::InvalidPlayerIndex
+        );
+
+        // signatures in reverse order of players
+        assert_ok!(
+            MultiGomoku::update_by_indexed_state(
+                Origin::signed(players[0]),
+                indexed_proof(vec![1, 0])
+            )
+        );
+        assert_eq!(MultiGomoku::get_seq_num(app_id), Some(1));
+    })
+}
+
+#[cfg(feature = "runtime-benchmarks")]
+#[test]
+fn test_pass_benchmark_update_by_state() {
+    let (mut ext, _) = ExtBuilder::build_with_watchtower();
+    ext.execute_with(|| {
+        System::set_block_number(1);
+        assert_ok!(benchmarking::test_benchmark_update_by_state::<TestRuntime>());
+    })
+}
+
+#[cfg(feature = "runtime-benchmarks")]
+#[test]
+fn test_pass_benchmark_update_by_action() {
+    let (mut ext, _) = ExtBuilder::build_with_watchtower();
+    ext.execute_with(|| {
+        System::set_block_number(1);
+        assert_ok!(benchmarking::test_benchmark_update_by_action::<TestRuntime>());
+    })
+}
+
 #[test]
 fn test_pass_intend_settle_with_higher_seq() {
     ExtBuilder::build().execute_with(|| {
@@ -176,27 +608,128 @@ fn test_fail_player1_places_stone_at_3_12_before_settle_finalized_time() {
 }
 
 #[test]
-fn test_pass_player1_places_stone_at_3_12_after_settle_finalized_time() {
+fn test_pass_settle_period_restarts_on_each_update_by_state() {
     ExtBuilder::build().execute_with(|| {
+        System::set_block_number(1);
         let nonce1 = 2;
         let alice_pair = account_pair("Alice");
-        let bob_pair = account_pair("Bob");        
-        let (players, players_pair) 
+        let bob_pair = account_pair("Bob");
+        let (players, players_pair)
             = get_sorted_peer(alice_pair, bob_pair);
-        
+
         let app_id = app_initiate(nonce1, players.clone(), 2, 2, 5, 5);
-        
-        place_stone_and_update_by_state_two_times(app_id, players.clone(), players_pair);
 
-        let settle_finalized_time = MultiGomoku::get_settle_finalized_time(app_id).unwrap();
-        System::set_block_number(settle_finalized_time + 1);
+        // each settle lasts one timeout from the latest update
+        place_stone_and_update_by_state(app_id, players.clone(), players_pair.clone());
+        assert_eq!(MultiGomoku::get_settle_finalized_time(app_id), Some(3));
 
+        let mut board_state = vec![0; 228];
+        board_state[1] = 2; // turn color
+        board_state[2] = 2; // black id
+        board_state[3] = 1; // (0, 0)
+        board_state[4] = 1; // (0, 1)
+        board_state[5] = 1; // (0, 2)
+        board_state[6] = 1; // (0, 3)
+        board_state[101] = 2;
+        board_state[102] = 2;
+        board_state[103] = 2;
+        let state_proof = get_state_proof(4, board_state, 2, app_id, players_pair);
+        System::set_block_number(2);
         assert_ok!(
-            MultiGomoku::update_by_action(
-                Origin::signed(players[0]),
-                app_id,
-                vec![3, 12]
-            )
+            MultiGomoku::update_by_state(
+                Origin::signed(players[1]),
+                state_proof
+            )
+        );
+        assert_eq!(MultiGomoku::get_settle_finalized_time(app_id), Some(4));
+    })
+}
+
+#[test]
+fn test_pass_update_by_state_with_anti_diagonal_five() {
+    ExtBuilder::build().execute_with(|| {
+        let black: u8 = 1;
+        let white: u8 = 2;
+        let alice_pair = account_pair("Alice");
+        let bob_pair = account_pair("Bob");
+        let (players, players_pair)
+            = get_sorted_peer(alice_pair, bob_pair);
+
+        let app_id = app_initiate(1, players.clone(), 2, 2, 5, 5);
+
+        let mut board_state = vec![0; 228];
+        board_state[1] = white; // turn color
+        board_state[2] = 2; // black id
+        board_state[63] = black; // (4, 0)
+        board_state[49] = black; // (3, 1)
+        board_state[35] = black; // (2, 2)
+        board_state[21] = black; // (1, 3)
+        board_state[7] = black; // (0, 4)
+        board_state[153] = white; // (10, 0)
+        board_state[154] = white; // (10, 1)
+        board_state[155] = white; // (10, 2)
+        board_state[156] = white; // (10, 3)
+
+        // a co-signed 228-byte board still decodes, and five-in-a-row can not be hidden
+        let state_proof = get_state_proof(1, board_state.clone(), 2, app_id, players_pair.clone());
+        assert_noop!(
+            MultiGomoku::update_by_state(
+                Origin::signed(players[0]),
+                state_proof
+            ),
+            "winner not match board"
+        );
+        let state_proof = get_state_proof(1, board_state[..227].to_vec(), 2, app_id, players_pair.clone());
+        assert_noop!(
+            MultiGomoku::update_by_state(
+                Origin::signed(players[0]),
+                state_proof
+            ),
+            "invalid state length"
+        );
+
+        board_state[0] = black; // winner color
+        board_state[1] = 0; // turn color
+        let state_proof = get_state_proof(1, board_state.clone(), 2, app_id, players_pair);
+        assert_ok!(
+            MultiGomoku::update_by_state(
+                Origin::signed(players[0]),
+                state_proof
+            )
+        );
+        assert_eq!(MultiGomoku::get_state(app_id, 2).unwrap(), board_state);
+        assert_ok!(
+            MultiGomoku::get_outcome(
+                Origin::signed(players[0]),
+                app_id,
+                black
+            )
+        );
+    })
+}
+
+#[test]
+fn test_pass_player1_places_stone_at_3_12_after_settle_finalized_time() {
+    ExtBuilder::build().execute_with(|| {
+        let nonce1 = 2;
+        let alice_pair = account_pair("Alice");
+        let bob_pair = account_pair("Bob");        
+        let (players, players_pair) 
+            = get_sorted_peer(alice_pair, bob_pair);
+        
+        let app_id = app_initiate(nonce1, players.clone(), 2, 2, 5, 5);
+        
+        place_stone_and_update_by_state_two_times(app_id, players.clone(), players_pair);
+
+        let settle_finalized_time = MultiGomoku::get_settle_finalized_time(app_id).unwrap();
+        System::set_block_number(settle_finalized_time + 1);
+
+        assert_ok!(
+            MultiGomoku::update_by_action(
+                Origin::signed(players[0]),
+                app_id,
+                vec![3, 12]
+            )
         );
 
         let turn = MultiGomoku::get_state(app_id, 0).unwrap();
@@ -240,56 +773,875 @@ fn test_fail_player1_tries_to_place_another_stone() {
 }
 
 #[test]
-fn test_fail_player2_tries_to_place_stone_at_occupied_slot_3_12() {
+fn test_fail_player2_tries_to_place_stone_at_occupied_slot_3_12() {
+    ExtBuilder::build().execute_with(|| {
+        let nonce1 = 2;
+        let alice_pair = account_pair("Alice");
+        let bob_pair = account_pair("Bob");        
+        let (players, players_pair) 
+            = get_sorted_peer(alice_pair, bob_pair);
+        
+        let app_id = app_initiate(nonce1, players.clone(), 2, 2, 5, 5);
+        
+        place_stone_and_update_by_state_two_times(app_id, players.clone(), players_pair);
+
+        let settle_finalized_time = MultiGomoku::get_settle_finalized_time(app_id).unwrap();
+        System::set_block_number(settle_finalized_time + 1);
+
+        assert_ok!(
+            MultiGomoku::update_by_action(
+                Origin::signed(players[0]),
+                app_id,
+                vec![3, 12]
+            )
+        );
+
+        assert_noop!(
+            MultiGomoku::update_by_action(
+                Origin::signed(players[1]),
+                app_id,
+                vec![3, 12]
+            ),
+            "slot is occupied"
+        );
+    })
+}
+
+#[test]
+fn test_player2_places_stone_at_0_4_and_wins() {
+    ExtBuilder::build().execute_with(|| {
+        let nonce = 2;
+        let alice_pair = account_pair("Alice");
+        let bob_pair = account_pair("Bob");        
+        let (players, players_pair) 
+            = get_sorted_peer(alice_pair, bob_pair);
+        
+        let app_id = app_initiate(nonce, players.clone(), 2, 2, 5, 5);
+        
+        place_stone_and_update_by_state_two_times(app_id, players.clone(), players_pair);
+
+        let settle_finalized_time = MultiGomoku::get_settle_finalized_time(app_id).unwrap();
+        System::set_block_number(settle_finalized_time + 1);
+
+        assert_ok!(
+            MultiGomoku::update_by_action(
+                Origin::signed(players[0]),
+                app_id,
+                vec![3, 12]
+            )
+        );
+
+        assert_ok!(
+            MultiGomoku::update_by_action(
+                Origin::signed(players[1]),
+                app_id,
+                vec![0, 4]
+            )
+        ); 
+        let turn = MultiGomoku::get_state(app_id, 0).unwrap();
+        assert_eq!(turn, vec![0]);
+        assert_ok!(
+            MultiGomoku::is_finalized(
+                Origin::signed(players[0]),
+                app_id
+            )
+        );
+        assert_ok!(
+            MultiGomoku::get_outcome(
+                Origin::signed(players[0]),
+                app_id,
+                1
+            )
+        );
+    })
+}
+
+#[test]
+fn test_fail_not_player_places_stone() {
+    ExtBuilder::build().execute_with(|| {
+        let nonce = 2;
+        let alice_pair = account_pair("Alice");
+        let bob_pair = account_pair("Bob");        
+        let (players, players_pair) 
+            = get_sorted_peer(alice_pair, bob_pair);
+        
+        let app_id = app_initiate(nonce, players.clone(), 2, 2, 5, 5);
+        
+        place_stone_and_update_by_state_two_times(app_id, players.clone(), players_pair);
+
+        let settle_finalized_time = MultiGomoku::get_settle_finalized_time(app_id).unwrap();
+        System::set_block_number(settle_finalized_time + 1);
+
+        let risa = account_key("Risa"); // not app player
+        assert_noop!(
+            MultiGomoku::update_by_action(
+                Origin::signed(risa),
+                app_id,
+                vec![3, 12]
+            ),
+            "Not your turn"
+        );
+    })
+}
+
+#[test]
+fn test_fail_finalize_on_action_timeout_before_action_deadline() {
+    ExtBuilder::build().execute_with(|| {
+        let nonce = 2;
+        let alice_pair = account_pair("Alice");
+        let bob_pair = account_pair("Bob");        
+        let (players, players_pair) 
+            = get_sorted_peer(alice_pair, bob_pair);
+        
+        let app_id = app_initiate(nonce, players.clone(), 2, 2, 5, 5);
+        
+        place_stone_and_update_by_state_two_times(app_id, players.clone(), players_pair);
+
+        let settle_finalized_time = MultiGomoku::get_settle_finalized_time(app_id).unwrap();
+        System::set_block_number(settle_finalized_time + 1);
+
+        assert_ok!(
+            MultiGomoku::update_by_action(
+                Origin::signed(players[0]),
+                app_id,
+                vec![3, 12]
+            )
+        );
+
+        assert_noop!(
+            MultiGomoku::finalize_on_action_timeout(
+                Origin::signed(players[0]),
+                app_id
+            ),
+            "deadline does not passes"
+        );
+    })
+}
+
+#[test]
+fn test_pass_update_by_relayed_action() {
+    ExtBuilder::build().execute_with(|| {
+        let alice_pair = account_pair("Alice");
+        let bob_pair = account_pair("Bob");
+        let (players, players_pair)
+            = get_sorted_peer(alice_pair, bob_pair);
+        let relayer = account_pair("Carl").public();
+
+        let app_id = app_initiate(2, players.clone(), 2, 2, 5, 5);
+
+        let mut board_state = vec![0; 228];
+        board_state[1] = 2; // turn color
+        board_state[2] = 1; // black player id
+        board_state[3] = 1; // (0, 0)
+        board_state[4] = 1; // (0, 1)
+        board_state[5] = 1; // (0, 2)
+        board_state[6] = 1; // (0, 3)
+        board_state[101] = 2;
+        board_state[102] = 2;
+        board_state[103] = 2;
+        let state_proof = get_state_proof(3, board_state, 2, app_id, players_pair.clone());
+        assert_ok!(
+            MultiGomoku::update_by_state(
+                Origin::signed(players[0]),
+                state_proof
+            )
+        );
+
+        let settle_finalized_time = MultiGomoku::get_settle_finalized_time(app_id).unwrap();
+        System::set_block_number(settle_finalized_time + 1);
+
+        // players[1] is in turn and signs its action off-chain
+        let seq_num = MultiGomoku::get_seq_num(app_id).unwrap();
+        let payload = relayed_action_payload(&app_id, seq_num, &[3, 12]);
+        assert_noop!(
+            MultiGomoku::update_by_relayed_action(
+                Origin::signed(relayer),
+                app_id,
+                vec![3, 12],
+                players_pair[0].sign(&payload),
+                None
+            ),
+            "invalid action signature"
+        );
+        assert_noop!(
+            MultiGomoku::update_by_relayed_action(
+                Origin::signed(relayer),
+                app_id,
+                vec![4, 12],
+                players_pair[1].sign(&payload),
+                None
+            ),
+            "invalid action signature"
+        );
+        assert_ok!(
+            MultiGomoku::update_by_relayed_action(
+                Origin::signed(relayer),
+                app_id,
+                vec![3, 12],
+                players_pair[1].sign(&payload),
+                None
+            )
+        );
+        assert_eq!(MultiGomoku::move_history(app_id).last().unwrap().player_id, 2);
+
+        // the relayed signature does not move for the other player
+        assert_noop!(
+            MultiGomoku::update_by_relayed_action(
+                Origin::signed(relayer),
+                app_id,
+                vec![3, 12],
+                players_pair[1].sign(&payload),
+                None
+            ),
+            "invalid action signature"
+        );
+        assert_noop!(
+            MultiGomoku::update_by_action(
+                Origin::signed(relayer),
+                app_id,
+                vec![4, 12]
+            ),
+            "Not your turn"
+        );
+    })
+}
+
+#[test]
+fn test_pass_update_by_state_with_moves() {
+    ExtBuilder::build().execute_with(|| {
+        let alice_pair = account_pair("Alice");
+        let bob_pair = account_pair("Bob");
+        let (players, players_pair)
+            = get_sorted_peer(alice_pair, bob_pair);
+
+        let app_id = app_initiate(2, players.clone(), 2, 2, 5, 5);
+        let board_state = gomoku_engine::parse_board_state("
+            winner color: 0
+            turn color: 2
+            black id: 1
+            0  1  2  3  4  5  6  7  8  9 10 11 12 13 14
+         0  X  X  X  X  .  .  .  .  .  .  .  .  .  .  .
+         1  .  .  .  .  .  .  .  .  .  .  .  .  .  .  .
+         2  .  .  .  .  .  .  .  .  .  .  .  .  .  .  .
+         3  .  .  .  .  .  .  .  .  .  .  .  .  .  .  .
+         4  .  .  .  .  .  .  .  .  .  .  .  .  .  .  .
+         5  .  .  .  .  .  .  .  .  .  .  .  .  .  .  .
+         6  .  .  .  .  .  .  .  .  .  O  O  O  .  .  .
+         7  .  .  .  .  .  .  .  .  .  .  .  .  .  .  .
+         8  .  .  .  .  .  .  .  .  .  .  .  .  .  .  .
+         9  .  .  .  .  .  .  .  .  .  .  .  .  .  .  .
+        10  .  .  .  .  .  .  .  .  .  .  .  .  .  .  .
+        11  .  .  .  .  .  .  .  .  .  .  .  .  .  .  .
+        12  .  .  .  .  .  .  .  .  .  .  .  .  .  .  .
+        13  .  .  .  .  .  .  .  .  .  .  .  .  .  .  .
+        14  .  .  .  .  .  .  .  .  .  .  .  .  .  .  .
+        ").unwrap();
+        let state_proof = get_state_proof(2, board_state, 2, app_id, players_pair.clone());
+        let signed_move = |seq_num: u128, action: Vec<u8>, pair: &sr25519::Pair| SignedMove {
+            sig: pair.sign(&signed_move_payload(&app_id, seq_num, &action)),
+            action: action,
+        };
+
+        // only the player in turn signs its move
+        assert_noop!(
+            MultiGomoku::update_by_state_with_moves(
+                Origin::signed(players[0]),
+                state_proof.clone(),
+                vec![signed_move(3, vec![6, 12], &players_pair[0])]
+            ),
+            "invalid move signature"
+        );
+        // a move is signed for the sequence number it leads to
+        assert_noop!(
+            MultiGomoku::update_by_state_with_moves(
+                Origin::signed(players[0]),
+                state_proof.clone(),
+                vec![signed_move(4, vec![6, 12], &players_pair[1])]
+            ),
+            "invalid move signature"
+        );
+        assert_noop!(
+            MultiGomoku::update_by_state_with_moves(
+                Origin::signed(players[0]),
+                state_proof.clone(),
+                vec![signed_move(3, vec![0, 0], &players_pair[1])]
+            ),
+            "slot is occupied"
+        );
+
+        // the winning move signed only by players[0] is not lost
+        assert_ok!(
+            MultiGomoku::update_by_state_with_moves(
+                Origin::signed(players[0]),
+                state_proof,
+                vec![
+                    signed_move(3, vec![6, 12], &players_pair[1]),
+                    signed_move(4, vec![0, 4], &players_pair[0]),
+                ]
+            )
+        );
+        assert_eq!(MultiGomoku::get_seq_num(app_id), Some(4));
+        assert_ok!(
+            MultiGomoku::is_finalized(
+                Origin::signed(players[0]),
+                app_id
+            )
+        );
+    })
+}
+
+#[test]
+fn test_pass_finalize_on_action_timeout_after_action_deadline() {
+    ExtBuilder::build().execute_with(|| {
+        let nonce = 2;
+        let alice_pair = account_pair("Alice");
+        let bob_pair = account_pair("Bob");        
+        let (players, players_pair) 
+            = get_sorted_peer(alice_pair, bob_pair);
+        
+        let app_id = app_initiate(nonce, players.clone(), 2, 2, 5, 5);
+        
+        let none: u8 = 0;
+        let black: u8 = 1;
+        let white: u8 = 2;
+        let black_player_id2 = 1;
+
+        let mut board_state = vec![0; 228];
+        board_state[0] = none; 
+        board_state[1] = white; // turn color
+        board_state[2] = black_player_id2;
+        board_state[3] = black; // (0, 0)
+        board_state[4] = black; // (0, 1)
+        board_state[5] = black; // (0, 2)
+        board_state[6] = black; // (0, 3)
+        board_state[101] = white;
+        board_state[102] = white;
+        board_state[103] = white;
+
+        let state_proof = get_state_proof(3, board_state, 2, app_id, players_pair);
+        assert_ok!(
+            MultiGomoku::update_by_state(
+                Origin::signed(players[0]),
+                state_proof
+            )
+        );
+
+        let settle_finalized_time = MultiGomoku::get_settle_finalized_time(app_id).unwrap();
+        System::set_block_number(settle_finalized_time + 1);
+
+        assert_ok!(
+            MultiGomoku::update_by_action(
+                Origin::signed(players[1]),
+                app_id,
+                vec![3, 12]
+            )
+        );
+
+        let deadline = MultiGomoku::get_action_deadline(app_id).unwrap();
+        System::set_block_number(deadline + 1);
+
+        assert_ok!(
+            MultiGomoku::finalize_on_action_timeout(
+                Origin::signed(players[0]),
+                app_id
+            )
+        );
+        assert_ok!(
+            MultiGomoku::is_finalized(
+                Origin::signed(players[0]),
+                app_id
+            )
+        );
+        assert_ok!(
+            MultiGomoku::get_outcome(
+                Origin::signed(players[0]),
+                app_id,
+                2
+            )
+        );
+    })
+}
+
+#[test]
+fn test_pass_finalize_on_seed_timeout() {
+    ExtBuilder::build().execute_with(|| {
+        System::set_block_number(1);
+        let black: u8 = 1;
+        let alice_pair = account_pair("Alice");
+        let bob_pair = account_pair("Bob");
+        let (players, _)
+            = get_sorted_peer(alice_pair, bob_pair);
+
+        let initiate_request = AppInitiateRequest {
+            nonce: 1,
+            players: players.clone(),
+            player_num: 2,
+            timeout: 2,
+            min_stone_offchain: 5,
+            max_stone_onchain: 5,
+            time_bank: 0,
+            increment: 0,
+            hashed_board: false,
+            encoding: StateEncoding::Scale,
+            eth_players: vec![],
+            arbiter: None,
+            seed_timeout: Some(2),
+        };
+        assert_ok!(MultiGomoku::app_initiate(
+            Origin::signed(players[0]),
+            initiate_request.clone())
+        );
+        let app_id = MultiGomoku::get_app_id(initiate_request.nonce, initiate_request.players);
+
+        let secrets = vec![H256::from_low_u64_be(1), H256::from_low_u64_be(2)];
+        for (player, secret) in players.iter().zip(secrets.iter()) {
+            assert_ok!(
+                MultiGomoku::commit_seed(
+                    Origin::signed(*player),
+                    app_id,
+                    seed_commitment::<_, BlakeTwo256>(&app_id, player, secret)
+                )
+            );
+        }
+
+        // the reveal period restarts once every player committed
+        System::set_block_number(3);
+        assert_ok!(
+            MultiGomoku::reveal_seed(
+                Origin::signed(players[1]),
+                app_id,
+                secrets[1]
+            )
+        );
+        assert_noop!(
+            MultiGomoku::finalize_on_seed_timeout(
+                Origin::signed(players[1]),
+                app_id
+            ),
+            "seed deadline no passes"
+        );
+
+        // the player who did not reveal in time loses the game
+        System::set_block_number(4);
+        assert_ok!(
+            MultiGomoku::finalize_on_seed_timeout(
+                Origin::signed(players[1]),
+                app_id
+            )
+        );
+        assert_ok!(
+            MultiGomoku::is_finalized(
+                Origin::signed(players[0]),
+                app_id
+            )
+        );
+        assert_ok!(
+            MultiGomoku::get_outcome(
+                Origin::signed(players[0]),
+                app_id,
+                black
+            )
+        );
+        assert_eq!(MultiGomoku::get_seed(app_id), None);
+        let expected_event = TestEvent::multi_gomoku(RawEvent::SeedForfeited(app_id, vec![players[0]]));
+        assert!(System::events().iter().any(|a| a.event == expected_event));
+    })
+}
+
+#[test]
+fn test_pass_arbitrate_after_settle_finalized_time() {
+    ExtBuilder::build().execute_with(|| {
+        let white: u8 = 2;
+        let alice_pair = account_pair("Alice");
+        let bob_pair = account_pair("Bob");
+        let (players, players_pair)
+            = get_sorted_peer(alice_pair, bob_pair);
+        let arbiter = account_pair("Carl").public();
+
+        let mut initiate_request = AppInitiateRequest {
+            nonce: 1,
+            players: players.clone(),
+            player_num: 2,
+            timeout: 2,
+            min_stone_offchain: 5,
+            max_stone_onchain: 5,
+            time_bank: 0,
+            increment: 0,
+            hashed_board: false,
+            encoding: StateEncoding::Scale,
+            eth_players: vec![],
+            arbiter: Some(players[1]),
+            seed_timeout: None,
+        };
+        assert_noop!(
+            MultiGomoku::app_initiate(
+                Origin::signed(players[0]),
+                initiate_request.clone()
+            ),
+            "arbiter is a player"
+        );
+        initiate_request.arbiter = Some(arbiter);
+        assert_ok!(MultiGomoku::app_initiate(
+            Origin::signed(players[0]),
+            initiate_request.clone())
+        );
+        let app_id = MultiGomoku::get_app_id(initiate_request.nonce, initiate_request.players);
+        assert_noop!(
+            MultiGomoku::arbitrate(
+                Origin::signed(arbiter),
+                app_id,
+                white
+            ),
+            "app not in settle or action mode"
+        );
+
+        place_stone_and_update_by_state(app_id, players.clone(), players_pair);
+        let settle_finalized_time = MultiGomoku::get_settle_finalized_time(app_id).unwrap();
+        System::set_block_number(settle_finalized_time + 1);
+        assert_noop!(
+            MultiGomoku::arbitrate(
+                Origin::signed(players[0]),
+                app_id,
+                white
+            ),
+            Error::<TestRuntime>::NotArbiter
+        );
+
+        // the ruling overrides the winner by timeout
+        assert_ok!(
+            MultiGomoku::arbitrate(
+                Origin::signed(arbiter),
+                app_id,
+                white
+            )
+        );
+        assert_ok!(
+            MultiGomoku::is_finalized(
+                Origin::signed(players[0]),
+                app_id
+            )
+        );
+        assert_ok!(
+            MultiGomoku::get_outcome(
+                Origin::signed(players[0]),
+                app_id,
+                white
+            )
+        );
+        let expected_ruling = Ruling {
+            arbiter: arbiter,
+            outcome: white,
+            block_number: settle_finalized_time + 1,
+        };
+        assert_eq!(MultiGomoku::ruling(app_id), Some(expected_ruling));
+        let expected_event = TestEvent::multi_gomoku(RawEvent::Arbitrated(app_id, arbiter, white));
+        assert!(System::events().iter().any(|a| a.event == expected_event));
+
+        // a finalized app is no longer ruled
+        assert_noop!(
+            MultiGomoku::arbitrate(
+                Origin::signed(arbiter),
+                app_id,
+                0
+            ),
+            "app not in settle or action mode"
+        );
+    })
+}
+
+#[test]
+fn test_pass_replay_moves_from_resume_point() {
+    ExtBuilder::build().execute_with(|| {
+        let nonce = 2;
+        let alice_pair = account_pair("Alice");
+        let bob_pair = account_pair("Bob");        
+        let (players, players_pair) 
+            = get_sorted_peer(alice_pair, bob_pair);
+        
+        let app_id = app_initiate(nonce, players.clone(), 2, 2, 5, 5);
+        
+        place_stone_and_update_by_state_two_times(app_id, players.clone(), players_pair);
+        let base_state = MultiGomoku::get_state(app_id, 2).unwrap();
+        let resume_point = MultiGomoku::resume_point(app_id).unwrap();
+        assert_eq!(resume_point.seq_num, 4);
+        assert_eq!(resume_point.state_hash, BlakeTwo256::hash(&base_state));
+
+        let settle_finalized_time = MultiGomoku::get_settle_finalized_time(app_id).unwrap();
+        System::set_block_number(settle_finalized_time + 1);
+
+        assert_ok!(
+            MultiGomoku::update_by_action(
+                Origin::signed(players[0]),
+                app_id,
+                vec![3, 12]
+            )
+        );
+        assert_ok!(
+            MultiGomoku::update_by_action(
+                Origin::signed(players[1]),
+                app_id,
+                vec![0, 4]
+            )
+        ); 
+
+        let move_history = MultiGomoku::move_history(app_id);
+        assert_eq!(move_history.len(), 2);
+        assert_eq!(move_history[0].player_id, 1);
+        assert_eq!((move_history[0].x, move_history[0].y), (3, 12));
+        assert_eq!(move_history[0].block_number, settle_finalized_time + 1);
+        assert_eq!(move_history[0].seq_num, 5);
+        assert_eq!(move_history[1].player_id, 2);
+        assert_eq!(move_history[1].seq_num, 6);
+
+        let replayed_state = MultiGomoku::replay_moves(app_id, base_state).unwrap();
+        assert_eq!(replayed_state, MultiGomoku::get_state(app_id, 2).unwrap());
+        assert_eq!(replayed_state[0], 1);
+        assert_eq!(replayed_state[1], 0);
+    })
+}
+
+#[test]
+fn test_pass_replay_moves_after_rotate_player_key() {
+    ExtBuilder::build().execute_with(|| {
+        let nonce = 2;
+        let alice_pair = account_pair("Alice");
+        let bob_pair = account_pair("Bob");
+        let (players, players_pair)
+            = get_sorted_peer(alice_pair, bob_pair);
+
+        let app_id = app_initiate(nonce, players.clone(), 2, 2, 5, 5);
+
+        place_stone_and_update_by_state_two_times(app_id, players.clone(), players_pair.clone());
+        let base_state = MultiGomoku::get_state(app_id, 2).unwrap();
+
+        let settle_finalized_time = MultiGomoku::get_settle_finalized_time(app_id).unwrap();
+        System::set_block_number(settle_finalized_time + 1);
+        assert_ok!(
+            MultiGomoku::update_by_action(
+                Origin::signed(players[0]),
+                app_id,
+                vec![3, 12]
+            )
+        );
+
+        // "Dave" sorts below players[1], so it takes the slot of players[0]
+        let new_pair = account_pair("Dave");
+        let rotation = KeyRotation {
+            app_id: app_id,
+            old_player: players[0],
+            new_player: new_pair.public(),
+            nonce: 0,
+        };
+        assert_ok!(
+            MultiGomoku::rotate_player_key(
+                Origin::signed(players[1]),
+                rotation.clone(),
+                players_pair[0].sign(&rotation.signing_payload()),
+                new_pair.sign(&rotation.signing_payload())
+            )
+        );
+        // the history keeps the player id of the move
+        assert_eq!(MultiGomoku::move_history(app_id)[0].player_id, 1);
+
+        assert_ok!(
+            MultiGomoku::update_by_action(
+                Origin::signed(players[1]),
+                app_id,
+                vec![0, 4]
+            )
+        );
+
+        let replayed_state = MultiGomoku::replay_moves(app_id, base_state).unwrap();
+        assert_eq!(replayed_state, MultiGomoku::get_state(app_id, 2).unwrap());
+        assert_eq!(replayed_state[0], 1);
+        assert_eq!(replayed_state[1], 0);
+
+        // no rotation once the game is over
+        assert_eq!(MultiGomoku::get_status(app_id), Some(AppStatus::Finalized));
+        let rotation = KeyRotation {
+            app_id: app_id,
+            old_player: players[1],
+            new_player: account_key("Carl"),
+            nonce: 1,
+        };
+        assert_noop!(
+            MultiGomoku::rotate_player_key(
+                Origin::signed(players[1]),
+                rotation.clone(),
+                players_pair[1].sign(&rotation.signing_payload()),
+                account_pair("Carl").sign(&rotation.signing_payload())
+            ),
+            "app state is finalized"
+        );
+    })
+}
+
+#[test]
+fn test_fail_replay_moves_with_different_base_state() {
+    ExtBuilder::build().execute_with(|| {
+        let nonce = 2;
+        let alice_pair = account_pair("Alice");
+        let bob_pair = account_pair("Bob");        
+        let (players, players_pair) 
+            = get_sorted_peer(alice_pair, bob_pair);
+        
+        let app_id = app_initiate(nonce, players.clone(), 2, 2, 5, 5);
+        
+        place_stone_and_update_by_state_two_times(app_id, players.clone(), players_pair);
+
+        assert_noop!(
+            MultiGomoku::replay_moves(app_id, vec![0; 228]),
+            "base state not match"
+        );
+    })
+}
+
+#[test]
+fn test_pass_update_by_state_records_stone_num() {
+    ExtBuilder::build().execute_with(|| {
+        let nonce = 2;
+        let alice_pair = account_pair("Alice");
+        let bob_pair = account_pair("Bob");        
+        let (players, players_pair) 
+            = get_sorted_peer(alice_pair, bob_pair);
+        
+        let app_id = app_initiate(nonce, players.clone(), 2, 2, 5, 5);
+        
+        place_stone_and_update_by_state(app_id, players.clone(), players_pair);
+        let gomoku_info = MultiGomoku::gmoku_info(app_id).unwrap();
+        assert_eq!(gomoku_info.gomoku_state.stone_num, Some(7));
+    })
+}
+
+#[test]
+fn test_fail_update_by_state_with_inconsistent_board() {
     ExtBuilder::build().execute_with(|| {
-        let nonce1 = 2;
+        let nonce = 2;
+        let black: u8 = 1;
+        let white: u8 = 2;
         let alice_pair = account_pair("Alice");
         let bob_pair = account_pair("Bob");        
         let (players, players_pair) 
             = get_sorted_peer(alice_pair, bob_pair);
         
-        let app_id = app_initiate(nonce1, players.clone(), 2, 2, 5, 5);
-        
-        place_stone_and_update_by_state_two_times(app_id, players.clone(), players_pair);
+        let app_id = app_initiate(nonce, players.clone(), 2, 2, 0, 5);
 
-        let settle_finalized_time = MultiGomoku::get_settle_finalized_time(app_id).unwrap();
-        System::set_block_number(settle_finalized_time + 1);
+        // black id out of range
+        let mut board_state = vec![0; 228];
+        board_state[1] = black;
+        board_state[2] = 3;
+        let state_proof = get_state_proof(1, board_state, 2, app_id, players_pair.clone());
+        assert_noop!(
+            MultiGomoku::update_by_state(
+                Origin::signed(players[0]),
+                state_proof
+            ),
+            Error::<TestRuntime>::InvalidBlackId
+        );
 
-        assert_ok!(
-            MultiGomoku::update_by_action(
+        // slot out of range
+        let mut board_state = vec![0; 228];
+        board_state[1] = black;
+        board_state[2] = 1;
+        board_state[3] = 3;
+        let state_proof = get_state_proof(1, board_state, 2, app_id, players_pair.clone());
+        assert_noop!(
+            MultiGomoku::update_by_state(
                 Origin::signed(players[0]),
-                app_id,
-                vec![3, 12]
-            )
+                state_proof
+            ),
+            "invalid slot state"
         );
 
+        // white is in turn after placing more stones than black
+        let mut board_state = vec![0; 228];
+        board_state[1] = white;
+        board_state[2] = 1;
+        board_state[3] = white;
+        let state_proof = get_state_proof(1, board_state, 2, app_id, players_pair.clone());
         assert_noop!(
-            MultiGomoku::update_by_action(
-                Origin::signed(players[1]),
-                app_id,
-                vec![3, 12]
+            MultiGomoku::update_by_state(
+                Origin::signed(players[0]),
+                state_proof
             ),
-            "slot is occupied"
+            "invalid turn state"
+        );
+
+        // black has won but white is still in turn
+        let mut board_state = vec![0; 228];
+        board_state[0] = black;
+        board_state[1] = white;
+        board_state[2] = 1;
+        for i in 3..8 {
+            board_state[i] = black; // (0, 0) to (0, 4)
+        }
+        for i in 101..105 {
+            board_state[i] = white;
+        }
+        let state_proof = get_state_proof(1, board_state, 2, app_id, players_pair.clone());
+        assert_noop!(
+            MultiGomoku::update_by_state(
+                Origin::signed(players[0]),
+                state_proof
+            ),
+            "invalid turn state"
+        );
+
+        // winner color without five in a row
+        let mut board_state = vec![0; 228];
+        board_state[0] = white;
+        board_state[2] = 1;
+        let state_proof = get_state_proof(1, board_state, 2, app_id, players_pair);
+        assert_noop!(
+            MultiGomoku::update_by_state(
+                Origin::signed(players[0]),
+                state_proof
+            ),
+            "winner not match board"
         );
     })
 }
 
 #[test]
-fn test_player2_places_stone_at_0_4_and_wins() {
+fn test_black_player_loses_on_time_bank_exhausted() {
     ExtBuilder::build().execute_with(|| {
-        let nonce = 2;
         let alice_pair = account_pair("Alice");
         let bob_pair = account_pair("Bob");        
         let (players, players_pair) 
             = get_sorted_peer(alice_pair, bob_pair);
-        
-        let app_id = app_initiate(nonce, players.clone(), 2, 2, 5, 5);
-        
+
+        let initiate_request = AppInitiateRequest {
+            nonce: 2,
+            players: players.clone(),
+            player_num: 2,
+            timeout: 2,
+            min_stone_offchain: 5,
+            max_stone_onchain: 5,
+            time_bank: 4,
+            increment: 1,
+            hashed_board: false,
+            encoding: StateEncoding::Scale,
+            eth_players: vec![],
+            arbiter: None,
+            seed_timeout: None,
+        };
+        assert_ok!(MultiGomoku::app_initiate(
+            Origin::signed(players[0]),
+            initiate_request.clone())
+        );
+        let app_id = MultiGomoku::get_app_id(initiate_request.nonce, initiate_request.players);
+
         place_stone_and_update_by_state_two_times(app_id, players.clone(), players_pair);
 
+        // clock of white player starts when settle period passes
         let settle_finalized_time = MultiGomoku::get_settle_finalized_time(app_id).unwrap();
-        System::set_block_number(settle_finalized_time + 1);
+        assert_eq!(
+            MultiGomoku::get_action_deadline(app_id).unwrap(),
+            settle_finalized_time + 4
+        );
 
+        // white player spends 1 block of the time bank and gains 1 block of increment
+        System::set_block_number(settle_finalized_time + 1);
         assert_ok!(
             MultiGomoku::update_by_action(
                 Origin::signed(players[0]),
@@ -297,18 +1649,25 @@ fn test_player2_places_stone_at_0_4_and_wins() {
                 vec![3, 12]
             )
         );
+        let gomoku_info = MultiGomoku::gmoku_info(app_id).unwrap();
+        assert_eq!(gomoku_info.gomoku_state.time_banks, vec![4, 4]);
+        assert_eq!(
+            MultiGomoku::get_action_deadline(app_id).unwrap(),
+            settle_finalized_time + 5
+        );
 
-        assert_ok!(
+        // black player runs out of time
+        System::set_block_number(settle_finalized_time + 6);
+        assert_noop!(
             MultiGomoku::update_by_action(
                 Origin::signed(players[1]),
                 app_id,
                 vec![0, 4]
-            )
-        ); 
-        let turn = MultiGomoku::get_state(app_id, 0).unwrap();
-        assert_eq!(turn, vec![0]);
+            ),
+            "time bank exhausted"
+        );
         assert_ok!(
-            MultiGomoku::is_finalized(
+            MultiGomoku::finalize_on_action_timeout(
                 Origin::signed(players[0]),
                 app_id
             )
@@ -317,143 +1676,118 @@ fn test_player2_places_stone_at_0_4_and_wins() {
             MultiGomoku::get_outcome(
                 Origin::signed(players[0]),
                 app_id,
-                1
+                2
             )
         );
     })
 }
 
 #[test]
-fn test_fail_not_player_places_stone() {
+fn test_pass_update_by_action_with_hashed_board() {
     ExtBuilder::build().execute_with(|| {
-        let nonce = 2;
         let alice_pair = account_pair("Alice");
         let bob_pair = account_pair("Bob");        
         let (players, players_pair) 
             = get_sorted_peer(alice_pair, bob_pair);
-        
-        let app_id = app_initiate(nonce, players.clone(), 2, 2, 5, 5);
-        
-        place_stone_and_update_by_state_two_times(app_id, players.clone(), players_pair);
 
-        let settle_finalized_time = MultiGomoku::get_settle_finalized_time(app_id).unwrap();
-        System::set_block_number(settle_finalized_time + 1);
-
-        let risa = account_key("Risa"); // not app player
-        assert_noop!(
-            MultiGomoku::update_by_action(
-                Origin::signed(risa),
-                app_id,
-                vec![3, 12]
-            ),
-            "Not your turn"
+        let initiate_request = AppInitiateRequest {
+            nonce: 2,
+            players: players.clone(),
+            player_num: 2,
+            timeout: 2,
+            min_stone_offchain: 5,
+            max_stone_onchain: 5,
+            time_bank: 0,
+            increment: 0,
+            hashed_board: true,
+            encoding: StateEncoding::Scale,
+            eth_players: vec![],
+            arbiter: None,
+            seed_timeout: None,
+        };
+        assert_ok!(MultiGomoku::app_initiate(
+            Origin::signed(players[0]),
+            initiate_request.clone())
         );
-    })
-}
+        let app_id = MultiGomoku::get_app_id(initiate_request.nonce, initiate_request.players);
 
-#[test]
-fn test_fail_finalize_on_action_timeout_before_action_deadline() {
-    ExtBuilder::build().execute_with(|| {
-        let nonce = 2;
-        let alice_pair = account_pair("Alice");
-        let bob_pair = account_pair("Bob");        
-        let (players, players_pair) 
-            = get_sorted_peer(alice_pair, bob_pair);
-        
-        let app_id = app_initiate(nonce, players.clone(), 2, 2, 5, 5);
-        
         place_stone_and_update_by_state_two_times(app_id, players.clone(), players_pair);
 
+        // only the hash of the board is stored
+        assert_eq!(MultiGomoku::get_state(app_id, 2), None);
+
+        let mut board_state = vec![0; 228];
+        board_state[1] = 2; // turn color
+        board_state[2] = 2; // black player id
+        board_state[3] = 1; // (0, 0)
+        board_state[4] = 1; // (0, 1)
+        board_state[5] = 1; // (0, 2)
+        board_state[6] = 1; // (0, 3)
+        board_state[101] = 2;
+        board_state[102] = 2;
+        board_state[103] = 2;
+
         let settle_finalized_time = MultiGomoku::get_settle_finalized_time(app_id).unwrap();
         System::set_block_number(settle_finalized_time + 1);
-
-        assert_ok!(
-            MultiGomoku::update_by_action(
+        let mut wrong_witness = board_state.clone();
+        wrong_witness[3] = 0;
+        assert_noop!(
+            MultiGomoku::update_by_action_with_witness(
                 Origin::signed(players[0]),
                 app_id,
-                vec![3, 12]
-            )
+                vec![3, 12],
+                wrong_witness
+            ),
+            "board witness not match"
         );
-
+        // the slots match the hash but the turn color does not match the record
+        let mut wrong_witness = board_state.clone();
+        wrong_witness[1] = 1;
         assert_noop!(
-            MultiGomoku::finalize_on_action_timeout(
+            MultiGomoku::update_by_action_with_witness(
                 Origin::signed(players[0]),
-                app_id
+                app_id,
+                vec![3, 12],
+                wrong_witness
             ),
-            "deadline does not passes"
+            "board witness not match"
         );
-    })
-}
-
-#[test]
-fn test_pass_finalize_on_action_timeout_after_action_deadline() {
-    ExtBuilder::build().execute_with(|| {
-        let nonce = 2;
-        let alice_pair = account_pair("Alice");
-        let bob_pair = account_pair("Bob");        
-        let (players, players_pair) 
-            = get_sorted_peer(alice_pair, bob_pair);
-        
-        let app_id = app_initiate(nonce, players.clone(), 2, 2, 5, 5);
-        
-        let none: u8 = 0;
-        let black: u8 = 1;
-        let white: u8 = 2;
-        let black_player_id2 = 1;
-
-        let mut board_state = vec![0; 228];
-        board_state[0] = none; 
-        board_state[1] = white; // turn color
-        board_state[2] = black_player_id2;
-        board_state[3] = black; // (0, 0)
-        board_state[4] = black; // (0, 1)
-        board_state[5] = black; // (0, 2)
-        board_state[6] = black; // (0, 3)
-        board_state[101] = white;
-        board_state[102] = white;
-        board_state[103] = white;
-
-        let state_proof = get_state_proof(3, board_state, 2, app_id, players_pair);
         assert_ok!(
-            MultiGomoku::update_by_state(
+            MultiGomoku::update_by_action_with_witness(
                 Origin::signed(players[0]),
-                state_proof
+                app_id,
+                vec![3, 12],
+                board_state.clone()
             )
         );
 
-        let settle_finalized_time = MultiGomoku::get_settle_finalized_time(app_id).unwrap();
-        System::set_block_number(settle_finalized_time + 1);
-
+        // the board after the action is emitted as the next witness
+        board_state[1] = 1; // turn color
+        board_state[60] = 2; // (3, 12)
+        let expected_event = TestEvent::multi_gomoku(RawEvent::BoardUpdated(app_id, 5, board_state.clone()));
+        assert!(System::events().iter().any(|a| a.event == expected_event));
         assert_ok!(
-            MultiGomoku::update_by_action(
+            MultiGomoku::update_by_action_with_witness(
                 Origin::signed(players[1]),
                 app_id,
-                vec![3, 12]
-            )
-        );
-
-        let deadline = MultiGomoku::get_action_deadline(app_id).unwrap();
-        System::set_block_number(deadline + 1);
-
-        assert_ok!(
-            MultiGomoku::finalize_on_action_timeout(
-                Origin::signed(players[0]),
-                app_id
-            )
-        );
-        assert_ok!(
-            MultiGomoku::is_finalized(
-                Origin::signed(players[0]),
-                app_id
+                vec![0, 4],
+                board_state.clone()
             )
         );
         assert_ok!(
             MultiGomoku::get_outcome(
                 Origin::signed(players[0]),
                 app_id,
-                2
+                1
             )
         );
+
+        // the winner is kept in the record and the hash still commits to the slots
+        board_state[7] = 1; // (0, 4)
+        let record = MultiGomoku::gmoku_info(app_id).unwrap().gomoku_state.board_state.unwrap();
+        assert_eq!(record.winner_color, 1);
+        assert_eq!(record.turn_color, 0);
+        assert_eq!(record.board, BoardRecord::Hashed(BlakeTwo256::hash(&board_state[3..])));
     })
 }
 
@@ -472,6 +1806,13 @@ fn app_initiate(
         timeout: timeout,
         min_stone_offchain: min_stone_offchain,
         max_stone_onchain: max_stone_onchain,
+        time_bank: 0,
+        increment: 0,
+        hashed_board: false,
+        encoding: StateEncoding::Scale,
+        eth_players: vec![],
+        arbiter: None,
+        seed_timeout: None,
     };
 
     assert_ok!(MultiGomoku::app_initiate(
@@ -483,12 +1824,101 @@ fn app_initiate(
     return app_id;
 }
 
+#[test]
+fn test_pass_watchtower_answers_stale_settle() {
+    let (mut ext, pool_state) = ExtBuilder::build_with_watchtower();
+    ext.execute_with(|| {
+        System::set_block_number(1);
+        let alice_pair = account_pair("Alice");
+        let bob_pair = account_pair("Bob");
+        let (players, players_pair)
+            = get_sorted_peer(alice_pair, bob_pair);
+
+        let initiate_request = AppInitiateRequest {
+            nonce: 0,
+            players: players.clone(),
+            player_num: 2,
+            timeout: 2,
+            min_stone_offchain: 5,
+            max_stone_onchain: 5,
+            time_bank: 0,
+            increment: 0,
+            hashed_board: false,
+            encoding: StateEncoding::Scale,
+            eth_players: vec![],
+            arbiter: None,
+            seed_timeout: None,
+        };
+        assert_ok!(MultiGomoku::app_initiate(
+            Origin::signed(players[0]),
+            initiate_request.clone())
+        );
+
+        let app_id = MultiGomoku::get_app_id(initiate_request.nonce, initiate_request.players.clone());
+        let mut board_state = vec![0; 228];
+        board_state[1] = 1; // turn color
+        board_state[2] = 2; // black id
+        board_state[3] = 2;
+        board_state[4] = 2;
+        board_state[5] = 1;
+        board_state[6] = 1;
+        board_state[7] = 2;
+        board_state[8] = 2;
+        board_state[9] = 1;
+        let stale_proof = get_state_proof(1, board_state.clone(), 2, app_id, players_pair.clone());
+        let latest_proof = get_state_proof(3, board_state, 2, app_id, players_pair);
+        MultiGomoku::watch_state_proof(latest_proof.clone());
+
+        // nothing to answer before settlement
+        MultiGomoku::offchain_worker(1);
+        assert!(pool_state.read().transactions.is_empty());
+
+        assert_ok!(
+            MultiGomoku::update_by_state(
+                Origin::signed(players[0]),
+                stale_proof
+            )
+        );
+        MultiGomoku::offchain_worker(1);
+        let tx = pool_state.write().transactions.pop().unwrap();
+        assert!(pool_state.read().transactions.is_empty());
+        let tx = Extrinsic::decode(&mut &*tx).unwrap();
+        assert_eq!(tx.signature.unwrap().0, 0);
+        assert_eq!(tx.call, mock::Call::MultiGomoku(crate::Call::update_by_state(latest_proof.clone())));
+
+        // the answer is not repeated until the chain state changes
+        MultiGomoku::offchain_worker(1);
+        assert!(pool_state.read().transactions.is_empty());
+
+        // latest state is settled, nothing left to answer
+        assert_ok!(
+            MultiGomoku::update_by_state(
+                Origin::signed(players[1]),
+                latest_proof
+            )
+        );
+        MultiGomoku::offchain_worker(2);
+        assert!(pool_state.read().transactions.is_empty());
+    })
+}
+
 fn get_state_proof(
     seq: u128,
     board_state: Vec<u8>,
     timeout: BlockNumber,
     app_id: H256,
     players_pair: Vec<sr25519::Pair>,
+) -> StateProof<BlockNumber, H256, Signature> {
+    get_encoded_state_proof(seq, board_state, timeout, app_id, players_pair, StateEncoding::Scale)
+}
+
+fn get_encoded_state_proof(
+    seq: u128,
+    board_state: Vec<u8>,
+    timeout: BlockNumber,
+    app_id: H256,
+    players_pair: Vec<sr25519::Pair>,
+    encoding: StateEncoding,
 ) -> StateProof<BlockNumber, H256, Signature> {
     let app_state = AppState {
         seq_num: seq,
@@ -496,7 +1926,7 @@ fn get_state_proof(
         timeout: timeout,
         app_id: app_id,
     };
-    let encoded = MultiGomoku::encode_app_state(app_state.clone());
+    let encoded = app_state.payload(encoding).unwrap();
     let sig_1 = players_pair[0].sign(&encoded);
     let sig_2 = players_pair[1].sign(&encoded);
     let state_proof = StateProof {
@@ -507,6 +1937,32 @@ fn get_state_proof(
     return state_proof;
 }
 
+fn get_eth_state_proof(
+    seq: u128,
+    board_state: Vec<u8>,
+    timeout: BlockNumber,
+    app_id: H256,
+    eth_pairs: Vec<ethereum::Pair>,
+) -> EthStateProof<BlockNumber, H256> {
+    let app_state = AppState {
+        seq_num: seq,
+        board_state: board_state,
+        timeout: timeout,
+        app_id: app_id,
+    };
+    let encoded = app_state.ethereum_payload().unwrap();
+    let sigs = eth_pairs.iter().map(|pair| pair.sign(&encoded)).collect();
+
+    StateProof {
+        app_state: app_state,
+        sigs: sigs,
+    }
+}
+
+fn eth_pair(seed: u8) -> ethereum::Pair {
+    ethereum::Pair::from_secret(&[seed; 32]).unwrap()
+}
+
 fn place_stone_and_update_by_state(
     app_id: H256, 
     players: Vec<AccountId>, 
@@ -0,0 +1,145 @@
+//! Encoded types of multi gomoku for SCALE clients
+
+use super::*;
+
+capps_type_defs::type_defs! {
+    enum StateEncoding {
+        Scale,
+        Protobuf,
+        Ethereum,
+    }
+    struct AppInitiateRequest<AccountId, BlockNumber> {
+        nonce: u128,
+        player_num: u8,
+        players: Vec<AccountId>,
+        timeout: BlockNumber,
+        min_stone_offchain: u8,
+        max_stone_onchain: u8,
+        time_bank: BlockNumber,
+        increment: BlockNumber,
+        hashed_board: bool,
+        encoding: StateEncoding,
+        eth_players: Vec<H160>,
+        arbiter: Option<AccountId>,
+        seed_timeout: Option<BlockNumber>,
+    }
+    type AppInitiateRequestOf = AppInitiateRequest;
+    struct AppState<BlockNumber, Hash> {
+        seq_num: u128,
+        board_state: Vec<u8>,
+        timeout: BlockNumber,
+        app_id: Hash,
+    }
+    type AppStateOf = AppState;
+    struct StateProof<BlockNumber, Hash, Signature> {
+        app_state: AppState<BlockNumber, Hash>,
+        sigs: Vec<Signature>,
+    }
+    type StateProofOf = StateProof;
+    struct PlayerSig<Signature> {
+        player: u8,
+        sig: Signature,
+    }
+    struct IndexedStateProof<BlockNumber, Hash, Signature> {
+        app_state: AppState<BlockNumber, Hash>,
+        sigs: Vec<PlayerSig<Signature>>,
+    }
+    type IndexedStateProofOf = IndexedStateProof;
+    struct SessionKeyDelegation<AccountId, BlockNumber, Hash> {
+        app_id: Hash,
+        player: AccountId,
+        session_key: AccountId,
+        expires_at: BlockNumber,
+    }
+    type SessionKeyDelegationOf = SessionKeyDelegation;
+    struct KeyRotation<AccountId, Hash> {
+        app_id: Hash,
+        old_player: AccountId,
+        new_player: AccountId,
+        nonce: u32,
+    }
+    type KeyRotationOf = KeyRotation;
+    struct SignedMove<Signature> {
+        action: Vec<u8>,
+        sig: Signature,
+    }
+    struct EthStateProof<BlockNumber, Hash> {
+        app_state: AppState<BlockNumber, Hash>,
+        sigs: Vec<EcdsaSignature>,
+    }
+    type EthStateProofOf = EthStateProof;
+    enum AppStatus {
+        Idle,
+        Settle,
+        Action,
+        Finalized,
+    }
+    struct GomokuInfo<AccountId, BlockNumber, Hash> {
+        players: Vec<AccountId>,
+        player_num: u8,
+        seq_num: u128,
+        timeout: BlockNumber,
+        deadline: BlockNumber,
+        status: AppStatus,
+        gomoku_state: GomokuState<BlockNumber, Hash>,
+        encoding: StateEncoding,
+        eth_players: Vec<H160>,
+        arbiter: Option<AccountId>,
+    }
+    type GomokuInfoOf = GomokuInfo;
+    enum StateKey {
+        TurnColor,
+        WinnerColor,
+        FullState,
+    }
+    struct GomokuState<BlockNumber, Hash> {
+        board_state: Option<BoardState<BoardRecord<Hash>>>,
+        stone_num: Option<u16>,
+        stone_num_onchain: Option<u16>,
+        state_key: Option<StateKey>,
+        min_stone_offchain: u8,
+        max_stone_onchain: u8,
+        time_banks: Vec<BlockNumber>,
+        increment: BlockNumber,
+        hashed_board: bool,
+    }
+    struct BoardState<Hash> as BoardState<BoardRecord<Hash>> {
+        winner_color: u8,
+        turn_color: u8,
+        black_id: u8,
+        board: BoardRecord<Hash>,
+    }
+    enum BoardRecord<Hash> {
+        Packed(Board),
+        Hashed(Hash),
+    }
+    // 15 rows of 2 bits per slot, see `gomoku_engine::Board`
+    type Board = [u32; 15];
+    struct MoveRecord<BlockNumber> {
+        player_id: u8,
+        x: u8,
+        y: u8,
+        block_number: BlockNumber,
+        seq_num: u128,
+    }
+    type MoveRecordOf = MoveRecord;
+    struct ResumePoint<Hash> {
+        seq_num: u128,
+        state_hash: Hash,
+    }
+    type ResumePointOf = ResumePoint;
+    struct Ruling<AccountId, BlockNumber> {
+        arbiter: AccountId,
+        outcome: u8,
+        block_number: BlockNumber,
+    }
+    type RulingOf = Ruling;
+    struct SeedRound<BlockNumber, Hash> {
+        commits: Vec<Option<Hash>>,
+        secrets: Vec<Option<Hash>>,
+        timeout: BlockNumber,
+        deadline: BlockNumber,
+        seed: Option<Hash>,
+    }
+    type SeedRoundOf = SeedRound;
+}
@@ -0,0 +1,98 @@
+//! Watchtower of multi gomoku apps, see `capps_watchtower`
+//!
+//! Runtimes enable it by setting `SignedWatchtower` as `Trait::Watchtower`
+//! and disable it with `()`.
+
+use super::*;
+use capps_watchtower::{SettleCall, SignedSubmitter, Watched, WatchedProof, WatchedStatus};
+
+pub use capps_watchtower::{crypto, KEY_TYPE};
+
+/// Offchain local storage key of the list of watched app ids
+pub const WATCHED_APPS_KEY: &[u8] = b"multi_gomoku::watchtower::apps";
+
+/// Prefix of the offchain local storage key of the latest state proof of an app
+const PROOF_KEY_PREFIX: &[u8] = b"multi_gomoku::watchtower::proof::";
+
+/// Offchain local storage key of the latest state proof of an app
+///
+/// Parameter:
+/// `app_id`: Id of app
+pub fn watched_proof_key<Hash: Encode>(app_id: &Hash) -> Vec<u8> {
+    capps_watchtower::proof_key(PROOF_KEY_PREFIX, app_id)
+}
+
+/// Watched state proof of an app, signed by players or by Ethereum accounts of players
+pub type WatchedProofOf<T> = WatchedProof<StateProofOf<T>, EthStateProofOf<T>>;
+
+/// Watchtower submitting `update_by_state` and `update_by_eth_state`
+/// transactions signed by an `AuthorityId` key in the node keystore
+pub type SignedWatchtower<T, AuthorityId> = SignedSubmitter<T, Call<T>, AuthorityId>;
+
+impl<T: Trait> SettleCall<WatchedProofOf<T>> for Call<T> {
+    fn settle(state_proof: WatchedProofOf<T>) -> Self {
+        match state_proof {
+            WatchedProof::Players(state_proof) => Call::update_by_state(state_proof),
+            WatchedProof::Ethereum(state_proof) => Call::update_by_eth_state(state_proof),
+        }
+    }
+}
+
+impl<T: Trait> Watched for Module<T> {
+    type Id = T::Hash;
+    type StateProof = WatchedProofOf<T>;
+    type Submitter = T::Watchtower;
+
+    const WATCHED_IDS_KEY: &'static [u8] = WATCHED_APPS_KEY;
+    const PROOF_KEY_PREFIX: &'static [u8] = PROOF_KEY_PREFIX;
+
+    fn proof_id(state_proof: &WatchedProofOf<T>) -> T::Hash {
+        match state_proof {
+            WatchedProof::Players(state_proof) => state_proof.app_state.app_id,
+            WatchedProof::Ethereum(state_proof) => state_proof.app_state.app_id,
+        }
+    }
+
+    fn proof_seq_num(state_proof: &WatchedProofOf<T>) -> u128 {
+        match state_proof {
+            WatchedProof::Players(state_proof) => state_proof.app_state.seq_num,
+            WatchedProof::Ethereum(state_proof) => state_proof.app_state.seq_num,
+        }
+    }
+
+    fn status(app_id: T::Hash) -> WatchedStatus {
+        match Self::get_status(app_id) {
+            Some(AppStatus::Settle) => WatchedStatus::Settle,
+            Some(AppStatus::Finalized) => WatchedStatus::Finalized,
+            _ => WatchedStatus::Other,
+        }
+    }
+
+    fn seq_num(app_id: T::Hash) -> u128 {
+        Self::get_seq_num(app_id).unwrap_or(0)
+    }
+
+    fn settle_events() -> Vec<(T::Hash, u128)> {
+        let probe = <T as Trait>::Event::from(Event::<T>::IntendSettle(Default::default(), 0));
+        capps_watchtower::block_events::<T, _>(probe.into())
+    }
+}
+
+impl<T: Trait> Module<T> {
+    /// Watch an app with a co-signed state proof, keeping the proof of the highest sequence number
+    ///
+    /// Parameter:
+    /// `state_proof`: Signed off-chain app state
+    pub fn watch_state_proof(state_proof: StateProofOf<T>) {
+        capps_watchtower::watch_state_proof::<Self>(WatchedProof::Players(state_proof))
+    }
+
+    /// Watch an app with a state proof signed by Ethereum accounts of players,
+    /// keeping the proof of the highest sequence number
+    ///
+    /// Parameter:
+    /// `state_proof`: Off-chain app state signed by Ethereum accounts
+    pub fn watch_eth_state_proof(state_proof: EthStateProofOf<T>) {
+        capps_watchtower::watch_state_proof::<Self>(WatchedProof::Ethereum(state_proof))
+    }
+}
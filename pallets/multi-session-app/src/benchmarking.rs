@@ -0,0 +1,51 @@
+//! Benchmarks of multi session app
+
+use super::*;
+use capps_batch_verify::benchmarking::{sign, sorted_players};
+use frame_benchmarking::{account, benchmarks};
+use frame_system::RawOrigin;
+
+const SEED: u32 = 0;
+const MAX_PLAYERS: u32 = 100;
+
+benchmarks! {
+    _ { }
+
+    // Dispute with a state co-signed by every player of the session
+    update_by_state {
+        let n in 2 .. MAX_PLAYERS;
+        let caller: T::AccountId = account("caller", 0, SEED);
+        let (players, keys): (Vec<T::AccountId>, Vec<_>) = sorted_players::<T::AccountId>(n)
+            .into_iter()
+            .unzip();
+        let timeout = T::BlockNumber::from(10u32);
+        let initiate_request = SessionInitiateRequestOf::<T> {
+            nonce: 0,
+            player_num: n as u8,
+            players: players.clone(),
+            timeout: timeout,
+            encoding: StateEncoding::Scale,
+            eth_players: vec![],
+            arbiter: None,
+            seed_timeout: None,
+            threshold: n as u8,
+        };
+        Module::<T>::session_initiate(RawOrigin::Signed(caller.clone()).into(), initiate_request)?;
+
+        let session_id = Module::<T>::get_session_id(0, players);
+        let app_state = AppStateOf::<T> {
+            seq_num: 1,
+            state: 3,
+            timeout: timeout,
+            session_id: session_id,
+        };
+        let encoded = app_state.signing_payload();
+        let state_proof = StateProofOf::<T> {
+            app_state: app_state,
+            sigs: keys.iter().map(|key| sign(key, &encoded)).collect(),
+        };
+    }: _(RawOrigin::Signed(caller), state_proof)
+    verify {
+        assert_eq!(Module::<T>::get_seq_num(session_id), Some(1));
+    }
+}
@@ -1,30 +1,49 @@
 #![cfg_attr(not(feature = "std"), no_std)]
 
 mod mock;
+mod watchtower;
+pub mod types;
+#[cfg(feature = "runtime-benchmarks")]
+mod benchmarking;
 #[cfg(test)]
 mod tests;
 
 use codec::{Decode, Encode};
 use frame_support::{
     decl_module, decl_storage, decl_event, decl_error, ensure,
-    storage::StorageMap,
+    storage::{StorageDoubleMap, StorageMap},
     traits::Get,
 };
 use frame_system::{self as system, ensure_signed};
 use sp_runtime::{DispatchResult, DispatchError};
 use sp_runtime::traits::{
-    Hash, IdentifyAccount, AccountIdConversion, 
+    Hash, IdentifyAccount, UniqueSaturatedInto, AccountIdConversion, 
     Member, Verify, Zero,
 };
 use sp_runtime::{ModuleId, RuntimeDebug};
-use sp_std::{prelude::*, vec::Vec};
+use sp_std::{convert::TryInto, prelude::*, vec::Vec};
+use sp_core::{H160, H256};
+use capps_batch_verify::verify_all;
+use capps_state_encoding::ethereum;
+
+pub use capps_app_common::{
+    combined_seed, seed_commitment, KeyRotation, Ruling, SeedRound, SessionKeyDelegation,
+    KEY_ROTATION_PREFIX, SESSION_KEY_DELEGATION_PREFIX,
+};
+pub use capps_state_encoding::{ethereum::EcdsaSignature, StateEncoding};
+pub use watchtower::{crypto, watched_proof_key, SignedWatchtower, WatchedProofOf, KEY_TYPE, WATCHED_SESSIONS_KEY};
 
 #[derive(Clone, Eq, PartialEq, PartialOrd, Ord, Encode, Decode, RuntimeDebug)]
 pub struct SessionInitiateRequest<AccountId, BlockNumber> {
-    nonce: u128,
-    player_num: u8,
-    players: Vec<AccountId>,
-    timeout: BlockNumber,
+    pub nonce: u128,
+    pub player_num: u8,
+    pub players: Vec<AccountId>,
+    pub timeout: BlockNumber,
+    pub encoding: StateEncoding,
+    pub eth_players: Vec<H160>,
+    pub threshold: u8, // number of players whose signatures make a threshold state proof valid
+    pub arbiter: Option<AccountId>, // account ruling the outcome in settle or action mode
+    pub seed_timeout: Option<BlockNumber>, // period to commit and then to reveal a random seed, None for no random seed
 }
 
 pub type SessionInitiateRequestOf<T> = SessionInitiateRequest<
@@ -34,10 +53,10 @@ pub type SessionInitiateRequestOf<T> = SessionInitiateRequest<
 
 #[derive(Clone, Eq, PartialEq, PartialOrd, Ord, Encode, Decode, RuntimeDebug)]
 pub struct AppState<BlockNumber, Hash> {
-    seq_num: u128,
-    state: u8,
-    timeout: BlockNumber,
-    session_id: Hash,
+    pub seq_num: u128,
+    pub state: u8,
+    pub timeout: BlockNumber,
+    pub session_id: Hash,
 }
 
 pub type AppStateOf<T> = AppState<
@@ -47,8 +66,8 @@ pub type AppStateOf<T> = AppState<
 
 #[derive(Clone, Eq, PartialEq, PartialOrd, Ord, Encode, Decode, RuntimeDebug)]
 pub struct StateProof<BlockNumber, Hash, Signature> {
-    app_state: AppState<BlockNumber, Hash>,
-    sigs: Vec<Signature>,
+    pub app_state: AppState<BlockNumber, Hash>,
+    pub sigs: Vec<Signature>,
 }
 
 pub type StateProofOf<T> = StateProof<
@@ -57,6 +76,95 @@ pub type StateProofOf<T> = StateProof<
     <T as Trait>::Signature,
 >;
 
+/// Signature of the player at an index of session players
+#[derive(Clone, Eq, PartialEq, PartialOrd, Ord, Encode, Decode, RuntimeDebug)]
+pub struct PlayerSig<Signature> {
+    pub player: u8,
+    pub sig: Signature,
+}
+
+/// State proof signed by at least threshold players
+pub type ThresholdStateProof<BlockNumber, Hash, Signature> = StateProof<BlockNumber, Hash, PlayerSig<Signature>>;
+
+pub type ThresholdStateProofOf<T> = ThresholdStateProof<
+    <T as system::Trait>::BlockNumber,
+    <T as system::Trait>::Hash,
+    <T as Trait>::Signature,
+>;
+
+pub type SessionKeyDelegationOf<T> = SessionKeyDelegation<
+    <T as system::Trait>::AccountId,
+    <T as system::Trait>::BlockNumber,
+    <T as system::Trait>::Hash,
+>;
+
+pub type KeyRotationOf<T> = KeyRotation<
+    <T as system::Trait>::AccountId,
+    <T as system::Trait>::Hash,
+>;
+
+/// State proof signed by Ethereum accounts of players
+pub type EthStateProof<BlockNumber, Hash> = StateProof<BlockNumber, Hash, EcdsaSignature>;
+
+pub type EthStateProofOf<T> = EthStateProof<
+    <T as system::Trait>::BlockNumber,
+    <T as system::Trait>::Hash,
+>;
+
+impl<BlockNumber: Encode, Hash: Encode> AppState<BlockNumber, Hash> {
+    /// Encode app state into the payload signed by players
+    pub fn signing_payload(&self) -> Vec<u8> {
+        let mut encoded = self.seq_num.encode();
+        encoded.extend(self.state.encode());
+        encoded.extend(self.timeout.encode());
+        encoded.extend(self.session_id.encode());
+
+        return encoded;
+    }
+}
+
+impl<BlockNumber: Encode + Clone + TryInto<u64>, Hash: Encode> AppState<BlockNumber, Hash> {
+    /// Encode app state into the protobuf `AppState` message signed by Celer's clients,
+    /// with the session id as app id and no nonce
+    ///
+    /// Returns `None` if a number does not fit in a `uint64` field.
+    pub fn protobuf_payload(&self) -> Option<Vec<u8>> {
+        capps_state_encoding::protobuf::app_state(
+            0,
+            self.seq_num,
+            &[self.state],
+            self.timeout.clone().try_into().ok()?,
+            &self.session_id.encode(),
+        )
+    }
+
+    /// Encode app state as `abi.encodePacked` signed by Ethereum accounts of players,
+    /// with the session id as app id and no nonce
+    ///
+    /// Returns `None` if the timeout does not fit in a `uint64`.
+    pub fn ethereum_payload(&self) -> Option<Vec<u8>> {
+        Some(ethereum::app_state(
+            None,
+            self.seq_num,
+            &[self.state],
+            self.timeout.clone().try_into().ok()?,
+            &self.session_id.encode(),
+        ))
+    }
+
+    /// Encode app state into the payload signed by players under an encoding
+    ///
+    /// Parameter:
+    /// `encoding`: Encoding chosen at initiation
+    pub fn payload(&self, encoding: StateEncoding) -> Option<Vec<u8>> {
+        match encoding {
+            StateEncoding::Scale => Some(self.signing_payload()),
+            StateEncoding::Protobuf => self.protobuf_payload(),
+            StateEncoding::Ethereum => self.ethereum_payload(),
+        }
+    }
+}
+
 #[derive(Clone, Eq, PartialEq, PartialOrd, Ord, Encode, Decode, RuntimeDebug)]
 pub enum SessionStatus {
     Idle = 0,
@@ -74,6 +182,10 @@ pub struct SessionInfo<AccountId, BlockNumber> {
     timeout: BlockNumber,
     deadline: BlockNumber,
     status: SessionStatus,
+    encoding: StateEncoding,
+    eth_players: Vec<H160>,
+    threshold: u8,
+    arbiter: Option<AccountId>,
 }
 
 pub type SessionInfoOf<T> = SessionInfo<
@@ -81,18 +193,81 @@ pub type SessionInfoOf<T> = SessionInfo<
     <T as system::Trait>::BlockNumber,
 >;
 
+pub type RulingOf<T> = Ruling<
+    <T as system::Trait>::AccountId,
+    <T as system::Trait>::BlockNumber,
+>;
+
+pub type SeedRoundOf<T> = SeedRound<
+    <T as system::Trait>::BlockNumber,
+    <T as system::Trait>::Hash,
+>;
+
 pub const MULTI_SESSION_APP_ID: ModuleId = ModuleId(*b"_multi__");
 
+/// Get Id of session from its nonce and players, without a runtime
+///
+/// Parameters:
+/// `nonce`: Nonce of session
+/// `players`: AccountId of players
+pub fn session_id<AccountId, Hashing>(
+    nonce: u128,
+    players: &[AccountId],
+) -> Hashing::Output
+where
+    AccountId: Encode + Decode + Default,
+    Hashing: Hash,
+{
+    let app_account: AccountId = MULTI_SESSION_APP_ID.into_account();
+    let mut encoded = app_account.encode();
+    encoded.extend(nonce.encode());
+    players.iter()
+        .for_each(|player| { encoded.extend(player.encode()); });
+
+    Hashing::hash(&encoded)
+}
+
+/// Get Id of session signed by Ethereum accounts, hashed with keccak256 as
+/// Ethereum contracts do
+///
+/// Parameters:
+/// `nonce`: Nonce of session
+/// `eth_players`: Ethereum addresses of players
+pub fn eth_session_id(nonce: u128, eth_players: &[H160]) -> H256 {
+    ethereum::app_id(MULTI_SESSION_APP_ID.0, nonce, eth_players)
+}
+
+/// Signatures of an off-chain state proof
+enum StateSigs<Signature> {
+    /// Signatures of player accounts
+    Players(Vec<Signature>),
+    /// Signatures of Ethereum accounts of players
+    Ethereum(Vec<EcdsaSignature>),
+    /// Signatures of at least threshold player accounts
+    Threshold(Vec<PlayerSig<Signature>>),
+}
+
 pub trait Trait: system::Trait {
     type Event: From<Event<Self>> + Into<<Self as system::Trait>::Event>;
     type Public: IdentifyAccount<AccountId = Self::AccountId>;
-    type Signature: Verify<Signer = <Self as Trait>::Public> + Member + Decode + Encode; 
+    type Signature: Verify<Signer = <Self as Trait>::Public> + Member + Decode + Encode;
+    /// Watchtower submitting state proofs from the off-chain worker, `()` for none
+    type Watchtower: capps_watchtower::SubmitProof<WatchedProofOf<Self>>;
 }
 
 decl_storage! {
     trait Store for Module<T: Trait> as MultiSessionApp {
         pub SessionInfoMap get(fn session_info):
             map hasher(blake2_128_concat) T::Hash => Option<SessionInfoOf<T>>;
+        pub RulingMap get(fn ruling):
+            map hasher(blake2_128_concat) T::Hash => Option<RulingOf<T>>;
+        pub SeedRoundMap get(fn seed_round):
+            map hasher(blake2_128_concat) T::Hash => Option<SeedRoundOf<T>>;
+        pub SessionKeyMap get(fn session_key):
+            double_map hasher(blake2_128_concat) T::Hash, hasher(blake2_128_concat) T::AccountId
+            => Option<(T::AccountId, T::BlockNumber)>;
+        pub KeyRotationNonceMap get(fn key_rotation_nonce):
+            map hasher(blake2_128_concat) T::Hash => u32;
     }
 }
 
@@ -102,6 +277,11 @@ decl_module!  {
 
         fn deposit_event() = default;
 
+        /// Answer stale settlements of sessions watched by this node
+        fn offchain_worker(block_number: T::BlockNumber) {
+            capps_watchtower::answer_stale_settles::<Self>(block_number.unique_saturated_into());
+        }
+
         /// Initiate multi session app
         ///
         /// Parameters:
@@ -112,22 +292,53 @@ decl_module!  {
         /// - Complexity: `O(1)`
         ///   - 1 storage insertion `SessionInfoMap`
         ///   - 1 storage reads `SessionInfoMap`
+        ///   - 1 storage insertion `SeedRoundMap` with a seed timeout
         /// - Based on benchmark;
         ///     19.78　µs
         /// # </weight>
-        #[weight = 20_000_000 + T::DbWeight::get().reads_writes(1, 1)]
+        #[weight = 20_000_000 + T::DbWeight::get().reads_writes(1, 2)]
         fn session_initiate(
             origin,
             initiate_request: SessionInitiateRequestOf<T>
         ) -> DispatchResult {
-            let session_id = Self::get_session_id(initiate_request.nonce, initiate_request.players.clone());
+            let session_id = Self::get_initiate_session_id(&initiate_request)?;
             ensure!(
                 SessionInfoMap::<T>::contains_key(&session_id) == false,
                 "session_id is used"
             );
             
+            // is_ordered_account indexes the first player, so reject empty players first
+            ensure!(
+                !initiate_request.players.is_empty()
+                    && initiate_request.player_num as usize == initiate_request.players.len(),
+                "invalid player length"
+            );
             // check whether account is asscending order
             Self::is_ordered_account(initiate_request.players.clone())?;
+            ensure!(
+                initiate_request.threshold > 0 && initiate_request.threshold <= initiate_request.player_num,
+                "invalid threshold"
+            );
+            if let Some(arbiter) = &initiate_request.arbiter {
+                ensure!(
+                    !initiate_request.players.contains(arbiter),
+                    "arbiter is a player"
+                );
+            }
+            let seed_round = match initiate_request.seed_timeout {
+                Some(seed_timeout) => {
+                    ensure!(
+                        !seed_timeout.is_zero(),
+                        "invalid seed timeout"
+                    );
+                    Some(SeedRoundOf::<T>::new(
+                        initiate_request.players.len(),
+                        seed_timeout,
+                        frame_system::Module::<T>::block_number(),
+                    ))
+                },
+                None => None,
+            };
 
             let session_info = SessionInfoOf::<T> {
                 state: 0,
@@ -137,67 +348,237 @@ decl_module!  {
                 timeout: initiate_request.timeout,
                 deadline: Zero::zero(),
                 status: SessionStatus::Idle,
+                encoding: initiate_request.encoding,
+                eth_players: initiate_request.eth_players,
+                threshold: initiate_request.threshold,
+                arbiter: initiate_request.arbiter,
             };
             SessionInfoMap::<T>::insert(session_id, session_info);
+            // players commit to a random seed before the first settle
+            if let Some(seed_round) = seed_round {
+                SeedRoundMap::<T>::insert(session_id, seed_round);
+            }
         
             Ok(())
         }
 
         /// Update state according to an off-chain state proof
         ///
+        /// The i-th signature is of the i-th player, as the indexed signature of
+        /// player i in `update_by_threshold_state` with every player signing.
+        ///
         /// Parameters:
         /// - `state_proof`: Signed off-chain session state
         ///
         /// # <weight>
         /// ## Weight
-        /// - Complexity `O(1)`
-        /// # <weight>
-        /// ## Weight
-        /// - Complexity: `O(1)`
+        /// - Complexity: `O(N)`
+        ///   - N: Number of signatures
         ///   - 1 storage mutation `SessionInfoMap`
         ///   - 1 storage read `SessionInfoMap`
-        /// - Based on benchmark;
-        ///     48.44　µs
+        ///   - 1 storage read `SeedRoundMap`
+        ///   - 1 storage read `SessionKeyMap` per player
+        /// - Based on the 2-player `update_by_state` benchmark and 1 signature
+        ///     verification of 50　µs per further signature, as signatures are
+        ///     verified one by one; the benchmark runs over 2 to 100 players
+        ///     48.44　µs + 50　µs per signature beyond 2
         /// # </weight>
-        #[weight = 49_000_000 + T::DbWeight::get().reads_writes(1, 1)]
+        #[weight = 48_440_000 + 50_000_000 * (state_proof.sigs.len() as u64).saturating_sub(2)
+            + T::DbWeight::get().reads_writes(2 + state_proof.sigs.len() as u64, 1)]
         fn update_by_state(
             origin,
             state_proof: StateProofOf<T>
         ) -> DispatchResult {
             ensure_signed(origin)?;
             // submit ad settle off-chain state
-            let mut new_session_info: SessionInfoOf<T> = Self::intend_settle(state_proof.clone())?;
-            
-            let state = state_proof.app_state.state;
-            if state == 1 || state == 2 {
-                new_session_info = SessionInfoOf::<T> {
-                    state: state,
-                    players: new_session_info.players,
-                    player_num: new_session_info.player_num,
-                    seq_num: new_session_info.seq_num,
-                    timeout: new_session_info.timeout,
-                    deadline: new_session_info.deadline,
-                    status: SessionStatus::Finalized
-                }
-            } else {
-                new_session_info = SessionInfoOf::<T> {
-                    state: state,
-                    players: new_session_info.players,
-                    player_num: new_session_info.player_num,
-                    seq_num: new_session_info.seq_num,
-                    timeout: new_session_info.timeout,
-                    deadline: new_session_info.deadline,
-                    status: new_session_info.status
-                }
+            Self::update_state(state_proof.app_state, StateSigs::Players(state_proof.sigs))
+        }
+
+        /// Update state according to an off-chain state proof signed by
+        /// Ethereum accounts of players
+        ///
+        /// Parameters:
+        /// - `state_proof`: Off-chain session state signed by Ethereum accounts
+        ///
+        /// # <weight>
+        /// ## Weight
+        /// - Complexity: `O(N)`
+        ///   - N: Number of signatures
+        ///   - 1 storage mutation `SessionInfoMap`
+        ///   - 1 storage read `SessionInfoMap`
+        ///   - 1 storage read `SeedRoundMap`
+        /// - Based on `update_by_state` and a secp256k1 recovery of
+        ///     50　µs per signature
+        /// # </weight>
+        #[weight = 49_000_000 + 50_000_000 * state_proof.sigs.len() as u64
+            + T::DbWeight::get().reads_writes(2, 1)]
+        fn update_by_eth_state(
+            origin,
+            state_proof: EthStateProofOf<T>
+        ) -> DispatchResult {
+            ensure_signed(origin)?;
+            Self::update_state(state_proof.app_state, StateSigs::Ethereum(state_proof.sigs))
+        }
+
+        /// Delegate off-chain state signing of a player to a session key
+        ///
+        /// The delegation is signed by the player, so any account may submit
+        /// it. A delegation only replaces one which expires earlier.
+        ///
+        /// Parameters:
+        /// - `delegation`: Session key of a player and its expiry
+        /// - `signature`: Signature of the player on the delegation
+        ///
+        /// # <weight>
+        /// ## Weight
+        /// - Complexity: `O(1)`
+        ///   - 1 storage read `SessionInfoMap`
+        ///   - 1 storage read `SessionKeyMap`
+        ///   - 1 storage insertion `SessionKeyMap`
+        /// - Based on 1 signature verification of 50　µs
+        /// # </weight>
+        #[weight = 60_000_000 + T::DbWeight::get().reads_writes(2, 1)]
+        fn delegate_session_key(
+            origin,
+            delegation: SessionKeyDelegationOf<T>,
+            signature: <T as Trait>::Signature
+        ) -> DispatchResult {
+            ensure_signed(origin)?;
+            let session_info = match SessionInfoMap::<T>::get(delegation.app_id) {
+                Some(info) => info,
+                None => Err(Error::<T>::SessionInfoNotExist)?,
+            };
+            ensure!(
+                session_info.encoding != StateEncoding::Ethereum,
+                "session is signed by Ethereum accounts"
+            );
+            ensure!(
+                session_info.players.contains(&delegation.player),
+                "not a player of session"
+            );
+            ensure!(
+                delegation.expires_at > frame_system::Module::<T>::block_number(),
+                "session key is expired"
+            );
+            if let Some((_, expires_at)) = SessionKeyMap::<T>::get(delegation.app_id, &delegation.player) {
+                ensure!(
+                    delegation.expires_at > expires_at,
+                    "session key expires earlier than current one"
+                );
             }
-            let session_id = state_proof.app_state.session_id;
-            SessionInfoMap::<T>::mutate(&session_id, |session_info| *session_info = Some(new_session_info.clone()));
+            ensure!(
+                signature.verify(&delegation.signing_payload()[..], &delegation.player),
+                "invalid delegation signature"
+            );
 
-            // emit IntendSettle event
-            Self::deposit_event(Event::<T>::IntendSettle(session_id, new_session_info.seq_num));
+            SessionKeyMap::<T>::insert(
+                delegation.app_id,
+                &delegation.player,
+                (delegation.session_key.clone(), delegation.expires_at)
+            );
+
+            // emit SessionKeyDelegated event
+            Self::deposit_event(RawEvent::SessionKeyDelegated(
+                delegation.app_id,
+                delegation.player,
+                delegation.session_key,
+                delegation.expires_at,
+            ));
 
             Ok(())
         }
+
+        /// Replace the account of a player in a live session
+        ///
+        /// The rotation is signed by the old account of the player or by the
+        /// arbiter, and by the new account, so any account may submit it. The
+        /// other players cannot take over the seat, and no account joins
+        /// without its own consent. The new account takes the place of the old
+        /// one, keeping the player id used by app states, and must sort
+        /// between the neighbouring players.
+        ///
+        /// Parameters:
+        /// - `rotation`: Old and new account of a player
+        /// - `signature`: Signature of the old account or of the arbiter
+        /// - `new_signature`: Signature of the new account
+        ///
+        /// # <weight>
+        /// ## Weight
+        /// - Complexity: `O(1)`
+        ///   - 1 storage mutation `SessionInfoMap`
+        ///   - 1 storage read `SessionInfoMap`
+        ///   - 1 storage mutation `KeyRotationNonceMap`
+        ///   - 1 storage read `KeyRotationNonceMap`
+        ///   - 1 storage removal `SessionKeyMap`
+        /// - Based on 3 signature verifications of 50　µs
+        /// # </weight>
+        #[weight = 160_000_000 + T::DbWeight::get().reads_writes(2, 3)]
+        fn rotate_player_key(
+            origin,
+            rotation: KeyRotationOf<T>,
+            signature: <T as Trait>::Signature,
+            new_signature: <T as Trait>::Signature
+        ) -> DispatchResult {
+            ensure_signed(origin)?;
+            let mut session_info = match SessionInfoMap::<T>::get(rotation.app_id) {
+                Some(info) => info,
+                None => Err(Error::<T>::SessionInfoNotExist)?,
+            };
+            ensure!(
+                session_info.status != SessionStatus::Finalized,
+                "session is finalized"
+            );
+            let index = Self::valid_rotation(
+                &rotation,
+                signature,
+                new_signature,
+                &session_info.players,
+                session_info.arbiter.as_ref(),
+            )?;
+
+            session_info.players[index] = rotation.new_player.clone();
+            SessionInfoMap::<T>::insert(rotation.app_id, session_info);
+            KeyRotationNonceMap::<T>::insert(rotation.app_id, rotation.nonce + 1);
+            // a session key delegated by the old account is no longer accepted
+            SessionKeyMap::<T>::remove(rotation.app_id, &rotation.old_player);
+
+            // emit PlayerKeyRotated event
+            Self::deposit_event(RawEvent::PlayerKeyRotated(
+                rotation.app_id,
+                rotation.old_player,
+                rotation.new_player,
+            ));
+
+            Ok(())
+        }
+
+        /// Update state according to an off-chain state proof signed by at
+        /// least threshold players
+        ///
+        /// Signatures name the index of their player and may come in any order.
+        ///
+        /// Parameters:
+        /// - `state_proof`: Off-chain session state signed by indexed players
+        ///
+        /// # <weight>
+        /// ## Weight
+        /// - Complexity: `O(N)`
+        ///   - N: Number of signatures
+        ///   - 1 storage mutation `SessionInfoMap`
+        ///   - 1 storage read `SessionInfoMap`
+        ///   - 1 storage read `SeedRoundMap`
+        ///   - 1 storage read `SessionKeyMap` per signing player
+        /// - Based on `update_by_state` with the same number of signatures
+        /// # </weight>
+        #[weight = 48_440_000 + 50_000_000 * (state_proof.sigs.len() as u64).saturating_sub(2)
+            + T::DbWeight::get().reads_writes(2 + state_proof.sigs.len() as u64, 1)]
+        fn update_by_threshold_state(
+            origin,
+            state_proof: ThresholdStateProofOf<T>
+        ) -> DispatchResult {
+            ensure_signed(origin)?;
+            Self::update_state(state_proof.app_state, StateSigs::Threshold(state_proof.sigs))
+        }
         
 
         /// Update state according to an on-chain action
@@ -232,6 +613,10 @@ decl_module!  {
                     timeout: new_session_info.timeout,
                     deadline: new_session_info.deadline,
                     status: SessionStatus::Finalized,
+                    encoding: new_session_info.encoding,
+                    eth_players: new_session_info.eth_players,
+                    threshold: new_session_info.threshold,
+                    arbiter: new_session_info.arbiter,
                 }
             } 
             SessionInfoMap::<T>::mutate(&session_id, |session_info| *session_info = Some(new_session_info));
@@ -286,12 +671,227 @@ decl_module!  {
                 timeout: session_info.timeout,
                 deadline: session_info.deadline,
                 status: SessionStatus::Finalized,
+                encoding: session_info.encoding,
+                eth_players: session_info.eth_players,
+                threshold: session_info.threshold,
+                arbiter: session_info.arbiter,
             };
             SessionInfoMap::<T>::mutate(&session_id, |session_info| *session_info = Some(new_session_info));
 
             Ok(())
         }
 
+        /// Commit to a random seed
+        ///
+        /// Every player commits before the commit deadline, which runs from
+        /// the initiation of the session. Once every player committed, the
+        /// reveal period starts. Commitments are not taken in the initiate
+        /// request: any account submits it and players do not sign it, so
+        /// the submitter could put in a commitment to a secret a player never
+        /// knew and make the player forfeit. Each player commits by itself.
+        ///
+        /// Parameters:
+        /// - `session_id`: Id of session
+        /// - `commitment`: Commitment of the player, see `seed_commitment`
+        ///
+        /// # <weight>
+        /// ## Weight
+        /// - Complexity: `O(N)`
+        ///   - N: Number of players
+        ///   - 1 storage read `SessionInfoMap`
+        ///   - 1 storage mutation `SeedRoundMap`
+        ///   - 1 storage read `SeedRoundMap`
+        /// # </weight>
+        #[weight = 20_000_000 + T::DbWeight::get().reads_writes(2, 1)]
+        fn commit_seed(
+            origin,
+            session_id: T::Hash,
+            commitment: T::Hash
+        ) -> DispatchResult {
+            let caller = ensure_signed(origin)?;
+            let (index, mut seed_round) = Self::live_seed_round(session_id, &caller)?;
+            seed_round.commit(index, commitment, frame_system::Module::<T>::block_number())?;
+            SeedRoundMap::<T>::insert(session_id, seed_round);
+
+            // emit SeedCommitted event
+            Self::deposit_event(RawEvent::SeedCommitted(session_id, caller));
+
+            Ok(())
+        }
+
+        /// Reveal the secret committed to a random seed
+        ///
+        /// The random seed is decided once every player revealed.
+        ///
+        /// Parameters:
+        /// - `session_id`: Id of session
+        /// - `secret`: Secret of the commitment of the player
+        ///
+        /// # <weight>
+        /// ## Weight
+        /// - Complexity: `O(N)`
+        ///   - N: Number of players
+        ///   - 1 storage read `SessionInfoMap`
+        ///   - 1 storage mutation `SeedRoundMap`
+        ///   - 1 storage read `SeedRoundMap`
+        /// # </weight>
+        #[weight = 25_000_000 + T::DbWeight::get().reads_writes(2, 1)]
+        fn reveal_seed(
+            origin,
+            session_id: T::Hash,
+            secret: T::Hash
+        ) -> DispatchResult {
+            let caller = ensure_signed(origin)?;
+            let (index, mut seed_round) = Self::live_seed_round(session_id, &caller)?;
+            let seed = seed_round.reveal::<T::AccountId, T::Hashing>(&session_id, index, &caller, secret)?;
+            SeedRoundMap::<T>::insert(session_id, seed_round);
+
+            // emit SeedRevealed and SeedDecided events
+            Self::deposit_event(RawEvent::SeedRevealed(session_id, caller));
+            if let Some(seed) = seed {
+                Self::deposit_event(RawEvent::SeedDecided(session_id, seed));
+            }
+
+            Ok(())
+        }
+
+        /// Finalize session against players who did not commit or reveal a random
+        /// seed in time
+        ///
+        /// The first player who did not default wins: the session is finalized
+        /// with its player id as state, or 0 if every player defaulted.
+        ///
+        /// Parameters:
+        /// - `session_id`: Id of session
+        ///
+        /// # <weight>
+        /// ## Weight
+        /// - Complexity: `O(N)`
+        ///   - N: Number of players
+        ///   - 1 storage mutation `SessionInfoMap`
+        ///   - 1 storage read `SessionInfoMap`
+        ///   - 1 storage read `SeedRoundMap`
+        /// # </weight>
+        #[weight = 25_000_000 + T::DbWeight::get().reads_writes(2, 1)]
+        fn finalize_on_seed_timeout(
+            origin,
+            session_id: T::Hash
+        ) -> DispatchResult {
+            ensure_signed(origin)?;
+            let session_info = match SessionInfoMap::<T>::get(session_id) {
+                Some(info) => info,
+                None => Err(Error::<T>::SessionInfoNotExist)?,
+            };
+            ensure!(
+                session_info.status != SessionStatus::Finalized,
+                "session is finalized"
+            );
+            let seed_round = match SeedRoundMap::<T>::get(session_id) {
+                Some(seed_round) => seed_round,
+                None => Err("session has no random seed")?,
+            };
+            seed_round.check_timeout(frame_system::Module::<T>::block_number())?;
+
+            let defaulted = seed_round.defaulted();
+            let defaulters: Vec<T::AccountId> = session_info.players.iter()
+                .zip(defaulted.iter())
+                .filter(|(_, defaulted)| **defaulted)
+                .map(|(player, _)| player.clone())
+                .collect();
+            let winner = defaulted.iter()
+                .position(|defaulted| !defaulted)
+                .map_or(0, |index| index as u8 + 1);
+            let new_session_info = SessionInfoOf::<T> {
+                state: winner,
+                players: session_info.players,
+                player_num: session_info.player_num,
+                seq_num: session_info.seq_num,
+                timeout: session_info.timeout,
+                deadline: session_info.deadline,
+                status: SessionStatus::Finalized,
+                encoding: session_info.encoding,
+                eth_players: session_info.eth_players,
+                threshold: session_info.threshold,
+                arbiter: session_info.arbiter,
+            };
+            SessionInfoMap::<T>::mutate(&session_id, |session_info| *session_info = Some(new_session_info));
+
+            // emit SeedForfeited event
+            Self::deposit_event(RawEvent::SeedForfeited(session_id, defaulters));
+
+            Ok(())
+        }
+
+        /// Finalize session with the state ruled by its arbiter
+        ///
+        /// The ruling overrides the settle and action timeouts and is recorded
+        /// in `RulingMap`.
+        ///
+        /// Parameters:
+        /// - `session_id`: Id of session
+        /// - `state`: Outcome the session is finalized with, 1 or 2 as the
+        ///     finalizing states of `update_by_state`
+        ///
+        /// # <weight>
+        /// ## Weight
+        /// - Complexity: `O(1)`
+        ///   - 1 storage mutation `SessionInfoMap`
+        ///   - 1 storage read `SessionInfoMap`
+        ///   - 1 storage insertion `RulingMap`
+        /// - Based on `finalize_on_action_timeout`
+        /// # </weight>
+        #[weight = 17_000_000 + T::DbWeight::get().reads_writes(1, 2)]
+        fn arbitrate(
+            origin,
+            session_id: T::Hash,
+            state: u8
+        ) -> DispatchResult {
+            let caller = ensure_signed(origin)?;
+            let session_info = match SessionInfoMap::<T>::get(session_id) {
+                Some(session) => session,
+                None => Err(Error::<T>::SessionInfoNotExist)?,
+            };
+            ensure!(
+                session_info.arbiter.as_ref() == Some(&caller),
+                Error::<T>::NotArbiter
+            );
+            ensure!(
+                session_info.status == SessionStatus::Settle || session_info.status == SessionStatus::Action,
+                "session not in settle or action mode"
+            );
+            ensure!(
+                state == 1 || state == 2,
+                "invalid outcome state"
+            );
+
+            let new_session_info = SessionInfoOf::<T> {
+                state: state,
+                players: session_info.players,
+                player_num: session_info.player_num,
+                seq_num: session_info.seq_num,
+                timeout: session_info.timeout,
+                deadline: session_info.deadline,
+                status: SessionStatus::Finalized,
+                encoding: session_info.encoding,
+                eth_players: session_info.eth_players,
+                threshold: session_info.threshold,
+                arbiter: session_info.arbiter,
+            };
+            SessionInfoMap::<T>::mutate(&session_id, |session_info| *session_info = Some(new_session_info));
+
+            let block_number = frame_system::Module::<T>::block_number();
+            RulingMap::<T>::insert(&session_id, RulingOf::<T> {
+                arbiter: caller.clone(),
+                outcome: state,
+                block_number: block_number,
+            });
+
+            // emit Arbitrated event
+            Self::deposit_event(RawEvent::Arbitrated(session_id, caller, state));
+
+            Ok(())
+        }
+
         /// Check whether session is finalized
         ///
         /// Parameters:
@@ -364,10 +964,26 @@ decl_module!  {
 
 decl_event! (
     pub enum Event<T> where
-        <T as system::Trait>::Hash
+        <T as system::Trait>::Hash,
+        <T as system::Trait>::AccountId,
+        <T as system::Trait>::BlockNumber
     {
+        /// SessionKeyDelegated(session_id, player, session_key, expires_at)
+        SessionKeyDelegated(Hash, AccountId, AccountId, BlockNumber),
+        /// PlayerKeyRotated(session_id, old_player, new_player)
+        PlayerKeyRotated(Hash, AccountId, AccountId),
         /// IntendSettle(session_id, seq_num)
         IntendSettle(Hash, u128),
+        /// Arbitrated(session_id, arbiter, state)
+        Arbitrated(Hash, AccountId, u8),
+        /// SeedCommitted(session_id, player)
+        SeedCommitted(Hash, AccountId),
+        /// SeedRevealed(session_id, player)
+        SeedRevealed(Hash, AccountId),
+        /// SeedDecided(session_id, seed)
+        SeedDecided(Hash, Hash),
+        /// SeedForfeited(session_id, defaulters)
+        SeedForfeited(Hash, Vec<AccountId>),
     }
 );
 
@@ -375,6 +991,18 @@ decl_error! {
     pub enum Error for Module<T: Trait> {
         // SessionInfo is not exist
         SessionInfoNotExist,
+        // App state does not fit in the encoding of session
+        AppStateNotEncodable,
+        // Number of signatures does not match the players
+        InvalidSignatureNum,
+        // Fewer players than the threshold signed
+        NotEnoughSignatures,
+        // Player index of a signature is out of range
+        InvalidPlayerIndex,
+        // A player signed more than once
+        DuplicateSigner,
+        // Caller is not the arbiter of session
+        NotArbiter,
     }
 }
 
@@ -388,13 +1016,47 @@ impl<T: Trait> Module<T> {
         nonce: u128,
         players: Vec<T::AccountId>,
     ) -> T::Hash {
-        let multi_session_app_account = Self::app_account();
-        let mut encoded = multi_session_app_account.encode();
-        encoded.extend(nonce.encode());
-        players.into_iter()
-            .for_each(|players| { encoded.extend(players.encode()); });
-        let session_id = T::Hashing::hash(&encoded);
-        return session_id;
+        session_id::<T::AccountId, T::Hashing>(nonce, &players)
+    }
+
+    /// Get Id of session signed by Ethereum accounts
+    ///
+    /// Returns `None` if the runtime hash is not 32 bytes.
+    ///
+    /// Parameters:
+    /// `nonce`: Nonce of session
+    /// `eth_players`: Ethereum addresses of players
+    pub fn get_eth_session_id(
+        nonce: u128,
+        eth_players: Vec<H160>,
+    ) -> Option<T::Hash> {
+        let session_id = eth_session_id(nonce, &eth_players);
+        T::Hash::decode(&mut session_id.as_bytes()).ok()
+    }
+
+    /// Get Id of session to initiate, from Ethereum addresses of players in Ethereum encoding
+    ///
+    /// Parameter:
+    /// `initiate_request`: Session initiate request message
+    fn get_initiate_session_id(
+        initiate_request: &SessionInitiateRequestOf<T>,
+    ) -> Result<T::Hash, DispatchError> {
+        if initiate_request.encoding != StateEncoding::Ethereum {
+            ensure!(
+                initiate_request.eth_players.is_empty(),
+                "Ethereum players without Ethereum encoding"
+            );
+            return Ok(Self::get_session_id(initiate_request.nonce, initiate_request.players.clone()));
+        }
+
+        ensure!(
+            initiate_request.eth_players.len() == initiate_request.players.len(),
+            "invalid number of Ethereum players"
+        );
+        let session_id = Self::get_eth_session_id(initiate_request.nonce, initiate_request.eth_players.clone())
+            .ok_or("runtime hash is not 32 bytes")?;
+
+        Ok(session_id)
     }
 
     /// Get session state
@@ -470,30 +1132,177 @@ impl<T: Trait> Module<T> {
         return Some(session_info.seq_num);
     }
 
+    /// Get the undecided random seed round of session and the index of a player
+    ///
+    /// Parameters:
+    /// `session_id`: Id of session
+    /// `player`: AccountId of player
+    fn live_seed_round(
+        session_id: T::Hash,
+        player: &T::AccountId,
+    ) -> Result<(usize, SeedRoundOf<T>), DispatchError> {
+        let session_info = match SessionInfoMap::<T>::get(session_id) {
+            Some(info) => info,
+            None => Err(Error::<T>::SessionInfoNotExist)?,
+        };
+        ensure!(
+            session_info.status != SessionStatus::Finalized,
+            "session is finalized"
+        );
+        let index = match session_info.players.iter().position(|account| account == player) {
+            Some(index) => index,
+            None => Err("not a player of session")?,
+        };
+        let seed_round = match SeedRoundMap::<T>::get(session_id) {
+            Some(seed_round) => seed_round,
+            None => Err("session has no random seed")?,
+        };
+        seed_round.check_live(frame_system::Module::<T>::block_number())?;
+
+        Ok((index, seed_round))
+    }
+
+    /// Get random seed of session, which must be decided before the first settle
+    ///
+    /// Parameter:
+    /// `session_id`: Id of session
+    fn decided_seed(session_id: T::Hash) -> Result<Option<T::Hash>, DispatchError> {
+        match SeedRoundMap::<T>::get(session_id) {
+            Some(seed_round) => match seed_round.seed {
+                Some(seed) => Ok(Some(seed)),
+                None => Err("random seed is not decided")?,
+            },
+            None => Ok(None),
+        }
+    }
+
+    /// Get random seed of session, None if it is not decided
+    ///
+    /// Parameter:
+    /// `session_id`: Id of session
+    pub fn get_seed(session_id: T::Hash) -> Option<T::Hash> {
+        SeedRoundMap::<T>::get(session_id).and_then(|seed_round| seed_round.seed)
+    }
+
 
     /// Get multi session app account id
     pub fn app_account() -> T::AccountId {
         MULTI_SESSION_APP_ID.into_account()
     }
 
+    /// Settle an off-chain state and finalize session if it decides the outcome
+    ///
+    /// Parameters:
+    /// `app_state`: Off-chain app state
+    /// `sigs`: Signatures of app state
+    fn update_state(
+        app_state: AppStateOf<T>,
+        sigs: StateSigs<<T as Trait>::Signature>,
+    ) -> DispatchResult {
+        let mut new_session_info: SessionInfoOf<T> = Self::intend_settle(app_state.clone(), sigs)?;
+
+        let state = app_state.state;
+        if state == 1 || state == 2 {
+            new_session_info = SessionInfoOf::<T> {
+                state: state,
+                players: new_session_info.players,
+                player_num: new_session_info.player_num,
+                seq_num: new_session_info.seq_num,
+                timeout: new_session_info.timeout,
+                deadline: new_session_info.deadline,
+                status: SessionStatus::Finalized,
+                encoding: new_session_info.encoding,
+                eth_players: new_session_info.eth_players,
+                threshold: new_session_info.threshold,
+                arbiter: new_session_info.arbiter,
+            }
+        } else {
+            new_session_info = SessionInfoOf::<T> {
+                state: state,
+                players: new_session_info.players,
+                player_num: new_session_info.player_num,
+                seq_num: new_session_info.seq_num,
+                timeout: new_session_info.timeout,
+                deadline: new_session_info.deadline,
+                status: new_session_info.status,
+                encoding: new_session_info.encoding,
+                eth_players: new_session_info.eth_players,
+                threshold: new_session_info.threshold,
+                arbiter: new_session_info.arbiter,
+            }
+        }
+        let session_id = app_state.session_id;
+        SessionInfoMap::<T>::mutate(&session_id, |session_info| *session_info = Some(new_session_info.clone()));
+
+        // emit IntendSettle event
+        Self::deposit_event(Event::<T>::IntendSettle(session_id, new_session_info.seq_num));
+
+        Ok(())
+    }
+
     /// Submit and settle offchain state
     ///
-    /// Parameter:
-    /// `state_proof`: Signed off-chain app state
+    /// Parameters:
+    /// `app_state`: Off-chain app state
+    /// `sigs`: Signatures of app state
     fn intend_settle(
-        state_proof: StateProofOf<T>
+        app_state: AppStateOf<T>,
+        sigs: StateSigs<<T as Trait>::Signature>,
     ) -> Result<SessionInfoOf<T>, DispatchError> {
-        let app_state = state_proof.app_state;
         let session_info = match SessionInfoMap::<T>::get(app_state.session_id) {
             Some(session) => session,
             None => Err(Error::<T>::SessionInfoNotExist)?,
         };
-        ensure!(
-            state_proof.sigs.len() as u8 == session_info.player_num,
-            "invalid number of players"
-        );
-        let encoded = Self::encode_app_state(app_state.clone());
-        Self::valid_signers(state_proof.sigs, &encoded, session_info.players.clone())?;
+        Self::decided_seed(app_state.session_id)?;
+        let encoded = Self::encode_app_state(app_state.clone(), session_info.encoding)?;
+        match sigs {
+            StateSigs::Players(sigs) => {
+                ensure!(
+                    sigs.len() == session_info.players.len(),
+                    Error::<T>::InvalidSignatureNum
+                );
+                ensure!(
+                    session_info.encoding != StateEncoding::Ethereum,
+                    "session is signed by Ethereum accounts"
+                );
+                // the i-th signature is of the i-th player, and every player signs
+                let sigs = sigs.into_iter()
+                    .enumerate()
+                    .map(|(player, sig)| PlayerSig { player: player as u8, sig: sig })
+                    .collect();
+                Self::valid_threshold_signers(
+                    sigs,
+                    &encoded,
+                    app_state.session_id,
+                    &session_info.players,
+                    session_info.players.len() as u8,
+                )?;
+            },
+            StateSigs::Ethereum(sigs) => {
+                ensure!(
+                    sigs.len() == session_info.players.len(),
+                    Error::<T>::InvalidSignatureNum
+                );
+                ensure!(
+                    session_info.encoding == StateEncoding::Ethereum,
+                    "session is not signed by Ethereum accounts"
+                );
+                Self::valid_eth_signers(&sigs, &encoded, &session_info.eth_players)?;
+            },
+            StateSigs::Threshold(sigs) => {
+                ensure!(
+                    session_info.encoding != StateEncoding::Ethereum,
+                    "session is signed by Ethereum accounts"
+                );
+                Self::valid_threshold_signers(
+                    sigs,
+                    &encoded,
+                    app_state.session_id,
+                    &session_info.players,
+                    session_info.threshold,
+                )?;
+            },
+        }
         ensure!(
             session_info.status != SessionStatus::Finalized,
             "app state is finalized"
@@ -512,7 +1321,11 @@ impl<T: Trait> Module<T> {
             seq_num: app_state.seq_num,
             timeout: session_info.timeout,
             deadline: block_number + session_info.timeout,
-            status: SessionStatus::Settle
+            status: SessionStatus::Settle,
+            encoding: session_info.encoding,
+            eth_players: session_info.eth_players,
+            threshold: session_info.threshold,
+            arbiter: session_info.arbiter,
         };
 
         Ok(new_session_info)
@@ -544,7 +1357,11 @@ impl<T: Trait> Module<T> {
                 seq_num:  session_info.seq_num + 1,
                 timeout: session_info.timeout,
                 deadline: block_number + session_info.timeout,
-                status: SessionStatus::Action
+                status: SessionStatus::Action,
+                encoding: session_info.encoding,
+                eth_players: session_info.eth_players,
+                threshold: session_info.threshold,
+                arbiter: session_info.arbiter,
             };
         } else {
             ensure!(
@@ -558,13 +1375,31 @@ impl<T: Trait> Module<T> {
                 seq_num:  session_info.seq_num + 1,
                 timeout: session_info.timeout,
                 deadline: block_number + session_info.timeout,
-                status: SessionStatus::Action
+                status: SessionStatus::Action,
+                encoding: session_info.encoding,
+                eth_players: session_info.eth_players,
+                threshold: session_info.threshold,
+                arbiter: session_info.arbiter,
             };
         }
 
         Ok(new_session_info)
     }
 
+    /// Get accounts signing off-chain states of players, with the live
+    /// session key of a player in place of its account
+    ///
+    /// Parameters:
+    /// `session_id`: Id of session
+    /// `players`: AccountId of players
+    fn signing_accounts(session_id: T::Hash, players: &[T::AccountId]) -> Vec<T::AccountId> {
+        capps_app_common::signing_accounts(
+            players,
+            frame_system::Module::<T>::block_number(),
+            |player| SessionKeyMap::<T>::get(session_id, player),
+        )
+    }
+
     /// Verify off-chain state signatures
     ///
     /// Parameters:
@@ -576,17 +1411,129 @@ impl<T: Trait> Module<T> {
         encoded: &[u8],
         signers: Vec<T::AccountId>,
     ) -> Result<(), DispatchError> {
-        for i in 0..signers.len() {
-            let signature = &signatures[i];
-            ensure!(
-                signature.verify(encoded, &signers[i]),
-                "Check co-sigs failed"
-            );
+        ensure!(
+            signatures.len() == signers.len(),
+            Error::<T>::InvalidSignatureNum
+        );
+        // every signature is checked before the state is used
+        ensure!(
+            verify_all(&signatures, encoded, &signers),
+            "Check co-sigs failed"
+        );
+
+        Ok(())
+    }
+
+    /// Verify off-chain state signatures of at least threshold distinct players,
+    /// each signed by the account or the live session key of its player
+    ///
+    /// Parameters:
+    /// `signatures`: Signatures with the index of their player
+    /// `encoded`: Encoded app state
+    /// `session_id`: Id of session
+    /// `players`: AccountId of players
+    /// `threshold`: Number of players to sign
+    fn valid_threshold_signers(
+        signatures: Vec<PlayerSig<<T as Trait>::Signature>>,
+        encoded: &[u8],
+        session_id: T::Hash,
+        players: &[T::AccountId],
+        threshold: u8,
+    ) -> Result<(), DispatchError> {
+        ensure!(
+            signatures.len() >= threshold as usize,
+            Error::<T>::NotEnoughSignatures
+        );
+        // place every signature at the index of its player
+        let mut player_sigs: Vec<Option<<T as Trait>::Signature>> = Vec::new();
+        player_sigs.resize(players.len(), None);
+        for signature in signatures {
+            let player_sig = player_sigs.get_mut(signature.player as usize)
+                .ok_or(Error::<T>::InvalidPlayerIndex)?;
+            ensure!(player_sig.is_none(), Error::<T>::DuplicateSigner);
+            *player_sig = Some(signature.sig);
         }
 
+        let mut signers: Vec<T::AccountId> = Vec::new();
+        let mut sigs: Vec<<T as Trait>::Signature> = Vec::new();
+        for (player, player_sig) in players.iter().zip(player_sigs.into_iter()) {
+            if let Some(sig) = player_sig {
+                signers.push(player.clone());
+                sigs.push(sig);
+            }
+        }
+        // session keys are read only for the players who signed
+        let signers = Self::signing_accounts(session_id, &signers);
+
+        Self::valid_signers(sigs, encoded, signers)
+    }
+
+    /// Verify off-chain state signatures of Ethereum accounts, in any order
+    ///
+    /// Parameters:
+    /// `signatures`: Signatures from Ethereum accounts of players
+    /// `encoded`: Packed app state
+    /// `signers`: Ethereum addresses of players
+    fn valid_eth_signers(
+        signatures: &[EcdsaSignature],
+        encoded: &[u8],
+        signers: &[H160],
+    ) -> Result<(), DispatchError> {
+        ensure!(signatures.len() == signers.len(), "Check co-sigs failed");
+        let mut recovered = signatures.iter()
+            .map(|signature| ethereum::recover_signer(signature, encoded))
+            .collect::<Option<Vec<H160>>>()
+            .ok_or("Check co-sigs failed")?;
+        let mut expected = signers.to_vec();
+        recovered.sort();
+        expected.sort();
+        ensure!(recovered == expected, "Check co-sigs failed");
+
         Ok(())
     }
 
+    /// Check a key rotation of a live session and its signatures, returning
+    /// the index of the rotated player
+    ///
+    /// The new account keeps players in ascending order.
+    ///
+    /// Parameters:
+    /// `rotation`: Old and new account of a player
+    /// `signature`: Signature of the old account or of the arbiter
+    /// `new_signature`: Signature of the new account
+    /// `players`: AccountId of players
+    /// `arbiter`: AccountId of arbiter
+    fn valid_rotation(
+        rotation: &KeyRotationOf<T>,
+        signature: <T as Trait>::Signature,
+        new_signature: <T as Trait>::Signature,
+        players: &[T::AccountId],
+        arbiter: Option<&T::AccountId>,
+    ) -> Result<usize, DispatchError> {
+        let index = rotation.rotated_index(
+            players,
+            arbiter,
+            KeyRotationNonceMap::<T>::get(rotation.app_id),
+        )?;
+        ensure!(
+            rotation.keeps_order(players, index),
+            "player is not ascending order"
+        );
+
+        let payload = rotation.signing_payload();
+        ensure!(
+            signature.verify(&payload[..], &rotation.old_player)
+                || arbiter.iter().any(|arbiter| signature.verify(&payload[..], arbiter)),
+            "invalid rotation signature"
+        );
+        ensure!(
+            new_signature.verify(&payload[..], &rotation.new_player),
+            "invalid new account signature"
+        );
+
+        Ok(index)
+    }
+
     /// Check whether account is asscending order
     ///
     /// Parameter:
@@ -594,31 +1541,24 @@ impl<T: Trait> Module<T> {
     fn is_ordered_account(
         players: Vec<T::AccountId>
     ) -> Result<(), DispatchError> {
-        let mut prev = &players[0];
-        for i in 1..players.len() {
-            ensure!(
-                prev < &players[1],
-                "player is not ascending order"
-            );
-            prev = &players[i];
-        }
+        ensure!(
+            capps_app_common::is_ordered(&players),
+            "player is not ascending order"
+        );
 
         Ok(())
     }
 
     /// Encode app state
     ///
-    /// Parameter:
+    /// Parameters:
     /// `app_state`: app state
+    /// `encoding`: Encoding of session
     fn encode_app_state(
-        app_state: AppStateOf<T>
-    ) -> Vec<u8> {
-        let mut encoded = app_state.seq_num.encode();
-        encoded.extend(app_state.state.encode());
-        encoded.extend(app_state.timeout.encode());
-        encoded.extend(app_state.session_id.encode());
-
-        return encoded;
+        app_state: AppStateOf<T>,
+        encoding: StateEncoding,
+    ) -> Result<Vec<u8>, DispatchError> {
+        app_state.payload(encoding).ok_or_else(|| Error::<T>::AppStateNotEncodable.into())
     }
 }
 
@@ -9,9 +9,14 @@ use frame_support::{
 use frame_system as system;
 use sp_core::{sr25519, Pair, H256};
 use pallet_balances;
-use sp_runtime::testing::Header;
-use sp_runtime::traits::{BlakeTwo256, IdentityLookup};
+use sp_core::offchain::{testing::{self, PoolState}, OffchainExt, TransactionPoolExt};
+use sp_core::testing::KeyStore;
+use sp_core::traits::{BareCryptoStore, KeystoreExt};
+use sp_runtime::testing::{Header, TestXt};
+use sp_runtime::traits::{BlakeTwo256, Extrinsic as ExtrinsicT, IdentityLookup};
 use sp_runtime::Perbill;
+use std::sync::Arc;
+use parking_lot::RwLock;
 
 #[derive(Clone, Eq, PartialEq)]
 pub struct TestRuntime;
@@ -19,6 +24,7 @@ pub struct TestRuntime;
 pub(crate) type AccountId = sr25519::Public;
 pub(crate) type BlockNumber = u64;
 pub(crate) type Signature = sr25519::Signature;
+pub(crate) type Extrinsic = TestXt<Call, ()>;
 
 impl_outer_event! {
     pub enum TestEvent for TestRuntime {
@@ -90,6 +96,32 @@ impl Trait for TestRuntime {
     type Event = TestEvent;
     type Public = sr25519::Public;
     type Signature = sr25519::Signature;
+    type Watchtower = SignedWatchtower<TestRuntime, crypto::WatchtowerId>;
+}
+
+impl frame_system::offchain::SigningTypes for TestRuntime {
+    type Public = sr25519::Public;
+    type Signature = sr25519::Signature;
+}
+
+impl<LocalCall> frame_system::offchain::SendTransactionTypes<LocalCall> for TestRuntime where
+    Call: From<LocalCall>,
+{
+    type OverarchingCall = Call;
+    type Extrinsic = Extrinsic;
+}
+
+impl<LocalCall> frame_system::offchain::CreateSignedTransaction<LocalCall> for TestRuntime where
+    Call: From<LocalCall>,
+{
+    fn create_transaction<C: frame_system::offchain::AppCrypto<sr25519::Public, sr25519::Signature>>(
+        call: Call,
+        _public: sr25519::Public,
+        _account: AccountId,
+        nonce: u64,
+    ) -> Option<(Call, <Extrinsic as ExtrinsicT>::SignaturePayload)> {
+        Some((call, (nonce, ())))
+    }
 }
 
 pub type MultiApp = Module<TestRuntime>;
@@ -104,6 +136,19 @@ impl ExtBuilder {
         let ext = sp_io::TestExternalities::new(t);
         ext
     }
+
+    pub fn build_with_watchtower() -> (sp_io::TestExternalities, Arc<RwLock<PoolState>>) {
+        let (offchain, _state) = testing::TestOffchainExt::new();
+        let (pool, pool_state) = testing::TestTransactionPoolExt::new();
+        let keystore = KeyStore::new();
+        keystore.write().sr25519_generate_new(KEY_TYPE, Some("//Watchtower")).unwrap();
+
+        let mut ext = Self::build();
+        ext.register_extension(OffchainExt::new(offchain));
+        ext.register_extension(TransactionPoolExt::new(pool));
+        ext.register_extension(KeystoreExt(keystore));
+        (ext, pool_state)
+    }
 }
 
 pub(crate) fn account_pair(s: &str) -> sr25519::Pair {
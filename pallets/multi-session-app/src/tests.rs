@@ -1,7 +1,8 @@
 use super::*;
 use mock::*;
-use sp_core::{sr25519, Pair, H256};
+use sp_core::{sr25519, Pair, H160, H256};
 use frame_support::{assert_ok, assert_noop};
+use sp_runtime::traits::{BlakeTwo256, OffchainWorker};
 
 #[test]
 fn test_pass_initiate() {
@@ -14,7 +15,12 @@ fn test_pass_initiate() {
             nonce: 1,
             player_num: 2,
             players: players.clone(),
-            timeout: 2
+            timeout: 2,
+            encoding: StateEncoding::Scale,
+            eth_players: vec![],
+            threshold: 2,
+            arbiter: None,
+            seed_timeout: None,
         };
         assert_ok!(
             MultiApp::session_initiate(
@@ -37,7 +43,12 @@ fn test_pass_update_by_state_state_is_5() {
             nonce: 1,
             player_num: 2,
             players: players.clone(),
-            timeout: 2
+            timeout: 2,
+            encoding: StateEncoding::Scale,
+            eth_players: vec![],
+            threshold: 2,
+            arbiter: None,
+            seed_timeout: None,
         };
         assert_ok!(
             MultiApp::session_initiate(
@@ -65,6 +76,10 @@ fn test_pass_update_by_state_state_is_5() {
             timeout: 2,
             deadline: 3,
             status: SessionStatus::Settle,
+            encoding: StateEncoding::Scale,
+            eth_players: vec![],
+            threshold: 2,
+            arbiter: None,
         };
         assert_eq!(session_info, expected_session_info);
 
@@ -87,8 +102,9 @@ fn test_pass_update_by_state_state_is_5() {
 }
 
 #[test]
-fn test_fail_update_by_action_before_settle_finalized_time() {
+fn test_pass_update_by_state_with_protobuf_encoding() {
     ExtBuilder::build().execute_with(|| {
+        System::set_block_number(1);
         let alice_pair = account_pair("Alice");
         let bob_pair = account_pair("Bob");
         let (players, players_pair) = get_sorted_peer(alice_pair.clone(), bob_pair.clone());
@@ -97,7 +113,12 @@ fn test_fail_update_by_action_before_settle_finalized_time() {
             nonce: 1,
             player_num: 2,
             players: players.clone(),
-            timeout: 2
+            timeout: 2,
+            encoding: StateEncoding::Protobuf,
+            eth_players: vec![],
+            threshold: 2,
+            arbiter: None,
+            seed_timeout: None,
         };
         assert_ok!(
             MultiApp::session_initiate(
@@ -107,37 +128,83 @@ fn test_fail_update_by_action_before_settle_finalized_time() {
         );
 
         let session_id = MultiApp::get_session_id(initiate_request.nonce, initiate_request.players.clone());
-        let state_proof = get_state_proof(1, 5, 2, session_id, players_pair);
-        assert_ok!(
+        // states signed in the SCALE encoding are not accepted
+        let state_proof = get_state_proof(1, 1, 2, session_id, players_pair.clone());
+        assert_noop!(
             MultiApp::update_by_state(
                 Origin::signed(players[0]),
                 state_proof
-            )
+            ),
+            "Check co-sigs failed"
         );
 
+        let state_proof = get_encoded_state_proof(
+            u64::max_value() as u128 + 1, 1, 2, session_id, players_pair.clone(), StateEncoding::Scale
+        );
         assert_noop!(
-            MultiApp::update_by_action(
+            MultiApp::update_by_state(
                 Origin::signed(players[0]),
-                session_id,
-                1
+                state_proof
             ),
-            "app not in action mode"
+            Error::<TestRuntime>::AppStateNotEncodable
+        );
+
+        let state_proof = get_encoded_state_proof(
+            1, 1, 2, session_id, players_pair, StateEncoding::Protobuf
+        );
+        assert_ok!(
+            MultiApp::update_by_state(
+                Origin::signed(players[0]),
+                state_proof
+            )
+        );
+        assert_ok!(
+            MultiApp::is_finalized(
+                Origin::signed(players[0]),
+                session_id,
+            )
         );
     })
 }
 
 #[test]
-fn test_pass_update_by_action_after_settle_finalized_time() {
+fn test_pass_update_by_eth_state() {
     ExtBuilder::build().execute_with(|| {
+        System::set_block_number(1);
         let alice_pair = account_pair("Alice");
         let bob_pair = account_pair("Bob");
         let (players, players_pair) = get_sorted_peer(alice_pair.clone(), bob_pair.clone());
+        let eth_players: Vec<H160> = vec![eth_pair(1).address(), eth_pair(2).address()];
+
+        let initiate_request = SessionInitiateRequest {
+            nonce: 1,
+            player_num: 2,
+            players: players.clone(),
+            timeout: 2,
+            encoding: StateEncoding::Ethereum,
+            eth_players: vec![eth_players[0]],
+            threshold: 2,
+            arbiter: None,
+            seed_timeout: None,
+        };
+        assert_noop!(
+            MultiApp::session_initiate(
+                Origin::signed(players[0]),
+                initiate_request
+            ),
+            "invalid number of Ethereum players"
+        );
 
         let initiate_request = SessionInitiateRequest {
             nonce: 1,
             player_num: 2,
             players: players.clone(),
-            timeout: 2
+            timeout: 2,
+            encoding: StateEncoding::Ethereum,
+            eth_players: eth_players.clone(),
+            threshold: 2,
+            arbiter: None,
+            seed_timeout: None,
         };
         assert_ok!(
             MultiApp::session_initiate(
@@ -146,41 +213,66 @@ fn test_pass_update_by_action_after_settle_finalized_time() {
             )
         );
 
-        let session_id = MultiApp::get_session_id(initiate_request.nonce, initiate_request.players.clone());
-        let state_proof = get_state_proof(1, 3, 2, session_id, players_pair);
-        assert_ok!(
+        let session_id = MultiApp::get_eth_session_id(initiate_request.nonce, eth_players.clone()).unwrap();
+        assert_eq!(session_id, eth_session_id(1, &eth_players));
+
+        // states signed by substrate accounts are not accepted
+        let state_proof = get_encoded_state_proof(
+            1, 1, 2, session_id, players_pair, StateEncoding::Ethereum
+        );
+        assert_noop!(
             MultiApp::update_by_state(
                 Origin::signed(players[0]),
                 state_proof
-            )
-        ); 
-
+            ),
+            "session is signed by Ethereum accounts"
+        );
 
-        let settle_finalized_time = MultiApp::get_settle_finalized_time(session_id).unwrap();
-        System::set_block_number(settle_finalized_time + 1);
+        // every player signs
+        let state_proof = get_eth_state_proof(1, 1, 2, session_id, vec![eth_pair(1)]);
+        assert_noop!(
+            MultiApp::update_by_eth_state(
+                Origin::signed(players[0]),
+                state_proof
+            ),
+            Error::<TestRuntime>::InvalidSignatureNum
+        );
 
+        let state_proof = get_eth_state_proof(1, 1, 2, session_id, vec![eth_pair(2), eth_pair(1)]);
         assert_ok!(
-            MultiApp::update_by_action(
+            MultiApp::update_by_eth_state(
+                Origin::signed(players[0]),
+                state_proof
+            )
+        );
+        assert_ok!(
+            MultiApp::is_finalized(
                 Origin::signed(players[0]),
                 session_id,
-                3
             )
         );
     })
 }
 
 #[test]
-fn test_fail_update_by_state_with_invalid_sequence_number() {
+fn test_pass_delegate_session_key() {
     ExtBuilder::build().execute_with(|| {
+        System::set_block_number(1);
         let alice_pair = account_pair("Alice");
         let bob_pair = account_pair("Bob");
-        let (players, players_pair) = get_sorted_peer(alice_pair.clone(), bob_pair.clone());
+        let (players, players_pair)
+            = get_sorted_peer(alice_pair, bob_pair);
 
         let initiate_request = SessionInitiateRequest {
             nonce: 1,
             player_num: 2,
             players: players.clone(),
-            timeout: 2
+            timeout: 2,
+            encoding: StateEncoding::Scale,
+            eth_players: vec![],
+            threshold: 2,
+            arbiter: None,
+            seed_timeout: None,
         };
         assert_ok!(
             MultiApp::session_initiate(
@@ -190,27 +282,78 @@ fn test_fail_update_by_state_with_invalid_sequence_number() {
         );
 
         let session_id = MultiApp::get_session_id(initiate_request.nonce, initiate_request.players.clone());
-        let mut state_proof = get_state_proof(1, 3, 2, session_id, players_pair.clone());
+
+        let session_pair = account_pair("Carl");
+        let mut delegation = SessionKeyDelegation {
+            app_id: session_id,
+            player: players[0],
+            session_key: session_pair.public(),
+            expires_at: 1,
+        };
+        assert_noop!(
+            MultiApp::delegate_session_key(
+                Origin::signed(players[1]),
+                delegation.clone(),
+                players_pair[0].sign(&delegation.signing_payload())
+            ),
+            "session key is expired"
+        );
+
+        // only the player signs its delegation, anyone submits it
+        delegation.expires_at = 10;
+        assert_noop!(
+            MultiApp::delegate_session_key(
+                Origin::signed(players[1]),
+                delegation.clone(),
+                players_pair[1].sign(&delegation.signing_payload())
+            ),
+            "invalid delegation signature"
+        );
+        assert_ok!(
+            MultiApp::delegate_session_key(
+                Origin::signed(players[1]),
+                delegation.clone(),
+                players_pair[0].sign(&delegation.signing_payload())
+            )
+        );
+        let expected_event = TestEvent::multi_app(RawEvent::SessionKeyDelegated(session_id, players[0], session_pair.public(), 10));
+        assert!(System::events().iter().any(|a| a.event == expected_event));
+        assert_eq!(MultiApp::session_key(session_id, players[0]), Some((session_pair.public(), 10)));
+
+        // a replayed delegation does not extend the session key
+        assert_noop!(
+            MultiApp::delegate_session_key(
+                Origin::signed(players[1]),
+                delegation.clone(),
+                players_pair[0].sign(&delegation.signing_payload())
+            ),
+            "session key expires earlier than current one"
+        );
+
+        // the session key signs in place of the player
+        let state_proof = get_state_proof(1, 2, 2, session_id, vec![session_pair.clone(), players_pair[1].clone()]);
         assert_ok!(
             MultiApp::update_by_state(
-                Origin::signed(players[0]),
+                Origin::signed(players[1]),
                 state_proof
             )
-        ); 
+        );
 
-        state_proof = get_state_proof(0, 3, 2, session_id, players_pair);
+        // the expired session key is not accepted
+        System::set_block_number(10);
+        let state_proof = get_state_proof(2, 2, 2, session_id, vec![session_pair, players_pair[1].clone()]);
         assert_noop!(
             MultiApp::update_by_state(
-                Origin::signed(players[0]),
+                Origin::signed(players[1]),
                 state_proof
             ),
-            "invalid sequence number"
-        ); 
+            "Check co-sigs failed"
+        );
     })
 }
 
 #[test]
-fn test_fail_update_by_state_with_different_player_sigs() {
+fn test_fail_update_by_action_before_settle_finalized_time() {
     ExtBuilder::build().execute_with(|| {
         let alice_pair = account_pair("Alice");
         let bob_pair = account_pair("Bob");
@@ -220,7 +363,12 @@ fn test_fail_update_by_state_with_different_player_sigs() {
             nonce: 1,
             player_num: 2,
             players: players.clone(),
-            timeout: 2
+            timeout: 2,
+            encoding: StateEncoding::Scale,
+            eth_players: vec![],
+            threshold: 2,
+            arbiter: None,
+            seed_timeout: None,
         };
         assert_ok!(
             MultiApp::session_initiate(
@@ -230,19 +378,27 @@ fn test_fail_update_by_state_with_different_player_sigs() {
         );
 
         let session_id = MultiApp::get_session_id(initiate_request.nonce, initiate_request.players.clone());
-        let state_proof = get_state_proof(1, 3, 2, session_id, vec![players_pair[0].clone(), account_pair("Carl")]);
-        assert_noop!(
+        let state_proof = get_state_proof(1, 5, 2, session_id, players_pair);
+        assert_ok!(
             MultiApp::update_by_state(
                 Origin::signed(players[0]),
                 state_proof
+            )
+        );
+
+        assert_noop!(
+            MultiApp::update_by_action(
+                Origin::signed(players[0]),
+                session_id,
+                1
             ),
-            "Check co-sigs failed"
+            "app not in action mode"
         );
     })
 }
 
 #[test]
-fn test_pass_update_by_state_with_valid_a_seq_sig() {
+fn test_pass_update_by_action_after_settle_finalized_time() {
     ExtBuilder::build().execute_with(|| {
         let alice_pair = account_pair("Alice");
         let bob_pair = account_pair("Bob");
@@ -252,7 +408,12 @@ fn test_pass_update_by_state_with_valid_a_seq_sig() {
             nonce: 1,
             player_num: 2,
             players: players.clone(),
-            timeout: 2
+            timeout: 2,
+            encoding: StateEncoding::Scale,
+            eth_players: vec![],
+            threshold: 2,
+            arbiter: None,
+            seed_timeout: None,
         };
         assert_ok!(
             MultiApp::session_initiate(
@@ -262,8 +423,7 @@ fn test_pass_update_by_state_with_valid_a_seq_sig() {
         );
 
         let session_id = MultiApp::get_session_id(initiate_request.nonce, initiate_request.players.clone());
-        let mut state_proof = get_state_proof(1, 5, 2, session_id, players_pair.clone());
-    
+        let state_proof = get_state_proof(1, 3, 2, session_id, players_pair);
         assert_ok!(
             MultiApp::update_by_state(
                 Origin::signed(players[0]),
@@ -271,32 +431,22 @@ fn test_pass_update_by_state_with_valid_a_seq_sig() {
             )
         ); 
 
-        state_proof = get_state_proof(2, 2, 2, session_id, players_pair);
-        assert_ok!(
-            MultiApp::update_by_state(
-                Origin::signed(players[0]),
-                state_proof
-            )
-        );
+
+        let settle_finalized_time = MultiApp::get_settle_finalized_time(session_id).unwrap();
+        System::set_block_number(settle_finalized_time + 1);
 
         assert_ok!(
-            MultiApp::get_outcome(
+            MultiApp::update_by_action(
                 Origin::signed(players[0]),
                 session_id,
-                2
-            )
-        );
-        assert_ok!(
-            MultiApp::is_finalized(
-                Origin::signed(players[0]),
-                session_id
+                3
             )
         );
     })
 }
 
 #[test]
-fn test_fail_update_by_action_after_finalized() {
+fn test_fail_update_by_state_with_invalid_sequence_number() {
     ExtBuilder::build().execute_with(|| {
         let alice_pair = account_pair("Alice");
         let bob_pair = account_pair("Bob");
@@ -306,7 +456,12 @@ fn test_fail_update_by_action_after_finalized() {
             nonce: 1,
             player_num: 2,
             players: players.clone(),
-            timeout: 2
+            timeout: 2,
+            encoding: StateEncoding::Scale,
+            eth_players: vec![],
+            threshold: 2,
+            arbiter: None,
+            seed_timeout: None,
         };
         assert_ok!(
             MultiApp::session_initiate(
@@ -316,8 +471,7 @@ fn test_fail_update_by_action_after_finalized() {
         );
 
         let session_id = MultiApp::get_session_id(initiate_request.nonce, initiate_request.players.clone());
-        let state_proof = get_state_proof(1, 2, 2, session_id, players_pair.clone());
-    
+        let mut state_proof = get_state_proof(1, 3, 2, session_id, players_pair.clone());
         assert_ok!(
             MultiApp::update_by_state(
                 Origin::signed(players[0]),
@@ -325,19 +479,19 @@ fn test_fail_update_by_action_after_finalized() {
             )
         ); 
 
+        state_proof = get_state_proof(0, 3, 2, session_id, players_pair);
         assert_noop!(
-            MultiApp::update_by_action(
+            MultiApp::update_by_state(
                 Origin::signed(players[0]),
-                session_id,
-                2
+                state_proof
             ),
-            "app state is finalized"
-        );
+            "invalid sequence number"
+        ); 
     })
 }
 
 #[test]
-fn test_fail_update_by_state_after_finalized() {
+fn test_fail_update_by_state_with_different_player_sigs() {
     ExtBuilder::build().execute_with(|| {
         let alice_pair = account_pair("Alice");
         let bob_pair = account_pair("Bob");
@@ -347,7 +501,12 @@ fn test_fail_update_by_state_after_finalized() {
             nonce: 1,
             player_num: 2,
             players: players.clone(),
-            timeout: 2
+            timeout: 2,
+            encoding: StateEncoding::Scale,
+            eth_players: vec![],
+            threshold: 2,
+            arbiter: None,
+            seed_timeout: None,
         };
         assert_ok!(
             MultiApp::session_initiate(
@@ -357,29 +516,39 @@ fn test_fail_update_by_state_after_finalized() {
         );
 
         let session_id = MultiApp::get_session_id(initiate_request.nonce, initiate_request.players.clone());
-        let mut state_proof = get_state_proof(1, 2, 2, session_id, players_pair.clone());
-        assert_ok!(
+        let state_proof = get_state_proof(1, 3, 2, session_id, vec![players_pair[0].clone(), account_pair("Carl")]);
+        assert_noop!(
             MultiApp::update_by_state(
                 Origin::signed(players[0]),
                 state_proof
-            )
-        ); 
+            ),
+            "Check co-sigs failed"
+        );
 
-        state_proof = get_state_proof(2, 2, 2, session_id, players_pair);
+        // signatures are matched to players by position
+        let state_proof = get_state_proof(1, 3, 2, session_id, vec![players_pair[1].clone(), players_pair[0].clone()]);
         assert_noop!(
             MultiApp::update_by_state(
                 Origin::signed(players[0]),
                 state_proof
             ),
-            "app state is finalized"
+            "Check co-sigs failed"
+        );
+
+        let state_proof = get_state_proof(1, 3, 2, session_id, vec![players_pair[0].clone(), players_pair[0].clone()]);
+        assert_noop!(
+            MultiApp::update_by_state(
+                Origin::signed(players[0]),
+                state_proof
+            ),
+            "Check co-sigs failed"
         );
     })
 }
 
 #[test]
-fn test_pass_finalize_on_action_timeout() {
+fn test_fail_update_by_state_with_wrong_number_of_sigs() {
     ExtBuilder::build().execute_with(|| {
-        System::set_block_number(1);
         let alice_pair = account_pair("Alice");
         let bob_pair = account_pair("Bob");
         let (players, players_pair) = get_sorted_peer(alice_pair.clone(), bob_pair.clone());
@@ -388,7 +557,12 @@ fn test_pass_finalize_on_action_timeout() {
             nonce: 1,
             player_num: 2,
             players: players.clone(),
-            timeout: 2
+            timeout: 2,
+            encoding: StateEncoding::Scale,
+            eth_players: vec![],
+            threshold: 2,
+            arbiter: None,
+            seed_timeout: None,
         };
         assert_ok!(
             MultiApp::session_initiate(
@@ -398,40 +572,969 @@ fn test_pass_finalize_on_action_timeout() {
         );
 
         let session_id = MultiApp::get_session_id(initiate_request.nonce, initiate_request.players.clone());
-        let state_proof = get_state_proof(1, 2, 2, session_id, players_pair.clone());
-        assert_ok!(
+        let state_proof = get_state_proof(1, 3, 2, session_id, vec![players_pair[0].clone()]);
+        assert_noop!(
+            MultiApp::update_by_state(
+                Origin::signed(players[0]),
+                state_proof
+            ),
+            Error::<TestRuntime>::InvalidSignatureNum
+        );
+
+        // 258 signatures must not pass as 2 players
+        let mut state_proof = get_state_proof(1, 3, 2, session_id, players_pair.clone());
+        state_proof.sigs = state_proof.sigs.iter().cycle().take(258).cloned().collect();
+        assert_noop!(
             MultiApp::update_by_state(
                 Origin::signed(players[0]),
                 state_proof
+            ),
+            Error::<TestRuntime>::InvalidSignatureNum
+        );
+    })
+}
+
+#[test]
+fn test_pass_update_by_state_with_many_players() {
+    ExtBuilder::build().execute_with(|| {
+        System::set_block_number(1);
+        let mut players_pair: Vec<sr25519::Pair> = ["Alice", "Bob", "Carl", "Dave", "Eve"].iter()
+            .map(|name| account_pair(name))
+            .collect();
+        players_pair.sort_by_key(|pair| pair.public());
+        let players: Vec<AccountId> = players_pair.iter().map(|pair| pair.public()).collect();
+
+        let initiate_request = SessionInitiateRequest {
+            nonce: 1,
+            player_num: 5,
+            players: players.clone(),
+            timeout: 2,
+            encoding: StateEncoding::Scale,
+            eth_players: vec![],
+            threshold: 5,
+            arbiter: None,
+            seed_timeout: None,
+        };
+        assert_ok!(
+            MultiApp::session_initiate(
+                Origin::signed(players[0]),
+                initiate_request.clone()
             )
-        );   
+        );
 
-        System::set_block_number(5);
+        let session_id = MultiApp::get_session_id(initiate_request.nonce, initiate_request.players.clone());
+        let app_state = AppState {
+            seq_num: 1,
+            state: 3,
+            timeout: 2,
+            session_id: session_id,
+        };
+        let encoded = app_state.signing_payload();
+        let mut sigs: Vec<Signature> = players_pair.iter().map(|pair| pair.sign(&encoded)).collect();
+
+        // one invalid signature fails the whole batch
+        sigs[3] = account_pair("Ferdie").sign(&encoded);
+        let state_proof = StateProof {
+            app_state: app_state.clone(),
+            sigs: sigs.clone(),
+        };
+        assert_noop!(
+            MultiApp::update_by_state(
+                Origin::signed(players[0]),
+                state_proof
+            ),
+            "Check co-sigs failed"
+        );
+
+        sigs[3] = players_pair[3].sign(&encoded);
+        let state_proof = StateProof {
+            app_state: app_state,
+            sigs: sigs,
+        };
         assert_ok!(
-            MultiApp::finalize_on_action_timeout(
+            MultiApp::update_by_state(
                 Origin::signed(players[0]),
-                session_id
+                state_proof
             )
         );
+        assert_eq!(MultiApp::get_seq_num(session_id), Some(1));
     })
 }
 
-fn get_state_proof(
-    seq: u128,
-    state: u8,
-    timeout: BlockNumber,
-    session_id: H256,
-    players_pair: Vec<sr25519::Pair>
-) -> StateProof<BlockNumber, H256, Signature> {
-    let app_state = AppState {
-        seq_num: seq,
-        state: state,
-        timeout: timeout,
-        session_id: session_id
-    };
-
-    let encoded = MultiApp::encode_app_state(app_state.clone());
-    let sig_1 = players_pair[0].sign(&encoded);
+#[test]
+fn test_pass_update_by_threshold_state() {
+    ExtBuilder::build().execute_with(|| {
+        System::set_block_number(1);
+        let mut players_pair: Vec<sr25519::Pair> = ["Alice", "Bob", "Carl", "Dave", "Eve"].iter()
+            .map(|name| account_pair(name))
+            .collect();
+        players_pair.sort_by_key(|pair| pair.public());
+        let players: Vec<AccountId> = players_pair.iter().map(|pair| pair.public()).collect();
+
+        let mut initiate_request = SessionInitiateRequest {
+            nonce: 1,
+            player_num: 5,
+            players: players.clone(),
+            timeout: 2,
+            encoding: StateEncoding::Scale,
+            eth_players: vec![],
+            threshold: 6,
+            arbiter: None,
+            seed_timeout: None,
+        };
+        assert_noop!(
+            MultiApp::session_initiate(
+                Origin::signed(players[0]),
+                initiate_request.clone()
+            ),
+            "invalid threshold"
+        );
+        initiate_request.threshold = 3;
+        initiate_request.player_num = 4;
+        assert_noop!(
+            MultiApp::session_initiate(
+                Origin::signed(players[0]),
+                initiate_request.clone()
+            ),
+            "invalid player length"
+        );
+        let empty_request = SessionInitiateRequest {
+            players: vec![],
+            player_num: 0,
+            ..initiate_request.clone()
+        };
+        assert_noop!(
+            MultiApp::session_initiate(
+                Origin::signed(players[0]),
+                empty_request
+            ),
+            "invalid player length"
+        );
+        initiate_request.player_num = 5;
+        assert_ok!(
+            MultiApp::session_initiate(
+                Origin::signed(players[0]),
+                initiate_request.clone()
+            )
+        );
+
+        let session_id = MultiApp::get_session_id(initiate_request.nonce, initiate_request.players.clone());
+        let app_state = AppState {
+            seq_num: 1,
+            state: 3,
+            timeout: 2,
+            session_id: session_id,
+        };
+        let encoded = app_state.signing_payload();
+        let player_sig = |player: u8| PlayerSig {
+            player: player,
+            sig: players_pair[player as usize].sign(&encoded),
+        };
+        let threshold_proof = |sigs: Vec<PlayerSig<Signature>>| StateProof {
+            app_state: app_state.clone(),
+            sigs: sigs,
+        };
+
+        assert_noop!(
+            MultiApp::update_by_threshold_state(
+                Origin::signed(players[0]),
+                threshold_proof(vec![player_sig(0), player_sig(3)])
+            ),
+            Error::<TestRuntime>::NotEnoughSignatures
+        );
+        assert_noop!(
+            MultiApp::update_by_threshold_state(
+                Origin::signed(players[0]),
+                threshold_proof(vec![player_sig(0), player_sig(3), player_sig(0)])
+            ),
+            Error::<TestRuntime>::DuplicateSigner
+        );
+        let mut out_of_range = player_sig(4);
+        out_of_range.player = 5;
+        assert_noop!(
+            MultiApp::update_by_threshold_state(
+                Origin::signed(players[0]),
+                threshold_proof(vec![player_sig(0), player_sig(3), out_of_range])
+            ),
+            Error::<TestRuntime>::InvalidPlayerIndex
+        );
+        let mut misplaced = player_sig(4);
+        misplaced.player = 1;
+        assert_noop!(
+            MultiApp::update_by_threshold_state(
+                Origin::signed(players[0]),
+                threshold_proof(vec![player_sig(0), player_sig(3), misplaced])
+            ),
+            "Check co-sigs failed"
+        );
+
+        // any 3 of the 5 players, in any order
+        assert_ok!(
+            MultiApp::update_by_threshold_state(
+                Origin::signed(players[0]),
+                threshold_proof(vec![player_sig(4), player_sig(1), player_sig(2)])
+            )
+        );
+        assert_eq!(MultiApp::get_seq_num(session_id), Some(1));
+    })
+}
+
+#[test]
+fn test_pass_rotate_player_key() {
+    ExtBuilder::build().execute_with(|| {
+        System::set_block_number(1);
+        let mut players_pair: Vec<sr25519::Pair> = ["Alice", "Bob", "Carl"].iter()
+            .map(|name| account_pair(name))
+            .collect();
+        players_pair.sort_by_key(|pair| pair.public());
+        let players: Vec<AccountId> = players_pair.iter().map(|pair| pair.public()).collect();
+        let arbiter_pair = account_pair("Frank");
+
+        let initiate_request = SessionInitiateRequest {
+            nonce: 1,
+            player_num: 3,
+            players: players.clone(),
+            timeout: 2,
+            encoding: StateEncoding::Scale,
+            eth_players: vec![],
+            threshold: 3,
+            arbiter: Some(arbiter_pair.public()),
+            seed_timeout: None,
+        };
+        assert_ok!(
+            MultiApp::session_initiate(
+                Origin::signed(players[0]),
+                initiate_request.clone()
+            )
+        );
+
+        let session_id = MultiApp::get_session_id(initiate_request.nonce, initiate_request.players.clone());
+        // "Hank" sorts between players[0] and players[2], "Dave" below players[0]
+        let new_pair = account_pair("Hank");
+        let rotation = KeyRotation {
+            app_id: session_id,
+            old_player: players[1],
+            new_player: players[2],
+            nonce: 0,
+        };
+        assert_noop!(
+            MultiApp::rotate_player_key(
+                Origin::signed(players[0]),
+                rotation.clone(),
+                players_pair[1].sign(&rotation.signing_payload()),
+                players_pair[2].sign(&rotation.signing_payload())
+            ),
+            "new account already joins app"
+        );
+        let rotation = KeyRotation {
+            app_id: session_id,
+            old_player: players[1],
+            new_player: account_pair("Dave").public(),
+            nonce: 0,
+        };
+        assert_noop!(
+            MultiApp::rotate_player_key(
+                Origin::signed(players[0]),
+                rotation.clone(),
+                players_pair[1].sign(&rotation.signing_payload()),
+                account_pair("Dave").sign(&rotation.signing_payload())
+            ),
+            "player is not ascending order"
+        );
+
+        // the other players cannot take over the seat of a player
+        let rotation = KeyRotation {
+            app_id: session_id,
+            old_player: players[1],
+            new_player: new_pair.public(),
+            nonce: 0,
+        };
+        let payload = rotation.signing_payload();
+        assert_noop!(
+            MultiApp::rotate_player_key(
+                Origin::signed(players[0]),
+                rotation.clone(),
+                players_pair[0].sign(&payload),
+                new_pair.sign(&payload)
+            ),
+            "invalid rotation signature"
+        );
+        assert_noop!(
+            MultiApp::rotate_player_key(
+                Origin::signed(players[0]),
+                rotation.clone(),
+                arbiter_pair.sign(&payload),
+                players_pair[2].sign(&payload)
+            ),
+            "invalid new account signature"
+        );
+        // the arbiter authorizes the rotation of a lost account
+        assert_ok!(
+            MultiApp::rotate_player_key(
+                Origin::signed(players[0]),
+                rotation.clone(),
+                arbiter_pair.sign(&payload),
+                new_pair.sign(&payload)
+            )
+        );
+        assert_eq!(
+            MultiApp::session_info(session_id).unwrap().players,
+            vec![players[0], new_pair.public(), players[2]]
+        );
+        assert_eq!(MultiApp::key_rotation_nonce(session_id), 1);
+        let expected_event = TestEvent::multi_app(RawEvent::PlayerKeyRotated(session_id, players[1], new_pair.public()));
+        assert!(System::events().iter().any(|a| a.event == expected_event));
+
+        // a rotation signed for a spent nonce is replayed
+        let stale_rotation = KeyRotation {
+            app_id: session_id,
+            old_player: new_pair.public(),
+            new_player: account_pair("Judy").public(),
+            nonce: 0,
+        };
+        assert_noop!(
+            MultiApp::rotate_player_key(
+                Origin::signed(players[0]),
+                stale_rotation.clone(),
+                new_pair.sign(&stale_rotation.signing_payload()),
+                account_pair("Judy").sign(&stale_rotation.signing_payload())
+            ),
+            "invalid rotation nonce"
+        );
+
+        // the new account signs in the slot of the old one
+        let new_players_pair = vec![players_pair[0].clone(), new_pair.clone(), players_pair[2].clone()];
+        let state_proof = get_state_proof(1, 3, 2, session_id, new_players_pair.clone());
+        assert_ok!(
+            MultiApp::update_by_state(
+                Origin::signed(players[0]),
+                state_proof
+            )
+        );
+
+        // no rotation once the session is finalized
+        let state_proof = get_state_proof(2, 1, 2, session_id, new_players_pair);
+        assert_ok!(
+            MultiApp::update_by_state(
+                Origin::signed(players[0]),
+                state_proof
+            )
+        );
+        assert_eq!(MultiApp::get_status(session_id), Some(SessionStatus::Finalized));
+        let rotation = KeyRotation {
+            app_id: session_id,
+            old_player: new_pair.public(),
+            new_player: account_pair("Judy").public(),
+            nonce: 1,
+        };
+        assert_noop!(
+            MultiApp::rotate_player_key(
+                Origin::signed(players[0]),
+                rotation.clone(),
+                new_pair.sign(&rotation.signing_payload()),
+                account_pair("Judy").sign(&rotation.signing_payload())
+            ),
+            "session is finalized"
+        );
+    })
+}
+
+#[cfg(feature = "runtime-benchmarks")]
+#[test]
+fn test_pass_benchmark_update_by_state() {
+    let (mut ext, _) = ExtBuilder::build_with_watchtower();
+    ext.execute_with(|| {
+        System::set_block_number(1);
+        assert_ok!(benchmarking::test_benchmark_update_by_state::<TestRuntime>());
+    })
+}
+
+#[test]
+fn test_pass_update_by_state_with_valid_a_seq_sig() {
+    ExtBuilder::build().execute_with(|| {
+        let alice_pair = account_pair("Alice");
+        let bob_pair = account_pair("Bob");
+        let (players, players_pair) = get_sorted_peer(alice_pair.clone(), bob_pair.clone());
+
+        let initiate_request = SessionInitiateRequest {
+            nonce: 1,
+            player_num: 2,
+            players: players.clone(),
+            timeout: 2,
+            encoding: StateEncoding::Scale,
+            eth_players: vec![],
+            threshold: 2,
+            arbiter: None,
+            seed_timeout: None,
+        };
+        assert_ok!(
+            MultiApp::session_initiate(
+                Origin::signed(players[0]),
+                initiate_request.clone()
+            )
+        );
+
+        let session_id = MultiApp::get_session_id(initiate_request.nonce, initiate_request.players.clone());
+        let mut state_proof = get_state_proof(1, 5, 2, session_id, players_pair.clone());
+    
+        assert_ok!(
+            MultiApp::update_by_state(
+                Origin::signed(players[0]),
+                state_proof
+            )
+        ); 
+
+        state_proof = get_state_proof(2, 2, 2, session_id, players_pair);
+        assert_ok!(
+            MultiApp::update_by_state(
+                Origin::signed(players[0]),
+                state_proof
+            )
+        );
+
+        assert_ok!(
+            MultiApp::get_outcome(
+                Origin::signed(players[0]),
+                session_id,
+                2
+            )
+        );
+        assert_ok!(
+            MultiApp::is_finalized(
+                Origin::signed(players[0]),
+                session_id
+            )
+        );
+    })
+}
+
+#[test]
+fn test_fail_update_by_action_after_finalized() {
+    ExtBuilder::build().execute_with(|| {
+        let alice_pair = account_pair("Alice");
+        let bob_pair = account_pair("Bob");
+        let (players, players_pair) = get_sorted_peer(alice_pair.clone(), bob_pair.clone());
+
+        let initiate_request = SessionInitiateRequest {
+            nonce: 1,
+            player_num: 2,
+            players: players.clone(),
+            timeout: 2,
+            encoding: StateEncoding::Scale,
+            eth_players: vec![],
+            threshold: 2,
+            arbiter: None,
+            seed_timeout: None,
+        };
+        assert_ok!(
+            MultiApp::session_initiate(
+                Origin::signed(players[0]),
+                initiate_request.clone()
+            )
+        );
+
+        let session_id = MultiApp::get_session_id(initiate_request.nonce, initiate_request.players.clone());
+        let state_proof = get_state_proof(1, 2, 2, session_id, players_pair.clone());
+    
+        assert_ok!(
+            MultiApp::update_by_state(
+                Origin::signed(players[0]),
+                state_proof
+            )
+        ); 
+
+        assert_noop!(
+            MultiApp::update_by_action(
+                Origin::signed(players[0]),
+                session_id,
+                2
+            ),
+            "app state is finalized"
+        );
+    })
+}
+
+#[test]
+fn test_fail_update_by_state_after_finalized() {
+    ExtBuilder::build().execute_with(|| {
+        let alice_pair = account_pair("Alice");
+        let bob_pair = account_pair("Bob");
+        let (players, players_pair) = get_sorted_peer(alice_pair.clone(), bob_pair.clone());
+
+        let initiate_request = SessionInitiateRequest {
+            nonce: 1,
+            player_num: 2,
+            players: players.clone(),
+            timeout: 2,
+            encoding: StateEncoding::Scale,
+            eth_players: vec![],
+            threshold: 2,
+            arbiter: None,
+            seed_timeout: None,
+        };
+        assert_ok!(
+            MultiApp::session_initiate(
+                Origin::signed(players[0]),
+                initiate_request.clone()
+            )
+        );
+
+        let session_id = MultiApp::get_session_id(initiate_request.nonce, initiate_request.players.clone());
+        let mut state_proof = get_state_proof(1, 2, 2, session_id, players_pair.clone());
+        assert_ok!(
+            MultiApp::update_by_state(
+                Origin::signed(players[0]),
+                state_proof
+            )
+        ); 
+
+        state_proof = get_state_proof(2, 2, 2, session_id, players_pair);
+        assert_noop!(
+            MultiApp::update_by_state(
+                Origin::signed(players[0]),
+                state_proof
+            ),
+            "app state is finalized"
+        );
+    })
+}
+
+#[test]
+fn test_pass_finalize_on_action_timeout() {
+    ExtBuilder::build().execute_with(|| {
+        System::set_block_number(1);
+        let alice_pair = account_pair("Alice");
+        let bob_pair = account_pair("Bob");
+        let (players, players_pair) = get_sorted_peer(alice_pair.clone(), bob_pair.clone());
+
+        let initiate_request = SessionInitiateRequest {
+            nonce: 1,
+            player_num: 2,
+            players: players.clone(),
+            timeout: 2,
+            encoding: StateEncoding::Scale,
+            eth_players: vec![],
+            threshold: 2,
+            arbiter: None,
+            seed_timeout: None,
+        };
+        assert_ok!(
+            MultiApp::session_initiate(
+                Origin::signed(players[0]),
+                initiate_request.clone()
+            )
+        );
+
+        let session_id = MultiApp::get_session_id(initiate_request.nonce, initiate_request.players.clone());
+        let state_proof = get_state_proof(1, 2, 2, session_id, players_pair.clone());
+        assert_ok!(
+            MultiApp::update_by_state(
+                Origin::signed(players[0]),
+                state_proof
+            )
+        );   
+
+        System::set_block_number(5);
+        assert_ok!(
+            MultiApp::finalize_on_action_timeout(
+                Origin::signed(players[0]),
+                session_id
+            )
+        );
+    })
+}
+
+#[test]
+fn test_pass_arbitrate() {
+    ExtBuilder::build().execute_with(|| {
+        System::set_block_number(1);
+        let alice_pair = account_pair("Alice");
+        let bob_pair = account_pair("Bob");
+        let (players, players_pair) = get_sorted_peer(alice_pair, bob_pair);
+        let arbiter = account_pair("Carl").public();
+
+        let mut initiate_request = SessionInitiateRequest {
+            nonce: 1,
+            player_num: 2,
+            players: players.clone(),
+            timeout: 2,
+            encoding: StateEncoding::Scale,
+            eth_players: vec![],
+            threshold: 2,
+            arbiter: Some(players[1]),
+            seed_timeout: None,
+        };
+        assert_noop!(
+            MultiApp::session_initiate(
+                Origin::signed(players[0]),
+                initiate_request.clone()
+            ),
+            "arbiter is a player"
+        );
+        initiate_request.arbiter = Some(arbiter);
+        assert_ok!(
+            MultiApp::session_initiate(
+                Origin::signed(players[0]),
+                initiate_request.clone()
+            )
+        );
+
+        let session_id = MultiApp::get_session_id(initiate_request.nonce, initiate_request.players.clone());
+        assert_noop!(
+            MultiApp::arbitrate(
+                Origin::signed(arbiter),
+                session_id,
+                1
+            ),
+            "session not in settle or action mode"
+        );
+
+        // the ruling overrides the action timeout
+        let state_proof = get_state_proof(1, 5, 2, session_id, players_pair.clone());
+        assert_ok!(
+            MultiApp::update_by_state(
+                Origin::signed(players[0]),
+                state_proof
+            )
+        );
+        System::set_block_number(4);
+        assert_ok!(
+            MultiApp::update_by_action(
+                Origin::signed(players[0]),
+                session_id,
+                3
+            )
+        );
+        assert_noop!(
+            MultiApp::arbitrate(
+                Origin::signed(players[1]),
+                session_id,
+                1
+            ),
+            Error::<TestRuntime>::NotArbiter
+        );
+        assert_noop!(
+            MultiApp::arbitrate(
+                Origin::signed(arbiter),
+                session_id,
+                3
+            ),
+            "invalid outcome state"
+        );
+        assert_noop!(
+            MultiApp::arbitrate(
+                Origin::signed(arbiter),
+                session_id,
+                0
+            ),
+            "invalid outcome state"
+        );
+
+        assert_ok!(
+            MultiApp::arbitrate(
+                Origin::signed(arbiter),
+                session_id,
+                1
+            )
+        );
+        assert_eq!(MultiApp::get_state(session_id), Some(1));
+        assert_eq!(MultiApp::get_status(session_id), Some(SessionStatus::Finalized));
+        let expected_ruling = Ruling {
+            arbiter: arbiter,
+            outcome: 1,
+            block_number: 4,
+        };
+        assert_eq!(MultiApp::ruling(session_id), Some(expected_ruling));
+        let expected_event = TestEvent::multi_app(RawEvent::Arbitrated(session_id, arbiter, 1));
+        assert!(System::events().iter().any(|a| a.event == expected_event));
+    })
+}
+
+#[test]
+fn test_pass_commit_reveal_seed() {
+    ExtBuilder::build().execute_with(|| {
+        System::set_block_number(1);
+        let mut players_pair: Vec<sr25519::Pair> = ["Alice", "Bob", "Carl"].iter()
+            .map(|name| account_pair(name))
+            .collect();
+        players_pair.sort_by_key(|pair| pair.public());
+        let players: Vec<AccountId> = players_pair.iter().map(|pair| pair.public()).collect();
+
+        let mut initiate_request = SessionInitiateRequest {
+            nonce: 1,
+            player_num: 3,
+            players: players.clone(),
+            timeout: 2,
+            encoding: StateEncoding::Scale,
+            eth_players: vec![],
+            threshold: 2,
+            arbiter: None,
+            seed_timeout: Some(0),
+        };
+        assert_noop!(
+            MultiApp::session_initiate(
+                Origin::signed(players[0]),
+                initiate_request.clone()
+            ),
+            "invalid seed timeout"
+        );
+        initiate_request.seed_timeout = Some(3);
+        assert_ok!(
+            MultiApp::session_initiate(
+                Origin::signed(players[0]),
+                initiate_request.clone()
+            )
+        );
+
+        let session_id = MultiApp::get_session_id(initiate_request.nonce, initiate_request.players.clone());
+        let secrets: Vec<H256> = (1..4).map(H256::from_low_u64_be).collect();
+        let commitments: Vec<H256> = players.iter().zip(secrets.iter())
+            .map(|(player, secret)| seed_commitment::<_, BlakeTwo256>(&session_id, player, secret))
+            .collect();
+
+        // states are not settled before the random seed is decided
+        let state_proof = get_state_proof(1, 5, 2, session_id, players_pair.clone());
+        assert_noop!(
+            MultiApp::update_by_state(
+                Origin::signed(players[0]),
+                state_proof.clone()
+            ),
+            "random seed is not decided"
+        );
+        assert_noop!(
+            MultiApp::commit_seed(
+                Origin::signed(account_pair("Dave").public()),
+                session_id,
+                commitments[0]
+            ),
+            "not a player of session"
+        );
+
+        for (player, commitment) in players.iter().zip(commitments.iter()).take(2) {
+            assert_ok!(
+                MultiApp::commit_seed(
+                    Origin::signed(*player),
+                    session_id,
+                    *commitment
+                )
+            );
+        }
+        assert_noop!(
+            MultiApp::reveal_seed(
+                Origin::signed(players[0]),
+                session_id,
+                secrets[0]
+            ),
+            "seeds are not all committed"
+        );
+        assert_ok!(
+            MultiApp::commit_seed(
+                Origin::signed(players[2]),
+                session_id,
+                commitments[2]
+            )
+        );
+        assert_noop!(
+            MultiApp::reveal_seed(
+                Origin::signed(players[0]),
+                session_id,
+                secrets[1]
+            ),
+            "secret not match commitment"
+        );
+        for (player, secret) in players.iter().zip(secrets.iter()) {
+            assert_ok!(
+                MultiApp::reveal_seed(
+                    Origin::signed(*player),
+                    session_id,
+                    *secret
+                )
+            );
+        }
+
+        let seed = combined_seed::<BlakeTwo256>(&secrets);
+        assert_eq!(MultiApp::get_seed(session_id), Some(seed));
+        let expected_event = TestEvent::multi_app(RawEvent::SeedDecided(session_id, seed));
+        assert!(System::events().iter().any(|a| a.event == expected_event));
+
+        assert_ok!(
+            MultiApp::update_by_state(
+                Origin::signed(players[0]),
+                state_proof
+            )
+        );
+        assert_eq!(MultiApp::get_state(session_id), Some(5));
+    })
+}
+
+#[test]
+fn test_pass_finalize_on_seed_timeout() {
+    ExtBuilder::build().execute_with(|| {
+        System::set_block_number(1);
+        let alice_pair = account_pair("Alice");
+        let bob_pair = account_pair("Bob");
+        let (players, _) = get_sorted_peer(alice_pair, bob_pair);
+
+        let initiate_request = SessionInitiateRequest {
+            nonce: 1,
+            player_num: 2,
+            players: players.clone(),
+            timeout: 2,
+            encoding: StateEncoding::Scale,
+            eth_players: vec![],
+            threshold: 2,
+            arbiter: None,
+            seed_timeout: Some(2),
+        };
+        assert_ok!(
+            MultiApp::session_initiate(
+                Origin::signed(players[0]),
+                initiate_request.clone()
+            )
+        );
+
+        let session_id = MultiApp::get_session_id(initiate_request.nonce, initiate_request.players.clone());
+        let secrets = vec![H256::from_low_u64_be(1), H256::from_low_u64_be(2)];
+        let commitment = seed_commitment::<_, BlakeTwo256>(&session_id, &players[1], &secrets[1]);
+        assert_ok!(
+            MultiApp::commit_seed(
+                Origin::signed(players[1]),
+                session_id,
+                commitment
+            )
+        );
+        assert_noop!(
+            MultiApp::commit_seed(
+                Origin::signed(players[1]),
+                session_id,
+                commitment
+            ),
+            "seed is already committed"
+        );
+        assert_noop!(
+            MultiApp::finalize_on_seed_timeout(
+                Origin::signed(players[1]),
+                session_id
+            ),
+            "seed deadline no passes"
+        );
+
+        // the player who did not commit in time loses the session
+        System::set_block_number(4);
+        assert_noop!(
+            MultiApp::commit_seed(
+                Origin::signed(players[0]),
+                session_id,
+                seed_commitment::<_, BlakeTwo256>(&session_id, &players[0], &secrets[0])
+            ),
+            "seed deadline passed"
+        );
+        assert_ok!(
+            MultiApp::finalize_on_seed_timeout(
+                Origin::signed(players[1]),
+                session_id
+            )
+        );
+        assert_eq!(MultiApp::get_state(session_id), Some(2));
+        assert_eq!(MultiApp::get_status(session_id), Some(SessionStatus::Finalized));
+        assert_eq!(MultiApp::get_seed(session_id), None);
+        let expected_event = TestEvent::multi_app(RawEvent::SeedForfeited(session_id, vec![players[0]]));
+        assert!(System::events().iter().any(|a| a.event == expected_event));
+    })
+}
+
+#[test]
+fn test_pass_watchtower_answers_stale_settle() {
+    let (mut ext, pool_state) = ExtBuilder::build_with_watchtower();
+    ext.execute_with(|| {
+        System::set_block_number(1);
+        let alice_pair = account_pair("Alice");
+        let bob_pair = account_pair("Bob");
+        let (players, players_pair) = get_sorted_peer(alice_pair.clone(), bob_pair.clone());
+
+        let initiate_request = SessionInitiateRequest {
+            nonce: 1,
+            player_num: 2,
+            players: players.clone(),
+            timeout: 2,
+            encoding: StateEncoding::Scale,
+            eth_players: vec![],
+            threshold: 2,
+            arbiter: None,
+            seed_timeout: None,
+        };
+        assert_ok!(
+            MultiApp::session_initiate(
+                Origin::signed(players[0]),
+                initiate_request.clone()
+            )
+        );
+
+        let session_id = MultiApp::get_session_id(initiate_request.nonce, initiate_request.players.clone());
+        let stale_proof = get_state_proof(1, 5, 2, session_id, players_pair.clone());
+        let latest_proof = get_state_proof(3, 5, 2, session_id, players_pair);
+        MultiApp::watch_state_proof(latest_proof.clone());
+
+        // nothing to answer before settlement
+        MultiApp::offchain_worker(1);
+        assert!(pool_state.read().transactions.is_empty());
+
+        assert_ok!(
+            MultiApp::update_by_state(
+                Origin::signed(players[0]),
+                stale_proof
+            )
+        );
+        MultiApp::offchain_worker(1);
+        let tx = pool_state.write().transactions.pop().unwrap();
+        assert!(pool_state.read().transactions.is_empty());
+        let tx = Extrinsic::decode(&mut &*tx).unwrap();
+        assert_eq!(tx.signature.unwrap().0, 0);
+        assert_eq!(tx.call, mock::Call::MultiApp(crate::Call::update_by_state(latest_proof.clone())));
+
+        // the answer is not repeated until the chain state changes
+        MultiApp::offchain_worker(1);
+        assert!(pool_state.read().transactions.is_empty());
+
+        // latest state is settled, nothing left to answer
+        assert_ok!(
+            MultiApp::update_by_state(
+                Origin::signed(players[1]),
+                latest_proof
+            )
+        );
+        MultiApp::offchain_worker(2);
+        assert!(pool_state.read().transactions.is_empty());
+    })
+}
+
+fn get_state_proof(
+    seq: u128,
+    state: u8,
+    timeout: BlockNumber,
+    session_id: H256,
+    players_pair: Vec<sr25519::Pair>
+) -> StateProof<BlockNumber, H256, Signature> {
+    get_encoded_state_proof(seq, state, timeout, session_id, players_pair, StateEncoding::Scale)
+}
+
+fn get_encoded_state_proof(
+    seq: u128,
+    state: u8,
+    timeout: BlockNumber,
+    session_id: H256,
+    players_pair: Vec<sr25519::Pair>,
+    encoding: StateEncoding,
+) -> StateProof<BlockNumber, H256, Signature> {
+    let app_state = AppState {
+        seq_num: seq,
+        state: state,
+        timeout: timeout,
+        session_id: session_id
+    };
+
+    let encoded = app_state.payload(encoding).unwrap();
+    let sig_1 = players_pair[0].sign(&encoded);
     let sig_2 = players_pair[1].sign(&encoded);
     let state_proof = StateProof {
         app_state: app_state,
@@ -439,4 +1542,30 @@ fn get_state_proof(
     };
 
     return state_proof;
-}
\ No newline at end of file
+}
+fn get_eth_state_proof(
+    seq: u128,
+    state: u8,
+    timeout: BlockNumber,
+    session_id: H256,
+    eth_pairs: Vec<ethereum::Pair>,
+) -> EthStateProof<BlockNumber, H256> {
+    let app_state = AppState {
+        seq_num: seq,
+        state: state,
+        timeout: timeout,
+        session_id: session_id
+    };
+
+    let encoded = app_state.ethereum_payload().unwrap();
+    let sigs = eth_pairs.iter().map(|pair| pair.sign(&encoded)).collect();
+
+    StateProof {
+        app_state: app_state,
+        sigs: sigs,
+    }
+}
+
+fn eth_pair(seed: u8) -> ethereum::Pair {
+    ethereum::Pair::from_secret(&[seed; 32]).unwrap()
+}
@@ -0,0 +1,97 @@
+//! Encoded types of multi session app for SCALE clients
+
+use super::*;
+
+capps_type_defs::type_defs! {
+    enum StateEncoding {
+        Scale,
+        Protobuf,
+        Ethereum,
+    }
+    struct SessionInitiateRequest<AccountId, BlockNumber> {
+        nonce: u128,
+        player_num: u8,
+        players: Vec<AccountId>,
+        timeout: BlockNumber,
+        encoding: StateEncoding,
+        eth_players: Vec<H160>,
+        threshold: u8,
+        arbiter: Option<AccountId>,
+        seed_timeout: Option<BlockNumber>,
+    }
+    type SessionInitiateRequestOf = SessionInitiateRequest;
+    struct AppState<BlockNumber, Hash> {
+        seq_num: u128,
+        state: u8,
+        timeout: BlockNumber,
+        session_id: Hash,
+    }
+    type AppStateOf = AppState;
+    struct StateProof<BlockNumber, Hash, Signature> {
+        app_state: AppState<BlockNumber, Hash>,
+        sigs: Vec<Signature>,
+    }
+    type StateProofOf = StateProof;
+    struct PlayerSig<Signature> {
+        player: u8,
+        sig: Signature,
+    }
+    struct ThresholdStateProof<BlockNumber, Hash, Signature> {
+        app_state: AppState<BlockNumber, Hash>,
+        sigs: Vec<PlayerSig<Signature>>,
+    }
+    type ThresholdStateProofOf = ThresholdStateProof;
+    struct SessionKeyDelegation<AccountId, BlockNumber, Hash> {
+        app_id: Hash,
+        player: AccountId,
+        session_key: AccountId,
+        expires_at: BlockNumber,
+    }
+    type SessionKeyDelegationOf = SessionKeyDelegation;
+    struct KeyRotation<AccountId, Hash> {
+        app_id: Hash,
+        old_player: AccountId,
+        new_player: AccountId,
+        nonce: u32,
+    }
+    type KeyRotationOf = KeyRotation;
+    struct EthStateProof<BlockNumber, Hash> {
+        app_state: AppState<BlockNumber, Hash>,
+        sigs: Vec<EcdsaSignature>,
+    }
+    type EthStateProofOf = EthStateProof;
+    enum SessionStatus {
+        Idle,
+        Settle,
+        Action,
+        Finalized,
+    }
+    struct SessionInfo<AccountId, BlockNumber> {
+        state: u8,
+        players: Vec<AccountId>,
+        player_num: u8,
+        seq_num: u128,
+        timeout: BlockNumber,
+        deadline: BlockNumber,
+        status: SessionStatus,
+        encoding: StateEncoding,
+        eth_players: Vec<H160>,
+        threshold: u8,
+        arbiter: Option<AccountId>,
+    }
+    type SessionInfoOf = SessionInfo;
+    struct Ruling<AccountId, BlockNumber> {
+        arbiter: AccountId,
+        outcome: u8,
+        block_number: BlockNumber,
+    }
+    type RulingOf = Ruling;
+    struct SeedRound<BlockNumber, Hash> {
+        commits: Vec<Option<Hash>>,
+        secrets: Vec<Option<Hash>>,
+        timeout: BlockNumber,
+        deadline: BlockNumber,
+        seed: Option<Hash>,
+    }
+    type SeedRoundOf = SeedRound;
+}
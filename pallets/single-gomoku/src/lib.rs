@@ -1,6 +1,10 @@
 #![cfg_attr(not(feature = "std"), no_std)]
 
 mod mock;
+mod watchtower;
+pub mod types;
+#[cfg(feature = "runtime-benchmarks")]
+mod benchmarking;
 
 #[cfg(test)]
 mod tests;
@@ -8,24 +12,42 @@ mod tests;
 use codec::{Decode, Encode};
 use frame_support::{
     decl_module, decl_storage, decl_event, decl_error, ensure,
-    storage::StorageMap,
+    storage::{StorageDoubleMap, StorageMap},
     traits::Get,
 };
 use frame_system::{self as system, ensure_signed};
 use sp_runtime::traits::{
-    Hash, IdentifyAccount, 
+    Hash, IdentifyAccount, UniqueSaturatedInto, 
     Member, Verify, Zero, AccountIdConversion, 
 };
 use sp_runtime::{ModuleId, RuntimeDebug, DispatchResult, DispatchError};
-use sp_std::{prelude::*, vec::Vec};
+use sp_std::{convert::TryInto, prelude::*, vec::Vec};
+use sp_core::{H160, H256};
+use capps_state_encoding::ethereum;
+
+pub use capps_app_common::{
+    combined_seed, relayed_action_payload, seed_commitment, signed_move_payload, KeyRotation,
+    Ruling, SeedRound, SessionKeyDelegation, SignedMove, KEY_ROTATION_PREFIX,
+    RELAYED_ACTION_PREFIX, SESSION_KEY_DELEGATION_PREFIX, SIGNED_MOVE_PREFIX,
+};
+pub use capps_state_encoding::{ethereum::EcdsaSignature, StateEncoding};
+pub use watchtower::{crypto, watched_proof_key, SignedWatchtower, WatchedProofOf, KEY_TYPE, WATCHED_APPS_KEY};
+use gomoku_engine::{Board, Move};
 
 #[derive(Clone, Eq, PartialEq, PartialOrd, Ord, Encode, Decode, RuntimeDebug)]
 pub struct AppInitiateRequest<AccountId, BlockNumber> {
-    nonce: u128,
-    players: Vec<AccountId>,
-    timeout: BlockNumber,
-    min_stone_offchain: u8,
-    max_stone_onchain: u8,
+    pub nonce: u128,
+    pub players: Vec<AccountId>,
+    pub timeout: BlockNumber,
+    pub min_stone_offchain: u8,
+    pub max_stone_onchain: u8,
+    pub time_bank: BlockNumber, // time bank of each player in action mode, 0 for no time control
+    pub increment: BlockNumber, // time added to the time bank after each on-chain move
+    pub hashed_board: bool, // store only a hash of the board on-chain
+    pub encoding: StateEncoding, // encoding of app states signed by players
+    pub eth_players: Vec<H160>, // Ethereum addresses of players in Ethereum encoding, empty otherwise
+    pub arbiter: Option<AccountId>, // account ruling the winner in settle or action mode
+    pub seed_timeout: Option<BlockNumber>, // period to commit and then to reveal a random seed, None for no random seed
 }
 
 pub type AppInitiateRequestOf<T> = AppInitiateRequest<
@@ -35,11 +57,11 @@ pub type AppInitiateRequestOf<T> = AppInitiateRequest<
 
 #[derive(Clone, Eq, PartialEq, PartialOrd, Ord, Encode, Decode, RuntimeDebug)]
 pub struct AppState<BlockNumber, Hash> {
-    nonce: u128,
-    seq_num: u128,
-    board_state: Vec<u8>,
-    timeout: BlockNumber,
-    app_id: Hash,
+    pub nonce: u128,
+    pub seq_num: u128,
+    pub board_state: Vec<u8>,
+    pub timeout: BlockNumber,
+    pub app_id: Hash,
 }
 
 pub type AppStateOf<T> = AppState<
@@ -49,8 +71,8 @@ pub type AppStateOf<T> = AppState<
 
 #[derive(Clone, Eq, PartialEq, PartialOrd, Ord, Encode, Decode, RuntimeDebug)]
 pub struct StateProof<BlockNumber, Hash, Signature> {
-    app_state: AppState<BlockNumber, Hash>,
-    sigs: Vec<Signature>,
+    pub app_state: AppState<BlockNumber, Hash>,
+    pub sigs: Vec<Signature>,
 }
 
 pub type StateProofOf<T> = StateProof<
@@ -59,6 +81,83 @@ pub type StateProofOf<T> = StateProof<
     <T as Trait>::Signature,
 >;
 
+pub type SessionKeyDelegationOf<T> = SessionKeyDelegation<
+    <T as system::Trait>::AccountId,
+    <T as system::Trait>::BlockNumber,
+    <T as system::Trait>::Hash,
+>;
+
+pub type KeyRotationOf<T> = KeyRotation<
+    <T as system::Trait>::AccountId,
+    <T as system::Trait>::Hash,
+>;
+
+pub type SignedMoveOf<T> = SignedMove<<T as Trait>::Signature>;
+
+/// State proof signed by Ethereum accounts of players
+pub type EthStateProof<BlockNumber, Hash> = StateProof<BlockNumber, Hash, EcdsaSignature>;
+
+pub type EthStateProofOf<T> = EthStateProof<
+    <T as system::Trait>::BlockNumber,
+    <T as system::Trait>::Hash,
+>;
+
+impl<BlockNumber: Encode, Hash: Encode> AppState<BlockNumber, Hash> {
+    /// Encode app state into the payload signed by players
+    pub fn signing_payload(&self) -> Vec<u8> {
+        let mut encoded = self.nonce.encode();
+        encoded.extend(self.seq_num.encode());
+        self.board_state.iter()
+            .for_each(|state| { encoded.extend(state.encode()); });
+        encoded.extend(self.timeout.encode());
+        encoded.extend(self.app_id.encode());
+
+        return encoded;
+    }
+}
+
+impl<BlockNumber: Encode + Clone + TryInto<u64>, Hash: Encode> AppState<BlockNumber, Hash> {
+    /// Encode app state into the protobuf `AppState` message signed by Celer's clients,
+    /// with the board state as state
+    ///
+    /// Returns `None` if a number does not fit in a `uint64` field.
+    pub fn protobuf_payload(&self) -> Option<Vec<u8>> {
+        capps_state_encoding::protobuf::app_state(
+            self.nonce,
+            self.seq_num,
+            &self.board_state,
+            self.timeout.clone().try_into().ok()?,
+            &self.app_id.encode(),
+        )
+    }
+
+    /// Encode app state as `abi.encodePacked` signed by Ethereum accounts of players,
+    /// with the board state as state
+    ///
+    /// Returns `None` if the timeout does not fit in a `uint64`.
+    pub fn ethereum_payload(&self) -> Option<Vec<u8>> {
+        Some(ethereum::app_state(
+            Some(self.nonce),
+            self.seq_num,
+            &self.board_state,
+            self.timeout.clone().try_into().ok()?,
+            &self.app_id.encode(),
+        ))
+    }
+
+    /// Encode app state into the payload signed by players under an encoding
+    ///
+    /// Parameter:
+    /// `encoding`: Encoding chosen at initiation
+    pub fn payload(&self, encoding: StateEncoding) -> Option<Vec<u8>> {
+        match encoding {
+            StateEncoding::Scale => Some(self.signing_payload()),
+            StateEncoding::Protobuf => self.protobuf_payload(),
+            StateEncoding::Ethereum => self.ethereum_payload(),
+        }
+    }
+}
+
 #[derive(Clone, Eq, PartialEq, PartialOrd, Ord, Encode, Decode, RuntimeDebug)]
 pub enum AppStatus {
     Idle = 0,
@@ -68,19 +167,23 @@ pub enum AppStatus {
 }
 
 #[derive(Clone, Eq, PartialEq, PartialOrd, Ord, Encode, Decode, RuntimeDebug)]
-pub struct GomokuInfo<AccountId, BlockNumber> {
+pub struct GomokuInfo<AccountId, BlockNumber, Hash> {
     nonce: u128,
     players: Vec<AccountId>,
     seq_num: u128,
     timeout: BlockNumber,
     deadline: BlockNumber,
     status: AppStatus,
-    gomoku_state: GomokuState,
+    gomoku_state: GomokuState<BlockNumber, Hash>,
+    encoding: StateEncoding,
+    eth_players: Vec<H160>,
+    arbiter: Option<AccountId>,
 }
 
 pub type GomokuInfoOf<T> = GomokuInfo<
     <T as system::Trait>::AccountId,
     <T as system::Trait>::BlockNumber,
+    <T as system::Trait>::Hash,
 >;
 
 #[derive(Clone, Eq, PartialEq, PartialOrd, Ord, Encode, Decode, RuntimeDebug)]
@@ -91,27 +194,172 @@ pub enum StateKey {
 }
 
 #[derive(Clone, Eq, PartialEq, PartialOrd, Ord, Encode, Decode, RuntimeDebug)]
-struct GomokuState {
-    board_state: Option<Vec<u8>>, // 227 length: u8 winner + u8 turn + 15*15 board
+struct GomokuState<BlockNumber, Hash> {
+    board_state: Option<BoardState<BoardRecord<Hash>>>, // u8 winner + u8 turn + packed or hashed 15*15 board
     stone_num: Option<u16>, // number of stones
     stone_num_onchain: Option<u16>, // number of stones places on-chain
     state_key: Option<StateKey>, // key of turn, winner fullstate
     min_stone_offchain: u8, // minimal number of stones before go onchain
     max_stone_onchain: u8, // maximal number of stones after go onchain
+    time_banks: Vec<BlockNumber>, // remaining time bank of each player, empty for no time control
+    increment: BlockNumber, // time added to the time bank after each on-chain move
+    hashed_board: bool, // store only a hash of the board on-chain
+}
+
+#[derive(Clone, Copy, Default, Eq, PartialEq, PartialOrd, Ord, Encode, Decode, RuntimeDebug)]
+struct BoardState<B = Board> {
+    winner: u8, // winner player id
+    turn: u8, // player id in turn
+    board: B, // 15*15 board
+}
+
+#[derive(Clone, Copy, Eq, PartialEq, PartialOrd, Ord, Encode, Decode, RuntimeDebug)]
+enum BoardRecord<Hash> {
+    Packed(Board), // 15*15 board, 2 bits per slot
+    Hashed(Hash), // hash of the 225 board slots, board state is given as a witness
+}
+
+impl BoardState {
+    /// Decode board state from the 227 length layout of off-chain states
+    ///
+    /// Parameter:
+    /// `state`: u8 winner + u8 turn + 15*15 board
+    fn from_bytes(state: &[u8]) -> Option<BoardState> {
+        if state.len() != 227 {
+            return None;
+        }
+        let board = Board::from_slots(&state[2..])?;
+
+        Some(BoardState {
+            winner: state[0],
+            turn: state[1],
+            board: board,
+        })
+    }
+
+    /// Encode board state into the 227 length layout of off-chain states
+    fn to_bytes(&self) -> Vec<u8> {
+        let mut state = Vec::with_capacity(227);
+        state.push(self.winner);
+        state.push(self.turn);
+        self.board.extend_slots(&mut state);
+
+        return state;
+    }
+}
+
+#[derive(Clone, Eq, PartialEq, PartialOrd, Ord, Encode, Decode, RuntimeDebug)]
+pub struct MoveRecord<BlockNumber> {
+    pub player_id: u8, // id of the moving player, kept across key rotations
+    pub x: u8,
+    pub y: u8,
+    pub block_number: BlockNumber,
+    pub seq_num: u128,
+}
+
+pub type MoveRecordOf<T> = MoveRecord<<T as system::Trait>::BlockNumber>;
+
+#[derive(Clone, Eq, PartialEq, PartialOrd, Ord, Encode, Decode, RuntimeDebug)]
+pub struct ResumePoint<Hash> {
+    seq_num: u128, // sequence number of the co-signed state
+    state_hash: Hash, // hash of the co-signed board state
 }
 
+pub type ResumePointOf<T> = ResumePoint<<T as system::Trait>::Hash>;
+
+pub type RulingOf<T> = Ruling<
+    <T as system::Trait>::AccountId,
+    <T as system::Trait>::BlockNumber,
+>;
+
+pub type SeedRoundOf<T> = SeedRound<
+    <T as system::Trait>::BlockNumber,
+    <T as system::Trait>::Hash,
+>;
+
 pub const SINGLE_GOMOKU_ID: ModuleId = ModuleId(*b"s_gomoku");
 
+/// Get Id of app from its nonce and players, without a runtime
+///
+/// Parameters:
+/// `nonce`: Nonce of app
+/// `players`: AccountId of players
+pub fn app_id<AccountId, Hashing>(
+    nonce: u128,
+    players: &[AccountId],
+) -> Hashing::Output
+where
+    AccountId: Encode + Decode + Default,
+    Hashing: Hash,
+{
+    let app_account: AccountId = SINGLE_GOMOKU_ID.into_account();
+    let mut encoded = app_account.encode();
+    encoded.extend(nonce.encode());
+    encoded.extend(players[0].encode());
+    encoded.extend(players[1].encode());
+
+    Hashing::hash(&encoded)
+}
+
+/// Get Id of app signed by Ethereum accounts, hashed with keccak256 as
+/// Ethereum contracts do
+///
+/// Parameters:
+/// `nonce`: Nonce of app
+/// `eth_players`: Ethereum addresses of players
+pub fn eth_app_id(nonce: u128, eth_players: &[H160]) -> H256 {
+    ethereum::app_id(SINGLE_GOMOKU_ID.0, nonce, eth_players)
+}
+
+/// Get id of the player moving first, decided by the random seed
+///
+/// Parameter:
+/// `seed`: Random seed of app
+pub fn first_mover(seed: &[u8]) -> u8 {
+    seed[0] % 2 + 1
+}
+
+/// Authorization of an on-chain action
+enum ActionAuth<AccountId, Signature> {
+    /// Action submitted by the player in turn
+    Caller(AccountId),
+    /// Action signed by the player in turn and submitted by any account
+    Signed(Signature),
+}
+
+/// Signatures of an off-chain state proof
+enum StateSigs<Signature> {
+    /// Signatures of player accounts
+    Players(Vec<Signature>),
+    /// Signatures of Ethereum accounts of players
+    Ethereum(Vec<EcdsaSignature>),
+}
+
 pub trait Trait: system::Trait {
     type Event: From<Event<Self>> + Into<<Self as frame_system::Trait>::Event>;
     type Public: IdentifyAccount<AccountId = Self::AccountId>;
     type Signature: Verify<Signer = <Self as Trait>::Public> + Member + Decode + Encode; 
+    /// Watchtower submitting state proofs from the off-chain worker, `()` for none
+    type Watchtower: capps_watchtower::SubmitProof<WatchedProofOf<Self>>;
 }
 
 decl_storage! {
     trait Store for Module<T: Trait> as SingleGomoku {
         pub SingleGomokuInfoMap get(fn gomoku_info): 
             map hasher(blake2_128_concat) T::Hash => Option<GomokuInfoOf<T>>;
+        pub MoveHistoryMap get(fn move_history):
+            map hasher(blake2_128_concat) T::Hash => Vec<MoveRecordOf<T>>;
+        pub ResumePointMap get(fn resume_point):
+            map hasher(blake2_128_concat) T::Hash => Option<ResumePointOf<T>>;
+        pub RulingMap get(fn ruling):
+            map hasher(blake2_128_concat) T::Hash => Option<RulingOf<T>>;
+        pub SeedRoundMap get(fn seed_round):
+            map hasher(blake2_128_concat) T::Hash => Option<SeedRoundOf<T>>;
+        pub SessionKeyMap get(fn session_key):
+            double_map hasher(blake2_128_concat) T::Hash, hasher(blake2_128_concat) T::AccountId
+            => Option<(T::AccountId, T::BlockNumber)>;
+        pub KeyRotationNonceMap get(fn key_rotation_nonce):
+            map hasher(blake2_128_concat) T::Hash => u32;
     }
 }
 
@@ -121,6 +369,11 @@ decl_module! {
         
         fn deposit_event() = default;
 
+        /// Answer stale settlements of apps watched by this node
+        fn offchain_worker(block_number: T::BlockNumber) {
+            capps_watchtower::answer_stale_settles::<Self>(block_number.unique_saturated_into());
+        }
+
         /// Initiate single gomoku app
         ///
         /// Parameters:
@@ -131,17 +384,18 @@ decl_module! {
         /// - Complexity: `O(1)`
         ///   - 1 storage insertion `GomokuInfoMap`
         ///   - 1 storage reads `GomokuxInfoMap`
+        ///   - 1 storage insertion `SeedRoundMap` with a seed timeout
         /// - Based on benchmark;
         ///     17.89　µs
         /// # </weight>
-        #[weight = 18_000_000 + T::DbWeight::get().reads_writes(1, 1)]
+        #[weight = 18_000_000 + T::DbWeight::get().reads_writes(1, 2)]
         fn app_initiate(
             origin,
             initiate_request: AppInitiateRequestOf<T>
         ) -> DispatchResult {
             ensure_signed(origin)?;
 
-            let app_id = Self::get_app_id(initiate_request.nonce, initiate_request.players.clone());
+            let app_id = Self::get_initiate_app_id(&initiate_request)?;
             ensure!(
                 SingleGomokuInfoMap::<T>::contains_key(&app_id) == false,
                 "AppId already exists"
@@ -150,6 +404,26 @@ decl_module! {
                 initiate_request.players.len() == 2,
                 "invalid player length"
             );
+            if let Some(arbiter) = &initiate_request.arbiter {
+                ensure!(
+                    !initiate_request.players.contains(arbiter),
+                    "arbiter is a player"
+                );
+            }
+            let seed_round = match initiate_request.seed_timeout {
+                Some(seed_timeout) => {
+                    ensure!(
+                        !seed_timeout.is_zero(),
+                        "invalid seed timeout"
+                    );
+                    Some(SeedRoundOf::<T>::new(
+                        initiate_request.players.len(),
+                        seed_timeout,
+                        frame_system::Module::<T>::block_number(),
+                    ))
+                },
+                None => None,
+            };
 
             let gomoku_state = GomokuState {
                 board_state: None,
@@ -158,6 +432,13 @@ decl_module! {
                 state_key: None,
                 min_stone_offchain: initiate_request.min_stone_offchain,
                 max_stone_onchain: initiate_request.max_stone_onchain,
+                time_banks: if initiate_request.time_bank.is_zero() {
+                    vec![]
+                } else {
+                    vec![initiate_request.time_bank; 2]
+                },
+                increment: initiate_request.increment,
+                hashed_board: initiate_request.hashed_board,
             };
             let gomoku_info = GomokuInfoOf::<T> {
                 nonce: initiate_request.nonce,
@@ -167,8 +448,15 @@ decl_module! {
                 deadline: Zero::zero(),
                 status: AppStatus::Idle,
                 gomoku_state: gomoku_state,
+                encoding: initiate_request.encoding,
+                eth_players: initiate_request.eth_players,
+                arbiter: initiate_request.arbiter,
             };
             SingleGomokuInfoMap::<T>::insert(app_id, gomoku_info);
+            // players commit to a random seed before the first settle
+            if let Some(seed_round) = seed_round {
+                SeedRoundMap::<T>::insert(app_id, seed_round);
+            }
 
             Ok(())
         }
@@ -186,10 +474,14 @@ decl_module! {
         /// - Complexity: `O(1)`
         ///   - 1 storage mutation `GomokuInfoMap`
         ///   - 1 storage read `GomokuInfoMap`
+        ///   - 1 storage insertion `ResumePointMap`
+        ///   - 1 storage removal `MoveHistoryMap`
+        ///   - 1 storage read `SessionKeyMap` per player
+        ///   - 1 storage read `SeedRoundMap`
         /// - Based on benchmark;
         ///     50.27　µs
         /// # </weight>
-        #[weight = 51_000_000 + T::DbWeight::get().reads_writes(1, 1)]
+        #[weight = 51_000_000 + T::DbWeight::get().reads_writes(4, 3)]
         fn update_by_state(
             origin,
             state_proof: StateProofOf<T>
@@ -197,59 +489,204 @@ decl_module! {
             ensure_signed(origin)?;
 
             // submit and settle off-chain state
-            let mut gomoku_info: GomokuInfoOf<T> = Self::intend_settle(state_proof.clone())?;
+            Self::update_state(state_proof.app_state, StateSigs::Players(state_proof.sigs), Vec::new())
+        }
+
+        /// Update on-chain state according to an off-chain state proof
+        /// followed by moves signed only by their movers
+        ///
+        /// The i-th move is signed by the player in turn on
+        /// `signed_move_payload` with sequence number `seq_num + i + 1` of the
+        /// state proof, and is checked by the gomoku rules.
+        ///
+        /// Parameters:
+        /// - `state_proof`: Signed off-chain app state
+        /// - `moves`: Moves played after the app state, in order
+        ///
+        /// # <weight>
+        /// ## Weight
+        /// - Complexity: `O(M)`
+        ///   - M: Number of moves
+        ///   - 1 storage mutation `GomokuInfoMap`
+        ///   - 1 storage read `GomokuInfoMap`
+        ///   - 1 storage insertion `ResumePointMap`
+        ///   - 1 storage removal `MoveHistoryMap`
+        ///   - 1 storage read `SessionKeyMap` per player, shared by the state proof and the moves
+        ///   - 1 storage read `SeedRoundMap`
+        /// - Based on `update_by_state` and 55　µs per move to verify its
+        ///     signature and place its stone
+        /// # </weight>
+        #[weight = 51_000_000 + 55_000_000 * moves.len() as u64 + T::DbWeight::get().reads_writes(4, 3)]
+        fn update_by_state_with_moves(
+            origin,
+            state_proof: StateProofOf<T>,
+            moves: Vec<SignedMoveOf<T>>
+        ) -> DispatchResult {
+            ensure_signed(origin)?;
+            Self::update_state(state_proof.app_state, StateSigs::Players(state_proof.sigs), moves)
+        }
+
+        /// Update state according to an off-chain state proof signed by
+        /// Ethereum accounts of players
+        ///
+        /// Parameters:
+        /// - `state_proof`: Off-chain app state signed by Ethereum accounts
+        ///
+        /// # <weight>
+        /// ## Weight
+        /// - Complexity: `O(1)`
+        ///   - 1 storage mutation `GomokuInfoMap`
+        ///   - 1 storage read `GomokuInfoMap`
+        ///   - 1 storage insertion `ResumePointMap`
+        ///   - 1 storage removal `MoveHistoryMap`
+        ///   - 1 storage read `SeedRoundMap`
+        /// - Based on `update_by_state` and 2 secp256k1 recoveries of
+        ///     50　µs
+        /// # </weight>
+        #[weight = 151_000_000 + T::DbWeight::get().reads_writes(2, 3)]
+        fn update_by_eth_state(
+            origin,
+            state_proof: EthStateProofOf<T>
+        ) -> DispatchResult {
+            ensure_signed(origin)?;
+            Self::update_state(state_proof.app_state, StateSigs::Ethereum(state_proof.sigs), Vec::new())
+        }
 
-            let _state = state_proof.app_state.board_state;
+        /// Delegate off-chain state signing of a player to a session key
+        ///
+        /// The delegation is signed by the player, so any account may submit
+        /// it. A delegation only replaces one which expires earlier.
+        ///
+        /// Parameters:
+        /// - `delegation`: Session key of a player and its expiry
+        /// - `signature`: Signature of the player on the delegation
+        ///
+        /// # <weight>
+        /// ## Weight
+        /// - Complexity: `O(1)`
+        ///   - 1 storage read `GomokuInfoMap`
+        ///   - 1 storage read `SessionKeyMap`
+        ///   - 1 storage insertion `SessionKeyMap`
+        /// - Based on 1 signature verification of 50　µs
+        /// # </weight>
+        #[weight = 60_000_000 + T::DbWeight::get().reads_writes(2, 1)]
+        fn delegate_session_key(
+            origin,
+            delegation: SessionKeyDelegationOf<T>,
+            signature: <T as Trait>::Signature
+        ) -> DispatchResult {
+            ensure_signed(origin)?;
+            let gomoku_info = match SingleGomokuInfoMap::<T>::get(delegation.app_id) {
+                Some(info) => info,
+                None => Err(Error::<T>::SingleGomokuInfoNotExist)?,
+            };
             ensure!(
-                _state.len() == 227,
-                "invalid board state length"
+                gomoku_info.encoding != StateEncoding::Ethereum,
+                "app is signed by Ethereum accounts"
             );
-
-            let count = 0;
-            if _state[0] != 0 {
-                gomoku_info = Self::win_game(_state[0], gomoku_info.clone())?;
-            } else {
-                // advance to _state[2];
-                let mut _state_iter = _state.iter();
-                for _i in 0..3 {
-                    _state_iter.next();
-                }
-                // load other states only if winner is not specified
-                let count = _state_iter.filter(|&x| *x != 0).count() as u8;
-    
+            ensure!(
+                gomoku_info.players.contains(&delegation.player),
+                "not a player of app"
+            );
+            ensure!(
+                delegation.expires_at > frame_system::Module::<T>::block_number(),
+                "session key is expired"
+            );
+            if let Some((_, expires_at)) = SessionKeyMap::<T>::get(delegation.app_id, &delegation.player) {
                 ensure!(
-                    count >= gomoku_info.gomoku_state.min_stone_offchain,
-                    "not enough offchain stones"
+                    delegation.expires_at > expires_at,
+                    "session key expires earlier than current one"
                 );
             }
+            ensure!(
+                signature.verify(&delegation.signing_payload()[..], &delegation.player),
+                "invalid delegation signature"
+            );
 
-            let new_gomoku_state = GomokuState {
-                board_state: Some(_state),
-                stone_num: Some(count),
-                stone_num_onchain: gomoku_info.gomoku_state.stone_num_onchain,
-                state_key: gomoku_info.gomoku_state.state_key,
-                min_stone_offchain: gomoku_info.gomoku_state.min_stone_offchain,
-                max_stone_onchain: gomoku_info.gomoku_state.max_stone_onchain,
-            };
-            let new_gomoku_info = GomokuInfoOf::<T> {
-                nonce: gomoku_info.nonce,
-                players: gomoku_info.players,
-                seq_num: gomoku_info.seq_num,
-                timeout: gomoku_info.timeout,
-                deadline: gomoku_info.deadline,
-                status: gomoku_info.status,
-                gomoku_state: new_gomoku_state,
+            SessionKeyMap::<T>::insert(
+                delegation.app_id,
+                &delegation.player,
+                (delegation.session_key.clone(), delegation.expires_at)
+            );
+
+            // emit SessionKeyDelegated event
+            Self::deposit_event(RawEvent::SessionKeyDelegated(
+                delegation.app_id,
+                delegation.player,
+                delegation.session_key,
+                delegation.expires_at,
+            ));
+
+            Ok(())
+        }
+
+        /// Replace the account of a player in a live app
+        ///
+        /// The rotation is signed by the old account of the player or by the
+        /// arbiter, and by the new account, so any account may submit it. The
+        /// other player cannot take over the seat alone, and no account joins
+        /// without its own consent. The new account takes the place of the old
+        /// one, keeping the player id used by app states.
+        ///
+        /// Parameters:
+        /// - `rotation`: Old and new account of a player
+        /// - `signature`: Signature of the old account or of the arbiter
+        /// - `new_signature`: Signature of the new account
+        ///
+        /// # <weight>
+        /// ## Weight
+        /// - Complexity: `O(1)`
+        ///   - 1 storage mutation `GomokuInfoMap`
+        ///   - 1 storage read `GomokuInfoMap`
+        ///   - 1 storage mutation `KeyRotationNonceMap`
+        ///   - 1 storage read `KeyRotationNonceMap`
+        ///   - 1 storage removal `SessionKeyMap`
+        /// - Based on 3 signature verifications of 50　µs
+        /// # </weight>
+        #[weight = 160_000_000 + T::DbWeight::get().reads_writes(2, 3)]
+        fn rotate_player_key(
+            origin,
+            rotation: KeyRotationOf<T>,
+            signature: <T as Trait>::Signature,
+            new_signature: <T as Trait>::Signature
+        ) -> DispatchResult {
+            ensure_signed(origin)?;
+            let mut gomoku_info = match SingleGomokuInfoMap::<T>::get(rotation.app_id) {
+                Some(info) => info,
+                None => Err(Error::<T>::SingleGomokuInfoNotExist)?,
             };
-            let app_id = state_proof.app_state.app_id;
-            SingleGomokuInfoMap::<T>::mutate(app_id, |info| *info = Some(new_gomoku_info.clone()));
-            
-            Self::deposit_event(RawEvent::IntendSettle(app_id, new_gomoku_info.seq_num));
+            ensure!(
+                gomoku_info.status != AppStatus::Finalized,
+                "app state is finalized"
+            );
+            let index = Self::valid_rotation(
+                &rotation,
+                signature,
+                new_signature,
+                &gomoku_info.players,
+                gomoku_info.arbiter.as_ref(),
+            )?;
+
+            gomoku_info.players[index] = rotation.new_player.clone();
+            SingleGomokuInfoMap::<T>::insert(rotation.app_id, gomoku_info);
+            KeyRotationNonceMap::<T>::insert(rotation.app_id, rotation.nonce + 1);
+            // a session key delegated by the old account is no longer accepted
+            SessionKeyMap::<T>::remove(rotation.app_id, &rotation.old_player);
+
+            // emit PlayerKeyRotated event
+            Self::deposit_event(RawEvent::PlayerKeyRotated(
+                rotation.app_id,
+                rotation.old_player,
+                rotation.new_player,
+            ));
 
             Ok(())
         }
 
         /// Update state according to an on-chain action
         ///
+        /// In hash-committed mode, use `update_by_action_with_witness` instead.
+        ///
         /// Parameters:
         /// - `app_id`: Id of app
         /// - `action`: Action data
@@ -257,132 +694,92 @@ decl_module! {
         /// # <weight>
         /// ## Weight
         /// - Complexity: `O(1)`
-        ///   - 2 storage mutation `GomokuInfoMap`
+        ///   - 1 storage mutation `GomokuInfoMap`
         ///   - 1 storage read `GomokuInfoMap`
-        /// - Based on benchmark;
+        ///   - 1 storage mutation `MoveHistoryMap`
+        /// - Based on benchmark of the board vector on reference hardware, which
+        ///   the bitboard does not exceed as it places and scans stones without
+        ///   allocating; `update_by_action` in `benchmarking.rs` measures the bitboard
         ///     47.23　µs
         /// # </weight>
-        #[weight = 48_000_000 + T::DbWeight::get().reads_writes(1, 2)]
+        #[weight = 48_000_000 + T::DbWeight::get().reads_writes(2, 2)]
         fn update_by_action(
             origin,
             app_id: T::Hash,
             action: Vec<u8>
         ) -> DispatchResult {
             let caller = ensure_signed(origin)?;
-            
-            // apply an action to the on-chain state
-            let gomoku_info = Self::apply_action(app_id)?;
-            let gomoku_state = gomoku_info.gomoku_state.clone();
-            let mut board_state = gomoku_info.gomoku_state.board_state.unwrap_or(vec![0; 227]);
-            let turn = board_state[1];
-            ensure!(
-                caller == gomoku_info.players[turn as usize - 1],
-                "not your turn"    
-            );
-
-            let x = action[0];
-            let y = action[1];
-            ensure!(
-                Self::check_boundary(x, y),
-                "out of boundary"
-            );
-            let index: usize = Self::state_index(x, y);
-            ensure!(
-                board_state[index] == 0,
-                "slot is occupied"
-            );
-
-            // place the stone
-            board_state[index] = turn;
-            let new_stone_num = gomoku_state.stone_num.unwrap_or(0) + 1;
-            let new_stone_num_onchain = gomoku_state.stone_num_onchain.unwrap_or(0) + 1;
-            let new_gomoku_state_1 = GomokuState {
-                board_state: Some(board_state.clone()),
-                stone_num: Some(new_stone_num),
-                stone_num_onchain: Some(new_stone_num_onchain),
-                state_key: gomoku_state.state_key.clone(),
-                min_stone_offchain: gomoku_state.min_stone_offchain,
-                max_stone_onchain: gomoku_state.max_stone_onchain,
-            };
-            let mut new_gomoku_info_1 = GomokuInfoOf::<T> {
-                nonce: gomoku_info.nonce,
-                players: gomoku_info.players.clone(),
-                seq_num: gomoku_info.seq_num,
-                timeout: gomoku_info.timeout,
-                deadline: gomoku_info.deadline,
-                status: gomoku_info.status.clone(),
-                gomoku_state: new_gomoku_state_1,
-            };
-            SingleGomokuInfoMap::<T>::mutate(app_id, |info| *info = Some(new_gomoku_info_1.clone()));
-
-            // check if there is five-in-a-row including this new stone
-            if Self::check_five(board_state.clone(), x, y, 1, 0) // horizontal bidirection
-                || Self::check_five(board_state.clone(), x, y, 0, 1) // vertical bidirection
-                || Self::check_five(board_state.clone(), x, y, 1, 1) // main-diagonal bidirection
-                || Self::check_five(board_state.clone(), x, y, 1, -1) // anti-diagonal bidirection
-            {
-                new_gomoku_info_1 = Self::win_game(turn, new_gomoku_info_1)?;
-                SingleGomokuInfoMap::<T>::mutate(app_id, |info| *info = Some(new_gomoku_info_1));
-                return Ok(());
-            }
+            Self::play_action(app_id, action, None, ActionAuth::Caller(caller))
+        }
 
-            if new_stone_num == 225 
-                || new_stone_num_onchain as u8 > gomoku_state.max_stone_onchain {
-                    // all slots occupied, game is over with no winner
-                    // set turn 0
-                    board_state[1] = 0;
-                    let new_gomoku_state_2 = GomokuState {
-                        board_state: Some(board_state),
-                        stone_num: Some(new_stone_num),
-                        stone_num_onchain: Some(new_stone_num_onchain),
-                        state_key: gomoku_state.state_key,
-                        min_stone_offchain: gomoku_state.min_stone_offchain,
-                        max_stone_onchain: gomoku_state.max_stone_onchain,
-                    };
-                    let new_gomoku_info_2 = GomokuInfoOf::<T> {
-                        nonce: gomoku_info.nonce,
-                        players: gomoku_info.players,
-                        seq_num: gomoku_info.seq_num,
-                        timeout: gomoku_info.timeout,
-                        deadline: gomoku_info.deadline,
-                        status: AppStatus::Finalized,
-                        gomoku_state: new_gomoku_state_2,
-                    };
-                    SingleGomokuInfoMap::<T>::mutate(app_id, |info| *info = Some(new_gomoku_info_2.clone()));
-            } else {
-                // toggle turn and update game phase
-                if turn == 1 {
-                    // set turn 2
-                    board_state[1] = 2;
-                } else {
-                    // set turn 1
-                    board_state[1] = 1;
-                }
-                let new_gomoku_state_2 = GomokuState {
-                    board_state: Some(board_state),
-                    stone_num: Some(new_stone_num),
-                    stone_num_onchain: Some(new_stone_num_onchain),
-                    state_key: gomoku_state.state_key,
-                    min_stone_offchain: gomoku_state.min_stone_offchain,
-                    max_stone_onchain: gomoku_state.max_stone_onchain,
-                };
-                let new_gomoku_info_2 = GomokuInfoOf::<T> {
-                    nonce: gomoku_info.nonce,
-                    players: gomoku_info.players,
-                    seq_num: gomoku_info.seq_num,
-                    timeout: gomoku_info.timeout,
-                    deadline: gomoku_info.deadline,
-                    status: gomoku_info.status,
-                    gomoku_state: new_gomoku_state_2,
-                };
-                SingleGomokuInfoMap::<T>::mutate(app_id, |info| *info = Some(new_gomoku_info_2.clone()));
-            }
+        /// Update state according to an on-chain action, with the current board
+        /// as a witness of the board hash in hash-committed mode
+        ///
+        /// Parameters:
+        /// - `app_id`: Id of app
+        /// - `action`: Action data
+        /// - `board_witness`: Current board state, ignored when the full board is on-chain
+        ///
+        /// # <weight>
+        /// ## Weight
+        /// - Complexity: `O(1)`
+        ///   - 1 storage mutation `GomokuInfoMap`
+        ///   - 1 storage read `GomokuInfoMap`
+        ///   - 1 storage mutation `MoveHistoryMap`
+        /// - Based on benchmark of the board vector on reference hardware, which
+        ///   the bitboard does not exceed as it places and scans stones without
+        ///   allocating; `update_by_action` in `benchmarking.rs` measures the bitboard
+        ///     47.23　µs
+        /// # </weight>
+        #[weight = 48_000_000 + T::DbWeight::get().reads_writes(2, 2)]
+        fn update_by_action_with_witness(
+            origin,
+            app_id: T::Hash,
+            action: Vec<u8>,
+            board_witness: Vec<u8>
+        ) -> DispatchResult {
+            let caller = ensure_signed(origin)?;
+            Self::play_action(app_id, action, Some(board_witness), ActionAuth::Caller(caller))
+        }
 
-            Ok(())
+        /// Update state according to an on-chain action signed by the player
+        /// in turn and submitted by any account
+        ///
+        /// The signature on `relayed_action_payload` takes the place of the
+        /// origin check. It is bound to the current seq_num, so it authorizes
+        /// exactly one move and can not be replayed after that move.
+        ///
+        /// Parameters:
+        /// - `app_id`: Id of app
+        /// - `action`: Action data
+        /// - `signature`: Signature of the player in turn or its session key
+        /// - `board_witness`: Current board state in hash-committed mode, ignored otherwise
+        ///
+        /// # <weight>
+        /// ## Weight
+        /// - Complexity: `O(1)`
+        ///   - 1 storage mutation `GomokuInfoMap`
+        ///   - 1 storage read `GomokuInfoMap`
+        ///   - 1 storage mutation `MoveHistoryMap`
+        ///   - 1 storage read `SessionKeyMap`
+        /// - Based on `update_by_action` and 1 signature verification of 50　µs
+        /// # </weight>
+        #[weight = 98_000_000 + T::DbWeight::get().reads_writes(3, 2)]
+        fn update_by_relayed_action(
+            origin,
+            app_id: T::Hash,
+            action: Vec<u8>,
+            signature: <T as Trait>::Signature,
+            board_witness: Option<Vec<u8>>
+        ) -> DispatchResult {
+            ensure_signed(origin)?;
+            Self::play_action(app_id, action, board_witness, ActionAuth::Signed(signature))
         }
 
         /// Finalized based on current state in case of on-chain action timeout
         ///
+        /// With time control, the player in turn loses once the time bank runs out.
+        ///
         /// Parameters:
         /// - `app_id`: Id of app
         ///
@@ -399,7 +796,7 @@ decl_module! {
             origin,
             app_id: T::Hash
         ) -> DispatchResult {
-            let gomoku_info = match SingleGomokuInfoMap::<T>::get(app_id) {
+            let mut gomoku_info = match SingleGomokuInfoMap::<T>::get(app_id) {
                 Some(info) => info,
                 None => Err(Error::<T>::SingleGomokuInfoNotExist)?,
             };
@@ -412,26 +809,225 @@ decl_module! {
                 );
             } else if gomoku_info.status == AppStatus::Settle {
                 ensure!(
-                    block_number > gomoku_info.deadline + gomoku_info.timeout,
+                    block_number > Self::first_action_deadline(&gomoku_info),
                     "while settling"
                 );
             } else {
                 return Ok(());
             }
 
-            let board_state = match gomoku_info.clone().gomoku_state.board_state {
+            let board_state = match gomoku_info.gomoku_state.board_state {
                 Some(state) => state,
                 None => Err(Error::<T>::EmptyBoardState)?,
             };
-            if board_state[1] == 1 {
-                let new_gomoku_info = Self::win_game(2, gomoku_info)?;
-                SingleGomokuInfoMap::<T>::mutate(app_id, |info| *info = Some(new_gomoku_info.clone()));
-            } else if board_state[1] == 2 {
-                let new_gomoku_info = Self::win_game(1, gomoku_info)?;
-                SingleGomokuInfoMap::<T>::mutate(app_id, |info| *info = Some(new_gomoku_info.clone()));
+            if board_state.turn == 1 {
+                Self::win_game(2, &mut gomoku_info)?;
+            } else if board_state.turn == 2 {
+                Self::win_game(1, &mut gomoku_info)?;
+            } else {
+                return Ok(());
+            }
+            SingleGomokuInfoMap::<T>::insert(app_id, gomoku_info);
+
+            Ok(())
+        }
+
+        /// Commit to a random seed
+        ///
+        /// Every player commits before the commit deadline, which runs from
+        /// the initiation of the app. Once every player committed, the reveal
+        /// period starts. Commitments are not taken in the initiate request:
+        /// any account submits it and players do not sign it, so the submitter
+        /// could put in a commitment to a secret a player never knew and make
+        /// the player forfeit. Each player commits by itself.
+        ///
+        /// Parameters:
+        /// - `app_id`: Id of app
+        /// - `commitment`: Commitment of the player, see `seed_commitment`
+        ///
+        /// # <weight>
+        /// ## Weight
+        /// - Complexity: `O(N)`
+        ///   - N: Number of players
+        ///   - 1 storage read `GomokuInfoMap`
+        ///   - 1 storage mutation `SeedRoundMap`
+        ///   - 1 storage read `SeedRoundMap`
+        /// # </weight>
+        #[weight = 20_000_000 + T::DbWeight::get().reads_writes(2, 1)]
+        fn commit_seed(
+            origin,
+            app_id: T::Hash,
+            commitment: T::Hash
+        ) -> DispatchResult {
+            let caller = ensure_signed(origin)?;
+            let (index, mut seed_round) = Self::live_seed_round(app_id, &caller)?;
+            seed_round.commit(index, commitment, frame_system::Module::<T>::block_number())?;
+            SeedRoundMap::<T>::insert(app_id, seed_round);
+
+            // emit SeedCommitted event
+            Self::deposit_event(RawEvent::SeedCommitted(app_id, caller));
+
+            Ok(())
+        }
+
+        /// Reveal the secret committed to a random seed
+        ///
+        /// The random seed is decided once every player revealed.
+        ///
+        /// Parameters:
+        /// - `app_id`: Id of app
+        /// - `secret`: Secret of the commitment of the player
+        ///
+        /// # <weight>
+        /// ## Weight
+        /// - Complexity: `O(N)`
+        ///   - N: Number of players
+        ///   - 1 storage read `GomokuInfoMap`
+        ///   - 1 storage mutation `SeedRoundMap`
+        ///   - 1 storage read `SeedRoundMap`
+        /// # </weight>
+        #[weight = 25_000_000 + T::DbWeight::get().reads_writes(2, 1)]
+        fn reveal_seed(
+            origin,
+            app_id: T::Hash,
+            secret: T::Hash
+        ) -> DispatchResult {
+            let caller = ensure_signed(origin)?;
+            let (index, mut seed_round) = Self::live_seed_round(app_id, &caller)?;
+            let seed = seed_round.reveal::<T::AccountId, T::Hashing>(&app_id, index, &caller, secret)?;
+            SeedRoundMap::<T>::insert(app_id, seed_round);
+
+            // emit SeedRevealed and SeedDecided events
+            Self::deposit_event(RawEvent::SeedRevealed(app_id, caller));
+            if let Some(seed) = seed {
+                Self::deposit_event(RawEvent::SeedDecided(app_id, seed));
+            }
+
+            Ok(())
+        }
+
+        /// Finalize app against players who did not commit or reveal a random
+        /// seed in time
+        ///
+        /// The first player who did not default wins the game, nobody wins if
+        /// every player defaulted.
+        ///
+        /// Parameters:
+        /// - `app_id`: Id of app
+        ///
+        /// # <weight>
+        /// ## Weight
+        /// - Complexity: `O(N)`
+        ///   - N: Number of players
+        ///   - 1 storage mutation `GomokuInfoMap`
+        ///   - 1 storage read `GomokuInfoMap`
+        ///   - 1 storage read `SeedRoundMap`
+        /// # </weight>
+        #[weight = 25_000_000 + T::DbWeight::get().reads_writes(2, 1)]
+        fn finalize_on_seed_timeout(
+            origin,
+            app_id: T::Hash
+        ) -> DispatchResult {
+            ensure_signed(origin)?;
+            let mut gomoku_info = match SingleGomokuInfoMap::<T>::get(app_id) {
+                Some(info) => info,
+                None => Err(Error::<T>::SingleGomokuInfoNotExist)?,
+            };
+            ensure!(
+                gomoku_info.status != AppStatus::Finalized,
+                "app state is finalized"
+            );
+            let seed_round = match SeedRoundMap::<T>::get(app_id) {
+                Some(seed_round) => seed_round,
+                None => Err("app has no random seed")?,
+            };
+            seed_round.check_timeout(frame_system::Module::<T>::block_number())?;
+
+            let defaulted = seed_round.defaulted();
+            let defaulters: Vec<T::AccountId> = gomoku_info.players.iter()
+                .zip(defaulted.iter())
+                .filter(|(_, defaulted)| **defaulted)
+                .map(|(player, _)| player.clone())
+                .collect();
+            let winner = defaulted.iter()
+                .position(|defaulted| !defaulted)
+                .map_or(0, |index| index as u8 + 1);
+            let board_state = BoardState {
+                winner: winner,
+                turn: 0,
+                board: Board::default(),
+            };
+            let hashed_board = gomoku_info.gomoku_state.hashed_board;
+            gomoku_info.gomoku_state.board_state = Some(Self::record_board(&board_state, hashed_board));
+            gomoku_info.status = AppStatus::Finalized;
+            SingleGomokuInfoMap::<T>::insert(app_id, gomoku_info);
+
+            // emit SeedForfeited event
+            Self::deposit_event(RawEvent::SeedForfeited(app_id, defaulters));
+
+            Ok(())
+        }
+
+        /// Finalize app with the winner ruled by its arbiter
+        ///
+        /// The ruling overrides the settle and action timeouts and is recorded
+        /// in `RulingMap`.
+        ///
+        /// Parameters:
+        /// - `app_id`: Id of app
+        /// - `winner`: Winner player id, 0 for a draw
+        ///
+        /// # <weight>
+        /// ## Weight
+        /// - Complexity: `O(1)`
+        ///   - 1 storage mutation `GomokuInfoMap`
+        ///   - 1 storage read `GomokuInfoMap`
+        ///   - 1 storage insertion `RulingMap`
+        /// - Based on `finalize_on_action_timeout`
+        /// # </weight>
+        #[weight = 31_000_000 + T::DbWeight::get().reads_writes(1, 2)]
+        fn arbitrate(
+            origin,
+            app_id: T::Hash,
+            winner: u8
+        ) -> DispatchResult {
+            let caller = ensure_signed(origin)?;
+            let mut gomoku_info = match SingleGomokuInfoMap::<T>::get(app_id) {
+                Some(info) => info,
+                None => Err(Error::<T>::SingleGomokuInfoNotExist)?,
+            };
+            ensure!(
+                gomoku_info.arbiter.as_ref() == Some(&caller),
+                Error::<T>::NotArbiter
+            );
+            ensure!(
+                gomoku_info.status == AppStatus::Settle || gomoku_info.status == AppStatus::Action,
+                "app not in settle or action mode"
+            );
+
+            if winner == 0 {
+                // draw
+                let board_state = match gomoku_info.gomoku_state.board_state.as_mut() {
+                    Some(state) => state,
+                    None => Err(Error::<T>::EmptyBoardState)?,
+                };
+                board_state.winner = 0;
+                board_state.turn = 0;
+                gomoku_info.status = AppStatus::Finalized;
             } else {
-                return Ok(());
+                Self::win_game(winner, &mut gomoku_info)?;
             }
+            SingleGomokuInfoMap::<T>::insert(app_id, gomoku_info);
+
+            let block_number = frame_system::Module::<T>::block_number();
+            RulingMap::<T>::insert(&app_id, RulingOf::<T> {
+                arbiter: caller.clone(),
+                outcome: winner,
+                block_number: block_number,
+            });
+
+            // emit Arbitrated event
+            Self::deposit_event(RawEvent::Arbitrated(app_id, caller, winner));
 
             Ok(())
         }
@@ -500,7 +1096,7 @@ decl_module! {
             
             // If outcome is false, return DispatchError::Other("FalseOutcome")
             ensure!(
-                board_state[0] == query,
+                board_state.winner == query,
                 "FalseOutcome"
             );
             
@@ -511,11 +1107,29 @@ decl_module! {
 }
 
 decl_event! (
-    pub enum Event<T> where 
-        <T as system::Trait>::Hash
+    pub enum Event<T> where
+        <T as system::Trait>::Hash,
+        <T as system::Trait>::AccountId,
+        <T as system::Trait>::BlockNumber
     {
+        /// SessionKeyDelegated(app_id, player, session_key, expires_at)
+        SessionKeyDelegated(Hash, AccountId, AccountId, BlockNumber),
+        /// PlayerKeyRotated(app_id, old_player, new_player)
+        PlayerKeyRotated(Hash, AccountId, AccountId),
         /// IntendSettle(app_id, seq_num)
         IntendSettle(Hash, u128),
+        /// BoardUpdated(app_id, seq_num, board_state) in hash-committed mode
+        BoardUpdated(Hash, u128, Vec<u8>),
+        /// Arbitrated(app_id, arbiter, winner)
+        Arbitrated(Hash, AccountId, u8),
+        /// SeedCommitted(app_id, player)
+        SeedCommitted(Hash, AccountId),
+        /// SeedRevealed(app_id, player)
+        SeedRevealed(Hash, AccountId),
+        /// SeedDecided(app_id, seed)
+        SeedDecided(Hash, Hash),
+        /// SeedForfeited(app_id, defaulters)
+        SeedForfeited(Hash, Vec<AccountId>),
     }
 );
 
@@ -525,6 +1139,16 @@ decl_error! {
         SingleGomokuInfoNotExist,
         // BoardState is empty
         EmptyBoardState,
+        // ResumePoint is not exist
+        ResumePointNotExist,
+        // MoveRecord is not played by player 1 or 2
+        InvalidMoveRecord,
+        // App state does not fit in the encoding of app
+        AppStateNotEncodable,
+        // Caller is not the arbiter of app
+        NotArbiter,
+        // Number of signatures does not match the players
+        InvalidSignatureNum,
     }
 }
 
@@ -538,13 +1162,47 @@ impl<T: Trait> Module<T> {
         nonce: u128,
         players: Vec<T::AccountId>,
     ) -> T::Hash {
-        let single_gomoku_app_account = Self::app_account();
-        let mut encoded = single_gomoku_app_account.encode();
-        encoded.extend(nonce.encode());
-        encoded.extend(players[0].encode());
-        encoded.extend(players[1].encode());
-        let app_id = T::Hashing::hash(&encoded);
-        return app_id;
+        app_id::<T::AccountId, T::Hashing>(nonce, &players)
+    }
+
+    /// Get Id of app signed by Ethereum accounts
+    ///
+    /// Returns `None` if the runtime hash is not 32 bytes.
+    ///
+    /// Parameters:
+    /// `nonce`: Nonce of app
+    /// `eth_players`: Ethereum addresses of players
+    pub fn get_eth_app_id(
+        nonce: u128,
+        eth_players: Vec<H160>,
+    ) -> Option<T::Hash> {
+        let app_id = eth_app_id(nonce, &eth_players);
+        T::Hash::decode(&mut app_id.as_bytes()).ok()
+    }
+
+    /// Get Id of app to initiate, from Ethereum addresses of players in Ethereum encoding
+    ///
+    /// Parameter:
+    /// `initiate_request`: App initiate request message
+    fn get_initiate_app_id(
+        initiate_request: &AppInitiateRequestOf<T>,
+    ) -> Result<T::Hash, DispatchError> {
+        if initiate_request.encoding != StateEncoding::Ethereum {
+            ensure!(
+                initiate_request.eth_players.is_empty(),
+                "Ethereum players without Ethereum encoding"
+            );
+            return Ok(Self::get_app_id(initiate_request.nonce, initiate_request.players.clone()));
+        }
+
+        ensure!(
+            initiate_request.eth_players.len() == initiate_request.players.len(),
+            "invalid number of Ethereum players"
+        );
+        let app_id = Self::get_eth_app_id(initiate_request.nonce, initiate_request.eth_players.clone())
+            .ok_or("runtime hash is not 32 bytes")?;
+
+        Ok(app_id)
     }
 
     /// Get app state
@@ -557,13 +1215,24 @@ impl<T: Trait> Module<T> {
             Some(info) => info,
             None => return None
         };
-        let board_state = gomoku_info.gomoku_state.board_state.unwrap();
+        let board_state = match gomoku_info.gomoku_state.board_state {
+            Some(state) => state,
+            None => return None
+        };
         if key == StateKey::Winner as u8 {
-            return Some(vec![board_state[0]]);
+            return Some(vec![board_state.winner]);
         } else if key == StateKey::Turn as u8 {
-            return Some(vec![board_state[1]]);
+            return Some(vec![board_state.turn]);
         } else if key == StateKey::FullState as u8 {
-            return Some(board_state);
+            // only the hash of the board is on-chain in hash-committed mode
+            return match board_state.board {
+                BoardRecord::Packed(board) => Some(BoardState {
+                    winner: board_state.winner,
+                    turn: board_state.turn,
+                    board: board,
+                }.to_bytes()),
+                BoardRecord::Hashed(_) => None,
+            };
         } else {
             return None;
         }
@@ -610,7 +1279,7 @@ impl<T: Trait> Module<T> {
         if gomoku_info.status == AppStatus::Action {
             return Some(gomoku_info.deadline);
         } else if gomoku_info.status ==  AppStatus::Settle {
-            return Some(gomoku_info.deadline + gomoku_info.timeout);
+            return Some(Self::first_action_deadline(&gomoku_info));
         } else {
             return None;
         }
@@ -628,25 +1297,237 @@ impl<T: Trait> Module<T> {
         return Some(gomoku_info.seq_num);
     }
 
+    /// Get the undecided random seed round of app and the index of a player
+    ///
+    /// Parameters:
+    /// `app_id`: Id of app
+    /// `player`: AccountId of player
+    fn live_seed_round(
+        app_id: T::Hash,
+        player: &T::AccountId,
+    ) -> Result<(usize, SeedRoundOf<T>), DispatchError> {
+        let gomoku_info = match SingleGomokuInfoMap::<T>::get(app_id) {
+            Some(info) => info,
+            None => Err(Error::<T>::SingleGomokuInfoNotExist)?,
+        };
+        ensure!(
+            gomoku_info.status != AppStatus::Finalized,
+            "app state is finalized"
+        );
+        let index = match gomoku_info.players.iter().position(|account| account == player) {
+            Some(index) => index,
+            None => Err("not a player of app")?,
+        };
+        let seed_round = match SeedRoundMap::<T>::get(app_id) {
+            Some(seed_round) => seed_round,
+            None => Err("app has no random seed")?,
+        };
+        seed_round.check_live(frame_system::Module::<T>::block_number())?;
+
+        Ok((index, seed_round))
+    }
+
+    /// Get random seed of app, which must be decided before the first settle
+    ///
+    /// Parameter:
+    /// `app_id`: Id of app
+    fn decided_seed(app_id: T::Hash) -> Result<Option<T::Hash>, DispatchError> {
+        match SeedRoundMap::<T>::get(app_id) {
+            Some(seed_round) => match seed_round.seed {
+                Some(seed) => Ok(Some(seed)),
+                None => Err("random seed is not decided")?,
+            },
+            None => Ok(None),
+        }
+    }
+
+    /// Get random seed of app, None if it is not decided
+    ///
+    /// Parameter:
+    /// `app_id`: Id of app
+    pub fn get_seed(app_id: T::Hash) -> Option<T::Hash> {
+        SeedRoundMap::<T>::get(app_id).and_then(|seed_round| seed_round.seed)
+    }
+
+    /// Replay on-chain moves from the co-signed state the game resumed from
+    ///
+    /// Parameters:
+    /// `app_id`: Id of app
+    /// `base_state`: Board state of the co-signed state proof the game resumed from
+    pub fn replay_moves(
+        app_id: T::Hash,
+        base_state: Vec<u8>,
+    ) -> Result<Vec<u8>, DispatchError> {
+        let gomoku_info = match SingleGomokuInfoMap::<T>::get(app_id) {
+            Some(info) => info,
+            None => Err(Error::<T>::SingleGomokuInfoNotExist)?,
+        };
+        let resume_point = match ResumePointMap::<T>::get(app_id) {
+            Some(point) => point,
+            None => Err(Error::<T>::ResumePointNotExist)?,
+        };
+        ensure!(
+            T::Hashing::hash(&base_state) == resume_point.state_hash,
+            "base state not match"
+        );
+
+        let mut board_state = BoardState::from_bytes(&base_state).ok_or("invalid slot state")?;
+        let mut stone_num_onchain: u16 = 0;
+        for record in MoveHistoryMap::<T>::get(app_id) {
+            ensure!(
+                record.player_id == 1 || record.player_id == 2,
+                Error::<T>::InvalidMoveRecord
+            );
+            let outcome = board_state.board.play(Move::new(record.x, record.y), record.player_id)
+                .map_err(|_| Error::<T>::InvalidMoveRecord)?;
+            stone_num_onchain += 1;
+
+            outcome.limit(stone_num_onchain, gomoku_info.gomoku_state.max_stone_onchain as u16)
+                .record(&mut board_state.winner, &mut board_state.turn);
+        }
+
+        Ok(board_state.to_bytes())
+    }
+
     /// Get single gomoku app account id
     pub fn app_account() -> T::AccountId {
         SINGLE_GOMOKU_ID.into_account()
     }
 
-    /// Submit and settle off-chain state
+    /// Settle a co-signed board state and finalize app if it has a winner
     ///
-    /// Parameter:
-    /// `state_proof`: Signed off-chain app state
+    /// Parameters:
+    /// `app_state`: Off-chain app state
+    /// `sigs`: Signatures of app state
+    fn update_state(
+        app_state: AppStateOf<T>,
+        sigs: StateSigs<<T as Trait>::Signature>,
+        moves: Vec<SignedMoveOf<T>>,
+    ) -> DispatchResult {
+        let (mut gomoku_info, signers) = Self::intend_settle(app_state.clone(), sigs)?;
+
+        let mut _state = app_state.board_state;
+        ensure!(
+            _state.len() == 227,
+            "invalid board state length"
+        );
+        let mut board_state = BoardState::from_bytes(&_state).ok_or("invalid slot state")?;
+
+        // check consistency of the co-signed board and count its stones
+        let mut count = Self::valid_board_state(&board_state)?;
+        // the player picked by the random seed moves first
+        if let Some(seed) = Self::decided_seed(app_state.app_id)? {
+            Self::valid_first_mover(&board_state, first_mover(seed.as_ref()))?;
+        }
+        // moves signed only by their movers continue the co-signed board
+        if !moves.is_empty() {
+            let move_num = moves.len();
+            // moves only follow states signed by players, whose signing accounts are already read
+            let signers = signers.ok_or("app is signed by Ethereum accounts")?;
+            Self::apply_signed_moves(app_state.app_id, &signers, &mut board_state, app_state.seq_num, moves)?;
+            count += move_num as u16;
+            gomoku_info.seq_num = app_state.seq_num + move_num as u128;
+            _state = board_state.to_bytes();
+        }
+        let state_hash = T::Hashing::hash(&_state);
+        let hashed_board = gomoku_info.gomoku_state.hashed_board;
+        gomoku_info.gomoku_state.board_state = Some(Self::record_board(&board_state, hashed_board));
+        gomoku_info.gomoku_state.stone_num = Some(count);
+        if board_state.winner != 0 {
+            Self::win_game(board_state.winner, &mut gomoku_info)?;
+        } else {
+            ensure!(
+                count >= gomoku_info.gomoku_state.min_stone_offchain as u16,
+                "not enough offchain stones"
+            );
+        }
+
+        let app_id = app_state.app_id;
+        let seq_num = gomoku_info.seq_num;
+        SingleGomokuInfoMap::<T>::insert(app_id, gomoku_info);
+
+        // on-chain moves are replayed from the latest co-signed state
+        let resume_point = ResumePointOf::<T> {
+            seq_num: seq_num,
+            state_hash: state_hash,
+        };
+        ResumePointMap::<T>::insert(app_id, resume_point);
+        MoveHistoryMap::<T>::remove(app_id);
+        
+        Self::deposit_event(RawEvent::IntendSettle(app_id, seq_num));
+        if hashed_board {
+            Self::deposit_event(RawEvent::BoardUpdated(app_id, seq_num, _state));
+        }
+
+        Ok(())
+    }
+
+    /// Apply moves signed only by their movers to a co-signed board
+    ///
+    /// Parameters:
+    /// `app_id`: Id of app
+    /// `signers`: Accounts signing off-chain states of players
+    /// `board_state`: Co-signed board state
+    /// `seq_num`: Sequence number of the co-signed state
+    /// `moves`: Moves played after the co-signed state, in order
+    fn apply_signed_moves(
+        app_id: T::Hash,
+        signers: &[T::AccountId],
+        board_state: &mut BoardState,
+        seq_num: u128,
+        moves: Vec<SignedMoveOf<T>>,
+    ) -> DispatchResult {
+        for (i, signed_move) in moves.into_iter().enumerate() {
+            let turn = board_state.turn;
+            ensure!(turn != 0, "game is over");
+            let payload = signed_move_payload(&app_id, seq_num + i as u128 + 1, &signed_move.action);
+            ensure!(
+                signed_move.sig.verify(&payload[..], &signers[turn as usize - 1]),
+                "invalid move signature"
+            );
+            let mv = Move::from_action(&signed_move.action).ok_or("invalid action length")?;
+            board_state.board.play(mv, turn)
+                .map_err(|e| e.as_str())?
+                .record(&mut board_state.winner, &mut board_state.turn);
+        }
+
+        Ok(())
+    }
+
+    /// Submit and settle off-chain state, returning the app info and the
+    /// accounts signing off-chain states of players, None for Ethereum accounts
+    ///
+    /// Parameters:
+    /// `app_state`: Off-chain app state
+    /// `sigs`: Signatures of app state
     fn intend_settle(
-        state_proof: StateProofOf<T>
-    ) -> Result<GomokuInfoOf<T>, DispatchError> {
-        let app_state = state_proof.app_state;
-        let gomoku_info = match SingleGomokuInfoMap::<T>::get(app_state.app_id) {
+        app_state: AppStateOf<T>,
+        sigs: StateSigs<<T as Trait>::Signature>,
+    ) -> Result<(GomokuInfoOf<T>, Option<Vec<T::AccountId>>), DispatchError> {
+        let mut gomoku_info = match SingleGomokuInfoMap::<T>::get(app_state.app_id) {
             Some(info) => info,
             None => Err(Error::<T>::SingleGomokuInfoNotExist)?,
         };
-        let encoded = Self::encode_app_state(app_state.clone());
-        Self::valid_signers(state_proof.sigs, &encoded, gomoku_info.players.clone())?;
+        let encoded = Self::encode_app_state(app_state.clone(), gomoku_info.encoding)?;
+        let signers = match sigs {
+            StateSigs::Players(sigs) => {
+                ensure!(
+                    gomoku_info.encoding != StateEncoding::Ethereum,
+                    "app is signed by Ethereum accounts"
+                );
+                let signers = Self::signing_accounts(app_state.app_id, &gomoku_info.players);
+                Self::valid_signers(sigs, &encoded, signers.clone())?;
+                Some(signers)
+            },
+            StateSigs::Ethereum(sigs) => {
+                ensure!(
+                    gomoku_info.encoding == StateEncoding::Ethereum,
+                    "app is not signed by Ethereum accounts"
+                );
+                Self::valid_eth_signers(&sigs, &encoded, &gomoku_info.eth_players)?;
+                None
+            },
+        };
         ensure!(
             gomoku_info.status != AppStatus::Finalized,
             "app state is finalized"
@@ -661,17 +1542,84 @@ impl<T: Trait> Module<T> {
         );
 
         let block_number = frame_system::Module::<T>::block_number();
-        let new_gomoku_info = GomokuInfoOf::<T> {
-            nonce: gomoku_info.nonce,
-            players: gomoku_info.players,
-            seq_num: app_state.seq_num,
-            timeout: gomoku_info.timeout,
-            deadline: block_number + gomoku_info.timeout,
-            status: AppStatus::Settle,
-            gomoku_state: gomoku_info.gomoku_state,
+        gomoku_info.seq_num = app_state.seq_num;
+        gomoku_info.deadline = block_number + gomoku_info.timeout;
+        gomoku_info.status = AppStatus::Settle;
+
+        Ok((gomoku_info, signers))
+    }
+
+    /// Apply an action of the player in turn to the on-chain state
+    ///
+    /// Parameters:
+    /// `app_id`: Id of app
+    /// `action`: Action data
+    /// `board_witness`: Current board state in hash-committed mode, ignored otherwise
+    /// `auth`: Submitter or signature of the action
+    fn play_action(
+        app_id: T::Hash,
+        action: Vec<u8>,
+        board_witness: Option<Vec<u8>>,
+        auth: ActionAuth<T::AccountId, <T as Trait>::Signature>,
+    ) -> DispatchResult {
+        // apply an action to the on-chain state
+        let mut gomoku_info = Self::apply_action(app_id)?;
+        let mut board_state = Self::load_board(&gomoku_info, board_witness)?;
+        let turn = board_state.turn;
+        ensure!(turn != 0, "not your turn");
+        let player = gomoku_info.players[turn as usize - 1].clone();
+        match auth {
+            ActionAuth::Caller(caller) => ensure!(caller == player, "not your turn"),
+            ActionAuth::Signed(signature) => {
+                let payload = relayed_action_payload(&app_id, gomoku_info.seq_num, &action);
+                let signer = Self::signing_accounts(app_id, &[player.clone()]).remove(0);
+                ensure!(
+                    signature.verify(&payload[..], &signer),
+                    "invalid action signature"
+                );
+            },
+        }
+
+        let mv = Move::from_action(&action).ok_or("invalid action length")?;
+
+        // place the stone
+        let outcome = board_state.board.play(mv, turn).map_err(|e| e.as_str())?;
+        let block_number = frame_system::Module::<T>::block_number();
+        let move_record = MoveRecordOf::<T> {
+            player_id: turn,
+            x: mv.x,
+            y: mv.y,
+            block_number: block_number,
+            seq_num: gomoku_info.seq_num,
         };
+        MoveHistoryMap::<T>::mutate(app_id, |history| history.push(move_record));
+
+        let gomoku_state = &mut gomoku_info.gomoku_state;
+        let new_stone_num = gomoku_state.stone_num.unwrap_or(0) + 1;
+        let new_stone_num_onchain = gomoku_state.stone_num_onchain.unwrap_or(0) + 1;
+        gomoku_state.stone_num = Some(new_stone_num);
+        gomoku_state.stone_num_onchain = Some(new_stone_num_onchain);
+
+        let over = outcome.limit(new_stone_num_onchain, gomoku_state.max_stone_onchain as u16)
+            .record(&mut board_state.winner, &mut board_state.turn);
+        if over {
+            // five-in-a-row including this new stone, all slots occupied or
+            // on-chain stones run out
+            gomoku_info.status = AppStatus::Finalized;
+        }
+        let hashed_board = gomoku_info.gomoku_state.hashed_board;
+        gomoku_info.gomoku_state.board_state = Some(Self::record_board(&board_state, hashed_board));
+        // start the clock of the next player
+        gomoku_info.deadline = Self::turn_deadline(&gomoku_info, block_number);
+        let seq_num = gomoku_info.seq_num;
+        SingleGomokuInfoMap::<T>::insert(app_id, gomoku_info);
+
+        // new board is the witness of the next action
+        if hashed_board {
+            Self::deposit_event(RawEvent::BoardUpdated(app_id, seq_num, board_state.to_bytes()));
+        }
 
-        Ok(new_gomoku_info)
+        Ok(())
     }
 
     /// Apply an action to the on-chain state
@@ -681,7 +1629,7 @@ impl<T: Trait> Module<T> {
     fn apply_action(
         app_id: T::Hash
     ) -> Result<GomokuInfoOf<T>, DispatchError> {
-        let gomoku_info = match SingleGomokuInfoMap::<T>::get(app_id) {
+        let mut gomoku_info = match SingleGomokuInfoMap::<T>::get(app_id) {
             Some(info) => info,
             None => Err(Error::<T>::SingleGomokuInfoNotExist)?,
         };
@@ -691,34 +1639,188 @@ impl<T: Trait> Module<T> {
         );
 
         let block_number =  frame_system::Module::<T>::block_number();
-        let new_gomoku_info: GomokuInfoOf<T>;
-        if gomoku_info.status == AppStatus::Settle && block_number > gomoku_info.deadline {
-            new_gomoku_info = GomokuInfoOf::<T> {
-                nonce: gomoku_info.nonce,
-                players: gomoku_info.players,
-                seq_num:  gomoku_info.seq_num + 1,
-                timeout: gomoku_info.timeout,
-                deadline: block_number + gomoku_info.timeout,
-                status: AppStatus::Action,
-                gomoku_state: gomoku_info.gomoku_state
-            };
-        } else {
+        // app turns into action mode once settle period passes
+        if gomoku_info.status != AppStatus::Settle || block_number <= gomoku_info.deadline {
             ensure!(
                 gomoku_info.status ==  AppStatus::Action,
                 "app not in action mode"
             );
-            new_gomoku_info = GomokuInfoOf::<T> {
-                nonce: gomoku_info.nonce,
-                players: gomoku_info.players,
-                seq_num:  gomoku_info.seq_num + 1,
-                timeout: gomoku_info.timeout,
-                deadline: block_number + gomoku_info.timeout,
-                status: AppStatus::Action,
-                gomoku_state: gomoku_info.gomoku_state
-            };
         }
+        Self::charge_clock(&mut gomoku_info, block_number)?;
+        gomoku_info.seq_num = gomoku_info.seq_num + 1;
+        gomoku_info.status = AppStatus::Action;
+
+        Ok(gomoku_info)        
+    }
+
+    /// Charge the time spent on an on-chain move to the time bank of the player in turn
+    ///
+    /// Parameters:
+    /// `gomoku_info`: Info of gomoku app before applying the action
+    /// `block_number`: Current block number
+    fn charge_clock(
+        gomoku_info: &mut GomokuInfoOf<T>,
+        block_number: T::BlockNumber,
+    ) -> DispatchResult {
+        let turn = gomoku_info.gomoku_state.board_state.map_or(0, |state| state.turn);
+        if gomoku_info.gomoku_state.time_banks.is_empty() || turn == 0 {
+            return Ok(());
+        }
+
+        // the clock of the first on-chain move starts when settle period passes
+        let flag_fall = if gomoku_info.status == AppStatus::Settle {
+            Self::first_action_deadline(gomoku_info)
+        } else {
+            gomoku_info.deadline
+        };
+        ensure!(
+            block_number <= flag_fall,
+            "time bank exhausted"
+        );
+        let gomoku_state = &mut gomoku_info.gomoku_state;
+        gomoku_state.time_banks[turn as usize - 1] = flag_fall - block_number + gomoku_state.increment;
+
+        Ok(())
+    }
+
+    /// Get the deadline of the player in turn, starting from a given block
+    ///
+    /// Parameters:
+    /// `gomoku_info`: Info of gomoku app
+    /// `block_number`: Block number the turn starts at
+    fn turn_deadline(
+        gomoku_info: &GomokuInfoOf<T>,
+        block_number: T::BlockNumber,
+    ) -> T::BlockNumber {
+        let turn = gomoku_info.gomoku_state.board_state.map_or(0, |state| state.turn);
+        if gomoku_info.gomoku_state.time_banks.is_empty() || turn == 0 {
+            return block_number + gomoku_info.timeout;
+        }
+
+        return block_number + gomoku_info.gomoku_state.time_banks[turn as usize - 1];
+    }
+
+    /// Get the deadline of the first on-chain action after settle period
+    ///
+    /// Parameter:
+    /// `gomoku_info`: Info of gomoku app in settle mode
+    fn first_action_deadline(gomoku_info: &GomokuInfoOf<T>) -> T::BlockNumber {
+        Self::turn_deadline(gomoku_info, gomoku_info.deadline)
+    }
+
+    /// Load the on-chain board state
+    ///
+    /// Parameters:
+    /// `gomoku_info`: Info of gomoku app
+    /// `board_witness`: Board state committed to by the hash in hash-committed mode
+    fn load_board(
+        gomoku_info: &GomokuInfoOf<T>,
+        board_witness: Option<Vec<u8>>,
+    ) -> Result<BoardState, DispatchError> {
+        let board_state = match gomoku_info.gomoku_state.board_state {
+            Some(state) => state,
+            None => return Ok(BoardState::default()),
+        };
+        match board_state.board {
+            BoardRecord::Packed(board) => Ok(BoardState {
+                winner: board_state.winner,
+                turn: board_state.turn,
+                board: board,
+            }),
+            BoardRecord::Hashed(board_hash) => {
+                let witness = match board_witness {
+                    Some(witness) => witness,
+                    None => Err("empty board witness")?,
+                };
+                let witness_state = BoardState::from_bytes(&witness).ok_or("invalid slot state")?;
+                // only the slots are hashed, the rest of the witness must match the record
+                ensure!(
+                    T::Hashing::hash(&witness[2..]) == board_hash
+                        && witness_state.winner == board_state.winner
+                        && witness_state.turn == board_state.turn,
+                    "board witness not match"
+                );
+
+                Ok(witness_state)
+            }
+        }
+    }
+
+    /// Build the on-chain record of a board state
+    ///
+    /// Parameters:
+    /// `board_state`: Board state
+    /// `hashed_board`: Whether to store only a hash of the board
+    ///
+    /// Only the slots are hashed, so the winner and turn kept in the record
+    /// can change without the hash going stale.
+    fn record_board(
+        board_state: &BoardState,
+        hashed_board: bool,
+    ) -> BoardState<BoardRecord<T::Hash>> {
+        let board = if hashed_board {
+            let mut slots = Vec::with_capacity(225);
+            board_state.board.extend_slots(&mut slots);
+            BoardRecord::Hashed(T::Hashing::hash(&slots))
+        } else {
+            BoardRecord::Packed(board_state.board)
+        };
+
+        BoardState {
+            winner: board_state.winner,
+            turn: board_state.turn,
+            board: board,
+        }
+    }
+
+    /// Get accounts signing off-chain states of players, with the live
+    /// session key of a player in place of its account
+    ///
+    /// Parameters:
+    /// `app_id`: Id of app
+    /// `players`: AccountId of players
+    fn signing_accounts(app_id: T::Hash, players: &[T::AccountId]) -> Vec<T::AccountId> {
+        capps_app_common::signing_accounts(
+            players,
+            frame_system::Module::<T>::block_number(),
+            |player| SessionKeyMap::<T>::get(app_id, player),
+        )
+    }
+
+    /// Check a key rotation of a live app and its signatures, returning
+    /// the index of the rotated player
+    ///
+    /// Parameters:
+    /// `rotation`: Old and new account of a player
+    /// `signature`: Signature of the old account or of the arbiter
+    /// `new_signature`: Signature of the new account
+    /// `players`: AccountId of players
+    /// `arbiter`: AccountId of arbiter
+    fn valid_rotation(
+        rotation: &KeyRotationOf<T>,
+        signature: <T as Trait>::Signature,
+        new_signature: <T as Trait>::Signature,
+        players: &[T::AccountId],
+        arbiter: Option<&T::AccountId>,
+    ) -> Result<usize, DispatchError> {
+        let index = rotation.rotated_index(
+            players,
+            arbiter,
+            KeyRotationNonceMap::<T>::get(rotation.app_id),
+        )?;
+
+        let payload = rotation.signing_payload();
+        ensure!(
+            signature.verify(&payload[..], &rotation.old_player)
+                || arbiter.iter().any(|arbiter| signature.verify(&payload[..], arbiter)),
+            "invalid rotation signature"
+        );
+        ensure!(
+            new_signature.verify(&payload[..], &rotation.new_player),
+            "invalid new account signature"
+        );
 
-        Ok(new_gomoku_info)        
+        Ok(index)
     }
 
     /// Verify off-chain state signatures
@@ -732,6 +1834,10 @@ impl<T: Trait> Module<T> {
         encoded: &[u8],
         signers: Vec<T::AccountId>,
     ) -> DispatchResult {
+        ensure!(
+            signatures.len() == 2 && signers.len() == 2,
+            Error::<T>::InvalidSignatureNum
+        );
         let signature1 = &signatures[0];
         let signature2 = &signatures[1];
         ensure!(
@@ -744,6 +1850,30 @@ impl<T: Trait> Module<T> {
         Ok(())
     }
 
+    /// Verify off-chain state signatures of Ethereum accounts, in any order
+    ///
+    /// Parameters:
+    /// `signatures`: Signatures from Ethereum accounts of players
+    /// `encoded`: Packed app state
+    /// `signers`: Ethereum addresses of players
+    fn valid_eth_signers(
+        signatures: &[EcdsaSignature],
+        encoded: &[u8],
+        signers: &[H160],
+    ) -> DispatchResult {
+        ensure!(signatures.len() == signers.len(), "Check co-sigs failed");
+        let mut recovered = signatures.iter()
+            .map(|signature| ethereum::recover_signer(signature, encoded))
+            .collect::<Option<Vec<H160>>>()
+            .ok_or("Check co-sigs failed")?;
+        let mut expected = signers.to_vec();
+        recovered.sort();
+        expected.sort();
+        ensure!(recovered == expected, "Check co-sigs failed");
+
+        Ok(())
+    }
+
     /// Set game states when there is a winner
     ///
     /// Parameters:
@@ -751,159 +1881,119 @@ impl<T: Trait> Module<T> {
     /// `gomoku_info`: Info of gomoku state
     fn win_game(
         winner: u8, 
-        gomoku_info: GomokuInfoOf<T>,
-    ) -> Result<GomokuInfoOf<T>, DispatchError> {
+        gomoku_info: &mut GomokuInfoOf<T>,
+    ) -> DispatchResult {
         ensure!(
             u8::min_value() <= winner && winner <= 2,
             "invalid winner state"
         );
 
-        let gomoku_state = gomoku_info.gomoku_state;
-        let mut new_board_state = gomoku_state.board_state.unwrap_or(vec![0; 227]);
+        let board_state = match gomoku_info.gomoku_state.board_state.as_mut() {
+            Some(state) => state,
+            None => Err(Error::<T>::EmptyBoardState)?,
+        };
         // set winner
-        new_board_state[0] = winner;
-
-        let new_gomoku_info: GomokuInfoOf<T>;
+        board_state.winner = winner;
         if winner != 0 {// Game over
             // set turn 0
-            new_board_state[1] = 0; 
-
-            let new_gomoku_state = GomokuState {
-                board_state: Some(new_board_state),
-                stone_num: gomoku_state.stone_num,
-                stone_num_onchain: gomoku_state.stone_num_onchain,
-                state_key: gomoku_state.state_key,
-                min_stone_offchain: gomoku_state.min_stone_offchain,
-                max_stone_onchain: gomoku_state.max_stone_onchain
-            };
-            
-            new_gomoku_info = GomokuInfoOf::<T> {
-                nonce: gomoku_info.nonce,
-                players: gomoku_info.players,
-                seq_num: gomoku_info.seq_num,
-                timeout: gomoku_info.timeout,
-                deadline: gomoku_info.deadline,
-                status: AppStatus::Finalized,
-                gomoku_state: new_gomoku_state,
-            };
-        } else {
-            let new_gomoku_state = GomokuState {
-                board_state: Some(new_board_state),
-                stone_num: gomoku_state.stone_num,
-                stone_num_onchain: gomoku_state.stone_num_onchain,
-                state_key: gomoku_state.state_key,
-                min_stone_offchain: gomoku_state.min_stone_offchain,
-                max_stone_onchain: gomoku_state.max_stone_onchain
-            };
-            new_gomoku_info = GomokuInfoOf::<T> {
-                nonce: gomoku_info.nonce,
-                players: gomoku_info.players,
-                seq_num: gomoku_info.seq_num,
-                timeout: gomoku_info.timeout,
-                deadline: gomoku_info.deadline,
-                status: gomoku_info.status,
-                gomoku_state: new_gomoku_state,
-            };
+            board_state.turn = 0;
+            gomoku_info.status = AppStatus::Finalized;
         }
-        
-        return Ok(new_gomoku_info);
-    }
 
-    /// Check if there is five in a row in agiven direction
-    ///
-    /// Parameters:
-    /// `_x`: x coordinate on the board
-    /// `_y`: y coordinate on the board
-    /// `_xdir`: direction (-1 or 0 or 1) in x axis
-    /// `_ydir`: direction (-1 or 0 or 1) in y axis
-    fn check_five(
-        _board_state: Vec<u8>,
-        _x: u8,
-        _y: u8,
-        _xdir: i8,
-        _ydir: i8,
-    ) -> bool {
-        let mut count: u8 = 0;
-        count += Self::count_stone(_board_state.clone(), _x, _y, _xdir, _ydir).unwrap();
-        count += Self::count_stone(_board_state, _x, _y, -1 * _xdir, -1 * _ydir).unwrap() - 1; // reverse direction
-        if count >= 5 {
-            return true
-        } else {
-            return false;
-        }
+        Ok(())
     }
 
-    /// Count the maximum consecutive stones in a given direction
+    /// Check consistency of a co-signed board state and count its stones
     ///
-    /// Parameters:
-    /// `_x`: x coordinate on the board
-    /// `_y`: y coordinate on the board
-    /// `_xdir`: direction (-1 or 0 or 1) in x axis
-    /// `_ydir`: direction (-1 or 0 or 1) in y axis
-    fn count_stone(
-        _board_state: Vec<u8>, 
-        _x: u8, 
-        _y: u8, 
-        _xdir: i8, 
-        _ydir: i8
-    ) -> Option<u8> {
-        let mut count: u8 = 1;
-        while count <= 5 {
-            let x = (_x as i8 + _xdir * count as i8) as u8;
-            let y = (_y as i8 + _ydir * count as i8) as u8;
-            if Self::check_boundary(x, y) 
-                && (_board_state[Self::state_index(x, y)] == _board_state[Self::state_index(_x, _y)]) {
-                    count += 1;
-            } else {
-                return Some(count);
-            }
+    /// Parameter:
+    /// `board_state`: Decoded off-chain board state
+    fn valid_board_state(board_state: &BoardState) -> Result<u16, DispatchError> {
+        let winner = board_state.winner;
+        let turn = board_state.turn;
+        ensure!(
+            winner <= 2,
+            "invalid winner state"
+        );
+        ensure!(
+            turn <= 2,
+            "invalid turn state"
+        );
+        // no player is in turn once the game has a winner
+        ensure!(
+            winner == 0 || turn == 0,
+            "invalid turn state"
+        );
+
+        // number of stones of each player, indexed by player id
+        let stone_nums: [u16; 3] = [
+            0,
+            board_state.board.stone_num(1),
+            board_state.board.stone_num(2),
+        ];
+        // players place stones alternately
+        ensure!(
+            stone_nums[1] <= stone_nums[2] + 1 && stone_nums[2] <= stone_nums[1] + 1,
+            "invalid stone number"
+        );
+        // player in turn can not have placed more stones than the other player
+        if turn != 0 {
+            ensure!(
+                stone_nums[turn as usize] <= stone_nums[3 - turn as usize],
+                "invalid turn state"
+            );
         }
+        // only the winner has five in a row
+        ensure!(
+            board_state.board.has_five(1) == (winner == 1)
+                && board_state.board.has_five(2) == (winner == 2),
+            "winner not match board"
+        );
 
-        return None;
+        Ok(stone_nums[1] + stone_nums[2])
     }
 
-    /// Check if coordinate (x, y) is valid
+    /// Check that players move alternately from the first mover to board state
+    ///
+    /// The player in turn, or the winner of a finished game, tells who placed
+    /// the last stone, and so who placed the first one.
     ///
     /// Parameters:
-    /// `_x`: x coordinate on the board
-    /// `_y`: y coordinate on the board
-    fn check_boundary(x: u8, y: u8) -> bool {
-        // board dimention is 15*15
-        let board_dimention = 15;
-        if x < board_dimention && y < board_dimention {
-            return true;
-        } else {
-            return false;
+    /// `board_state`: Board state
+    /// `first`: Id of the player moving first
+    fn valid_first_mover(board_state: &BoardState, first: u8) -> DispatchResult {
+        let first_stone_num = board_state.board.stone_num(first);
+        let second_stone_num = board_state.board.stone_num(3 - first);
+        ensure!(
+            first_stone_num == second_stone_num || first_stone_num == second_stone_num + 1,
+            "first mover not match seed"
+        );
+        // player placing the last stone, the other player on an empty board
+        let last_mover = if first_stone_num == second_stone_num { 3 - first } else { first };
+        if board_state.turn != 0 {
+            ensure!(
+                board_state.turn != last_mover,
+                "first mover not match seed"
+            );
+        } else if board_state.winner != 0 {
+            ensure!(
+                board_state.winner == last_mover,
+                "first mover not match seed"
+            );
         }
-    }
 
-    /// Check if coordinate (x, y) is valid
-    ///
-    /// Parameters:
-    /// `_x`: x coordinate on the board
-    /// `_y`: y coordinate on the board
-    fn state_index(x: u8, y: u8) -> usize {
-        // board dimention is 15*15
-        let board_dimention = 15;
-        let index: usize = (2 + board_dimention * x + y) as usize;
-        return index;
+        Ok(())
     }
 
     /// Encode app state
     ///
-    /// Parameter:
+    /// Parameters:
     /// `app_state`: app state
+    /// `encoding`: Encoding of app
     fn encode_app_state(
-        app_state: AppStateOf<T>
-    ) -> Vec<u8> {
-        let mut encoded = app_state.nonce.encode();
-        encoded.extend(app_state.seq_num.encode());
-        app_state.board_state.iter()
-            .for_each(|state| { encoded.extend(state.encode()); });
-        encoded.extend(app_state.timeout.encode());
-        encoded.extend(app_state.app_id.encode());
-
-        return encoded;
+        app_state: AppStateOf<T>,
+        encoding: StateEncoding,
+    ) -> Result<Vec<u8>, DispatchError> {
+        app_state.payload(encoding).ok_or_else(|| Error::<T>::AppStateNotEncodable.into())
     }
 
 } 
\ No newline at end of file
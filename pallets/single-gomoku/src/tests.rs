@@ -1,6 +1,7 @@
 use super::*;
 use mock::*;
-use sp_core::{sr25519, Pair, H256};
+use sp_core::{sr25519, Pair, H160, H256};
+use sp_runtime::traits::{BlakeTwo256, OffchainWorker};
 use frame_support::{assert_ok, assert_noop};
 
 
@@ -18,6 +19,13 @@ fn test_pass_initiate() {
             timeout: 2,
             min_stone_offchain: 5,
             max_stone_onchain: 5,
+            time_bank: 0,
+            increment: 0,
+            hashed_board: false,
+            encoding: StateEncoding::Scale,
+            eth_players: vec![],
+            arbiter: None,
+            seed_timeout: None,
         };
 
         assert_ok!(SingleGomoku::app_initiate(
@@ -41,6 +49,13 @@ fn test_pass_update_by_state_and_player_2_win() {
             timeout: 2,
             min_stone_offchain: 5,
             max_stone_onchain: 5,
+            time_bank: 0,
+            increment: 0,
+            hashed_board: false,
+            encoding: StateEncoding::Scale,
+            eth_players: vec![],
+            arbiter: None,
+            seed_timeout: None,
         };
 
         assert_ok!(SingleGomoku::app_initiate(
@@ -52,6 +67,15 @@ fn test_pass_update_by_state_and_player_2_win() {
         let mut board_state = vec![0; 227];
         board_state[0] = 2; // winner
         board_state[1] = 0; // turn
+        board_state[2] = 2; // (0, 0)
+        board_state[3] = 2; // (0, 1)
+        board_state[4] = 2; // (0, 2)
+        board_state[5] = 2; // (0, 3)
+        board_state[6] = 2; // (0, 4)
+        board_state[17] = 1; // (1, 0)
+        board_state[18] = 1; // (1, 1)
+        board_state[19] = 1; // (1, 2)
+        board_state[20] = 1; // (1, 3)
         let state_proof = get_state_proof(0, 1, board_state, 0, app_id, players_pair);
         assert_ok!(
             SingleGomoku::update_by_state(
@@ -75,6 +99,391 @@ fn test_pass_update_by_state_and_player_2_win() {
     })
 }
 
+#[test]
+fn test_pass_update_by_state_with_protobuf_encoding() {
+    ExtBuilder::build().execute_with(|| {
+        let alice_pair = account_pair("Alice");
+        let bob_pair = account_pair("Bob");
+        let (players, players_pair)
+            = get_sorted_peer(alice_pair, bob_pair);
+
+        let initiate_request = AppInitiateRequest {
+            nonce: 0,
+            players: players.clone(),
+            timeout: 2,
+            min_stone_offchain: 5,
+            max_stone_onchain: 5,
+            time_bank: 0,
+            increment: 0,
+            hashed_board: false,
+            encoding: StateEncoding::Protobuf,
+            eth_players: vec![],
+            arbiter: None,
+            seed_timeout: None,
+        };
+        assert_ok!(SingleGomoku::app_initiate(
+            Origin::signed(players[0]),
+            initiate_request.clone())
+        );
+
+        let app_id = SingleGomoku::get_app_id(initiate_request.nonce, initiate_request.players.clone());
+        let mut board_state = vec![0; 227];
+        board_state[0] = 2; // winner
+        board_state[2] = 2; // (0, 0)
+        board_state[3] = 2; // (0, 1)
+        board_state[4] = 2; // (0, 2)
+        board_state[5] = 2; // (0, 3)
+        board_state[6] = 2; // (0, 4)
+        board_state[17] = 1; // (1, 0)
+        board_state[18] = 1; // (1, 1)
+        board_state[19] = 1; // (1, 2)
+        board_state[20] = 1; // (1, 3)
+
+        // states signed in the SCALE encoding are not accepted
+        let state_proof = get_state_proof(0, 1, board_state.clone(), 2, app_id, players_pair.clone());
+        assert_noop!(
+            SingleGomoku::update_by_state(
+                Origin::signed(players[0]),
+                state_proof
+            ),
+            "Check co-sigs failed"
+        );
+
+        let state_proof = get_encoded_state_proof(
+            0, u64::max_value() as u128 + 1, board_state.clone(), 2, app_id, players_pair.clone(), StateEncoding::Scale
+        );
+        assert_noop!(
+            SingleGomoku::update_by_state(
+                Origin::signed(players[0]),
+                state_proof
+            ),
+            Error::<TestRuntime>::AppStateNotEncodable
+        );
+
+        let state_proof = get_encoded_state_proof(
+            0, 1, board_state, 2, app_id, players_pair, StateEncoding::Protobuf
+        );
+        assert_ok!(
+            SingleGomoku::update_by_state(
+                Origin::signed(players[0]),
+                state_proof
+            )
+        );
+        assert_ok!(
+            SingleGomoku::get_outcome(
+                Origin::signed(players[0]),
+                app_id,
+                2
+            )
+        );
+    })
+}
+
+#[test]
+fn test_pass_update_by_eth_state() {
+    ExtBuilder::build().execute_with(|| {
+        let alice_pair = account_pair("Alice");
+        let bob_pair = account_pair("Bob");
+        let (players, players_pair)
+            = get_sorted_peer(alice_pair, bob_pair);
+        let eth_players: Vec<H160> = vec![eth_pair(1).address(), eth_pair(2).address()];
+
+        let initiate_request = AppInitiateRequest {
+            nonce: 0,
+            players: players.clone(),
+            timeout: 2,
+            min_stone_offchain: 5,
+            max_stone_onchain: 5,
+            time_bank: 0,
+            increment: 0,
+            hashed_board: false,
+            encoding: StateEncoding::Ethereum,
+            eth_players: eth_players.clone(),
+            arbiter: None,
+            seed_timeout: None,
+        };
+        assert_ok!(SingleGomoku::app_initiate(
+            Origin::signed(players[0]),
+            initiate_request.clone())
+        );
+
+        let app_id = SingleGomoku::get_eth_app_id(initiate_request.nonce, eth_players).unwrap();
+        let mut board_state = vec![0; 227];
+        board_state[0] = 2; // winner
+        board_state[2] = 2; // (0, 0)
+        board_state[3] = 2; // (0, 1)
+        board_state[4] = 2; // (0, 2)
+        board_state[5] = 2; // (0, 3)
+        board_state[6] = 2; // (0, 4)
+        board_state[17] = 1; // (1, 0)
+        board_state[18] = 1; // (1, 1)
+        board_state[19] = 1; // (1, 2)
+        board_state[20] = 1; // (1, 3)
+
+        // states signed by substrate accounts are not accepted
+        let state_proof = get_encoded_state_proof(
+            0, 1, board_state.clone(), 2, app_id, players_pair, StateEncoding::Ethereum
+        );
+        assert_noop!(
+            SingleGomoku::update_by_state(
+                Origin::signed(players[0]),
+                state_proof
+            ),
+            "app is signed by Ethereum accounts"
+        );
+
+        let state_proof = get_eth_state_proof(0, 1, board_state.clone(), 2, app_id, vec![eth_pair(1), eth_pair(3)]);
+        assert_noop!(
+            SingleGomoku::update_by_eth_state(
+                Origin::signed(players[0]),
+                state_proof
+            ),
+            "Check co-sigs failed"
+        );
+
+        let state_proof = get_eth_state_proof(0, 1, board_state, 2, app_id, vec![eth_pair(1), eth_pair(2)]);
+        assert_ok!(
+            SingleGomoku::update_by_eth_state(
+                Origin::signed(players[0]),
+                state_proof
+            )
+        );
+        assert_ok!(
+            SingleGomoku::get_outcome(
+                Origin::signed(players[0]),
+                app_id,
+                2
+            )
+        );
+    })
+}
+
+#[test]
+fn test_pass_delegate_session_key() {
+    ExtBuilder::build().execute_with(|| {
+        System::set_block_number(1);
+        let alice_pair = account_pair("Alice");
+        let bob_pair = account_pair("Bob");
+        let (players, players_pair)
+            = get_sorted_peer(alice_pair, bob_pair);
+
+        let initiate_request = AppInitiateRequest {
+            nonce: 0,
+            players: players.clone(),
+            timeout: 2,
+            min_stone_offchain: 0,
+            max_stone_onchain: 5,
+            time_bank: 0,
+            increment: 0,
+            hashed_board: false,
+            encoding: StateEncoding::Scale,
+            eth_players: vec![],
+            arbiter: None,
+            seed_timeout: None,
+        };
+        assert_ok!(SingleGomoku::app_initiate(
+            Origin::signed(players[0]),
+            initiate_request.clone())
+        );
+
+        let app_id = SingleGomoku::get_app_id(initiate_request.nonce, initiate_request.players.clone());
+        let mut board_state = vec![0; 227];
+        board_state[1] = 1; // turn color
+
+        let session_pair = account_pair("Carl");
+        let mut delegation = SessionKeyDelegation {
+            app_id: app_id,
+            player: players[0],
+            session_key: session_pair.public(),
+            expires_at: 1,
+        };
+        assert_noop!(
+            SingleGomoku::delegate_session_key(
+                Origin::signed(players[1]),
+                delegation.clone(),
+                players_pair[0].sign(&delegation.signing_payload())
+            ),
+            "session key is expired"
+        );
+
+        // only the player signs its delegation, anyone submits it
+        delegation.expires_at = 10;
+        assert_noop!(
+            SingleGomoku::delegate_session_key(
+                Origin::signed(players[1]),
+                delegation.clone(),
+                players_pair[1].sign(&delegation.signing_payload())
+            ),
+            "invalid delegation signature"
+        );
+        assert_ok!(
+            SingleGomoku::delegate_session_key(
+                Origin::signed(players[1]),
+                delegation.clone(),
+                players_pair[0].sign(&delegation.signing_payload())
+            )
+        );
+        let expected_event = TestEvent::single_gomoku(RawEvent::SessionKeyDelegated(app_id, players[0], session_pair.public(), 10));
+        assert!(System::events().iter().any(|a| a.event == expected_event));
+        assert_eq!(SingleGomoku::session_key(app_id, players[0]), Some((session_pair.public(), 10)));
+
+        // a replayed delegation does not extend the session key
+        assert_noop!(
+            SingleGomoku::delegate_session_key(
+                Origin::signed(players[1]),
+                delegation.clone(),
+                players_pair[0].sign(&delegation.signing_payload())
+            ),
+            "session key expires earlier than current one"
+        );
+
+        // the session key signs in place of the player
+        let state_proof = get_state_proof(0, 1, board_state.clone(), 2, app_id, vec![session_pair.clone(), players_pair[1].clone()]);
+        assert_ok!(
+            SingleGomoku::update_by_state(
+                Origin::signed(players[1]),
+                state_proof
+            )
+        );
+
+        // the expired session key is not accepted
+        System::set_block_number(10);
+        let state_proof = get_state_proof(0, 2, board_state.clone(), 2, app_id, vec![session_pair, players_pair[1].clone()]);
+        assert_noop!(
+            SingleGomoku::update_by_state(
+                Origin::signed(players[1]),
+                state_proof
+            ),
+            "Check co-sigs failed"
+        );
+    })
+}
+
+#[test]
+fn test_pass_rotate_player_key() {
+    ExtBuilder::build().execute_with(|| {
+        System::set_block_number(1);
+        let alice_pair = account_pair("Alice");
+        let bob_pair = account_pair("Bob");
+        let (players, players_pair)
+            = get_sorted_peer(alice_pair, bob_pair);
+        let arbiter_pair = account_pair("Carl");
+        let arbiter = arbiter_pair.public();
+
+        let initiate_request = AppInitiateRequest {
+            nonce: 0,
+            players: players.clone(),
+            timeout: 2,
+            min_stone_offchain: 0,
+            max_stone_onchain: 225,
+            time_bank: 0,
+            increment: 0,
+            hashed_board: false,
+            encoding: StateEncoding::Scale,
+            eth_players: vec![],
+            arbiter: Some(arbiter),
+            seed_timeout: None,
+        };
+        assert_ok!(SingleGomoku::app_initiate(
+            Origin::signed(players[0]),
+            initiate_request.clone())
+        );
+
+        let app_id = SingleGomoku::get_app_id(initiate_request.nonce, initiate_request.players.clone());
+        let mut rotation = KeyRotation {
+            app_id: app_id,
+            old_player: players[0],
+            new_player: arbiter,
+            nonce: 0,
+        };
+        assert_noop!(
+            SingleGomoku::rotate_player_key(
+                Origin::signed(players[1]),
+                rotation.clone(),
+                players_pair[0].sign(&rotation.signing_payload()),
+                arbiter_pair.sign(&rotation.signing_payload())
+            ),
+            "new account already joins app"
+        );
+
+        let new_pair = account_pair("Dave");
+        rotation.new_player = new_pair.public();
+        assert_noop!(
+            SingleGomoku::rotate_player_key(
+                Origin::signed(players[1]),
+                rotation.clone(),
+                new_pair.sign(&rotation.signing_payload()),
+                new_pair.sign(&rotation.signing_payload())
+            ),
+            "invalid rotation signature"
+        );
+        // the other player cannot take over the seat of a player
+        assert_noop!(
+            SingleGomoku::rotate_player_key(
+                Origin::signed(players[1]),
+                rotation.clone(),
+                players_pair[1].sign(&rotation.signing_payload()),
+                new_pair.sign(&rotation.signing_payload())
+            ),
+            "invalid rotation signature"
+        );
+        assert_noop!(
+            SingleGomoku::rotate_player_key(
+                Origin::signed(players[1]),
+                rotation.clone(),
+                players_pair[0].sign(&rotation.signing_payload()),
+                players_pair[0].sign(&rotation.signing_payload())
+            ),
+            "invalid new account signature"
+        );
+        rotation.nonce = 1;
+        assert_noop!(
+            SingleGomoku::rotate_player_key(
+                Origin::signed(players[1]),
+                rotation.clone(),
+                players_pair[0].sign(&rotation.signing_payload()),
+                new_pair.sign(&rotation.signing_payload())
+            ),
+            "invalid rotation nonce"
+        );
+
+        // the arbiter authorizes the rotation of a lost account
+        rotation.nonce = 0;
+        assert_ok!(
+            SingleGomoku::rotate_player_key(
+                Origin::signed(players[1]),
+                rotation.clone(),
+                arbiter_pair.sign(&rotation.signing_payload()),
+                new_pair.sign(&rotation.signing_payload())
+            )
+        );
+        assert_eq!(SingleGomoku::gomoku_info(app_id).unwrap().players, vec![new_pair.public(), players[1]]);
+        let expected_event = TestEvent::single_gomoku(RawEvent::PlayerKeyRotated(app_id, players[0], new_pair.public()));
+        assert!(System::events().iter().any(|a| a.event == expected_event));
+
+        // the new account keeps playing the stones of player 1
+        let mut board_state = vec![0; 227];
+        board_state[1] = 2; // turn
+        board_state[2] = 1; // (0, 0)
+        let state_proof = get_state_proof(0, 1, board_state.clone(), 2, app_id, players_pair.clone());
+        assert_noop!(
+            SingleGomoku::update_by_state(
+                Origin::signed(players[1]),
+                state_proof
+            ),
+            "Check co-sigs failed"
+        );
+        let state_proof = get_state_proof(0, 1, board_state, 2, app_id, vec![new_pair, players_pair[1].clone()]);
+        assert_ok!(
+            SingleGomoku::update_by_state(
+                Origin::signed(players[1]),
+                state_proof
+            )
+        );
+        assert_eq!(SingleGomoku::get_state(app_id, StateKey::Turn as u8), Some(vec![2]));
+    })
+}
+
 #[test]
 fn test_pass_state_new_game_and_update_by_state() {
     ExtBuilder::build().execute_with(|| {
@@ -89,6 +498,13 @@ fn test_pass_state_new_game_and_update_by_state() {
             timeout: 2,
             min_stone_offchain: 5,
             max_stone_onchain: 5,
+            time_bank: 0,
+            increment: 0,
+            hashed_board: false,
+            encoding: StateEncoding::Scale,
+            eth_players: vec![],
+            arbiter: None,
+            seed_timeout: None,
         };
 
         assert_ok!(SingleGomoku::app_initiate(
@@ -142,6 +558,13 @@ fn test_fail_update_by_state_with_invalid_seq_num() {
             timeout: 2,
             min_stone_offchain: 5,
             max_stone_onchain: 5,
+            time_bank: 0,
+            increment: 0,
+            hashed_board: false,
+            encoding: StateEncoding::Scale,
+            eth_players: vec![],
+            arbiter: None,
+            seed_timeout: None,
         };
 
         assert_ok!(SingleGomoku::app_initiate(
@@ -162,6 +585,55 @@ fn test_fail_update_by_state_with_invalid_seq_num() {
     })
 }
 
+#[test]
+fn test_fail_update_by_state_with_missing_signatures() {
+    ExtBuilder::build().execute_with(|| {
+        let alice_pair = account_pair("Alice");
+        let bob_pair = account_pair("Bob");
+        let (players, players_pair)
+            = get_sorted_peer(alice_pair, bob_pair);
+
+        let initiate_request = AppInitiateRequest {
+            nonce: 0,
+            players: players.clone(),
+            timeout: 2,
+            min_stone_offchain: 5,
+            max_stone_onchain: 5,
+            time_bank: 0,
+            increment: 0,
+            hashed_board: false,
+            encoding: StateEncoding::Scale,
+            eth_players: vec![],
+            arbiter: None,
+            seed_timeout: None,
+        };
+        assert_ok!(SingleGomoku::app_initiate(
+            Origin::signed(players[0]),
+            initiate_request.clone()
+        ));
+
+        let app_id = SingleGomoku::get_app_id(initiate_request.nonce, initiate_request.players.clone());
+        let mut state_proof = get_state_proof(0, 1, vec![0; 227], 2, app_id, players_pair);
+        state_proof.sigs.pop();
+        assert_noop!(
+            SingleGomoku::update_by_state(
+                Origin::signed(players[0]),
+                state_proof.clone()
+            ),
+            Error::<TestRuntime>::InvalidSignatureNum
+        );
+
+        state_proof.sigs.clear();
+        assert_noop!(
+            SingleGomoku::update_by_state(
+                Origin::signed(players[0]),
+                state_proof
+            ),
+            Error::<TestRuntime>::InvalidSignatureNum
+        );
+    })
+}
+
 #[test]
 fn test_pass_update_by_state_with_higher_seq() {
     ExtBuilder::build().execute_with(|| {
@@ -176,6 +648,13 @@ fn test_pass_update_by_state_with_higher_seq() {
             timeout: 2,
             min_stone_offchain: 5,
             max_stone_onchain: 5,
+            time_bank: 0,
+            increment: 0,
+            hashed_board: false,
+            encoding: StateEncoding::Scale,
+            eth_players: vec![],
+            arbiter: None,
+            seed_timeout: None,
         };
 
         assert_ok!(SingleGomoku::app_initiate(
@@ -250,6 +729,13 @@ fn test_player2_palces_stone_at_3_12_and_player1_takes_the_turn() {
             timeout: 2,
             min_stone_offchain: 5,
             max_stone_onchain: 5,
+            time_bank: 0,
+            increment: 0,
+            hashed_board: false,
+            encoding: StateEncoding::Scale,
+            eth_players: vec![],
+            arbiter: None,
+            seed_timeout: None,
         };
 
         assert_ok!(SingleGomoku::app_initiate(
@@ -290,6 +776,13 @@ fn test_fail_player2_tries_to_place_another_stone() {
             timeout: 2,
             min_stone_offchain: 5,
             max_stone_onchain: 5,
+            time_bank: 0,
+            increment: 0,
+            hashed_board: false,
+            encoding: StateEncoding::Scale,
+            eth_players: vec![],
+            arbiter: None,
+            seed_timeout: None,
         };
 
         assert_ok!(SingleGomoku::app_initiate(
@@ -324,19 +817,116 @@ fn test_fail_player2_tries_to_place_another_stone() {
 }
 
 #[test]
-fn test_fail_player1_place_a_stone_at_occupied_slot_3_12() {
+fn test_pass_update_by_relayed_action() {
     ExtBuilder::build().execute_with(|| {
         let alice_pair = account_pair("Alice");
-        let bob_pair = account_pair("Bob");        
-        let (players, players_pair) 
+        let bob_pair = account_pair("Bob");
+        let (players, players_pair)
             = get_sorted_peer(alice_pair, bob_pair);
-        
+        let relayer = account_pair("Carl").public();
+
         let initiate_request = AppInitiateRequest {
             nonce: 0,
             players: players.clone(),
             timeout: 2,
             min_stone_offchain: 5,
             max_stone_onchain: 5,
+            time_bank: 0,
+            increment: 0,
+            hashed_board: false,
+            encoding: StateEncoding::Scale,
+            eth_players: vec![],
+            arbiter: None,
+            seed_timeout: None,
+        };
+        assert_ok!(SingleGomoku::app_initiate(
+            Origin::signed(players[0]),
+            initiate_request.clone())
+        );
+
+        let app_id = SingleGomoku::get_app_id(initiate_request.nonce, initiate_request.players.clone());
+        place_stone(app_id, players.clone(), players_pair.clone());
+
+        let settle_finalized_time = SingleGomoku::get_settle_finalized_time(app_id).unwrap();
+        System::set_block_number(settle_finalized_time + 1);
+
+        // players[1] is in turn and signs its action off-chain
+        let seq_num = SingleGomoku::get_seq_num(app_id).unwrap();
+        let payload = relayed_action_payload(&app_id, seq_num, &[3, 12]);
+        assert_noop!(
+            SingleGomoku::update_by_relayed_action(
+                Origin::signed(relayer),
+                app_id,
+                vec![3, 12],
+                players_pair[0].sign(&payload),
+                None
+            ),
+            "invalid action signature"
+        );
+        assert_noop!(
+            SingleGomoku::update_by_relayed_action(
+                Origin::signed(relayer),
+                app_id,
+                vec![4, 12],
+                players_pair[1].sign(&payload),
+                None
+            ),
+            "invalid action signature"
+        );
+        assert_ok!(
+            SingleGomoku::update_by_relayed_action(
+                Origin::signed(relayer),
+                app_id,
+                vec![3, 12],
+                players_pair[1].sign(&payload),
+                None
+            )
+        );
+        assert_eq!(SingleGomoku::move_history(app_id).last().unwrap().player_id, 2);
+
+        // the relayed signature does not move for the other player
+        assert_noop!(
+            SingleGomoku::update_by_relayed_action(
+                Origin::signed(relayer),
+                app_id,
+                vec![3, 12],
+                players_pair[1].sign(&payload),
+                None
+            ),
+            "invalid action signature"
+        );
+        assert_noop!(
+            SingleGomoku::update_by_action(
+                Origin::signed(relayer),
+                app_id,
+                vec![4, 12]
+            ),
+            "not your turn"
+        );
+    })
+}
+
+#[test]
+fn test_fail_player1_place_a_stone_at_occupied_slot_3_12() {
+    ExtBuilder::build().execute_with(|| {
+        let alice_pair = account_pair("Alice");
+        let bob_pair = account_pair("Bob");        
+        let (players, players_pair) 
+            = get_sorted_peer(alice_pair, bob_pair);
+        
+        let initiate_request = AppInitiateRequest {
+            nonce: 0,
+            players: players.clone(),
+            timeout: 2,
+            min_stone_offchain: 5,
+            max_stone_onchain: 5,
+            time_bank: 0,
+            increment: 0,
+            hashed_board: false,
+            encoding: StateEncoding::Scale,
+            eth_players: vec![],
+            arbiter: None,
+            seed_timeout: None,
         };
 
         assert_ok!(SingleGomoku::app_initiate(
@@ -384,6 +974,13 @@ fn test_player1_places_a_stone_at_0_4_and_wins() {
             timeout: 2,
             min_stone_offchain: 5,
             max_stone_onchain: 5,
+            time_bank: 0,
+            increment: 0,
+            hashed_board: false,
+            encoding: StateEncoding::Scale,
+            eth_players: vec![],
+            arbiter: None,
+            seed_timeout: None,
         };
 
         assert_ok!(SingleGomoku::app_initiate(
@@ -415,6 +1012,30 @@ fn test_player1_places_a_stone_at_0_4_and_wins() {
         );
         let turn = SingleGomoku::get_state(app_id, 0).unwrap();
         assert_eq!(turn, vec![0]);
+        let board_state = SingleGomoku::get_state(app_id, 2).unwrap();
+        assert_eq!(
+            gomoku_engine::render_board_state(&board_state).unwrap(),
+            concat!(
+                "winner: 1\n",
+                "turn: 0\n",
+                "     0  1  2  3  4  5  6  7  8  9 10 11 12 13 14\n",
+                "  0  X  X  X  X  X  .  .  .  .  .  .  .  .  .  .\n",
+                "  1  .  .  .  .  .  .  .  .  .  .  .  .  .  .  .\n",
+                "  2  .  .  .  .  .  .  .  .  .  .  .  .  .  .  .\n",
+                "  3  .  .  .  .  .  .  .  .  .  .  .  .  O  .  .\n",
+                "  4  .  .  .  .  .  .  .  .  .  .  .  .  .  .  .\n",
+                "  5  .  .  .  .  .  .  .  .  .  .  .  .  .  .  .\n",
+                "  6  .  .  .  .  .  .  .  .  .  O  O  O  .  .  .\n",
+                "  7  .  .  .  .  .  .  .  .  .  .  .  .  .  .  .\n",
+                "  8  .  .  .  .  .  .  .  .  .  .  .  .  .  .  .\n",
+                "  9  .  .  .  .  .  .  .  .  .  .  .  .  .  .  .\n",
+                " 10  .  .  .  .  .  .  .  .  .  .  .  .  .  .  .\n",
+                " 11  .  .  .  .  .  .  .  .  .  .  .  .  .  .  .\n",
+                " 12  .  .  .  .  .  .  .  .  .  .  .  .  .  .  .\n",
+                " 13  .  .  .  .  .  .  .  .  .  .  .  .  .  .  .\n",
+                " 14  .  .  .  .  .  .  .  .  .  .  .  .  .  .  .\n",
+            )
+        );
         assert_ok!(
             SingleGomoku::is_finalized(
                 Origin::signed(players[0]),
@@ -445,6 +1066,13 @@ fn test_fail_finalize_on_action_timeout_before_action_deadline() {
             timeout: 2,
             min_stone_offchain: 5,
             max_stone_onchain: 5,
+            time_bank: 0,
+            increment: 0,
+            hashed_board: false,
+            encoding: StateEncoding::Scale,
+            eth_players: vec![],
+            arbiter: None,
+            seed_timeout: None,
         };
 
         assert_ok!(SingleGomoku::app_initiate(
@@ -496,6 +1124,13 @@ fn test_pass_finalize_on_action_timeout_after_action_deadline() {
             timeout: 2,
             min_stone_offchain: 5,
             max_stone_onchain: 5,
+            time_bank: 0,
+            increment: 0,
+            hashed_board: false,
+            encoding: StateEncoding::Scale,
+            eth_players: vec![],
+            arbiter: None,
+            seed_timeout: None,
         };
 
         assert_ok!(SingleGomoku::app_initiate(
@@ -547,67 +1182,1297 @@ fn test_pass_finalize_on_action_timeout_after_action_deadline() {
     })
 }
 
-fn get_state_proof(
-    nonce: u128,
-    seq: u128,
-    board_state: Vec<u8>,
-    timeout: BlockNumber,
-    app_id: H256,
-    players_pair: Vec<sr25519::Pair>,
-) -> StateProof<BlockNumber, H256, Signature> {
-    let app_state = AppState {
-        nonce: nonce,
-        seq_num: seq,
-        board_state: board_state,
-        timeout: timeout,
-        app_id: app_id,
-    };
-    let encoded = SingleGomoku::encode_app_state(app_state.clone());
-    let sig_1 = players_pair[0].sign(&encoded);
-    let sig_2 = players_pair[1].sign(&encoded);
-    let state_proof = StateProof {
-        app_state: app_state,
-        sigs: vec![sig_1, sig_2]
-    };
+#[test]
+fn test_pass_seed_decides_first_mover() {
+    ExtBuilder::build().execute_with(|| {
+        System::set_block_number(1);
+        let alice_pair = account_pair("Alice");
+        let bob_pair = account_pair("Bob");
+        let (players, players_pair)
+            = get_sorted_peer(alice_pair, bob_pair);
 
-    return state_proof;
+        let initiate_request = AppInitiateRequest {
+            nonce: 0,
+            players: players.clone(),
+            timeout: 2,
+            min_stone_offchain: 0,
+            max_stone_onchain: 5,
+            time_bank: 0,
+            increment: 0,
+            hashed_board: false,
+            encoding: StateEncoding::Scale,
+            eth_players: vec![],
+            arbiter: None,
+            seed_timeout: Some(2),
+        };
+        assert_ok!(SingleGomoku::app_initiate(
+            Origin::signed(players[0]),
+            initiate_request.clone())
+        );
+
+        let app_id = SingleGomoku::get_app_id(initiate_request.nonce, initiate_request.players.clone());
+        let secrets = vec![H256::from_low_u64_be(1), H256::from_low_u64_be(2)];
+        for (player, secret) in players.iter().zip(secrets.iter()) {
+            assert_ok!(
+                SingleGomoku::commit_seed(
+                    Origin::signed(*player),
+                    app_id,
+                    seed_commitment::<_, BlakeTwo256>(&app_id, player, secret)
+                )
+            );
+        }
+        assert_ok!(
+            SingleGomoku::reveal_seed(
+                Origin::signed(players[1]),
+                app_id,
+                secrets[1]
+            )
+        );
+        assert_noop!(
+            SingleGomoku::reveal_seed(
+                Origin::signed(players[1]),
+                app_id,
+                secrets[1]
+            ),
+            "seed is already revealed"
+        );
+        assert_ok!(
+            SingleGomoku::reveal_seed(
+                Origin::signed(players[0]),
+                app_id,
+                secrets[0]
+            )
+        );
+        let seed = combined_seed::<BlakeTwo256>(&secrets);
+        assert_eq!(SingleGomoku::get_seed(app_id), Some(seed));
+        let first = first_mover(seed.as_ref());
+
+        // the other player can not place the first stone
+        let mut board_state = vec![0; 227];
+        board_state[1] = first; // turn
+        board_state[2] = 3 - first; // (0, 0)
+        let state_proof = get_state_proof(0, 1, board_state, 2, app_id, players_pair.clone());
+        assert_noop!(
+            SingleGomoku::update_by_state(
+                Origin::signed(players[0]),
+                state_proof
+            ),
+            "first mover not match seed"
+        );
+        // nor be in turn on an empty board
+        let mut board_state = vec![0; 227];
+        board_state[1] = 3 - first; // turn
+        let state_proof = get_state_proof(0, 1, board_state, 2, app_id, players_pair.clone());
+        assert_noop!(
+            SingleGomoku::update_by_state(
+                Origin::signed(players[0]),
+                state_proof
+            ),
+            "first mover not match seed"
+        );
+        // nor be in turn with as many stones as the first mover
+        let mut board_state = vec![0; 227];
+        board_state[1] = 3 - first; // turn
+        board_state[2] = first; // (0, 0)
+        board_state[3] = 3 - first; // (0, 1)
+        let state_proof = get_state_proof(0, 1, board_state, 2, app_id, players_pair.clone());
+        assert_noop!(
+            SingleGomoku::update_by_state(
+                Origin::signed(players[0]),
+                state_proof
+            ),
+            "first mover not match seed"
+        );
+
+        let mut board_state = vec![0; 227];
+        board_state[1] = 3 - first; // turn
+        board_state[2] = first; // (0, 0)
+        let state_proof = get_state_proof(0, 1, board_state, 2, app_id, players_pair);
+        assert_ok!(
+            SingleGomoku::update_by_state(
+                Origin::signed(players[0]),
+                state_proof
+            )
+        );
+        assert_eq!(SingleGomoku::get_seq_num(app_id), Some(1));
+    })
 }
 
-fn place_stone(app_id: H256, players: Vec<AccountId>, players_pair: Vec<sr25519::Pair>) {
-    let mut board_state_1 = vec![0; 227];
-    board_state_1[0] = 0;
-    board_state_1[1] = 1;
-    board_state_1[2] = 2;
-    board_state_1[3] = 2;
-    board_state_1[4] = 1;
-    board_state_1[5] = 1;
-    board_state_1[6] = 2;
-    board_state_1[7] = 2;
-    board_state_1[8] = 1;
-    let state_proof = get_state_proof(0, 1, board_state_1.clone(), 0, app_id, players_pair.clone());
-    assert_ok!(
-        SingleGomoku::update_by_state(
+#[test]
+fn test_pass_arbitrate_draw_while_settling() {
+    ExtBuilder::build().execute_with(|| {
+        System::set_block_number(1);
+        let alice_pair = account_pair("Alice");
+        let bob_pair = account_pair("Bob");
+        let (players, players_pair)
+            = get_sorted_peer(alice_pair, bob_pair);
+        let arbiter = account_pair("Carl").public();
+
+        let mut initiate_request = AppInitiateRequest {
+            nonce: 0,
+            players: players.clone(),
+            timeout: 2,
+            min_stone_offchain: 5,
+            max_stone_onchain: 5,
+            time_bank: 0,
+            increment: 0,
+            hashed_board: false,
+            encoding: StateEncoding::Scale,
+            eth_players: vec![],
+            arbiter: Some(players[0]),
+            seed_timeout: None,
+        };
+        assert_noop!(
+            SingleGomoku::app_initiate(
+                Origin::signed(players[0]),
+                initiate_request.clone()
+            ),
+            "arbiter is a player"
+        );
+        initiate_request.arbiter = Some(arbiter);
+        assert_ok!(SingleGomoku::app_initiate(
             Origin::signed(players[0]),
-            state_proof
-        )
-    );
+            initiate_request.clone())
+        );
 
-    let mut board_state_2 = vec![0; 227];
-    board_state_2[0] = 0; // winner
-    board_state_2[1] = 2; // turn
-    board_state_2[2] = 1; // (0, 0)
-    board_state_2[3] = 1; // (0, 1)
-    board_state_2[4] = 1; // (0, 2)
-    board_state_2[5] = 1; // (0, 3)
-    board_state_2[101] = 2; 
-    board_state_2[102] = 2;
-    board_state_2[103] = 2;
-    let state_proof = get_state_proof(0, 2, board_state_2, 0, app_id, players_pair);
-    assert_ok!(
-        SingleGomoku::update_by_state(
+        let app_id = SingleGomoku::get_app_id(initiate_request.nonce, initiate_request.players.clone());
+        assert_noop!(
+            SingleGomoku::arbitrate(
+                Origin::signed(arbiter),
+                app_id,
+                0
+            ),
+            "app not in settle or action mode"
+        );
+
+        let mut board_state = vec![0; 227];
+        board_state[1] = 2; // turn
+        board_state[2] = 1; // (0, 0)
+        board_state[3] = 1; // (0, 1)
+        board_state[4] = 1; // (0, 2)
+        board_state[101] = 2;
+        board_state[102] = 2;
+        let state_proof = get_state_proof(0, 1, board_state, 2, app_id, players_pair);
+        assert_ok!(
+            SingleGomoku::update_by_state(
+                Origin::signed(players[0]),
+                state_proof
+            )
+        );
+        assert_noop!(
+            SingleGomoku::arbitrate(
+                Origin::signed(players[1]),
+                app_id,
+                0
+            ),
+            Error::<TestRuntime>::NotArbiter
+        );
+        assert_noop!(
+            SingleGomoku::arbitrate(
+                Origin::signed(arbiter),
+                app_id,
+                3
+            ),
+            "invalid winner state"
+        );
+
+        // the ruling finalizes the app before the settle period ends
+        assert_ok!(
+            SingleGomoku::arbitrate(
+                Origin::signed(arbiter),
+                app_id,
+                0
+            )
+        );
+        assert_ok!(
+            SingleGomoku::is_finalized(
+                Origin::signed(players[0]),
+                app_id
+            )
+        );
+        assert_ok!(
+            SingleGomoku::get_outcome(
+                Origin::signed(players[0]),
+                app_id,
+                0
+            )
+        );
+        assert_eq!(SingleGomoku::get_state(app_id, StateKey::Turn as u8), Some(vec![0]));
+        let expected_ruling = Ruling {
+            arbiter: arbiter,
+            outcome: 0,
+            block_number: 1,
+        };
+        assert_eq!(SingleGomoku::ruling(app_id), Some(expected_ruling));
+        let expected_event = TestEvent::single_gomoku(RawEvent::Arbitrated(app_id, arbiter, 0));
+        assert!(System::events().iter().any(|a| a.event == expected_event));
+    })
+}
+
+#[test]
+fn test_pass_replay_moves_from_resume_point() {
+    ExtBuilder::build().execute_with(|| {
+        let alice_pair = account_pair("Alice");
+        let bob_pair = account_pair("Bob");        
+        let (players, players_pair) 
+            = get_sorted_peer(alice_pair, bob_pair);
+        
+        let initiate_request = AppInitiateRequest {
+            nonce: 0,
+            players: players.clone(),
+            timeout: 2,
+            min_stone_offchain: 5,
+            max_stone_onchain: 5,
+            time_bank: 0,
+            increment: 0,
+            hashed_board: false,
+            encoding: StateEncoding::Scale,
+            eth_players: vec![],
+            arbiter: None,
+            seed_timeout: None,
+        };
+
+        assert_ok!(SingleGomoku::app_initiate(
             Origin::signed(players[0]),
-            state_proof
-        )
-    );
+            initiate_request.clone())
+        );
 
-}
\ No newline at end of file
+        let app_id = SingleGomoku::get_app_id(initiate_request.nonce, initiate_request.players.clone());
+        
+        // place stone
+        place_stone(app_id, players.clone(), players_pair);
+        let base_state = SingleGomoku::get_state(app_id, 2).unwrap();
+        let resume_point = SingleGomoku::resume_point(app_id).unwrap();
+        assert_eq!(resume_point.seq_num, 2);
+        assert_eq!(resume_point.state_hash, BlakeTwo256::hash(&base_state));
+    
+        let settle_finalized_time = SingleGomoku::get_settle_finalized_time(app_id).unwrap();
+        System::set_block_number(settle_finalized_time + 1);
+        assert_ok!(
+            SingleGomoku::update_by_action(
+                Origin::signed(players[1]),
+                app_id,
+                vec![3, 12]
+            )
+        );
+        assert_ok!(
+            SingleGomoku::update_by_action(
+                Origin::signed(players[0]),
+                app_id,
+                vec![0, 4]
+            )
+        );
+
+        let move_history = SingleGomoku::move_history(app_id);
+        assert_eq!(move_history.len(), 2);
+        assert_eq!(move_history[0].player_id, 2);
+        assert_eq!((move_history[0].x, move_history[0].y), (3, 12));
+        assert_eq!(move_history[0].block_number, settle_finalized_time + 1);
+        assert_eq!(move_history[0].seq_num, 3);
+        assert_eq!(move_history[1].player_id, 1);
+        assert_eq!(move_history[1].seq_num, 4);
+
+        let replayed_state = SingleGomoku::replay_moves(app_id, base_state).unwrap();
+        assert_eq!(replayed_state, SingleGomoku::get_state(app_id, 2).unwrap());
+        assert_eq!(replayed_state[0], 1);
+        assert_eq!(replayed_state[1], 0);
+    })
+}
+
+#[test]
+fn test_pass_replay_moves_after_rotate_player_key() {
+    ExtBuilder::build().execute_with(|| {
+        let alice_pair = account_pair("Alice");
+        let bob_pair = account_pair("Bob");
+        let (players, players_pair)
+            = get_sorted_peer(alice_pair, bob_pair);
+
+        let initiate_request = AppInitiateRequest {
+            nonce: 0,
+            players: players.clone(),
+            timeout: 2,
+            min_stone_offchain: 5,
+            max_stone_onchain: 5,
+            time_bank: 0,
+            increment: 0,
+            hashed_board: false,
+            encoding: StateEncoding::Scale,
+            eth_players: vec![],
+            arbiter: None,
+            seed_timeout: None,
+        };
+        assert_ok!(SingleGomoku::app_initiate(
+            Origin::signed(players[0]),
+            initiate_request.clone())
+        );
+
+        let app_id = SingleGomoku::get_app_id(initiate_request.nonce, initiate_request.players.clone());
+        place_stone(app_id, players.clone(), players_pair.clone());
+        let base_state = SingleGomoku::get_state(app_id, 2).unwrap();
+
+        let settle_finalized_time = SingleGomoku::get_settle_finalized_time(app_id).unwrap();
+        System::set_block_number(settle_finalized_time + 1);
+        assert_ok!(
+            SingleGomoku::update_by_action(
+                Origin::signed(players[1]),
+                app_id,
+                vec![3, 12]
+            )
+        );
+
+        // the player who moved on-chain rotates its account
+        let new_pair = account_pair("Dave");
+        let rotation = KeyRotation {
+            app_id: app_id,
+            old_player: players[1],
+            new_player: new_pair.public(),
+            nonce: 0,
+        };
+        assert_ok!(
+            SingleGomoku::rotate_player_key(
+                Origin::signed(players[0]),
+                rotation.clone(),
+                players_pair[1].sign(&rotation.signing_payload()),
+                new_pair.sign(&rotation.signing_payload())
+            )
+        );
+        // the history keeps the player id of the move
+        assert_eq!(SingleGomoku::move_history(app_id)[0].player_id, 2);
+
+        assert_ok!(
+            SingleGomoku::update_by_action(
+                Origin::signed(players[0]),
+                app_id,
+                vec![0, 4]
+            )
+        );
+
+        let replayed_state = SingleGomoku::replay_moves(app_id, base_state).unwrap();
+        assert_eq!(replayed_state, SingleGomoku::get_state(app_id, 2).unwrap());
+        assert_eq!(replayed_state[0], 1);
+        assert_eq!(replayed_state[1], 0);
+
+        // no rotation once the game is over
+        assert_eq!(SingleGomoku::get_status(app_id), Some(AppStatus::Finalized));
+        let rotation = KeyRotation {
+            app_id: app_id,
+            old_player: players[0],
+            new_player: account_pair("Carl").public(),
+            nonce: 1,
+        };
+        assert_noop!(
+            SingleGomoku::rotate_player_key(
+                Origin::signed(players[0]),
+                rotation.clone(),
+                players_pair[0].sign(&rotation.signing_payload()),
+                account_pair("Carl").sign(&rotation.signing_payload())
+            ),
+            "app state is finalized"
+        );
+    })
+}
+
+#[test]
+fn test_fail_replay_moves_with_different_base_state() {
+    ExtBuilder::build().execute_with(|| {
+        let alice_pair = account_pair("Alice");
+        let bob_pair = account_pair("Bob");        
+        let (players, players_pair) 
+            = get_sorted_peer(alice_pair, bob_pair);
+        
+        let initiate_request = AppInitiateRequest {
+            nonce: 0,
+            players: players.clone(),
+            timeout: 2,
+            min_stone_offchain: 5,
+            max_stone_onchain: 5,
+            time_bank: 0,
+            increment: 0,
+            hashed_board: false,
+            encoding: StateEncoding::Scale,
+            eth_players: vec![],
+            arbiter: None,
+            seed_timeout: None,
+        };
+
+        assert_ok!(SingleGomoku::app_initiate(
+            Origin::signed(players[0]),
+            initiate_request.clone())
+        );
+
+        let app_id = SingleGomoku::get_app_id(initiate_request.nonce, initiate_request.players.clone());
+        
+        // place stone
+        place_stone(app_id, players.clone(), players_pair);
+
+        assert_noop!(
+            SingleGomoku::replay_moves(app_id, vec![0; 227]),
+            "base state not match"
+        );
+    })
+}
+
+#[test]
+fn test_pass_update_by_state_records_stone_num() {
+    ExtBuilder::build().execute_with(|| {
+        let alice_pair = account_pair("Alice");
+        let bob_pair = account_pair("Bob");        
+        let (players, players_pair) 
+            = get_sorted_peer(alice_pair, bob_pair);
+        
+        let initiate_request = AppInitiateRequest {
+            nonce: 0,
+            players: players.clone(),
+            timeout: 2,
+            min_stone_offchain: 5,
+            max_stone_onchain: 5,
+            time_bank: 0,
+            increment: 0,
+            hashed_board: false,
+            encoding: StateEncoding::Scale,
+            eth_players: vec![],
+            arbiter: None,
+            seed_timeout: None,
+        };
+
+        assert_ok!(SingleGomoku::app_initiate(
+            Origin::signed(players[0]),
+            initiate_request.clone())
+        );
+
+        let app_id = SingleGomoku::get_app_id(initiate_request.nonce, initiate_request.players.clone());
+
+        // place stone
+        place_stone(app_id, players.clone(), players_pair);
+        let gomoku_info = SingleGomoku::gomoku_info(app_id).unwrap();
+        assert_eq!(gomoku_info.gomoku_state.stone_num, Some(7));
+
+        let settle_finalized_time = SingleGomoku::get_settle_finalized_time(app_id).unwrap();
+        System::set_block_number(settle_finalized_time + 1);
+        assert_ok!(
+            SingleGomoku::update_by_action(
+                Origin::signed(players[1]),
+                app_id,
+                vec![3, 12]
+            )
+        );
+        let gomoku_info = SingleGomoku::gomoku_info(app_id).unwrap();
+        assert_eq!(gomoku_info.gomoku_state.stone_num, Some(8));
+        assert_eq!(gomoku_info.gomoku_state.stone_num_onchain, Some(1));
+    })
+}
+
+#[test]
+fn test_fail_update_by_state_with_inconsistent_board() {
+    ExtBuilder::build().execute_with(|| {
+        let alice_pair = account_pair("Alice");
+        let bob_pair = account_pair("Bob");        
+        let (players, players_pair) 
+            = get_sorted_peer(alice_pair, bob_pair);
+        
+        let initiate_request = AppInitiateRequest {
+            nonce: 0,
+            players: players.clone(),
+            timeout: 2,
+            min_stone_offchain: 0,
+            max_stone_onchain: 5,
+            time_bank: 0,
+            increment: 0,
+            hashed_board: false,
+            encoding: StateEncoding::Scale,
+            eth_players: vec![],
+            arbiter: None,
+            seed_timeout: None,
+        };
+
+        assert_ok!(SingleGomoku::app_initiate(
+            Origin::signed(players[0]),
+            initiate_request.clone())
+        );
+
+        let app_id = SingleGomoku::get_app_id(initiate_request.nonce, initiate_request.players.clone());
+
+        // turn out of range
+        let mut board_state = vec![0; 227];
+        board_state[1] = 3;
+        let state_proof = get_state_proof(0, 1, board_state, 0, app_id, players_pair.clone());
+        assert_noop!(
+            SingleGomoku::update_by_state(
+                Origin::signed(players[0]),
+                state_proof
+            ),
+            "invalid turn state"
+        );
+
+        // player 1 has won but player 2 is still in turn
+        let mut board_state = vec![0; 227];
+        board_state[0] = 1;
+        board_state[1] = 2;
+        for i in 2..7 {
+            board_state[i] = 1; // (0, 0) to (0, 4)
+        }
+        for i in 101..105 {
+            board_state[i] = 2;
+        }
+        let state_proof = get_state_proof(0, 1, board_state, 0, app_id, players_pair.clone());
+        assert_noop!(
+            SingleGomoku::update_by_state(
+                Origin::signed(players[0]),
+                state_proof
+            ),
+            "invalid turn state"
+        );
+
+        // slot out of range
+        let mut board_state = vec![0; 227];
+        board_state[1] = 1;
+        board_state[2] = 3;
+        let state_proof = get_state_proof(0, 1, board_state, 0, app_id, players_pair.clone());
+        assert_noop!(
+            SingleGomoku::update_by_state(
+                Origin::signed(players[0]),
+                state_proof
+            ),
+            "invalid slot state"
+        );
+
+        // player 1 has placed two more stones than player 2
+        let mut board_state = vec![0; 227];
+        board_state[1] = 2;
+        board_state[2] = 1;
+        board_state[3] = 1;
+        let state_proof = get_state_proof(0, 1, board_state, 0, app_id, players_pair.clone());
+        assert_noop!(
+            SingleGomoku::update_by_state(
+                Origin::signed(players[0]),
+                state_proof
+            ),
+            "invalid stone number"
+        );
+
+        // player 1 is in turn after placing more stones than player 2
+        let mut board_state = vec![0; 227];
+        board_state[1] = 1;
+        board_state[2] = 1;
+        let state_proof = get_state_proof(0, 1, board_state, 0, app_id, players_pair.clone());
+        assert_noop!(
+            SingleGomoku::update_by_state(
+                Origin::signed(players[0]),
+                state_proof
+            ),
+            "invalid turn state"
+        );
+
+        // winner without five in a row
+        let mut board_state = vec![0; 227];
+        board_state[0] = 2;
+        let state_proof = get_state_proof(0, 1, board_state, 0, app_id, players_pair.clone());
+        assert_noop!(
+            SingleGomoku::update_by_state(
+                Origin::signed(players[0]),
+                state_proof
+            ),
+            "winner not match board"
+        );
+
+        // five in a row without winner
+        let mut board_state = vec![0; 227];
+        board_state[1] = 2;
+        for i in 0..5 {
+            board_state[2 + 15 * i] = 1; // (i, 0)
+            board_state[3 + 15 * i] = 2; // (i, 1)
+        }
+        board_state[3 + 15 * 4] = 0;
+        let state_proof = get_state_proof(0, 1, board_state, 0, app_id, players_pair);
+        assert_noop!(
+            SingleGomoku::update_by_state(
+                Origin::signed(players[0]),
+                state_proof
+            ),
+            "winner not match board"
+        );
+    })
+}
+
+#[test]
+fn test_pass_watchtower_answers_stale_settle() {
+    let (mut ext, pool_state) = ExtBuilder::build_with_watchtower();
+    ext.execute_with(|| {
+        System::set_block_number(1);
+        let alice_pair = account_pair("Alice");
+        let bob_pair = account_pair("Bob");
+        let (players, players_pair)
+            = get_sorted_peer(alice_pair, bob_pair);
+
+        let initiate_request = AppInitiateRequest {
+            nonce: 0,
+            players: players.clone(),
+            timeout: 2,
+            min_stone_offchain: 5,
+            max_stone_onchain: 5,
+            time_bank: 0,
+            increment: 0,
+            hashed_board: false,
+            encoding: StateEncoding::Scale,
+            eth_players: vec![],
+            arbiter: None,
+            seed_timeout: None,
+        };
+        assert_ok!(SingleGomoku::app_initiate(
+            Origin::signed(players[0]),
+            initiate_request.clone())
+        );
+
+        let app_id = SingleGomoku::get_app_id(initiate_request.nonce, initiate_request.players.clone());
+        let mut board_state = vec![0; 227];
+        board_state[1] = 1;
+        board_state[2] = 2;
+        board_state[3] = 2;
+        board_state[4] = 1;
+        board_state[5] = 1;
+        board_state[6] = 2;
+        board_state[7] = 2;
+        board_state[8] = 1;
+        let stale_proof = get_state_proof(0, 1, board_state.clone(), 2, app_id, players_pair.clone());
+        let latest_proof = get_state_proof(0, 3, board_state, 2, app_id, players_pair);
+        SingleGomoku::watch_state_proof(latest_proof.clone());
+
+        // nothing to answer before settlement
+        SingleGomoku::offchain_worker(1);
+        assert!(pool_state.read().transactions.is_empty());
+
+        assert_ok!(
+            SingleGomoku::update_by_state(
+                Origin::signed(players[0]),
+                stale_proof
+            )
+        );
+        SingleGomoku::offchain_worker(1);
+        let tx = pool_state.write().transactions.pop().unwrap();
+        assert!(pool_state.read().transactions.is_empty());
+        let tx = Extrinsic::decode(&mut &*tx).unwrap();
+        assert_eq!(tx.signature.unwrap().0, 0);
+        assert_eq!(tx.call, mock::Call::SingleGomoku(crate::Call::update_by_state(latest_proof.clone())));
+
+        // the answer is not repeated until the chain state changes
+        SingleGomoku::offchain_worker(1);
+        assert!(pool_state.read().transactions.is_empty());
+
+        // latest state is settled, nothing left to answer
+        assert_ok!(
+            SingleGomoku::update_by_state(
+                Origin::signed(players[1]),
+                latest_proof
+            )
+        );
+        SingleGomoku::offchain_worker(2);
+        assert!(pool_state.read().transactions.is_empty());
+    })
+}
+
+fn get_state_proof(
+    nonce: u128,
+    seq: u128,
+    board_state: Vec<u8>,
+    timeout: BlockNumber,
+    app_id: H256,
+    players_pair: Vec<sr25519::Pair>,
+) -> StateProof<BlockNumber, H256, Signature> {
+    get_encoded_state_proof(nonce, seq, board_state, timeout, app_id, players_pair, StateEncoding::Scale)
+}
+
+fn get_encoded_state_proof(
+    nonce: u128,
+    seq: u128,
+    board_state: Vec<u8>,
+    timeout: BlockNumber,
+    app_id: H256,
+    players_pair: Vec<sr25519::Pair>,
+    encoding: StateEncoding,
+) -> StateProof<BlockNumber, H256, Signature> {
+    let app_state = AppState {
+        nonce: nonce,
+        seq_num: seq,
+        board_state: board_state,
+        timeout: timeout,
+        app_id: app_id,
+    };
+    let encoded = app_state.payload(encoding).unwrap();
+    let sig_1 = players_pair[0].sign(&encoded);
+    let sig_2 = players_pair[1].sign(&encoded);
+    let state_proof = StateProof {
+        app_state: app_state,
+        sigs: vec![sig_1, sig_2]
+    };
+
+    return state_proof;
+}
+
+fn get_eth_state_proof(
+    nonce: u128,
+    seq: u128,
+    board_state: Vec<u8>,
+    timeout: BlockNumber,
+    app_id: H256,
+    eth_pairs: Vec<ethereum::Pair>,
+) -> EthStateProof<BlockNumber, H256> {
+    let app_state = AppState {
+        nonce: nonce,
+        seq_num: seq,
+        board_state: board_state,
+        timeout: timeout,
+        app_id: app_id,
+    };
+    let encoded = app_state.ethereum_payload().unwrap();
+    let sigs = eth_pairs.iter().map(|pair| pair.sign(&encoded)).collect();
+
+    StateProof {
+        app_state: app_state,
+        sigs: sigs,
+    }
+}
+
+fn eth_pair(seed: u8) -> ethereum::Pair {
+    ethereum::Pair::from_secret(&[seed; 32]).unwrap()
+}
+
+fn place_stone(app_id: H256, players: Vec<AccountId>, players_pair: Vec<sr25519::Pair>) {
+    let mut board_state_1 = vec![0; 227];
+    board_state_1[0] = 0;
+    board_state_1[1] = 1;
+    board_state_1[2] = 2;
+    board_state_1[3] = 2;
+    board_state_1[4] = 1;
+    board_state_1[5] = 1;
+    board_state_1[6] = 2;
+    board_state_1[7] = 2;
+    board_state_1[8] = 1;
+    let state_proof = get_state_proof(0, 1, board_state_1.clone(), 0, app_id, players_pair.clone());
+    assert_ok!(
+        SingleGomoku::update_by_state(
+            Origin::signed(players[0]),
+            state_proof
+        )
+    );
+
+    let board_state_2 = gomoku_engine::parse_board_state("
+        winner: 0
+        turn: 2
+            0  1  2  3  4  5  6  7  8  9 10 11 12 13 14
+         0  X  X  X  X  .  .  .  .  .  .  .  .  .  .  .
+         1  .  .  .  .  .  .  .  .  .  .  .  .  .  .  .
+         2  .  .  .  .  .  .  .  .  .  .  .  .  .  .  .
+         3  .  .  .  .  .  .  .  .  .  .  .  .  .  .  .
+         4  .  .  .  .  .  .  .  .  .  .  .  .  .  .  .
+         5  .  .  .  .  .  .  .  .  .  .  .  .  .  .  .
+         6  .  .  .  .  .  .  .  .  .  O  O  O  .  .  .
+         7  .  .  .  .  .  .  .  .  .  .  .  .  .  .  .
+         8  .  .  .  .  .  .  .  .  .  .  .  .  .  .  .
+         9  .  .  .  .  .  .  .  .  .  .  .  .  .  .  .
+        10  .  .  .  .  .  .  .  .  .  .  .  .  .  .  .
+        11  .  .  .  .  .  .  .  .  .  .  .  .  .  .  .
+        12  .  .  .  .  .  .  .  .  .  .  .  .  .  .  .
+        13  .  .  .  .  .  .  .  .  .  .  .  .  .  .  .
+        14  .  .  .  .  .  .  .  .  .  .  .  .  .  .  .
+    ").unwrap();
+    let state_proof = get_state_proof(0, 2, board_state_2, 0, app_id, players_pair);
+    assert_ok!(
+        SingleGomoku::update_by_state(
+            Origin::signed(players[0]),
+            state_proof
+        )
+    );
+
+}
+#[test]
+fn test_pass_update_by_state_with_moves() {
+    ExtBuilder::build().execute_with(|| {
+        let alice_pair = account_pair("Alice");
+        let bob_pair = account_pair("Bob");
+        let (players, players_pair)
+            = get_sorted_peer(alice_pair, bob_pair);
+
+        let initiate_request = AppInitiateRequest {
+            nonce: 0,
+            players: players.clone(),
+            timeout: 2,
+            min_stone_offchain: 5,
+            max_stone_onchain: 5,
+            time_bank: 0,
+            increment: 0,
+            hashed_board: false,
+            encoding: StateEncoding::Scale,
+            eth_players: vec![],
+            arbiter: None,
+            seed_timeout: None,
+        };
+        assert_ok!(SingleGomoku::app_initiate(
+            Origin::signed(players[0]),
+            initiate_request.clone())
+        );
+
+        let app_id = SingleGomoku::get_app_id(initiate_request.nonce, initiate_request.players.clone());
+        let board_state = gomoku_engine::parse_board_state("
+            winner: 0
+            turn: 2
+            0  1  2  3  4  5  6  7  8  9 10 11 12 13 14
+         0  X  X  X  X  .  .  .  .  .  .  .  .  .  .  .
+         1  .  .  .  .  .  .  .  .  .  .  .  .  .  .  .
+         2  .  .  .  .  .  .  .  .  .  .  .  .  .  .  .
+         3  .  .  .  .  .  .  .  .  .  .  .  .  .  .  .
+         4  .  .  .  .  .  .  .  .  .  .  .  .  .  .  .
+         5  .  .  .  .  .  .  .  .  .  .  .  .  .  .  .
+         6  .  .  .  .  .  .  .  .  .  O  O  O  .  .  .
+         7  .  .  .  .  .  .  .  .  .  .  .  .  .  .  .
+         8  .  .  .  .  .  .  .  .  .  .  .  .  .  .  .
+         9  .  .  .  .  .  .  .  .  .  .  .  .  .  .  .
+        10  .  .  .  .  .  .  .  .  .  .  .  .  .  .  .
+        11  .  .  .  .  .  .  .  .  .  .  .  .  .  .  .
+        12  .  .  .  .  .  .  .  .  .  .  .  .  .  .  .
+        13  .  .  .  .  .  .  .  .  .  .  .  .  .  .  .
+        14  .  .  .  .  .  .  .  .  .  .  .  .  .  .  .
+        ").unwrap();
+        let state_proof = get_state_proof(0, 2, board_state, 2, app_id, players_pair.clone());
+        let signed_move = |seq_num: u128, action: Vec<u8>, pair: &sr25519::Pair| SignedMove {
+            sig: pair.sign(&signed_move_payload(&app_id, seq_num, &action)),
+            action: action,
+        };
+
+        // only the player in turn signs its move
+        assert_noop!(
+            SingleGomoku::update_by_state_with_moves(
+                Origin::signed(players[0]),
+                state_proof.clone(),
+                vec![signed_move(3, vec![6, 12], &players_pair[0])]
+            ),
+            "invalid move signature"
+        );
+        // a move is signed for the sequence number it leads to
+        assert_noop!(
+            SingleGomoku::update_by_state_with_moves(
+                Origin::signed(players[0]),
+                state_proof.clone(),
+                vec![signed_move(4, vec![6, 12], &players_pair[1])]
+            ),
+            "invalid move signature"
+        );
+        assert_noop!(
+            SingleGomoku::update_by_state_with_moves(
+                Origin::signed(players[0]),
+                state_proof.clone(),
+                vec![signed_move(3, vec![0, 0], &players_pair[1])]
+            ),
+            "slot is occupied"
+        );
+
+        // the winning move signed only by players[0] is not lost
+        assert_ok!(
+            SingleGomoku::update_by_state_with_moves(
+                Origin::signed(players[0]),
+                state_proof,
+                vec![
+                    signed_move(3, vec![6, 12], &players_pair[1]),
+                    signed_move(4, vec![0, 4], &players_pair[0]),
+                ]
+            )
+        );
+        assert_eq!(SingleGomoku::get_seq_num(app_id), Some(4));
+        assert_ok!(
+            SingleGomoku::is_finalized(
+                Origin::signed(players[0]),
+                app_id
+            )
+        );
+    })
+}
+
+#[cfg(feature = "runtime-benchmarks")]
+#[test]
+fn test_pass_benchmark_update_by_action() {
+    let (mut ext, _) = ExtBuilder::build_with_watchtower();
+    ext.execute_with(|| {
+        System::set_block_number(1);
+        assert_ok!(benchmarking::test_benchmark_update_by_action::<TestRuntime>());
+    })
+}
+
+#[test]
+fn test_pass_update_by_state_with_anti_diagonal_five() {
+    ExtBuilder::build().execute_with(|| {
+        let alice_pair = account_pair("Alice");
+        let bob_pair = account_pair("Bob");        
+        let (players, players_pair) 
+            = get_sorted_peer(alice_pair, bob_pair);
+        
+        let initiate_request = AppInitiateRequest {
+            nonce: 0,
+            players: players.clone(),
+            timeout: 2,
+            min_stone_offchain: 5,
+            max_stone_onchain: 5,
+            time_bank: 0,
+            increment: 0,
+            hashed_board: false,
+            encoding: StateEncoding::Scale,
+            eth_players: vec![],
+            arbiter: None,
+            seed_timeout: None,
+        };
+
+        assert_ok!(SingleGomoku::app_initiate(
+            Origin::signed(players[0]),
+            initiate_request.clone())
+        );
+
+        let app_id = SingleGomoku::get_app_id(initiate_request.nonce, initiate_request.players.clone());
+
+        let mut board_state = vec![0; 227];
+        board_state[1] = 2; // turn
+        board_state[62] = 1; // (4, 0)
+        board_state[48] = 1; // (3, 1)
+        board_state[34] = 1; // (2, 2)
+        board_state[20] = 1; // (1, 3)
+        board_state[6] = 1; // (0, 4)
+        board_state[152] = 2; // (10, 0)
+        board_state[153] = 2; // (10, 1)
+        board_state[154] = 2; // (10, 2)
+        board_state[155] = 2; // (10, 3)
+
+        // five-in-a-row on the anti-diagonal can not be hidden
+        let state_proof = get_state_proof(0, 1, board_state.clone(), 0, app_id, players_pair.clone());
+        assert_noop!(
+            SingleGomoku::update_by_state(
+                Origin::signed(players[0]),
+                state_proof
+            ),
+            "winner not match board"
+        );
+
+        board_state[0] = 1; // winner
+        board_state[1] = 0; // turn
+        let state_proof = get_state_proof(0, 1, board_state.clone(), 0, app_id, players_pair);
+        assert_ok!(
+            SingleGomoku::update_by_state(
+                Origin::signed(players[0]),
+                state_proof
+            )
+        );
+        assert_eq!(SingleGomoku::get_state(app_id, 2).unwrap(), board_state);
+        assert_ok!(
+            SingleGomoku::get_outcome(
+                Origin::signed(players[0]),
+                app_id,
+                1
+            )
+        );
+    })
+}
+
+#[test]
+fn test_player1_loses_on_time_bank_exhausted() {
+    ExtBuilder::build().execute_with(|| {
+        let alice_pair = account_pair("Alice");
+        let bob_pair = account_pair("Bob");        
+        let (players, players_pair) 
+            = get_sorted_peer(alice_pair, bob_pair);
+        
+        let initiate_request = AppInitiateRequest {
+            nonce: 0,
+            players: players.clone(),
+            timeout: 2,
+            min_stone_offchain: 5,
+            max_stone_onchain: 5,
+            time_bank: 4,
+            increment: 1,
+            hashed_board: false,
+            encoding: StateEncoding::Scale,
+            eth_players: vec![],
+            arbiter: None,
+            seed_timeout: None,
+        };
+
+        assert_ok!(SingleGomoku::app_initiate(
+            Origin::signed(players[0]),
+            initiate_request.clone())
+        );
+
+        let app_id = SingleGomoku::get_app_id(initiate_request.nonce, initiate_request.players.clone());
+        
+        // place stone
+        place_stone(app_id, players.clone(), players_pair);
+
+        // clock of player 2 starts when settle period passes
+        let settle_finalized_time = SingleGomoku::get_settle_finalized_time(app_id).unwrap();
+        assert_eq!(
+            SingleGomoku::get_action_deadline(app_id).unwrap(),
+            settle_finalized_time + 4
+        );
+
+        // player 2 spends 1 block of the time bank and gains 1 block of increment
+        System::set_block_number(settle_finalized_time + 1);
+        assert_ok!(
+            SingleGomoku::update_by_action(
+                Origin::signed(players[1]),
+                app_id,
+                vec![3, 12]
+            )
+        );
+        let gomoku_info = SingleGomoku::gomoku_info(app_id).unwrap();
+        assert_eq!(gomoku_info.gomoku_state.time_banks, vec![4, 4]);
+        assert_eq!(
+            SingleGomoku::get_action_deadline(app_id).unwrap(),
+            settle_finalized_time + 5
+        );
+
+        // player 1 runs out of time
+        System::set_block_number(settle_finalized_time + 6);
+        assert_noop!(
+            SingleGomoku::update_by_action(
+                Origin::signed(players[0]),
+                app_id,
+                vec![0, 4]
+            ),
+            "time bank exhausted"
+        );
+        assert_ok!(
+            SingleGomoku::finalize_on_action_timeout(
+                Origin::signed(players[0]),
+                app_id
+            )
+        );
+        assert_ok!(
+            SingleGomoku::get_outcome(
+                Origin::signed(players[0]),
+                app_id,
+                2
+            )
+        );
+    })
+}
+
+#[test]
+fn test_pass_update_by_action_with_hashed_board() {
+    ExtBuilder::build().execute_with(|| {
+        System::set_block_number(1);
+        let alice_pair = account_pair("Alice");
+        let bob_pair = account_pair("Bob");        
+        let (players, players_pair) 
+            = get_sorted_peer(alice_pair, bob_pair);
+        
+        let initiate_request = AppInitiateRequest {
+            nonce: 0,
+            players: players.clone(),
+            timeout: 2,
+            min_stone_offchain: 5,
+            max_stone_onchain: 5,
+            time_bank: 0,
+            increment: 0,
+            hashed_board: true,
+            encoding: StateEncoding::Scale,
+            eth_players: vec![],
+            arbiter: None,
+            seed_timeout: None,
+        };
+
+        assert_ok!(SingleGomoku::app_initiate(
+            Origin::signed(players[0]),
+            initiate_request.clone())
+        );
+
+        let app_id = SingleGomoku::get_app_id(initiate_request.nonce, initiate_request.players.clone());
+
+        let mut board_state = vec![0; 227];
+        board_state[0] = 0; // winner
+        board_state[1] = 2; // turn
+        board_state[2] = 1; // (0, 0)
+        board_state[3] = 1; // (0, 1)
+        board_state[4] = 1; // (0, 2)
+        board_state[5] = 1; // (0, 3)
+        board_state[101] = 2; 
+        board_state[102] = 2;
+        board_state[103] = 2;
+        let state_proof = get_state_proof(0, 1, board_state.clone(), 0, app_id, players_pair);
+        assert_ok!(
+            SingleGomoku::update_by_state(
+                Origin::signed(players[0]),
+                state_proof
+            )
+        );
+        let expected_event = TestEvent::single_gomoku(RawEvent::BoardUpdated(app_id, 1, board_state.clone()));
+        assert!(System::events().iter().any(|a| a.event == expected_event));
+
+        // only the hash of the board is stored
+        assert_eq!(SingleGomoku::get_state(app_id, 2), None);
+        assert_eq!(SingleGomoku::get_state(app_id, 0).unwrap(), vec![2]);
+
+        let settle_finalized_time = SingleGomoku::get_settle_finalized_time(app_id).unwrap();
+        System::set_block_number(settle_finalized_time + 1);
+        assert_noop!(
+            SingleGomoku::update_by_action(
+                Origin::signed(players[1]),
+                app_id,
+                vec![3, 12]
+            ),
+            "empty board witness"
+        );
+        let mut wrong_witness = board_state.clone();
+        wrong_witness[2] = 0;
+        assert_noop!(
+            SingleGomoku::update_by_action_with_witness(
+                Origin::signed(players[1]),
+                app_id,
+                vec![3, 12],
+                wrong_witness
+            ),
+            "board witness not match"
+        );
+        // the slots match the hash but the turn does not match the record
+        let mut wrong_witness = board_state.clone();
+        wrong_witness[1] = 1;
+        assert_noop!(
+            SingleGomoku::update_by_action_with_witness(
+                Origin::signed(players[1]),
+                app_id,
+                vec![3, 12],
+                wrong_witness
+            ),
+            "board witness not match"
+        );
+        assert_ok!(
+            SingleGomoku::update_by_action_with_witness(
+                Origin::signed(players[1]),
+                app_id,
+                vec![3, 12],
+                board_state.clone()
+            )
+        );
+
+        // the board after the action is emitted as the next witness
+        board_state[1] = 1; // turn
+        board_state[59] = 2; // (3, 12)
+        let expected_event = TestEvent::single_gomoku(RawEvent::BoardUpdated(app_id, 2, board_state.clone()));
+        assert!(System::events().iter().any(|a| a.event == expected_event));
+
+        assert_ok!(
+            SingleGomoku::update_by_action_with_witness(
+                Origin::signed(players[0]),
+                app_id,
+                vec![0, 4],
+                board_state.clone()
+            )
+        );
+        assert_ok!(
+            SingleGomoku::get_outcome(
+                Origin::signed(players[0]),
+                app_id,
+                1
+            )
+        );
+
+        // the winner is kept in the record and the hash still commits to the slots
+        board_state[6] = 1; // (0, 4)
+        let record = SingleGomoku::gomoku_info(app_id).unwrap().gomoku_state.board_state.unwrap();
+        assert_eq!(record.winner, 1);
+        assert_eq!(record.turn, 0);
+        assert_eq!(record.board, BoardRecord::Hashed(BlakeTwo256::hash(&board_state[2..])));
+    })
+}
+
+const SOAK_GAMES: u64 = 2000;
+
+#[test]
+fn test_pass_bots_play_randomized_games() {
+    for seed in 0..SOAK_GAMES {
+        ExtBuilder::build().execute_with(|| play_soak_game(seed));
+    }
+}
+
+/// Play a game between two bots, off-chain and then on-chain or abandoned,
+/// and check its end state against the referee
+fn play_soak_game(seed: u64) {
+    use gomoku_bot::{referee, Bot, Rng};
+
+    System::set_block_number(1);
+    let (players, players_pair) = get_sorted_peer(account_pair("Alice"), account_pair("Bob"));
+    let mut rng = Rng::new(seed);
+    let mut bots = [Bot::new(1, 2 * seed).with_noise(20), Bot::new(2, 2 * seed + 1).with_noise(20)];
+    let timeout = 1 + rng.below(3);
+    let max_stone_onchain = if rng.chance(1, 4) { rng.below(10) as u8 } else { 225 };
+    let initiate_request = AppInitiateRequest {
+        nonce: seed as u128,
+        players: players.clone(),
+        timeout: timeout,
+        min_stone_offchain: 0,
+        max_stone_onchain: max_stone_onchain,
+        time_bank: 0,
+        increment: 0,
+        hashed_board: false,
+        encoding: StateEncoding::Scale,
+        eth_players: vec![],
+        arbiter: None,
+        seed_timeout: None,
+    };
+    assert_ok!(SingleGomoku::app_initiate(Origin::signed(players[0]), initiate_request.clone()));
+    let app_id = SingleGomoku::get_app_id(initiate_request.nonce, players.clone());
+
+    // play off-chain and co-sign the last state
+    let mut slots = vec![0u8; 225];
+    let mut turn: u8 = 1;
+    let mut winner: u8 = 0;
+    let offchain_moves = rng.below(40);
+    for _ in 0..offchain_moves {
+        let mv = bots[turn as usize - 1].next_move(&slots).unwrap();
+        slots[mv.x as usize * 15 + mv.y as usize] = turn;
+        if referee::five_in_a_row(&slots) == vec![turn] {
+            winner = turn;
+            turn = 0;
+            break;
+        }
+        turn = 3 - turn;
+    }
+    let mut board_state = vec![winner, turn];
+    board_state.extend(&slots);
+    let state_proof = get_state_proof(
+        seed as u128, offchain_moves as u128 + 1, board_state, timeout, app_id, players_pair
+    );
+    let settler = players[rng.below(2) as usize];
+    assert_ok!(SingleGomoku::update_by_state(Origin::signed(settler), state_proof));
+
+    // move on-chain after the settle period until the game is over or abandoned
+    if winner == 0 {
+        System::set_block_number(SingleGomoku::get_settle_finalized_time(app_id).unwrap() + 1);
+        let mut stone_num_onchain = 0;
+        loop {
+            if rng.chance(1, 20) {
+                System::set_block_number(SingleGomoku::get_action_deadline(app_id).unwrap() + 1);
+                assert_ok!(SingleGomoku::finalize_on_action_timeout(Origin::signed(settler), app_id));
+                winner = 3 - turn;
+                break;
+            }
+
+            let mv = bots[turn as usize - 1].next_move(&slots).unwrap();
+            assert_ok!(SingleGomoku::update_by_action(
+                Origin::signed(players[turn as usize - 1]),
+                app_id,
+                vec![mv.x, mv.y]
+            ));
+            slots[mv.x as usize * 15 + mv.y as usize] = turn;
+            stone_num_onchain += 1;
+            if referee::five_in_a_row(&slots) == vec![turn] {
+                winner = turn;
+                break;
+            }
+            if referee::is_full(&slots) || stone_num_onchain > max_stone_onchain as u16 {
+                break;
+            }
+            turn = 3 - turn;
+            System::set_block_number(System::block_number() + rng.below(timeout + 1));
+        }
+    }
+
+    let mut expected = vec![winner, 0];
+    expected.extend(&slots);
+    assert_eq!(SingleGomoku::get_state(app_id, 2), Some(expected), "seed {}", seed);
+    assert_eq!(SingleGomoku::get_status(app_id), Some(AppStatus::Finalized), "seed {}", seed);
+    assert_ok!(SingleGomoku::get_outcome(Origin::signed(players[0]), app_id, winner));
+}
@@ -1,6 +1,8 @@
 #![cfg_attr(not(feature = "std"), no_std)]
 
 mod mock;
+mod watchtower;
+pub mod types;
 
 #[cfg(test)]
 mod tests;
@@ -8,22 +10,35 @@ mod tests;
 use codec::{Decode, Encode};
 use frame_support::{
     decl_module, decl_storage, decl_event, decl_error, ensure,
-    storage::StorageMap,
+    storage::{StorageDoubleMap, StorageMap},
     traits::Get,
 };
 use frame_system::{self as system, ensure_signed};
 use sp_runtime::traits::{
-    Hash, IdentifyAccount, 
+    Hash, IdentifyAccount, UniqueSaturatedInto, 
     Member, Verify, Zero, AccountIdConversion, 
 };
 use sp_runtime::{ModuleId, RuntimeDebug, DispatchResult, DispatchError};
-use sp_std::{prelude::*, vec::Vec};
+use sp_std::{convert::TryInto, prelude::*, vec::Vec};
+use sp_core::{H160, H256};
+use capps_state_encoding::ethereum;
+
+pub use capps_app_common::{
+    combined_seed, seed_commitment, KeyRotation, Ruling, SeedRound, SessionKeyDelegation,
+    KEY_ROTATION_PREFIX, SESSION_KEY_DELEGATION_PREFIX,
+};
+pub use capps_state_encoding::{ethereum::EcdsaSignature, StateEncoding};
+pub use watchtower::{crypto, watched_proof_key, SignedWatchtower, WatchedProofOf, KEY_TYPE, WATCHED_APPS_KEY};
 
 #[derive(Clone, Eq, PartialEq, PartialOrd, Ord, Encode, Decode, RuntimeDebug)]
 pub struct AppInitiateRequest<AccountId, BlockNumber> {
-    nonce: u128,
-    players: Vec<AccountId>,
-    timeout: BlockNumber,
+    pub nonce: u128,
+    pub players: Vec<AccountId>,
+    pub timeout: BlockNumber,
+    pub encoding: StateEncoding,
+    pub eth_players: Vec<H160>,
+    pub arbiter: Option<AccountId>,
+    pub seed_timeout: Option<BlockNumber>,
 }
 
 pub type AppInitiateRequestOf<T> = AppInitiateRequest<
@@ -33,11 +48,11 @@ pub type AppInitiateRequestOf<T> = AppInitiateRequest<
 
 #[derive(Clone, Eq, PartialEq, PartialOrd, Ord, Encode, Decode, RuntimeDebug)]
 pub struct AppState<BlockNumber, Hash> {
-    nonce: u128,
-    seq_num: u128,
-    state: u8,
-    timeout: BlockNumber,
-    app_id: Hash,
+    pub nonce: u128,
+    pub seq_num: u128,
+    pub state: u8,
+    pub timeout: BlockNumber,
+    pub app_id: Hash,
 }
 
 pub type AppStateOf<T> = AppState<
@@ -47,8 +62,8 @@ pub type AppStateOf<T> = AppState<
 
 #[derive(Clone, Eq, PartialEq, PartialOrd, Ord, Encode, Decode, RuntimeDebug)]
 pub struct StateProof<BlockNumber, Hash, Signature> {
-    app_state: AppState<BlockNumber, Hash>,
-    sigs: Vec<Signature>,
+    pub app_state: AppState<BlockNumber, Hash>,
+    pub sigs: Vec<Signature>,
 }
 
 pub type StateProofOf<T> = StateProof<
@@ -57,6 +72,78 @@ pub type StateProofOf<T> = StateProof<
     <T as Trait>::Signature,
 >;
 
+pub type SessionKeyDelegationOf<T> = SessionKeyDelegation<
+    <T as system::Trait>::AccountId,
+    <T as system::Trait>::BlockNumber,
+    <T as system::Trait>::Hash,
+>;
+
+pub type KeyRotationOf<T> = KeyRotation<
+    <T as system::Trait>::AccountId,
+    <T as system::Trait>::Hash,
+>;
+
+/// State proof signed by Ethereum accounts of players
+pub type EthStateProof<BlockNumber, Hash> = StateProof<BlockNumber, Hash, EcdsaSignature>;
+
+pub type EthStateProofOf<T> = EthStateProof<
+    <T as system::Trait>::BlockNumber,
+    <T as system::Trait>::Hash,
+>;
+
+impl<BlockNumber: Encode, Hash: Encode> AppState<BlockNumber, Hash> {
+    /// Encode app state into the payload signed by players
+    pub fn signing_payload(&self) -> Vec<u8> {
+        let mut encoded = self.nonce.encode();
+        encoded.extend(self.seq_num.encode());
+        encoded.extend(self.state.encode());
+        encoded.extend(self.timeout.encode());
+        encoded.extend(self.app_id.encode());
+
+        return encoded;
+    }
+}
+
+impl<BlockNumber: Encode + Clone + TryInto<u64>, Hash: Encode> AppState<BlockNumber, Hash> {
+    /// Encode app state into the protobuf `AppState` message signed by Celer's clients
+    ///
+    /// Returns `None` if a number does not fit in a `uint64` field.
+    pub fn protobuf_payload(&self) -> Option<Vec<u8>> {
+        capps_state_encoding::protobuf::app_state(
+            self.nonce,
+            self.seq_num,
+            &[self.state],
+            self.timeout.clone().try_into().ok()?,
+            &self.app_id.encode(),
+        )
+    }
+
+    /// Encode app state as `abi.encodePacked` signed by Ethereum accounts of players
+    ///
+    /// Returns `None` if the timeout does not fit in a `uint64`.
+    pub fn ethereum_payload(&self) -> Option<Vec<u8>> {
+        Some(ethereum::app_state(
+            Some(self.nonce),
+            self.seq_num,
+            &[self.state],
+            self.timeout.clone().try_into().ok()?,
+            &self.app_id.encode(),
+        ))
+    }
+
+    /// Encode app state into the payload signed by players under an encoding
+    ///
+    /// Parameter:
+    /// `encoding`: Encoding chosen at initiation
+    pub fn payload(&self, encoding: StateEncoding) -> Option<Vec<u8>> {
+        match encoding {
+            StateEncoding::Scale => Some(self.signing_payload()),
+            StateEncoding::Protobuf => self.protobuf_payload(),
+            StateEncoding::Ethereum => self.ethereum_payload(),
+        }
+    }
+}
+
 #[derive(Clone, Eq, PartialEq, PartialOrd, Ord, Encode, Decode, RuntimeDebug)]
 pub enum AppStatus {
     Idle = 0,
@@ -74,6 +161,9 @@ pub struct AppInfo<AccountId, BlockNumber> {
     timeout: BlockNumber,
     deadline: BlockNumber,
     status: AppStatus,
+    encoding: StateEncoding,
+    eth_players: Vec<H160>,
+    arbiter: Option<AccountId>,
 }
 
 pub type AppInfoOf<T> = AppInfo<
@@ -81,18 +171,79 @@ pub type AppInfoOf<T> = AppInfo<
     <T as system::Trait>::BlockNumber,
 >;
 
+pub type RulingOf<T> = Ruling<
+    <T as system::Trait>::AccountId,
+    <T as system::Trait>::BlockNumber,
+>;
+
+pub type SeedRoundOf<T> = SeedRound<
+    <T as system::Trait>::BlockNumber,
+    <T as system::Trait>::Hash,
+>;
+
 pub const SINGLE_SESSION_APP_ID: ModuleId = ModuleId(*b"_single_");
 
+/// Get Id of app from its nonce and players, without a runtime
+///
+/// Parameters:
+/// `nonce`: Nonce of app
+/// `players`: AccountId of players
+pub fn app_id<AccountId, Hashing>(
+    nonce: u128,
+    players: &[AccountId],
+) -> Hashing::Output
+where
+    AccountId: Encode + Decode + Default,
+    Hashing: Hash,
+{
+    let app_account: AccountId = SINGLE_SESSION_APP_ID.into_account();
+    let mut encoded = app_account.encode();
+    encoded.extend(nonce.encode());
+    encoded.extend(players[0].encode());
+    encoded.extend(players[1].encode());
+
+    Hashing::hash(&encoded)
+}
+
+/// Get Id of app signed by Ethereum accounts, hashed with keccak256 as
+/// Ethereum contracts do
+///
+/// Parameters:
+/// `nonce`: Nonce of app
+/// `eth_players`: Ethereum addresses of players
+pub fn eth_app_id(nonce: u128, eth_players: &[H160]) -> H256 {
+    ethereum::app_id(SINGLE_SESSION_APP_ID.0, nonce, eth_players)
+}
+
+/// Signatures of an off-chain state proof
+enum StateSigs<Signature> {
+    /// Signatures of player accounts
+    Players(Vec<Signature>),
+    /// Signatures of Ethereum accounts of players
+    Ethereum(Vec<EcdsaSignature>),
+}
+
 pub trait Trait: system::Trait {
     type Event: From<Event<Self>> + Into<<Self as frame_system::Trait>::Event>;
     type Public: IdentifyAccount<AccountId = Self::AccountId>;
     type Signature: Verify<Signer = <Self as Trait>::Public> + Member + Decode + Encode; 
+    /// Watchtower submitting state proofs from the off-chain worker, `()` for none
+    type Watchtower: capps_watchtower::SubmitProof<WatchedProofOf<Self>>;
 }
 
 decl_storage! {
     trait Store for Module<T: Trait> as SingleSessionApp {
         pub AppInfoMap get(fn app_info): 
             map hasher(blake2_128_concat) T::Hash => Option<AppInfoOf<T>>;
+        pub RulingMap get(fn ruling):
+            map hasher(blake2_128_concat) T::Hash => Option<RulingOf<T>>;
+        pub SeedRoundMap get(fn seed_round):
+            map hasher(blake2_128_concat) T::Hash => Option<SeedRoundOf<T>>;
+        pub SessionKeyMap get(fn session_key):
+            double_map hasher(blake2_128_concat) T::Hash, hasher(blake2_128_concat) T::AccountId
+            => Option<(T::AccountId, T::BlockNumber)>;
+        pub KeyRotationNonceMap get(fn key_rotation_nonce):
+            map hasher(blake2_128_concat) T::Hash => u32;
     }
 }
 
@@ -102,6 +253,11 @@ decl_module!  {
 
         fn deposit_event() = default;
 
+        /// Answer stale settlements of apps watched by this node
+        fn offchain_worker(block_number: T::BlockNumber) {
+            capps_watchtower::answer_stale_settles::<Self>(block_number.unique_saturated_into());
+        }
+
         /// Initiate single session app
         ///
         /// Parameters:
@@ -112,19 +268,40 @@ decl_module!  {
         /// - Complexity: `O(1)`
         ///   - 1 storage insertion `AppInfoMap`
         ///   - 1 storage reads `AppInfoMap`
+        ///   - 1 storage insertion `SeedRoundMap` with a seed timeout
         /// - Based on benchmark;
         ///     18.44　µs
         /// # </weight>
-        #[weight = 19_000_000 + T::DbWeight::get().reads_writes(1, 1)]
+        #[weight = 19_000_000 + T::DbWeight::get().reads_writes(1, 2)]
         fn app_initiate(
             origin,
             initiate_request: AppInitiateRequestOf<T>
         ) -> DispatchResult {
-            let app_id = Self::get_app_id(initiate_request.nonce, initiate_request.players.clone());
+            let app_id = Self::get_initiate_app_id(&initiate_request)?;
             ensure!(
                 AppInfoMap::<T>::contains_key(&app_id) == false,
                 "AppId alreads exists"
             );
+            if let Some(arbiter) = &initiate_request.arbiter {
+                ensure!(
+                    !initiate_request.players.contains(arbiter),
+                    "arbiter is a player"
+                );
+            }
+            let seed_round = match initiate_request.seed_timeout {
+                Some(seed_timeout) => {
+                    ensure!(
+                        !seed_timeout.is_zero(),
+                        "invalid seed timeout"
+                    );
+                    Some(SeedRoundOf::<T>::new(
+                        initiate_request.players.len(),
+                        seed_timeout,
+                        frame_system::Module::<T>::block_number(),
+                    ))
+                },
+                None => None,
+            };
 
             let app_info = AppInfoOf::<T> {
                 state: 0,
@@ -134,8 +311,15 @@ decl_module!  {
                 timeout: initiate_request.timeout,
                 deadline: Zero::zero(),
                 status: AppStatus::Idle,
+                encoding: initiate_request.encoding,
+                eth_players: initiate_request.eth_players,
+                arbiter: initiate_request.arbiter,
             };
             AppInfoMap::<T>::insert(app_id, app_info);
+            // players commit to a random seed before the first settle
+            if let Some(seed_round) = seed_round {
+                SeedRoundMap::<T>::insert(app_id, seed_round);
+            }
         
             Ok(())
         }
@@ -153,45 +337,172 @@ decl_module!  {
         /// - Complexity: `O(1)`
         ///   - 1 storage mutation `AppInfoMap`
         ///   - 1 storage read `AppInfoMap`
+        ///   - 1 storage read `SeedRoundMap`
+        ///   - 1 storage read `SessionKeyMap` per player
         /// - Based on benchmark;
         ///     44.68　µs
         /// # </weight>
-        #[weight = 45_000_000 + T::DbWeight::get().reads_writes(1, 1)]
+        #[weight = 45_000_000 + T::DbWeight::get().reads_writes(4, 1)]
         fn update_by_state(
             origin,
             state_proof: StateProofOf<T>
         ) -> DispatchResult {
             ensure_signed(origin)?;
             // submit ad settle off-chain state
-            let mut new_app_info: AppInfoOf<T> = Self::intend_settle(state_proof.clone())?;
-            
-            let state = state_proof.app_state.state;
-            if state == 1 || state == 2 {
-                new_app_info = AppInfoOf::<T> {
-                    state: state,
-                    nonce: new_app_info.nonce,
-                    players: new_app_info.players,
-                    seq_num: new_app_info.seq_num,
-                    timeout: new_app_info.timeout,
-                    deadline: new_app_info.deadline,
-                    status: AppStatus::Finalized
-                }
-            } else {
-                new_app_info = AppInfoOf::<T> {
-                    state: state,
-                    nonce: new_app_info.nonce,
-                    players: new_app_info.players,
-                    seq_num: new_app_info.seq_num,
-                    timeout: new_app_info.timeout,
-                    deadline: new_app_info.deadline,
-                    status: new_app_info.status
-                }
+            Self::update_state(state_proof.app_state, StateSigs::Players(state_proof.sigs))
+        }
+
+        /// Update state according to an off-chain state proof signed by
+        /// Ethereum accounts of players
+        ///
+        /// Parameters:
+        /// - `state_proof`: Off-chain app state signed by Ethereum accounts
+        ///
+        /// # <weight>
+        /// ## Weight
+        /// - Complexity: `O(1)`
+        ///   - 1 storage mutation `AppInfoMap`
+        ///   - 1 storage read `AppInfoMap`
+        ///   - 1 storage read `SeedRoundMap`
+        /// - Based on `update_by_state` and 2 secp256k1 recoveries of
+        ///     50　µs
+        /// # </weight>
+        #[weight = 145_000_000 + T::DbWeight::get().reads_writes(2, 1)]
+        fn update_by_eth_state(
+            origin,
+            state_proof: EthStateProofOf<T>
+        ) -> DispatchResult {
+            ensure_signed(origin)?;
+            Self::update_state(state_proof.app_state, StateSigs::Ethereum(state_proof.sigs))
+        }
+
+        /// Delegate off-chain state signing of a player to a session key
+        ///
+        /// The delegation is signed by the player, so any account may submit
+        /// it. A delegation only replaces one which expires earlier.
+        ///
+        /// Parameters:
+        /// - `delegation`: Session key of a player and its expiry
+        /// - `signature`: Signature of the player on the delegation
+        ///
+        /// # <weight>
+        /// ## Weight
+        /// - Complexity: `O(1)`
+        ///   - 1 storage read `AppInfoMap`
+        ///   - 1 storage read `SessionKeyMap`
+        ///   - 1 storage insertion `SessionKeyMap`
+        /// - Based on 1 signature verification of 50　µs
+        /// # </weight>
+        #[weight = 60_000_000 + T::DbWeight::get().reads_writes(2, 1)]
+        fn delegate_session_key(
+            origin,
+            delegation: SessionKeyDelegationOf<T>,
+            signature: <T as Trait>::Signature
+        ) -> DispatchResult {
+            ensure_signed(origin)?;
+            let app_info = match AppInfoMap::<T>::get(delegation.app_id) {
+                Some(info) => info,
+                None => Err(Error::<T>::AppInfoNotExist)?,
+            };
+            ensure!(
+                app_info.encoding != StateEncoding::Ethereum,
+                "app is signed by Ethereum accounts"
+            );
+            ensure!(
+                app_info.players.contains(&delegation.player),
+                "not a player of app"
+            );
+            ensure!(
+                delegation.expires_at > frame_system::Module::<T>::block_number(),
+                "session key is expired"
+            );
+            if let Some((_, expires_at)) = SessionKeyMap::<T>::get(delegation.app_id, &delegation.player) {
+                ensure!(
+                    delegation.expires_at > expires_at,
+                    "session key expires earlier than current one"
+                );
             }
-            let app_id = state_proof.app_state.app_id;
-            AppInfoMap::<T>::mutate(&app_id, |app_info| *app_info = Some(new_app_info.clone()));
+            ensure!(
+                signature.verify(&delegation.signing_payload()[..], &delegation.player),
+                "invalid delegation signature"
+            );
+
+            SessionKeyMap::<T>::insert(
+                delegation.app_id,
+                &delegation.player,
+                (delegation.session_key.clone(), delegation.expires_at)
+            );
+
+            // emit SessionKeyDelegated event
+            Self::deposit_event(RawEvent::SessionKeyDelegated(
+                delegation.app_id,
+                delegation.player,
+                delegation.session_key,
+                delegation.expires_at,
+            ));
+
+            Ok(())
+        }
+
+        /// Replace the account of a player in a live app
+        ///
+        /// The rotation is signed by the old account of the player or by the
+        /// arbiter, and by the new account, so any account may submit it. The
+        /// other player cannot take over the seat alone, and no account joins
+        /// without its own consent. The new account takes the place of the old
+        /// one, keeping the player id used by app states.
+        ///
+        /// Parameters:
+        /// - `rotation`: Old and new account of a player
+        /// - `signature`: Signature of the old account or of the arbiter
+        /// - `new_signature`: Signature of the new account
+        ///
+        /// # <weight>
+        /// ## Weight
+        /// - Complexity: `O(1)`
+        ///   - 1 storage mutation `AppInfoMap`
+        ///   - 1 storage read `AppInfoMap`
+        ///   - 1 storage mutation `KeyRotationNonceMap`
+        ///   - 1 storage read `KeyRotationNonceMap`
+        ///   - 1 storage removal `SessionKeyMap`
+        /// - Based on 3 signature verifications of 50　µs
+        /// # </weight>
+        #[weight = 160_000_000 + T::DbWeight::get().reads_writes(2, 3)]
+        fn rotate_player_key(
+            origin,
+            rotation: KeyRotationOf<T>,
+            signature: <T as Trait>::Signature,
+            new_signature: <T as Trait>::Signature
+        ) -> DispatchResult {
+            ensure_signed(origin)?;
+            let mut app_info = match AppInfoMap::<T>::get(rotation.app_id) {
+                Some(info) => info,
+                None => Err(Error::<T>::AppInfoNotExist)?,
+            };
+            ensure!(
+                app_info.status != AppStatus::Finalized,
+                "app state is finalized"
+            );
+            let index = Self::valid_rotation(
+                &rotation,
+                signature,
+                new_signature,
+                &app_info.players,
+                app_info.arbiter.as_ref(),
+            )?;
 
-            // Emit IntendSettle event
-            Self::deposit_event(RawEvent::IntendSettle(app_id, new_app_info.seq_num));
+            app_info.players[index] = rotation.new_player.clone();
+            AppInfoMap::<T>::insert(rotation.app_id, app_info);
+            KeyRotationNonceMap::<T>::insert(rotation.app_id, rotation.nonce + 1);
+            // a session key delegated by the old account is no longer accepted
+            SessionKeyMap::<T>::remove(rotation.app_id, &rotation.old_player);
+
+            // emit PlayerKeyRotated event
+            Self::deposit_event(RawEvent::PlayerKeyRotated(
+                rotation.app_id,
+                rotation.old_player,
+                rotation.new_player,
+            ));
 
             Ok(())
         }
@@ -229,6 +540,9 @@ decl_module!  {
                     timeout: new_app_info.timeout,
                     deadline: new_app_info.deadline,
                     status: AppStatus::Finalized,
+                    encoding: new_app_info.encoding,
+                    eth_players: new_app_info.eth_players,
+                    arbiter: new_app_info.arbiter,
                 }
             } 
             AppInfoMap::<T>::mutate(&app_id, |app_info| *app_info = Some(new_app_info));
@@ -283,9 +597,216 @@ decl_module!  {
                 timeout: app_info.timeout,
                 deadline: app_info.deadline,
                 status: AppStatus::Finalized,
+                encoding: app_info.encoding,
+                eth_players: app_info.eth_players,
+                arbiter: app_info.arbiter,
+            };
+            AppInfoMap::<T>::mutate(&app_id, |app_info| *app_info = Some(new_app_info));
+
+            Ok(())
+        }
+
+        /// Commit to a random seed
+        ///
+        /// Every player commits before the commit deadline, which runs from
+        /// the initiation of the app. Once every player committed, the reveal
+        /// period starts. Commitments are not taken in the initiate request:
+        /// any account submits it and players do not sign it, so the submitter
+        /// could put in a commitment to a secret a player never knew and make
+        /// the player forfeit. Each player commits by itself.
+        ///
+        /// Parameters:
+        /// - `app_id`: Id of app
+        /// - `commitment`: Commitment of the player, see `seed_commitment`
+        ///
+        /// # <weight>
+        /// ## Weight
+        /// - Complexity: `O(N)`
+        ///   - N: Number of players
+        ///   - 1 storage read `AppInfoMap`
+        ///   - 1 storage mutation `SeedRoundMap`
+        ///   - 1 storage read `SeedRoundMap`
+        /// # </weight>
+        #[weight = 20_000_000 + T::DbWeight::get().reads_writes(2, 1)]
+        fn commit_seed(
+            origin,
+            app_id: T::Hash,
+            commitment: T::Hash
+        ) -> DispatchResult {
+            let caller = ensure_signed(origin)?;
+            let (index, mut seed_round) = Self::live_seed_round(app_id, &caller)?;
+            seed_round.commit(index, commitment, frame_system::Module::<T>::block_number())?;
+            SeedRoundMap::<T>::insert(app_id, seed_round);
+
+            // emit SeedCommitted event
+            Self::deposit_event(RawEvent::SeedCommitted(app_id, caller));
+
+            Ok(())
+        }
+
+        /// Reveal the secret committed to a random seed
+        ///
+        /// The random seed is decided once every player revealed.
+        ///
+        /// Parameters:
+        /// - `app_id`: Id of app
+        /// - `secret`: Secret of the commitment of the player
+        ///
+        /// # <weight>
+        /// ## Weight
+        /// - Complexity: `O(N)`
+        ///   - N: Number of players
+        ///   - 1 storage read `AppInfoMap`
+        ///   - 1 storage mutation `SeedRoundMap`
+        ///   - 1 storage read `SeedRoundMap`
+        /// # </weight>
+        #[weight = 25_000_000 + T::DbWeight::get().reads_writes(2, 1)]
+        fn reveal_seed(
+            origin,
+            app_id: T::Hash,
+            secret: T::Hash
+        ) -> DispatchResult {
+            let caller = ensure_signed(origin)?;
+            let (index, mut seed_round) = Self::live_seed_round(app_id, &caller)?;
+            let seed = seed_round.reveal::<T::AccountId, T::Hashing>(&app_id, index, &caller, secret)?;
+            SeedRoundMap::<T>::insert(app_id, seed_round);
+
+            // emit SeedRevealed and SeedDecided events
+            Self::deposit_event(RawEvent::SeedRevealed(app_id, caller));
+            if let Some(seed) = seed {
+                Self::deposit_event(RawEvent::SeedDecided(app_id, seed));
+            }
+
+            Ok(())
+        }
+
+        /// Finalize app against players who did not commit or reveal a random
+        /// seed in time
+        ///
+        /// The first player who did not default wins: the app is finalized
+        /// with its player id as state, or 0 if every player defaulted.
+        ///
+        /// Parameters:
+        /// - `app_id`: Id of app
+        ///
+        /// # <weight>
+        /// ## Weight
+        /// - Complexity: `O(N)`
+        ///   - N: Number of players
+        ///   - 1 storage mutation `AppInfoMap`
+        ///   - 1 storage read `AppInfoMap`
+        ///   - 1 storage read `SeedRoundMap`
+        /// # </weight>
+        #[weight = 25_000_000 + T::DbWeight::get().reads_writes(2, 1)]
+        fn finalize_on_seed_timeout(
+            origin,
+            app_id: T::Hash
+        ) -> DispatchResult {
+            ensure_signed(origin)?;
+            let app_info = match AppInfoMap::<T>::get(app_id) {
+                Some(info) => info,
+                None => Err(Error::<T>::AppInfoNotExist)?,
+            };
+            ensure!(
+                app_info.status != AppStatus::Finalized,
+                "app state is finalized"
+            );
+            let seed_round = match SeedRoundMap::<T>::get(app_id) {
+                Some(seed_round) => seed_round,
+                None => Err("app has no random seed")?,
+            };
+            seed_round.check_timeout(frame_system::Module::<T>::block_number())?;
+
+            let defaulted = seed_round.defaulted();
+            let defaulters: Vec<T::AccountId> = app_info.players.iter()
+                .zip(defaulted.iter())
+                .filter(|(_, defaulted)| **defaulted)
+                .map(|(player, _)| player.clone())
+                .collect();
+            let winner = defaulted.iter()
+                .position(|defaulted| !defaulted)
+                .map_or(0, |index| index as u8 + 1);
+            let new_app_info = AppInfoOf::<T> {
+                state: winner,
+                nonce: app_info.nonce,
+                players: app_info.players,
+                seq_num: app_info.seq_num,
+                timeout: app_info.timeout,
+                deadline: app_info.deadline,
+                status: AppStatus::Finalized,
+                encoding: app_info.encoding,
+                eth_players: app_info.eth_players,
+                arbiter: app_info.arbiter,
+            };
+            AppInfoMap::<T>::mutate(&app_id, |app_info| *app_info = Some(new_app_info));
+
+            // emit SeedForfeited event
+            Self::deposit_event(RawEvent::SeedForfeited(app_id, defaulters));
+
+            Ok(())
+        }
+
+        /// Finalize app with the state ruled by its arbiter
+        ///
+        /// The ruling overrides the settle and action timeouts and is recorded
+        /// in `RulingMap`.
+        ///
+        /// Parameters:
+        /// - `app_id`: Id of app
+        /// - `state`: State the app is finalized with
+        ///
+        /// # <weight>
+        /// ## Weight
+        /// - Complexity: `O(1)`
+        ///   - 1 storage mutation `AppInfoMap`
+        ///   - 1 storage read `AppInfoMap`
+        ///   - 1 storage insertion `RulingMap`
+        /// - Based on `finalize_on_action_timeout`
+        /// # </weight>
+        #[weight = 22_000_000 + T::DbWeight::get().reads_writes(1, 2)]
+        fn arbitrate(
+            origin,
+            app_id: T::Hash,
+            state: u8
+        ) -> DispatchResult {
+            let caller = ensure_signed(origin)?;
+            let app_info = match AppInfoMap::<T>::get(app_id) {
+                Some(app) => app,
+                None => Err(Error::<T>::AppInfoNotExist)?,
+            };
+            ensure!(
+                app_info.arbiter.as_ref() == Some(&caller),
+                Error::<T>::NotArbiter
+            );
+            ensure!(
+                app_info.status == AppStatus::Settle || app_info.status == AppStatus::Action,
+                "app not in settle or action mode"
+            );
+
+            let new_app_info = AppInfoOf::<T> {
+                state: state,
+                nonce: app_info.nonce,
+                players: app_info.players,
+                seq_num: app_info.seq_num,
+                timeout: app_info.timeout,
+                deadline: app_info.deadline,
+                status: AppStatus::Finalized,
+                encoding: app_info.encoding,
+                eth_players: app_info.eth_players,
+                arbiter: app_info.arbiter,
             };
             AppInfoMap::<T>::mutate(&app_id, |app_info| *app_info = Some(new_app_info));
 
+            let block_number = frame_system::Module::<T>::block_number();
+            RulingMap::<T>::insert(&app_id, RulingOf::<T> {
+                arbiter: caller.clone(),
+                outcome: state,
+                block_number: block_number,
+            });
+
+            // emit Arbitrated event
+            Self::deposit_event(RawEvent::Arbitrated(app_id, caller, state));
+
             Ok(())
         }
 
@@ -360,11 +881,27 @@ decl_module!  {
 }
 
 decl_event! (
-    pub enum Event<T> where 
-        <T as system::Trait>::Hash
+    pub enum Event<T> where
+        <T as system::Trait>::Hash,
+        <T as system::Trait>::AccountId,
+        <T as system::Trait>::BlockNumber
     {
+        /// SessionKeyDelegated(app_id, player, session_key, expires_at)
+        SessionKeyDelegated(Hash, AccountId, AccountId, BlockNumber),
+        /// PlayerKeyRotated(app_id, old_player, new_player)
+        PlayerKeyRotated(Hash, AccountId, AccountId),
         /// IntendSettle(app_id, seq_num)
         IntendSettle(Hash, u128),
+        /// Arbitrated(app_id, arbiter, state)
+        Arbitrated(Hash, AccountId, u8),
+        /// SeedCommitted(app_id, player)
+        SeedCommitted(Hash, AccountId),
+        /// SeedRevealed(app_id, player)
+        SeedRevealed(Hash, AccountId),
+        /// SeedDecided(app_id, seed)
+        SeedDecided(Hash, Hash),
+        /// SeedForfeited(app_id, defaulters)
+        SeedForfeited(Hash, Vec<AccountId>),
     }
 );
 
@@ -372,6 +909,12 @@ decl_error! {
     pub enum Error for Module<T: Trait> {
         // AppInfo is not exist
         AppInfoNotExist,
+        // App state does not fit in the encoding of app
+        AppStateNotEncodable,
+        // Caller is not the arbiter of app
+        NotArbiter,
+        // Number of signatures does not match the players
+        InvalidSignatureNum,
     }
 }
 
@@ -385,13 +928,47 @@ impl<T: Trait> Module<T> {
         nonce: u128,
         players: Vec<T::AccountId>,
     ) -> T::Hash {
-        let app_account = Self::app_account();
-        let mut encoded = app_account.encode();
-        encoded.extend(nonce.encode());
-        encoded.extend(players[0].encode());
-        encoded.extend(players[1].encode());
-        let app_id = T::Hashing::hash(&encoded);
-        return app_id;
+        app_id::<T::AccountId, T::Hashing>(nonce, &players)
+    }
+
+    /// Get Id of app signed by Ethereum accounts
+    ///
+    /// Returns `None` if the runtime hash is not 32 bytes.
+    ///
+    /// Parameters:
+    /// `nonce`: Nonce of app
+    /// `eth_players`: Ethereum addresses of players
+    pub fn get_eth_app_id(
+        nonce: u128,
+        eth_players: Vec<H160>,
+    ) -> Option<T::Hash> {
+        let app_id = eth_app_id(nonce, &eth_players);
+        T::Hash::decode(&mut app_id.as_bytes()).ok()
+    }
+
+    /// Get Id of app to initiate, from Ethereum addresses of players in Ethereum encoding
+    ///
+    /// Parameter:
+    /// `initiate_request`: App initiate request message
+    fn get_initiate_app_id(
+        initiate_request: &AppInitiateRequestOf<T>,
+    ) -> Result<T::Hash, DispatchError> {
+        if initiate_request.encoding != StateEncoding::Ethereum {
+            ensure!(
+                initiate_request.eth_players.is_empty(),
+                "Ethereum players without Ethereum encoding"
+            );
+            return Ok(Self::get_app_id(initiate_request.nonce, initiate_request.players.clone()));
+        }
+
+        ensure!(
+            initiate_request.eth_players.len() == initiate_request.players.len(),
+            "invalid number of Ethereum players"
+        );
+        let app_id = Self::get_eth_app_id(initiate_request.nonce, initiate_request.eth_players.clone())
+            .ok_or("runtime hash is not 32 bytes")?;
+
+        Ok(app_id)
     }
 
     /// Get app state
@@ -467,25 +1044,142 @@ impl<T: Trait> Module<T> {
         return Some(app_info.seq_num);
     }
 
+    /// Get the undecided random seed round of app and the index of a player
+    ///
+    /// Parameters:
+    /// `app_id`: Id of app
+    /// `player`: AccountId of player
+    fn live_seed_round(
+        app_id: T::Hash,
+        player: &T::AccountId,
+    ) -> Result<(usize, SeedRoundOf<T>), DispatchError> {
+        let app_info = match AppInfoMap::<T>::get(app_id) {
+            Some(info) => info,
+            None => Err(Error::<T>::AppInfoNotExist)?,
+        };
+        ensure!(
+            app_info.status != AppStatus::Finalized,
+            "app state is finalized"
+        );
+        let index = match app_info.players.iter().position(|account| account == player) {
+            Some(index) => index,
+            None => Err("not a player of app")?,
+        };
+        let seed_round = match SeedRoundMap::<T>::get(app_id) {
+            Some(seed_round) => seed_round,
+            None => Err("app has no random seed")?,
+        };
+        seed_round.check_live(frame_system::Module::<T>::block_number())?;
+
+        Ok((index, seed_round))
+    }
+
+    /// Get random seed of app, which must be decided before the first settle
+    ///
+    /// Parameter:
+    /// `app_id`: Id of app
+    fn decided_seed(app_id: T::Hash) -> Result<Option<T::Hash>, DispatchError> {
+        match SeedRoundMap::<T>::get(app_id) {
+            Some(seed_round) => match seed_round.seed {
+                Some(seed) => Ok(Some(seed)),
+                None => Err("random seed is not decided")?,
+            },
+            None => Ok(None),
+        }
+    }
+
+    /// Get random seed of app, None if it is not decided
+    ///
+    /// Parameter:
+    /// `app_id`: Id of app
+    pub fn get_seed(app_id: T::Hash) -> Option<T::Hash> {
+        SeedRoundMap::<T>::get(app_id).and_then(|seed_round| seed_round.seed)
+    }
+
     /// Get single session app account id
     pub fn app_account() -> T::AccountId {
         SINGLE_SESSION_APP_ID.into_account()
     }
 
+    /// Settle an off-chain state and finalize app if it decides the outcome
+    ///
+    /// Parameters:
+    /// `app_state`: Off-chain app state
+    /// `sigs`: Signatures of app state
+    fn update_state(
+        app_state: AppStateOf<T>,
+        sigs: StateSigs<<T as Trait>::Signature>,
+    ) -> DispatchResult {
+        let mut new_app_info: AppInfoOf<T> = Self::intend_settle(app_state.clone(), sigs)?;
+
+        let state = app_state.state;
+        if state == 1 || state == 2 {
+            new_app_info = AppInfoOf::<T> {
+                state: state,
+                nonce: new_app_info.nonce,
+                players: new_app_info.players,
+                seq_num: new_app_info.seq_num,
+                timeout: new_app_info.timeout,
+                deadline: new_app_info.deadline,
+                status: AppStatus::Finalized,
+                encoding: new_app_info.encoding,
+                eth_players: new_app_info.eth_players,
+                arbiter: new_app_info.arbiter,
+            }
+        } else {
+            new_app_info = AppInfoOf::<T> {
+                state: state,
+                nonce: new_app_info.nonce,
+                players: new_app_info.players,
+                seq_num: new_app_info.seq_num,
+                timeout: new_app_info.timeout,
+                deadline: new_app_info.deadline,
+                status: new_app_info.status,
+                encoding: new_app_info.encoding,
+                eth_players: new_app_info.eth_players,
+                arbiter: new_app_info.arbiter,
+            }
+        }
+        let app_id = app_state.app_id;
+        AppInfoMap::<T>::mutate(&app_id, |app_info| *app_info = Some(new_app_info.clone()));
+
+        // Emit IntendSettle event
+        Self::deposit_event(RawEvent::IntendSettle(app_id, new_app_info.seq_num));
+
+        Ok(())
+    }
+
     /// Submit and settle offchain state
     ///
-    /// Parameter:
-    /// `state_proof`: Signed off-chain app state
+    /// Parameters:
+    /// `app_state`: Off-chain app state
+    /// `sigs`: Signatures of app state
     fn intend_settle(
-        state_proof: StateProofOf<T>
+        app_state: AppStateOf<T>,
+        sigs: StateSigs<<T as Trait>::Signature>,
     ) -> Result<AppInfoOf<T>, DispatchError> {
-        let app_state = state_proof.app_state;
         let app_info = match AppInfoMap::<T>::get(app_state.app_id) {
             Some(app) => app,
             None => Err(Error::<T>::AppInfoNotExist)?,
         };
-        let encoded = Self::encode_app_state(app_state.clone());
-        Self::valid_signers(state_proof.sigs, &encoded, app_info.players.clone())?;
+        Self::decided_seed(app_state.app_id)?;
+        let encoded = Self::encode_app_state(app_state.clone(), app_info.encoding)?;
+        match sigs {
+            StateSigs::Players(sigs) => {
+                ensure!(
+                    app_info.encoding != StateEncoding::Ethereum,
+                    "app is signed by Ethereum accounts"
+                );
+                Self::valid_signers(sigs, &encoded, Self::signing_accounts(app_state.app_id, &app_info.players))?;
+            },
+            StateSigs::Ethereum(sigs) => {
+                ensure!(
+                    app_info.encoding == StateEncoding::Ethereum,
+                    "app is not signed by Ethereum accounts"
+                );
+                Self::valid_eth_signers(&sigs, &encoded, &app_info.eth_players)?;
+            },
+        }
         ensure!(
             app_info.status != AppStatus::Finalized,
             "app state is finalized"
@@ -507,7 +1201,10 @@ impl<T: Trait> Module<T> {
             seq_num: app_state.seq_num,
             timeout: app_info.timeout,
             deadline: block_number + app_info.timeout,
-            status: AppStatus::Settle
+            status: AppStatus::Settle,
+            encoding: app_info.encoding,
+            eth_players: app_info.eth_players,
+            arbiter: app_info.arbiter,
         };
 
         Ok(new_app_info)
@@ -539,7 +1236,10 @@ impl<T: Trait> Module<T> {
                 seq_num:  app_info.seq_num + 1,
                 timeout: app_info.timeout,
                 deadline: block_number + app_info.timeout,
-                status: AppStatus::Action
+                status: AppStatus::Action,
+                encoding: app_info.encoding,
+                eth_players: app_info.eth_players,
+                arbiter: app_info.arbiter,
             };
         } else {
             ensure!(
@@ -553,13 +1253,66 @@ impl<T: Trait> Module<T> {
                 seq_num:  app_info.seq_num + 1,
                 timeout: app_info.timeout,
                 deadline: block_number + app_info.timeout,
-                status: AppStatus::Action
+                status: AppStatus::Action,
+                encoding: app_info.encoding,
+                eth_players: app_info.eth_players,
+                arbiter: app_info.arbiter,
             };
         }
 
         Ok(new_app_info)
     }
 
+    /// Get accounts signing off-chain states of players, with the live
+    /// session key of a player in place of its account
+    ///
+    /// Parameters:
+    /// `app_id`: Id of app
+    /// `players`: AccountId of players
+    fn signing_accounts(app_id: T::Hash, players: &[T::AccountId]) -> Vec<T::AccountId> {
+        capps_app_common::signing_accounts(
+            players,
+            frame_system::Module::<T>::block_number(),
+            |player| SessionKeyMap::<T>::get(app_id, player),
+        )
+    }
+
+    /// Check a key rotation of a live app and its signatures, returning
+    /// the index of the rotated player
+    ///
+    /// Parameters:
+    /// `rotation`: Old and new account of a player
+    /// `signature`: Signature of the old account or of the arbiter
+    /// `new_signature`: Signature of the new account
+    /// `players`: AccountId of players
+    /// `arbiter`: AccountId of arbiter
+    fn valid_rotation(
+        rotation: &KeyRotationOf<T>,
+        signature: <T as Trait>::Signature,
+        new_signature: <T as Trait>::Signature,
+        players: &[T::AccountId],
+        arbiter: Option<&T::AccountId>,
+    ) -> Result<usize, DispatchError> {
+        let index = rotation.rotated_index(
+            players,
+            arbiter,
+            KeyRotationNonceMap::<T>::get(rotation.app_id),
+        )?;
+
+        let payload = rotation.signing_payload();
+        ensure!(
+            signature.verify(&payload[..], &rotation.old_player)
+                || arbiter.iter().any(|arbiter| signature.verify(&payload[..], arbiter)),
+            "invalid rotation signature"
+        );
+        ensure!(
+            new_signature.verify(&payload[..], &rotation.new_player),
+            "invalid new account signature"
+        );
+
+        Ok(index)
+    }
+
     /// Verify off-chain state signatures
     ///
     /// Parameters:
@@ -571,6 +1324,10 @@ impl<T: Trait> Module<T> {
         encoded: &[u8],
         signers: Vec<T::AccountId>,
     ) -> DispatchResult {
+        ensure!(
+            signatures.len() == 2 && signers.len() == 2,
+            Error::<T>::InvalidSignatureNum
+        );
         let signature1 = &signatures[0];
         let signature2 = &signatures[1];
         ensure!(
@@ -583,20 +1340,40 @@ impl<T: Trait> Module<T> {
         Ok(())
     }
 
+    /// Verify off-chain state signatures of Ethereum accounts, in any order
+    ///
+    /// Parameters:
+    /// `signatures`: Signatures from Ethereum accounts of players
+    /// `encoded`: Packed app state
+    /// `signers`: Ethereum addresses of players
+    fn valid_eth_signers(
+        signatures: &[EcdsaSignature],
+        encoded: &[u8],
+        signers: &[H160],
+    ) -> DispatchResult {
+        ensure!(signatures.len() == signers.len(), "Check co-sigs failed");
+        let mut recovered = signatures.iter()
+            .map(|signature| ethereum::recover_signer(signature, encoded))
+            .collect::<Option<Vec<H160>>>()
+            .ok_or("Check co-sigs failed")?;
+        let mut expected = signers.to_vec();
+        recovered.sort();
+        expected.sort();
+        ensure!(recovered == expected, "Check co-sigs failed");
+
+        Ok(())
+    }
+
     /// Encode app state
     ///
-    /// Parameter:
+    /// Parameters:
     /// `app_state`: app state
+    /// `encoding`: Encoding of app
     fn encode_app_state(
-        app_state: AppStateOf<T>
-    ) -> Vec<u8> {
-        let mut encoded = app_state.nonce.encode();
-        encoded.extend(app_state.seq_num.encode());
-        encoded.extend(app_state.state.encode());
-        encoded.extend(app_state.timeout.encode());
-        encoded.extend(app_state.app_id.encode());
-
-        return encoded;
+        app_state: AppStateOf<T>,
+        encoding: StateEncoding,
+    ) -> Result<Vec<u8>, DispatchError> {
+        app_state.payload(encoding).ok_or_else(|| Error::<T>::AppStateNotEncodable.into())
     }
 
 }
@@ -1,7 +1,8 @@
 use super::*;
 use mock::*;
-use sp_core::{sr25519, Pair, H256};
+use sp_core::{sr25519, Pair, H160, H256};
 use frame_support::{assert_ok, assert_noop};
+use sp_runtime::traits::{BlakeTwo256, OffchainWorker};
 
 #[test]
 fn test_pass_initiate() {
@@ -15,6 +16,10 @@ fn test_pass_initiate() {
             nonce: 0,
             players: players_peers.clone(),
             timeout: 2,
+            encoding: StateEncoding::Scale,
+            eth_players: vec![],
+            arbiter: None,
+            seed_timeout: None,
         };
         
         assert_ok!(SingleApp::app_initiate(
@@ -36,6 +41,10 @@ fn test_fail_update_by_action() {
             nonce: 0,
             players: players_peers.clone(),
             timeout: 2,
+            encoding: StateEncoding::Scale,
+            eth_players: vec![],
+            arbiter: None,
+            seed_timeout: None,
         };
         
         assert_ok!(SingleApp::app_initiate(
@@ -67,6 +76,10 @@ fn test_pass_update_by_state_state_is_5() {
             nonce: 0,
             players: players_peers.clone(),
             timeout: 2,
+            encoding: StateEncoding::Scale,
+            eth_players: vec![],
+            arbiter: None,
+            seed_timeout: None,
         };
         assert_ok!(SingleApp::app_initiate(
             Origin::signed(players_peers[0]),
@@ -94,6 +107,9 @@ fn test_pass_update_by_state_state_is_5() {
             timeout: 2,
             deadline: 3,
             status: AppStatus::Settle,
+            encoding: StateEncoding::Scale,
+            eth_players: vec![],
+            arbiter: None,
         };
         assert_eq!(expected_app_info, app_info);
 
@@ -114,6 +130,430 @@ fn test_pass_update_by_state_state_is_5() {
     })
 }
 
+#[test]
+fn test_pass_update_by_state_with_protobuf_encoding() {
+    ExtBuilder::build().execute_with(|| {
+        System::set_block_number(1);
+        let alice_pair = account_pair("Alice");
+        let bob_pair = account_pair("Bob");
+        let (players_peers, players_pair)
+            = get_sorted_peer(alice_pair, bob_pair);
+
+        let initiate_request = AppInitiateRequest {
+            nonce: 0,
+            players: players_peers.clone(),
+            timeout: 2,
+            encoding: StateEncoding::Protobuf,
+            eth_players: vec![],
+            arbiter: None,
+            seed_timeout: None,
+        };
+        assert_ok!(SingleApp::app_initiate(
+            Origin::signed(players_peers[0]),
+            initiate_request.clone()
+        ));
+
+        let app_id = SingleApp::get_app_id(initiate_request.nonce, initiate_request.players.clone());
+        // states signed in the SCALE encoding are not accepted
+        let state_proof = get_state_proof(0, 2, 1, 2, app_id, players_pair.clone());
+        assert_noop!(
+            SingleApp::update_by_state(
+                Origin::signed(players_peers[0]),
+                state_proof
+            ),
+            "Check co-sigs failed"
+        );
+
+        let state_proof = get_encoded_state_proof(
+            0, u64::max_value() as u128 + 1, 1, 2, app_id, players_pair.clone(), StateEncoding::Scale
+        );
+        assert_noop!(
+            SingleApp::update_by_state(
+                Origin::signed(players_peers[0]),
+                state_proof
+            ),
+            Error::<TestRuntime>::AppStateNotEncodable
+        );
+
+        let state_proof = get_encoded_state_proof(
+            0, 2, 1, 2, app_id, players_pair, StateEncoding::Protobuf
+        );
+        assert_ok!(
+            SingleApp::update_by_state(
+                Origin::signed(players_peers[0]),
+                state_proof
+            )
+        );
+        assert_ok!(
+            SingleApp::is_finalized(
+                Origin::signed(players_peers[0]),
+                app_id
+            )
+        );
+    })
+}
+
+#[test]
+fn test_pass_update_by_eth_state() {
+    ExtBuilder::build().execute_with(|| {
+        System::set_block_number(1);
+        let alice_pair = account_pair("Alice");
+        let bob_pair = account_pair("Bob");
+        let (players_peers, players_pair)
+            = get_sorted_peer(alice_pair, bob_pair);
+        let eth_pairs = vec![eth_pair(1), eth_pair(2)];
+        let eth_players: Vec<H160> = eth_pairs.iter().map(|pair| pair.address()).collect();
+
+        let initiate_request = AppInitiateRequest {
+            nonce: 0,
+            players: players_peers.clone(),
+            timeout: 2,
+            encoding: StateEncoding::Scale,
+            eth_players: eth_players.clone(),
+            arbiter: None,
+            seed_timeout: None,
+        };
+        assert_noop!(
+            SingleApp::app_initiate(
+                Origin::signed(players_peers[0]),
+                initiate_request
+            ),
+            "Ethereum players without Ethereum encoding"
+        );
+
+        let initiate_request = AppInitiateRequest {
+            nonce: 0,
+            players: players_peers.clone(),
+            timeout: 2,
+            encoding: StateEncoding::Ethereum,
+            eth_players: eth_players.clone(),
+            arbiter: None,
+            seed_timeout: None,
+        };
+        assert_ok!(SingleApp::app_initiate(
+            Origin::signed(players_peers[0]),
+            initiate_request.clone()
+        ));
+
+        let app_id = SingleApp::get_eth_app_id(initiate_request.nonce, eth_players.clone()).unwrap();
+        assert_eq!(app_id, eth_app_id(0, &eth_players));
+        assert!(SingleApp::app_info(app_id).is_some());
+
+        // states signed by substrate accounts are not accepted
+        let state_proof = get_encoded_state_proof(
+            0, 2, 1, 2, app_id, players_pair, StateEncoding::Ethereum
+        );
+        assert_noop!(
+            SingleApp::update_by_state(
+                Origin::signed(players_peers[0]),
+                state_proof
+            ),
+            "app is signed by Ethereum accounts"
+        );
+
+        // states signed by other Ethereum accounts are not accepted
+        let state_proof = get_eth_state_proof(0, 2, 1, 2, app_id, vec![eth_pair(1), eth_pair(3)]);
+        assert_noop!(
+            SingleApp::update_by_eth_state(
+                Origin::signed(players_peers[0]),
+                state_proof
+            ),
+            "Check co-sigs failed"
+        );
+
+        // signatures of Ethereum accounts are accepted in any order
+        let state_proof = get_eth_state_proof(0, 2, 1, 2, app_id, vec![eth_pair(2), eth_pair(1)]);
+        assert_ok!(
+            SingleApp::update_by_eth_state(
+                Origin::signed(players_peers[0]),
+                state_proof
+            )
+        );
+
+        let app_info = SingleApp::app_info(app_id).unwrap();
+        let expected_app_info = AppInfo {
+            state: 1,
+            nonce: 0,
+            players: players_peers.clone(),
+            seq_num: 2,
+            timeout: 2,
+            deadline: 3,
+            status: AppStatus::Finalized,
+            encoding: StateEncoding::Ethereum,
+            eth_players: eth_players,
+            arbiter: None,
+        };
+        assert_eq!(expected_app_info, app_info);
+    })
+}
+
+#[test]
+fn test_pass_delegate_session_key() {
+    ExtBuilder::build().execute_with(|| {
+        System::set_block_number(1);
+        let alice_pair = account_pair("Alice");
+        let bob_pair = account_pair("Bob");
+        let (players_peers, players_pair)
+            = get_sorted_peer(alice_pair, bob_pair);
+
+        let initiate_request = AppInitiateRequest {
+            nonce: 0,
+            players: players_peers.clone(),
+            timeout: 2,
+            encoding: StateEncoding::Scale,
+            eth_players: vec![],
+            arbiter: None,
+            seed_timeout: None,
+        };
+        assert_ok!(SingleApp::app_initiate(
+            Origin::signed(players_peers[0]),
+            initiate_request.clone()
+        ));
+
+        let app_id = SingleApp::get_app_id(initiate_request.nonce, initiate_request.players.clone());
+
+        let session_pair = account_pair("Carl");
+        let mut delegation = SessionKeyDelegation {
+            app_id: app_id,
+            player: players_peers[0],
+            session_key: session_pair.public(),
+            expires_at: 1,
+        };
+        assert_noop!(
+            SingleApp::delegate_session_key(
+                Origin::signed(players_peers[1]),
+                delegation.clone(),
+                players_pair[0].sign(&delegation.signing_payload())
+            ),
+            "session key is expired"
+        );
+
+        // only the player signs its delegation, anyone submits it
+        delegation.expires_at = 10;
+        assert_noop!(
+            SingleApp::delegate_session_key(
+                Origin::signed(players_peers[1]),
+                delegation.clone(),
+                players_pair[1].sign(&delegation.signing_payload())
+            ),
+            "invalid delegation signature"
+        );
+        assert_ok!(
+            SingleApp::delegate_session_key(
+                Origin::signed(players_peers[1]),
+                delegation.clone(),
+                players_pair[0].sign(&delegation.signing_payload())
+            )
+        );
+        let expected_event = TestEvent::single_app(RawEvent::SessionKeyDelegated(app_id, players_peers[0], session_pair.public(), 10));
+        assert!(System::events().iter().any(|a| a.event == expected_event));
+        assert_eq!(SingleApp::session_key(app_id, players_peers[0]), Some((session_pair.public(), 10)));
+
+        // a replayed delegation does not extend the session key
+        assert_noop!(
+            SingleApp::delegate_session_key(
+                Origin::signed(players_peers[1]),
+                delegation.clone(),
+                players_pair[0].sign(&delegation.signing_payload())
+            ),
+            "session key expires earlier than current one"
+        );
+
+        // the session key signs in place of the player
+        let state_proof = get_state_proof(0, 1, 2, 2, app_id, vec![session_pair.clone(), players_pair[1].clone()]);
+        assert_ok!(
+            SingleApp::update_by_state(
+                Origin::signed(players_peers[1]),
+                state_proof
+            )
+        );
+
+        // the expired session key is not accepted
+        System::set_block_number(10);
+        let state_proof = get_state_proof(0, 2, 2, 2, app_id, vec![session_pair, players_pair[1].clone()]);
+        assert_noop!(
+            SingleApp::update_by_state(
+                Origin::signed(players_peers[1]),
+                state_proof
+            ),
+            "Check co-sigs failed"
+        );
+    })
+}
+
+#[test]
+fn test_pass_rotate_player_key() {
+    ExtBuilder::build().execute_with(|| {
+        System::set_block_number(1);
+        let alice_pair = account_pair("Alice");
+        let bob_pair = account_pair("Bob");
+        let (players_peers, players_pair)
+            = get_sorted_peer(alice_pair, bob_pair);
+        let arbiter_pair = account_pair("Frank");
+
+        let initiate_request = AppInitiateRequest {
+            nonce: 0,
+            players: players_peers.clone(),
+            timeout: 2,
+            encoding: StateEncoding::Scale,
+            eth_players: vec![],
+            arbiter: Some(arbiter_pair.public()),
+            seed_timeout: None,
+        };
+        assert_ok!(SingleApp::app_initiate(
+            Origin::signed(players_peers[0]),
+            initiate_request.clone()
+        ));
+
+        let app_id = SingleApp::get_app_id(initiate_request.nonce, initiate_request.players.clone());
+        let session_pair = account_pair("Dave");
+        let delegation = SessionKeyDelegation {
+            app_id: app_id,
+            player: players_peers[0],
+            session_key: session_pair.public(),
+            expires_at: 10,
+        };
+        assert_ok!(
+            SingleApp::delegate_session_key(
+                Origin::signed(players_peers[0]),
+                delegation.clone(),
+                players_pair[0].sign(&delegation.signing_payload())
+            )
+        );
+
+        let new_pair = account_pair("Carl");
+        let mut rotation = KeyRotation {
+            app_id: app_id,
+            old_player: players_peers[0],
+            new_player: new_pair.public(),
+            nonce: 1,
+        };
+        assert_noop!(
+            SingleApp::rotate_player_key(
+                Origin::signed(players_peers[1]),
+                rotation.clone(),
+                players_pair[0].sign(&rotation.signing_payload()),
+                new_pair.sign(&rotation.signing_payload())
+            ),
+            "invalid rotation nonce"
+        );
+        rotation.nonce = 0;
+        assert_noop!(
+            SingleApp::rotate_player_key(
+                Origin::signed(players_peers[1]),
+                rotation.clone(),
+                new_pair.sign(&rotation.signing_payload()),
+                new_pair.sign(&rotation.signing_payload())
+            ),
+            "invalid rotation signature"
+        );
+        // the other player cannot take over the seat of a player
+        assert_noop!(
+            SingleApp::rotate_player_key(
+                Origin::signed(players_peers[1]),
+                rotation.clone(),
+                players_pair[1].sign(&rotation.signing_payload()),
+                new_pair.sign(&rotation.signing_payload())
+            ),
+            "invalid rotation signature"
+        );
+        assert_noop!(
+            SingleApp::rotate_player_key(
+                Origin::signed(players_peers[1]),
+                rotation.clone(),
+                players_pair[0].sign(&rotation.signing_payload()),
+                players_pair[0].sign(&rotation.signing_payload())
+            ),
+            "invalid new account signature"
+        );
+
+        // the old account authorizes the rotation, the player keeps its slot
+        assert_ok!(
+            SingleApp::rotate_player_key(
+                Origin::signed(players_peers[1]),
+                rotation.clone(),
+                players_pair[0].sign(&rotation.signing_payload()),
+                new_pair.sign(&rotation.signing_payload())
+            )
+        );
+        assert_eq!(SingleApp::app_info(app_id).unwrap().players, vec![new_pair.public(), players_peers[1]]);
+        assert_eq!(SingleApp::key_rotation_nonce(app_id), 1);
+        assert_eq!(SingleApp::session_key(app_id, players_peers[0]), None);
+        let expected_event = TestEvent::single_app(RawEvent::PlayerKeyRotated(app_id, players_peers[0], new_pair.public()));
+        assert!(System::events().iter().any(|a| a.event == expected_event));
+
+        // a replayed rotation is not accepted
+        assert_noop!(
+            SingleApp::rotate_player_key(
+                Origin::signed(players_peers[1]),
+                rotation.clone(),
+                players_pair[0].sign(&rotation.signing_payload()),
+                new_pair.sign(&rotation.signing_payload())
+            ),
+            "not a player of app"
+        );
+
+        // the new account signs in place of the old one
+        let state_proof = get_state_proof(0, 1, 5, 2, app_id, vec![players_pair[0].clone(), players_pair[1].clone()]);
+        assert_noop!(
+            SingleApp::update_by_state(
+                Origin::signed(players_peers[1]),
+                state_proof
+            ),
+            "Check co-sigs failed"
+        );
+        let state_proof = get_state_proof(0, 1, 5, 2, app_id, vec![new_pair.clone(), players_pair[1].clone()]);
+        assert_ok!(
+            SingleApp::update_by_state(
+                Origin::signed(players_peers[1]),
+                state_proof
+            )
+        );
+
+        // the arbiter authorizes the rotation of a lost account
+        let rotation = KeyRotation {
+            app_id: app_id,
+            old_player: new_pair.public(),
+            new_player: players_peers[0],
+            nonce: 1,
+        };
+        assert_ok!(
+            SingleApp::rotate_player_key(
+                Origin::signed(players_peers[1]),
+                rotation.clone(),
+                arbiter_pair.sign(&rotation.signing_payload()),
+                players_pair[0].sign(&rotation.signing_payload())
+            )
+        );
+        assert_eq!(SingleApp::app_info(app_id).unwrap().players, players_peers);
+
+        // no rotation once the app is finalized
+        let state_proof = get_state_proof(0, 2, 1, 2, app_id, players_pair.clone());
+        assert_ok!(
+            SingleApp::update_by_state(
+                Origin::signed(players_peers[1]),
+                state_proof
+            )
+        );
+        assert_eq!(SingleApp::get_status(app_id), Some(AppStatus::Finalized));
+        let rotation = KeyRotation {
+            app_id: app_id,
+            old_player: players_peers[0],
+            new_player: new_pair.public(),
+            nonce: 2,
+        };
+        assert_noop!(
+            SingleApp::rotate_player_key(
+                Origin::signed(players_peers[1]),
+                rotation.clone(),
+                players_pair[0].sign(&rotation.signing_payload()),
+                new_pair.sign(&rotation.signing_payload())
+            ),
+            "app state is finalized"
+        );
+    })
+}
+
 #[test]
 fn test_fail_update_by_action_before_settle_finalized_time_should_fail() {
     ExtBuilder::build().execute_with(|| {
@@ -127,6 +567,10 @@ fn test_fail_update_by_action_before_settle_finalized_time_should_fail() {
             nonce: 0,
             players: players_peers.clone(),
             timeout: 2,
+            encoding: StateEncoding::Scale,
+            eth_players: vec![],
+            arbiter: None,
+            seed_timeout: None,
         };
         assert_ok!(SingleApp::app_initiate(
             Origin::signed(players_peers[0]),
@@ -166,6 +610,10 @@ fn test_pass_update_by_action_after_settle_finalized_time() {
             nonce: 0,
             players: players_peers.clone(),
             timeout: 2,
+            encoding: StateEncoding::Scale,
+            eth_players: vec![],
+            arbiter: None,
+            seed_timeout: None,
         };
         assert_ok!(SingleApp::app_initiate(
             Origin::signed(players_peers[0]),
@@ -213,6 +661,10 @@ fn test_fail_update_by_state_with_invlaid_seq_num() {
             nonce: 0,
             players: players_peers.clone(),
             timeout: 2,
+            encoding: StateEncoding::Scale,
+            eth_players: vec![],
+            arbiter: None,
+            seed_timeout: None,
         };
         assert_ok!(SingleApp::app_initiate(
             Origin::signed(players_peers[0]),
@@ -231,6 +683,50 @@ fn test_fail_update_by_state_with_invlaid_seq_num() {
     })
 }
 
+#[test]
+fn test_fail_update_by_state_with_missing_signatures() {
+    ExtBuilder::build().execute_with(|| {
+        let alice_pair = account_pair("Alice");
+        let bob_pair = account_pair("Bob");
+        let (players, players_pair)
+            = get_sorted_peer(alice_pair, bob_pair);
+
+        let initiate_request = AppInitiateRequest {
+            nonce: 0,
+            players: players.clone(),
+            timeout: 2,
+            encoding: StateEncoding::Scale,
+            eth_players: vec![],
+            arbiter: None,
+            seed_timeout: None,
+        };
+        assert_ok!(SingleApp::app_initiate(
+            Origin::signed(players[0]),
+            initiate_request.clone()
+        ));
+
+        let app_id = SingleApp::get_app_id(initiate_request.nonce, initiate_request.players.clone());
+        let mut state_proof = get_state_proof(0, 1, 0, 2, app_id, players_pair);
+        state_proof.sigs.pop();
+        assert_noop!(
+            SingleApp::update_by_state(
+                Origin::signed(players[0]),
+                state_proof.clone()
+            ),
+            Error::<TestRuntime>::InvalidSignatureNum
+        );
+
+        state_proof.sigs.clear();
+        assert_noop!(
+            SingleApp::update_by_state(
+                Origin::signed(players[0]),
+                state_proof
+            ),
+            Error::<TestRuntime>::InvalidSignatureNum
+        );
+    })
+}
+
 #[test]
 fn test_pass_update_by_state_state_is_2() {
     ExtBuilder::build().execute_with(|| {
@@ -244,6 +740,10 @@ fn test_pass_update_by_state_state_is_2() {
             nonce: 0,
             players: players_peers.clone(),
             timeout: 2,
+            encoding: StateEncoding::Scale,
+            eth_players: vec![],
+            arbiter: None,
+            seed_timeout: None,
         };
         assert_ok!(SingleApp::app_initiate(
             Origin::signed(players_peers[0]),
@@ -289,6 +789,10 @@ fn test_fail_update_by_action_after_finalized() {
             nonce: 0,
             players: players_peers.clone(),
             timeout: 2,
+            encoding: StateEncoding::Scale,
+            eth_players: vec![],
+            arbiter: None,
+            seed_timeout: None,
         };
         assert_ok!(SingleApp::app_initiate(
             Origin::signed(players_peers[0]),
@@ -327,6 +831,10 @@ fn test_fail_update_by_state_after_finalized() {
             nonce: 0,
             players: players_peers.clone(),
             timeout: 2,
+            encoding: StateEncoding::Scale,
+            eth_players: vec![],
+            arbiter: None,
+            seed_timeout: None,
         };
         assert_ok!(SingleApp::app_initiate(
             Origin::signed(players_peers[0]),
@@ -364,7 +872,11 @@ fn test_pass_finalize_on_action_timeout() {
         let initiate_request = AppInitiateRequest {
             nonce: 0,
             players: players.clone(),
-            timeout: 2
+            timeout: 2,
+            encoding: StateEncoding::Scale,
+            eth_players: vec![],
+            arbiter: None,
+            seed_timeout: None,
         };
         assert_ok!(
             SingleApp::app_initiate(
@@ -392,6 +904,309 @@ fn test_pass_finalize_on_action_timeout() {
     })
 }
 
+#[test]
+fn test_pass_arbitrate() {
+    ExtBuilder::build().execute_with(|| {
+        System::set_block_number(1);
+        let alice_pair = account_pair("Alice");
+        let bob_pair = account_pair("Bob");
+        let (players, players_pair) = get_sorted_peer(alice_pair, bob_pair);
+        let arbiter = account_pair("Carl").public();
+
+        let mut initiate_request = AppInitiateRequest {
+            nonce: 0,
+            players: players.clone(),
+            timeout: 2,
+            encoding: StateEncoding::Scale,
+            eth_players: vec![],
+            arbiter: Some(players[0]),
+            seed_timeout: None,
+        };
+        assert_noop!(
+            SingleApp::app_initiate(
+                Origin::signed(players[0]),
+                initiate_request.clone()
+            ),
+            "arbiter is a player"
+        );
+        initiate_request.arbiter = Some(arbiter);
+        assert_ok!(
+            SingleApp::app_initiate(
+                Origin::signed(players[0]),
+                initiate_request.clone()
+            )
+        );
+
+        let app_id = SingleApp::get_app_id(initiate_request.nonce, initiate_request.players.clone());
+        assert_noop!(
+            SingleApp::arbitrate(
+                Origin::signed(arbiter),
+                app_id,
+                1
+            ),
+            "app not in settle or action mode"
+        );
+
+        let state_proof = get_state_proof(0, 1, 5, 2, app_id, players_pair.clone());
+        assert_ok!(
+            SingleApp::update_by_state(
+                Origin::signed(players[0]),
+                state_proof
+            )
+        );
+        assert_noop!(
+            SingleApp::arbitrate(
+                Origin::signed(players[0]),
+                app_id,
+                1
+            ),
+            Error::<TestRuntime>::NotArbiter
+        );
+
+        // the ruling finalizes the app before the settle period ends
+        assert_ok!(
+            SingleApp::arbitrate(
+                Origin::signed(arbiter),
+                app_id,
+                2
+            )
+        );
+        assert_eq!(SingleApp::get_state(app_id), Some(2));
+        assert_eq!(SingleApp::get_status(app_id), Some(AppStatus::Finalized));
+        let expected_ruling = Ruling {
+            arbiter: arbiter,
+            outcome: 2,
+            block_number: 1,
+        };
+        assert_eq!(SingleApp::ruling(app_id), Some(expected_ruling));
+        let expected_event = TestEvent::single_app(RawEvent::Arbitrated(app_id, arbiter, 2));
+        assert!(System::events().iter().any(|a| a.event == expected_event));
+
+        assert_noop!(
+            SingleApp::arbitrate(
+                Origin::signed(arbiter),
+                app_id,
+                1
+            ),
+            "app not in settle or action mode"
+        );
+    })
+}
+
+#[test]
+fn test_pass_commit_reveal_seed() {
+    ExtBuilder::build().execute_with(|| {
+        System::set_block_number(1);
+        let alice_pair = account_pair("Alice");
+        let bob_pair = account_pair("Bob");
+        let (players, players_pair) = get_sorted_peer(alice_pair, bob_pair);
+
+        let mut initiate_request = AppInitiateRequest {
+            nonce: 0,
+            players: players.clone(),
+            timeout: 2,
+            encoding: StateEncoding::Scale,
+            eth_players: vec![],
+            arbiter: None,
+            seed_timeout: Some(0),
+        };
+        assert_noop!(
+            SingleApp::app_initiate(
+                Origin::signed(players[0]),
+                initiate_request.clone()
+            ),
+            "invalid seed timeout"
+        );
+        initiate_request.seed_timeout = Some(3);
+        assert_ok!(
+            SingleApp::app_initiate(
+                Origin::signed(players[0]),
+                initiate_request.clone()
+            )
+        );
+
+        let app_id = SingleApp::get_app_id(initiate_request.nonce, initiate_request.players.clone());
+        let secrets = vec![H256::from_low_u64_be(1), H256::from_low_u64_be(2)];
+        let commitments: Vec<H256> = players.iter().zip(secrets.iter())
+            .map(|(player, secret)| seed_commitment::<_, BlakeTwo256>(&app_id, player, secret))
+            .collect();
+
+        // states are not settled before the random seed is decided
+        let state_proof = get_state_proof(0, 1, 5, 2, app_id, players_pair.clone());
+        assert_noop!(
+            SingleApp::update_by_state(
+                Origin::signed(players[0]),
+                state_proof.clone()
+            ),
+            "random seed is not decided"
+        );
+
+        assert_ok!(
+            SingleApp::commit_seed(
+                Origin::signed(players[0]),
+                app_id,
+                commitments[0]
+            )
+        );
+        assert_noop!(
+            SingleApp::reveal_seed(
+                Origin::signed(players[0]),
+                app_id,
+                secrets[0]
+            ),
+            "seeds are not all committed"
+        );
+        assert_ok!(
+            SingleApp::commit_seed(
+                Origin::signed(players[1]),
+                app_id,
+                commitments[1]
+            )
+        );
+        assert_noop!(
+            SingleApp::reveal_seed(
+                Origin::signed(players[0]),
+                app_id,
+                secrets[1]
+            ),
+            "secret not match commitment"
+        );
+        assert_ok!(
+            SingleApp::reveal_seed(
+                Origin::signed(players[0]),
+                app_id,
+                secrets[0]
+            )
+        );
+        assert_ok!(
+            SingleApp::reveal_seed(
+                Origin::signed(players[1]),
+                app_id,
+                secrets[1]
+            )
+        );
+
+        let seed = combined_seed::<BlakeTwo256>(&secrets);
+        assert_eq!(SingleApp::get_seed(app_id), Some(seed));
+        let expected_event = TestEvent::single_app(RawEvent::SeedDecided(app_id, seed));
+        assert!(System::events().iter().any(|a| a.event == expected_event));
+
+        assert_ok!(
+            SingleApp::update_by_state(
+                Origin::signed(players[0]),
+                state_proof
+            )
+        );
+        assert_eq!(SingleApp::get_state(app_id), Some(5));
+    })
+}
+
+#[test]
+fn test_pass_watchtower_answers_stale_settle() {
+    let (mut ext, pool_state) = ExtBuilder::build_with_watchtower();
+    ext.execute_with(|| {
+        System::set_block_number(1);
+        let alice_pair = account_pair("Alice");
+        let bob_pair = account_pair("Bob");
+        let (players_peers, players_pair)
+            = get_sorted_peer(alice_pair, bob_pair);
+
+        let initiate_request = AppInitiateRequest {
+            nonce: 0,
+            players: players_peers.clone(),
+            timeout: 2,
+            encoding: StateEncoding::Scale,
+            eth_players: vec![],
+            arbiter: None,
+            seed_timeout: None,
+        };
+        assert_ok!(SingleApp::app_initiate(
+            Origin::signed(players_peers[0]),
+            initiate_request.clone()
+        ));
+
+        let app_id = SingleApp::get_app_id(initiate_request.nonce, initiate_request.players.clone());
+        let stale_proof = get_state_proof(0, 1, 5, 2, app_id, players_pair.clone());
+        let latest_proof = get_state_proof(0, 3, 5, 2, app_id, players_pair);
+        SingleApp::watch_state_proof(latest_proof.clone());
+
+        // nothing to answer before settlement
+        SingleApp::offchain_worker(1);
+        assert!(pool_state.read().transactions.is_empty());
+
+        assert_ok!(
+            SingleApp::update_by_state(
+                Origin::signed(players_peers[0]),
+                stale_proof
+            )
+        );
+        SingleApp::offchain_worker(1);
+        let tx = pool_state.write().transactions.pop().unwrap();
+        assert!(pool_state.read().transactions.is_empty());
+        let tx = Extrinsic::decode(&mut &*tx).unwrap();
+        assert_eq!(tx.signature.unwrap().0, 0);
+        assert_eq!(tx.call, mock::Call::SingleApp(crate::Call::update_by_state(latest_proof.clone())));
+
+        // the answer is not repeated until the chain state changes
+        SingleApp::offchain_worker(1);
+        assert!(pool_state.read().transactions.is_empty());
+
+        // latest state is settled, nothing left to answer
+        assert_ok!(
+            SingleApp::update_by_state(
+                Origin::signed(players_peers[1]),
+                latest_proof
+            )
+        );
+        SingleApp::offchain_worker(2);
+        assert!(pool_state.read().transactions.is_empty());
+    })
+}
+
+#[test]
+fn test_pass_watchtower_answers_stale_eth_settle() {
+    let (mut ext, pool_state) = ExtBuilder::build_with_watchtower();
+    ext.execute_with(|| {
+        System::set_block_number(1);
+        let alice_pair = account_pair("Alice");
+        let bob_pair = account_pair("Bob");
+        let (players_peers, _)
+            = get_sorted_peer(alice_pair, bob_pair);
+        let eth_pairs = vec![eth_pair(1), eth_pair(2)];
+        let eth_players: Vec<H160> = eth_pairs.iter().map(|pair| pair.address()).collect();
+
+        let initiate_request = AppInitiateRequest {
+            nonce: 0,
+            players: players_peers.clone(),
+            timeout: 2,
+            encoding: StateEncoding::Ethereum,
+            eth_players: eth_players.clone(),
+            arbiter: None,
+            seed_timeout: None,
+        };
+        assert_ok!(SingleApp::app_initiate(
+            Origin::signed(players_peers[0]),
+            initiate_request.clone()
+        ));
+
+        let app_id = SingleApp::get_eth_app_id(initiate_request.nonce, eth_players).unwrap();
+        let stale_proof = get_eth_state_proof(0, 1, 5, 2, app_id, eth_pairs.clone());
+        let latest_proof = get_eth_state_proof(0, 3, 5, 2, app_id, eth_pairs);
+        SingleApp::watch_eth_state_proof(latest_proof.clone());
+
+        assert_ok!(
+            SingleApp::update_by_eth_state(
+                Origin::signed(players_peers[0]),
+                stale_proof
+            )
+        );
+        SingleApp::offchain_worker(1);
+        let tx = pool_state.write().transactions.pop().unwrap();
+        assert!(pool_state.read().transactions.is_empty());
+        let tx = Extrinsic::decode(&mut &*tx).unwrap();
+        assert_eq!(tx.call, mock::Call::SingleApp(crate::Call::update_by_eth_state(latest_proof)));
+    })
+}
 
 fn get_state_proof(
     nonce: u128, 
@@ -400,6 +1215,18 @@ fn get_state_proof(
     timeout: BlockNumber,
     app_id: H256,
     players_pair: Vec<sr25519::Pair>
+) -> StateProof<BlockNumber, H256, Signature> {
+    get_encoded_state_proof(nonce, seq, state, timeout, app_id, players_pair, StateEncoding::Scale)
+}
+
+fn get_encoded_state_proof(
+    nonce: u128,
+    seq: u128,
+    state: u8,
+    timeout: BlockNumber,
+    app_id: H256,
+    players_pair: Vec<sr25519::Pair>,
+    encoding: StateEncoding,
 ) -> StateProof<BlockNumber, H256, Signature> {
     let app_state = AppState {
         nonce: nonce,
@@ -408,7 +1235,7 @@ fn get_state_proof(
         timeout: timeout,
         app_id: app_id,
     };
-    let encoded = SingleApp::encode_app_state(app_state.clone());
+    let encoded = app_state.payload(encoding).unwrap();
     let sig_1 = players_pair[0].sign(&encoded);
     let sig_2 = players_pair[1].sign(&encoded);
     let state_proof = StateProof {
@@ -419,3 +1246,31 @@ fn get_state_proof(
     return state_proof;
 }
 
+
+fn get_eth_state_proof(
+    nonce: u128,
+    seq: u128,
+    state: u8,
+    timeout: BlockNumber,
+    app_id: H256,
+    eth_pairs: Vec<ethereum::Pair>,
+) -> EthStateProof<BlockNumber, H256> {
+    let app_state = AppState {
+        nonce: nonce,
+        seq_num: seq,
+        state: state,
+        timeout: timeout,
+        app_id: app_id,
+    };
+    let encoded = app_state.ethereum_payload().unwrap();
+    let sigs = eth_pairs.iter().map(|pair| pair.sign(&encoded)).collect();
+
+    StateProof {
+        app_state: app_state,
+        sigs: sigs,
+    }
+}
+
+fn eth_pair(seed: u8) -> ethereum::Pair {
+    ethereum::Pair::from_secret(&[seed; 32]).unwrap()
+}
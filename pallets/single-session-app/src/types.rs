@@ -0,0 +1,86 @@
+//! Encoded types of single session app for SCALE clients
+
+use super::*;
+
+capps_type_defs::type_defs! {
+    enum StateEncoding {
+        Scale,
+        Protobuf,
+        Ethereum,
+    }
+    struct AppInitiateRequest<AccountId, BlockNumber> {
+        nonce: u128,
+        players: Vec<AccountId>,
+        timeout: BlockNumber,
+        encoding: StateEncoding,
+        eth_players: Vec<H160>,
+        arbiter: Option<AccountId>,
+        seed_timeout: Option<BlockNumber>,
+    }
+    type AppInitiateRequestOf = AppInitiateRequest;
+    struct AppState<BlockNumber, Hash> {
+        nonce: u128,
+        seq_num: u128,
+        state: u8,
+        timeout: BlockNumber,
+        app_id: Hash,
+    }
+    type AppStateOf = AppState;
+    struct StateProof<BlockNumber, Hash, Signature> {
+        app_state: AppState<BlockNumber, Hash>,
+        sigs: Vec<Signature>,
+    }
+    type StateProofOf = StateProof;
+    struct SessionKeyDelegation<AccountId, BlockNumber, Hash> {
+        app_id: Hash,
+        player: AccountId,
+        session_key: AccountId,
+        expires_at: BlockNumber,
+    }
+    type SessionKeyDelegationOf = SessionKeyDelegation;
+    struct KeyRotation<AccountId, Hash> {
+        app_id: Hash,
+        old_player: AccountId,
+        new_player: AccountId,
+        nonce: u32,
+    }
+    type KeyRotationOf = KeyRotation;
+    struct EthStateProof<BlockNumber, Hash> {
+        app_state: AppState<BlockNumber, Hash>,
+        sigs: Vec<EcdsaSignature>,
+    }
+    type EthStateProofOf = EthStateProof;
+    enum AppStatus {
+        Idle,
+        Settle,
+        Action,
+        Finalized,
+    }
+    struct AppInfo<AccountId, BlockNumber> {
+        state: u8,
+        nonce: u128,
+        players: Vec<AccountId>,
+        seq_num: u128,
+        timeout: BlockNumber,
+        deadline: BlockNumber,
+        status: AppStatus,
+        encoding: StateEncoding,
+        eth_players: Vec<H160>,
+        arbiter: Option<AccountId>,
+    }
+    type AppInfoOf = AppInfo;
+    struct Ruling<AccountId, BlockNumber> {
+        arbiter: AccountId,
+        outcome: u8,
+        block_number: BlockNumber,
+    }
+    type RulingOf = Ruling;
+    struct SeedRound<BlockNumber, Hash> {
+        commits: Vec<Option<Hash>>,
+        secrets: Vec<Option<Hash>>,
+        timeout: BlockNumber,
+        deadline: BlockNumber,
+        seed: Option<Hash>,
+    }
+    type SeedRoundOf = SeedRound;
+}
@@ -0,0 +1,63 @@
+//! Local simulator of single gomoku games against the pallet code
+//!
+//! Runs the pallet in-process on a test runtime, without a node, and reads
+//! commands from a script file or stdin, one per line. After each command
+//! it shows the block number, status, deadlines, board and events of the
+//! app, so disputes can be reproduced step by step. Run `help` for the
+//! commands; a failed command is reported and the session goes on.
+
+mod runtime;
+mod simulator;
+
+#[cfg(test)]
+mod tests;
+
+use std::fs::File;
+use std::io::{self, BufRead, BufReader};
+use std::path::PathBuf;
+
+use simulator::{Simulator, Step};
+use structopt::StructOpt;
+
+#[derive(Debug, StructOpt)]
+#[structopt(name = "capps-sim", about = "Simulate single gomoku games against the pallet code")]
+struct Opt {
+    /// Script file of commands, commands are read from stdin if omitted
+    script: Option<PathBuf>,
+}
+
+fn main() {
+    let opt = Opt::from_args();
+    let result = match opt.script {
+        Some(path) => File::open(&path)
+            .map_err(|e| format!("failed to open {}: {}", path.display(), e))
+            .and_then(|file| run(BufReader::new(file), true)),
+        None => run(io::stdin().lock(), false),
+    };
+    if let Err(e) = result {
+        eprintln!("Error: {}", e);
+        std::process::exit(1);
+    }
+}
+
+/// Run every command of the input until it ends or `quit`
+///
+/// Parameters:
+/// `input`: Command lines
+/// `echo`: Whether to print each command before its output, as for scripts
+fn run<R: BufRead>(input: R, echo: bool) -> Result<(), String> {
+    let mut simulator = Simulator::new();
+    for line in input.lines() {
+        let line = line.map_err(|e| format!("failed to read command: {}", e))?;
+        if echo && !line.trim().is_empty() {
+            println!("> {}", line.trim());
+        }
+        match simulator.execute(&line) {
+            Ok(Step::Output(output)) => print!("{}", output),
+            Ok(Step::Quit) => break,
+            Err(e) => println!("error: {}", e),
+        }
+    }
+
+    Ok(())
+}
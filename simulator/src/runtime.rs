@@ -0,0 +1,86 @@
+//! In-process runtime of the simulator, the single gomoku test runtime without balances
+
+use frame_support::{impl_outer_dispatch, impl_outer_event, impl_outer_origin, parameter_types, weights::Weight};
+use sp_core::{sr25519, H256};
+use sp_runtime::testing::Header;
+use sp_runtime::traits::{BlakeTwo256, IdentityLookup};
+use sp_runtime::Perbill;
+
+#[derive(Clone, Eq, PartialEq)]
+pub struct Runtime;
+
+pub type AccountId = sr25519::Public;
+pub type BlockNumber = u64;
+pub type Signature = sr25519::Signature;
+
+impl_outer_event! {
+    pub enum Event for Runtime {
+        system<T>,
+        single_gomoku<T>,
+    }
+}
+
+impl_outer_dispatch! {
+    pub enum Call for Runtime where origin: Origin {
+        frame_system::System,
+        single_gomoku::SingleGomoku,
+    }
+}
+
+impl_outer_origin! {
+    pub enum Origin for Runtime where system = frame_system {}
+}
+
+parameter_types! {
+    pub const BlockHashCount: u64 = 250;
+    pub const MaximumBlockWeight: Weight = 1024;
+    pub const MaximumBlockLength: u32 = 2 * 1024;
+    pub const AvailableBlockRatio: Perbill = Perbill::from_percent(75);
+}
+
+impl frame_system::Trait for Runtime {
+    type Origin = Origin;
+    type Call = ();
+    type Index = u64;
+    type BlockNumber = BlockNumber;
+    type Hash = H256;
+    type Hashing = BlakeTwo256;
+    type AccountId = AccountId;
+    type Lookup = IdentityLookup<Self::AccountId>;
+    type Header = Header;
+    type Event = Event;
+    type BlockHashCount = BlockHashCount;
+    type MaximumBlockWeight = MaximumBlockWeight;
+    type DbWeight = ();
+    type BlockExecutionWeight = ();
+    type ExtrinsicBaseWeight = ();
+    type MaximumExtrinsicWeight = MaximumBlockWeight;
+    type MaximumBlockLength = MaximumBlockLength;
+    type AvailableBlockRatio = AvailableBlockRatio;
+    type Version = ();
+    type ModuleToIndex = ();
+    type AccountData = ();
+    type OnNewAccount = ();
+    type OnKilledAccount = ();
+}
+
+impl single_gomoku::Trait for Runtime {
+    type Event = Event;
+    type Public = sr25519::Public;
+    type Signature = Signature;
+    type Watchtower = ();
+}
+
+pub type System = frame_system::Module<Runtime>;
+pub type SingleGomoku = single_gomoku::Module<Runtime>;
+
+/// Storage of a fresh chain at block 1, the first block events are recorded in
+pub fn new_externalities() -> sp_io::TestExternalities {
+    let storage = frame_system::GenesisConfig::default()
+        .build_storage::<Runtime>()
+        .expect("default genesis config is valid: qed");
+    let mut ext = sp_io::TestExternalities::new(storage);
+    ext.execute_with(|| System::set_block_number(1));
+
+    ext
+}
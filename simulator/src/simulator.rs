@@ -0,0 +1,298 @@
+//! Commands of the simulator, each applied to the in-process runtime
+
+use std::collections::BTreeMap;
+
+use capps_client::single_gomoku::{self, AppInitiateRequestBuilder, GameRecord, StateProof};
+use gomoku_engine::{parse_coordinate, render_board_state};
+use sp_core::{sr25519, Pair, H256};
+use sp_runtime::DispatchError;
+
+use crate::runtime::{new_externalities, AccountId, BlockNumber, Origin, Signature, SingleGomoku, System};
+
+pub type StateProofOf = StateProof<BlockNumber, H256, Signature>;
+
+/// Usage shown by the `help` command
+pub const USAGE: &str = "\
+commands:
+  players <uri> <uri>                  set the players, //Alice and //Bob by default
+  initiate [nonce] [timeout] [min_stone_offchain] [max_stone_onchain]
+                                       initiate an app, 0 2 0 225 by default
+  cosign <seq_num> [moves | 0x<board_state>]
+                                       co-sign a board state, given as moves from the
+                                       empty board with player 1 black, or in hex
+  settle [seq_num] [player]            submit a co-signed state by update_by_state,
+                                       the latest one by player 1 by default
+  place <player> <move>                place a stone by update_by_action, as in `place 2 h8`
+  wait <blocks>                        advance the block number
+  finalize [player]                    finalize_on_action_timeout, by player 1 by default
+  show                                 show the app without changing it
+  help                                 show this message
+";
+
+/// Outcome of a command
+#[derive(Debug, Eq, PartialEq)]
+pub enum Step {
+    /// Text to show after the command
+    Output(String),
+    /// End of the session
+    Quit,
+}
+
+/// Players, app and co-signed states of a simulated game
+pub struct Simulator {
+    ext: sp_io::TestExternalities,
+    game: Game,
+}
+
+struct Game {
+    pairs: Vec<sr25519::Pair>,
+    nonce: u128,
+    timeout: BlockNumber,
+    app_id: Option<H256>,
+    proofs: BTreeMap<u128, StateProofOf>,
+}
+
+impl Simulator {
+    /// Start at block 1 with //Alice and //Bob as players
+    pub fn new() -> Simulator {
+        let mut game = Game {
+            pairs: vec![],
+            nonce: 0,
+            timeout: 0,
+            app_id: None,
+            proofs: BTreeMap::new(),
+        };
+        game.set_players("//Alice", "//Bob").expect("dev accounts are valid: qed");
+
+        Simulator {
+            ext: new_externalities(),
+            game: game,
+        }
+    }
+
+    /// Run a command line
+    ///
+    /// Blank lines and lines starting with `#` are ignored.
+    ///
+    /// Parameter:
+    /// `line`: Command line
+    pub fn execute(&mut self, line: &str) -> Result<Step, String> {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            return Ok(Step::Output(String::new()));
+        }
+        let words: Vec<&str> = line.split_whitespace().collect();
+        let game = &mut self.game;
+
+        self.ext.execute_with(|| {
+            match words[0] {
+                "players" => {
+                    if words.len() != 3 {
+                        return Err("usage: players <uri> <uri>".to_string());
+                    }
+                    game.set_players(words[1], words[2])?;
+                    Ok(Step::Output(game.describe_players()))
+                },
+                "initiate" => game.initiate(&words[1..]),
+                "cosign" => game.cosign(&words[1..]),
+                "settle" => game.settle(&words[1..]),
+                "place" => game.place(&words[1..]),
+                "wait" => {
+                    let blocks: BlockNumber = parse_arg(words.get(1), "blocks", None)?;
+                    System::set_block_number(System::block_number() + blocks);
+                    game.report()
+                },
+                "finalize" => game.finalize(&words[1..]),
+                "show" => game.report(),
+                "help" => Ok(Step::Output(USAGE.to_string())),
+                "quit" | "exit" => Ok(Step::Quit),
+                command => Err(format!("unknown command `{}`, try `help`", command)),
+            }
+        })
+    }
+}
+
+impl Default for Simulator {
+    fn default() -> Simulator {
+        Simulator::new()
+    }
+}
+
+impl Game {
+    /// Set players, sorted in ascending order as the pallet expects
+    fn set_players(&mut self, uri_1: &str, uri_2: &str) -> Result<(), String> {
+        if self.app_id.is_some() {
+            return Err("players can not change after initiate".to_string());
+        }
+        let mut pairs = vec![];
+        for uri in &[uri_1, uri_2] {
+            let pair: sr25519::Pair = capps_client::pair_from_uri(uri)
+                .map_err(|e| format!("invalid secret URI `{}`: {:?}", uri, e))?;
+            pairs.push(pair);
+        }
+        pairs.sort_by_key(|pair| pair.public());
+        if pairs[0].public() == pairs[1].public() {
+            return Err("players must be different".to_string());
+        }
+        self.pairs = pairs;
+
+        Ok(())
+    }
+
+    fn describe_players(&self) -> String {
+        self.pairs.iter()
+            .enumerate()
+            .map(|(index, pair)| format!("player {}: {:?}\n", index + 1, pair.public()))
+            .collect()
+    }
+
+    fn players(&self) -> Vec<AccountId> {
+        self.pairs.iter().map(|pair| pair.public()).collect()
+    }
+
+    fn app_id(&self) -> Result<H256, String> {
+        self.app_id.ok_or_else(|| "app is not initiated".to_string())
+    }
+
+    /// Origin of player 1 or 2
+    fn origin(&self, player: Option<&&str>) -> Result<Origin, String> {
+        let player: usize = parse_arg(player, "player", Some(1))?;
+        match player {
+            1 | 2 => Ok(Origin::signed(self.pairs[player - 1].public())),
+            _ => Err(format!("invalid player {}, expected 1 or 2", player)),
+        }
+    }
+
+    fn initiate(&mut self, args: &[&str]) -> Result<Step, String> {
+        let nonce: u128 = parse_arg(args.get(0), "nonce", Some(0))?;
+        let timeout: BlockNumber = parse_arg(args.get(1), "timeout", Some(2))?;
+        let min_stone_offchain: u8 = parse_arg(args.get(2), "min_stone_offchain", Some(0))?;
+        let max_stone_onchain: u8 = parse_arg(args.get(3), "max_stone_onchain", Some(225))?;
+
+        let request = AppInitiateRequestBuilder::new(nonce, self.players(), timeout)
+            .min_stone_offchain(min_stone_offchain)
+            .max_stone_onchain(max_stone_onchain)
+            .build();
+        SingleGomoku::app_initiate(self.origin(None)?, request).map_err(dispatch_error)?;
+
+        self.nonce = nonce;
+        self.timeout = timeout;
+        self.app_id = Some(SingleGomoku::get_app_id(nonce, self.players()));
+        self.proofs.clear();
+        let mut report = self.describe_players();
+        report.push_str(&self.describe_app()?);
+
+        Ok(Step::Output(report))
+    }
+
+    fn cosign(&mut self, args: &[&str]) -> Result<Step, String> {
+        let app_id = self.app_id()?;
+        let seq_num: u128 = parse_arg(args.get(0), "seq_num", None)?;
+        let board_state = match args.get(1) {
+            Some(board) if board.starts_with("0x") => {
+                hex::decode(&board[2..]).map_err(|e| format!("invalid board state: {}", e))?
+            },
+            _ => {
+                let record = GameRecord::parse(&args[1..].join(" ")).map_err(|e| e.to_string())?;
+                single_gomoku::board_state_from_record(&record, 1).map_err(|e| e.to_string())?
+            },
+        };
+
+        let app_state = single_gomoku::app_state(self.nonce, seq_num, board_state, self.timeout, app_id);
+        let state_proof: StateProofOf = single_gomoku::sign_app_state(app_state, &self.pairs);
+        let text = render_board_state(&state_proof.app_state.board_state)
+            .unwrap_or_else(|e| format!("{}\n", e));
+        self.proofs.insert(seq_num, state_proof);
+
+        Ok(Step::Output(format!("co-signed seq_num {}\n{}", seq_num, text)))
+    }
+
+    fn settle(&mut self, args: &[&str]) -> Result<Step, String> {
+        let state_proof = match args.get(0) {
+            Some(_) => {
+                let seq_num: u128 = parse_arg(args.get(0), "seq_num", None)?;
+                self.proofs.get(&seq_num).ok_or_else(|| format!("no co-signed state of seq_num {}", seq_num))?
+            },
+            None => self.proofs.values().next_back().ok_or_else(|| "no co-signed state".to_string())?,
+        };
+        SingleGomoku::update_by_state(self.origin(args.get(1))?, state_proof.clone())
+            .map_err(dispatch_error)?;
+
+        self.report()
+    }
+
+    fn place(&mut self, args: &[&str]) -> Result<Step, String> {
+        let app_id = self.app_id()?;
+        if args.len() != 2 {
+            return Err("usage: place <player> <move>".to_string());
+        }
+        let mv = parse_coordinate(args[1]).ok_or_else(|| format!("invalid move `{}`", args[1]))?;
+        SingleGomoku::update_by_action(self.origin(args.get(0))?, app_id, vec![mv.x, mv.y])
+            .map_err(dispatch_error)?;
+
+        self.report()
+    }
+
+    fn finalize(&mut self, args: &[&str]) -> Result<Step, String> {
+        let app_id = self.app_id()?;
+        SingleGomoku::finalize_on_action_timeout(self.origin(args.get(0))?, app_id)
+            .map_err(dispatch_error)?;
+
+        self.report()
+    }
+
+    /// Report the app and the events since the last report
+    fn report(&self) -> Result<Step, String> {
+        let mut report = self.describe_app()?;
+        for record in System::events() {
+            report.push_str(&format!("event: {:?}\n", record.event));
+        }
+        System::reset_events();
+
+        Ok(Step::Output(report))
+    }
+
+    /// Describe block number, status, deadlines and board of the app
+    fn describe_app(&self) -> Result<String, String> {
+        let app_id = self.app_id()?;
+        let mut lines = vec![
+            format!("block: {}", System::block_number()),
+            format!("app_id: {:?}", app_id),
+        ];
+        if let Some(status) = SingleGomoku::get_status(app_id) {
+            lines.push(format!("status: {:?}", status));
+        }
+        if let Some(seq_num) = SingleGomoku::get_seq_num(app_id) {
+            lines.push(format!("seq_num: {}", seq_num));
+        }
+        if let Some(time) = SingleGomoku::get_settle_finalized_time(app_id) {
+            lines.push(format!("settle finalized time: {}", time));
+        }
+        if let Some(deadline) = SingleGomoku::get_action_deadline(app_id) {
+            lines.push(format!("action deadline: {}", deadline));
+        }
+        if let Some(board_state) = SingleGomoku::get_state(app_id, 2) {
+            let text = render_board_state(&board_state).map_err(|e| e.to_string())?;
+            lines.extend(text.lines().map(str::to_string));
+        }
+
+        Ok(lines.iter().map(|line| format!("{}\n", line)).collect())
+    }
+}
+
+/// Parse a command argument, falling back to its default if omitted
+fn parse_arg<T: std::str::FromStr>(arg: Option<&&str>, name: &str, default: Option<T>) -> Result<T, String> {
+    match (arg, default) {
+        (Some(arg), _) => arg.parse().map_err(|_| format!("invalid {} `{}`", name, arg)),
+        (None, Some(default)) => Ok(default),
+        (None, None) => Err(format!("missing {}", name)),
+    }
+}
+
+fn dispatch_error(e: DispatchError) -> String {
+    match e {
+        DispatchError::Other(message) => message.to_string(),
+        DispatchError::Module { message: Some(message), .. } => message.to_string(),
+        e => format!("{:?}", e),
+    }
+}
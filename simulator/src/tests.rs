@@ -0,0 +1,71 @@
+use super::*;
+
+fn execute(simulator: &mut Simulator, line: &str) -> String {
+    match simulator.execute(line) {
+        Ok(Step::Output(output)) => output,
+        other => panic!("unexpected result of `{}`: {:?}", line, other),
+    }
+}
+
+#[test]
+fn test_pass_play_game_to_five_on_chain() {
+    let mut simulator = Simulator::new();
+    let output = execute(&mut simulator, "initiate 0 2");
+    assert!(output.contains("player 1: "));
+    assert!(output.contains("status: Idle"));
+
+    execute(&mut simulator, "cosign 1 h8 a1 h9 a2 h10 a3 h11");
+    let output = execute(&mut simulator, "settle");
+    assert!(output.contains("status: Settle"));
+    assert!(output.contains("settle finalized time: 3"));
+    assert!(output.contains("event: single_gomoku(IntendSettle("));
+    assert!(output.contains("turn: 2"));
+
+    assert!(simulator.execute("place 2 a4").is_err());
+    let output = execute(&mut simulator, "wait 3");
+    assert!(output.contains("block: 4"));
+    assert!(!output.contains("event: "));
+
+    execute(&mut simulator, "place 2 a4");
+    let output = execute(&mut simulator, "place 1 h12");
+    assert!(output.contains("status: Finalized"));
+    assert!(output.contains("winner: 1"));
+    assert!(output.contains("  3  .  .  .  .  .  .  .  X  .  .  .  .  .  .  ."));
+}
+
+#[test]
+fn test_pass_answer_stale_settle() {
+    let mut simulator = Simulator::new();
+    execute(&mut simulator, "# player 2 settles a stale state, player 1 answers");
+    execute(&mut simulator, "initiate 1 2");
+    execute(&mut simulator, "cosign 1 h8 i9");
+    execute(&mut simulator, "cosign 2 h8 i9 h9 i10");
+    execute(&mut simulator, "settle 1 2");
+
+    let output = execute(&mut simulator, "settle 2 1");
+    assert!(output.contains("seq_num: 2"));
+    assert!(output.contains("action deadline: 5"));
+    assert_eq!(
+        simulator.execute("settle 1 2"),
+        Err("invalid sequence number".to_string())
+    );
+
+    execute(&mut simulator, "wait 4");
+    assert!(simulator.execute("finalize").is_err());
+    execute(&mut simulator, "wait 1");
+    let output = execute(&mut simulator, "finalize");
+    assert!(output.contains("status: Finalized"));
+    assert!(output.contains("winner: 2"));
+}
+
+#[test]
+fn test_fail_invalid_commands() {
+    let mut simulator = Simulator::new();
+    assert!(simulator.execute("cosign 1 h8").is_err());
+    assert!(simulator.execute("jump 3").is_err());
+    execute(&mut simulator, "initiate");
+    assert!(simulator.execute("players //Alice //Charlie").is_err());
+    assert!(simulator.execute("place 3 h8").is_err());
+    assert!(simulator.execute("wait").is_err());
+    assert_eq!(simulator.execute("quit"), Ok(Step::Quit));
+}
@@ -0,0 +1,127 @@
+//! Ethereum compatible app ids and state signatures
+//!
+//! Payloads are `abi.encodePacked` forms of the app state. Players sign
+//! `keccak256` of the payload as an Ethereum signed message, the way
+//! `eth_sign` of Ethereum wallets and `toEthSignedMessageHash` of Celer's
+//! contracts do, and are identified by the 20-byte addresses recovered from
+//! their signatures.
+
+use sp_io::hashing::keccak_256;
+use sp_std::vec::Vec;
+
+pub use sp_core::{ecdsa::Signature as EcdsaSignature, H160, H256};
+
+/// Prefix of a 32-byte Ethereum signed message
+const SIGNED_MESSAGE_PREFIX: &[u8] = b"\x19Ethereum Signed Message:\n32";
+
+/// Packed `uint256`, 32 bytes big-endian
+fn uint256(value: u128) -> [u8; 32] {
+    let mut word = [0; 32];
+    word[16..].copy_from_slice(&value.to_be_bytes());
+    word
+}
+
+/// Pack app state as `abi.encodePacked(uint256 nonce, uint256 seqNum,
+/// bytes state, uint256 timeout, bytes32 appId)`
+///
+/// Parameters:
+/// `nonce`: Nonce of app, `None` for apps without one, which leave it out
+/// `seq_num`: Sequence number of app state
+/// `state`: Encoded state or board
+/// `timeout`: Timeout of app in blocks
+/// `app_id`: Encoded id of app
+pub fn app_state(
+    nonce: Option<u128>,
+    seq_num: u128,
+    state: &[u8],
+    timeout: u64,
+    app_id: &[u8],
+) -> Vec<u8> {
+    let mut packed = Vec::new();
+    if let Some(nonce) = nonce {
+        packed.extend_from_slice(&uint256(nonce));
+    }
+    packed.extend_from_slice(&uint256(seq_num));
+    packed.extend_from_slice(state);
+    packed.extend_from_slice(&uint256(timeout as u128));
+    packed.extend_from_slice(app_id);
+
+    packed
+}
+
+/// Get Id of app as `keccak256(abi.encodePacked(bytes8 moduleId, uint256 nonce,
+/// address player, ..))`
+///
+/// Parameters:
+/// `module_id`: Id of pallet module
+/// `nonce`: Nonce of app
+/// `players`: Ethereum addresses of players
+pub fn app_id(module_id: [u8; 8], nonce: u128, players: &[H160]) -> H256 {
+    let mut packed = module_id.to_vec();
+    packed.extend_from_slice(&uint256(nonce));
+    players.iter().for_each(|player| packed.extend_from_slice(player.as_bytes()));
+
+    H256(keccak_256(&packed))
+}
+
+/// Hash signed by players, `keccak256` of the payload as an Ethereum signed message
+///
+/// Parameter:
+/// `payload`: Packed app state
+pub fn signed_message_hash(payload: &[u8]) -> [u8; 32] {
+    let mut message = SIGNED_MESSAGE_PREFIX.to_vec();
+    message.extend_from_slice(&keccak_256(payload));
+
+    keccak_256(&message)
+}
+
+/// Ethereum address of an uncompressed secp256k1 public key without its 0x04 prefix
+pub fn address(public: &[u8; 64]) -> H160 {
+    H160::from_slice(&keccak_256(public)[12..])
+}
+
+/// Recover the address which signed a payload, `None` for an invalid signature
+///
+/// Parameters:
+/// `signature`: 65-byte signature with recovery id 0, 1, 27 or 28
+/// `payload`: Packed app state
+pub fn recover_signer(signature: &EcdsaSignature, payload: &[u8]) -> Option<H160> {
+    let hash = signed_message_hash(payload);
+    let public = sp_io::crypto::secp256k1_ecdsa_recover(signature.as_ref(), &hash).ok()?;
+
+    Some(address(&public))
+}
+
+/// secp256k1 key pair of an Ethereum account
+#[cfg(feature = "std")]
+pub struct Pair {
+    secret: secp256k1::SecretKey,
+}
+
+#[cfg(feature = "std")]
+impl Pair {
+    /// Get key pair from a 32-byte secret key, `None` if it is out of range
+    pub fn from_secret(secret: &[u8; 32]) -> Option<Pair> {
+        Some(Pair { secret: secp256k1::SecretKey::parse(secret).ok()? })
+    }
+
+    /// Ethereum address of the key pair
+    pub fn address(&self) -> H160 {
+        let public = secp256k1::PublicKey::from_secret_key(&self.secret).serialize();
+        let mut uncompressed = [0; 64];
+        uncompressed.copy_from_slice(&public[1..]);
+
+        address(&uncompressed)
+    }
+
+    /// Sign a payload as an Ethereum signed message, with recovery id 27 or 28
+    pub fn sign(&self, payload: &[u8]) -> EcdsaSignature {
+        let message = secp256k1::Message::parse(&signed_message_hash(payload));
+        let (signature, recovery_id) = secp256k1::sign(&message, &self.secret);
+        let mut raw = [0; 65];
+        raw[..64].copy_from_slice(&signature.serialize());
+        raw[64] = 27 + recovery_id.serialize();
+
+        EcdsaSignature::from_raw(raw)
+    }
+}
@@ -0,0 +1,34 @@
+//! Encodings of off-chain app states signed by players
+//!
+//! Apps choose at initiation whether players sign the SCALE concatenation of
+//! the app state fields, its protobuf serialization, the form Celer's
+//! off-chain clients sign, or its `abi.encodePacked` form with Ethereum
+//! accounts.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+pub mod ethereum;
+pub mod protobuf;
+
+#[cfg(test)]
+mod tests;
+
+use codec::{Decode, Encode};
+
+/// Encoding of the app state payload signed by players
+#[derive(Clone, Copy, Eq, PartialEq, PartialOrd, Ord, Encode, Decode)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub enum StateEncoding {
+    /// SCALE encoded fields, concatenated in declaration order
+    Scale = 0,
+    /// Protobuf `AppState` message, see `protobuf::app_state`
+    Protobuf = 1,
+    /// Packed fields signed by Ethereum accounts of players, see `ethereum::app_state`
+    Ethereum = 2,
+}
+
+impl Default for StateEncoding {
+    fn default() -> Self {
+        StateEncoding::Scale
+    }
+}
@@ -0,0 +1,111 @@
+//! Canonical protobuf serialization of app states
+//!
+//! Signatures cover the serialized bytes, so every encoder has to produce
+//! the same bytes for the same state: fields are written in ascending field
+//! number, fields with default values are omitted and no unknown fields are
+//! written, as proto3 serializers do.
+
+use sp_std::vec::Vec;
+
+/// Field number of `nonce` in `AppState`
+pub const NONCE: u32 = 1;
+/// Field number of `seq_num` in `AppState`
+pub const SEQ_NUM: u32 = 2;
+/// Field number of `state` in `AppState`
+pub const STATE: u32 = 3;
+/// Field number of `timeout` in `AppState`
+pub const TIMEOUT: u32 = 4;
+/// Field number of `app_id` in `AppState`
+pub const APP_ID: u32 = 5;
+
+const WIRE_VARINT: u32 = 0;
+const WIRE_LENGTH_DELIMITED: u32 = 2;
+
+/// Writer of a protobuf message, fields must be written in ascending field number
+#[derive(Default)]
+pub struct Message {
+    buf: Vec<u8>,
+}
+
+impl Message {
+    pub fn new() -> Self {
+        Message { buf: Vec::new() }
+    }
+
+    /// Write a `uint64` field, omitted if 0
+    pub fn uint64(mut self, field: u32, value: u64) -> Self {
+        if value != 0 {
+            self.key(field, WIRE_VARINT);
+            self.varint(value);
+        }
+        self
+    }
+
+    /// Write a `bytes` field, omitted if empty
+    pub fn bytes(mut self, field: u32, value: &[u8]) -> Self {
+        if !value.is_empty() {
+            self.key(field, WIRE_LENGTH_DELIMITED);
+            self.varint(value.len() as u64);
+            self.buf.extend_from_slice(value);
+        }
+        self
+    }
+
+    /// Serialized message
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.buf
+    }
+
+    fn key(&mut self, field: u32, wire_type: u32) {
+        self.varint(((field << 3) | wire_type) as u64);
+    }
+
+    fn varint(&mut self, mut value: u64) {
+        while value >= 0x80 {
+            self.buf.push((value as u8 & 0x7f) | 0x80);
+            value >>= 7;
+        }
+        self.buf.push(value as u8);
+    }
+}
+
+/// Serialize app state as the protobuf message
+///
+/// ```protobuf
+/// message AppState {
+///     uint64 nonce = 1;
+///     uint64 seq_num = 2;
+///     bytes state = 3;
+///     uint64 timeout = 4;
+///     bytes app_id = 5;
+/// }
+/// ```
+///
+/// Returns `None` if `nonce` or `seq_num` does not fit in a `uint64`.
+///
+/// Parameters:
+/// `nonce`: Nonce of app, 0 for apps without one
+/// `seq_num`: Sequence number of app state
+/// `state`: Encoded state or board
+/// `timeout`: Timeout of app in blocks
+/// `app_id`: Encoded id of app
+pub fn app_state(
+    nonce: u128,
+    seq_num: u128,
+    state: &[u8],
+    timeout: u64,
+    app_id: &[u8],
+) -> Option<Vec<u8>> {
+    if nonce > u64::max_value() as u128 || seq_num > u64::max_value() as u128 {
+        return None;
+    }
+
+    let message = Message::new()
+        .uint64(NONCE, nonce as u64)
+        .uint64(SEQ_NUM, seq_num as u64)
+        .bytes(STATE, state)
+        .uint64(TIMEOUT, timeout)
+        .bytes(APP_ID, app_id);
+
+    Some(message.into_bytes())
+}
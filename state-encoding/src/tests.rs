@@ -0,0 +1,93 @@
+use super::*;
+use codec::Encode;
+
+#[test]
+fn test_pass_app_state_is_canonical_protobuf() {
+    let encoded = protobuf::app_state(1, 2, &[1], 2, &[0xaa, 0xbb]).unwrap();
+    assert_eq!(
+        encoded,
+        vec![0x08, 0x01, 0x10, 0x02, 0x1a, 0x01, 0x01, 0x20, 0x02, 0x2a, 0x02, 0xaa, 0xbb]
+    );
+
+    // default values are omitted and varints take as many bytes as needed
+    let encoded = protobuf::app_state(0, 300, &[], 1 << 32, &[0xcc]).unwrap();
+    assert_eq!(
+        encoded,
+        vec![0x10, 0xac, 0x02, 0x20, 0x80, 0x80, 0x80, 0x80, 0x10, 0x2a, 0x01, 0xcc]
+    );
+
+    let state = vec![7; 200];
+    let encoded = protobuf::app_state(0, 0, &state, 0, &[]).unwrap();
+    assert_eq!(encoded[..3], [0x1a, 0xc8, 0x01]);
+    assert_eq!(encoded[3..], state[..]);
+}
+
+#[test]
+fn test_fail_app_state_numbers_beyond_uint64() {
+    let max = u64::max_value() as u128;
+    assert!(protobuf::app_state(max, max, &[], 0, &[]).is_some());
+    assert_eq!(protobuf::app_state(max + 1, 1, &[], 0, &[]), None);
+    assert_eq!(protobuf::app_state(1, max + 1, &[], 0, &[]), None);
+}
+
+#[test]
+fn test_pass_state_encoding_defaults_to_scale() {
+    assert_eq!(StateEncoding::default(), StateEncoding::Scale);
+    assert_eq!(StateEncoding::Scale.encode(), vec![0]);
+    assert_eq!(StateEncoding::Protobuf.encode(), vec![1]);
+    assert_eq!(StateEncoding::Ethereum.encode(), vec![2]);
+}
+
+#[test]
+fn test_pass_ethereum_packed_app_state() {
+    let packed = ethereum::app_state(Some(1), 2, &[5], 3, &[0xaa; 32]);
+    assert_eq!(packed.len(), 32 + 32 + 1 + 32 + 32);
+    assert_eq!((packed[31], packed[63], packed[64], packed[96]), (1, 2, 5, 3));
+    assert_eq!(packed[97..], [0xaa; 32]);
+
+    // apps without a nonce leave it out
+    assert_eq!(ethereum::app_state(None, 2, &[5], 3, &[0xaa; 32]), packed[32..].to_vec());
+}
+
+#[test]
+fn test_pass_recover_ethereum_signer() {
+    let mut secret = [0; 32];
+    secret[31] = 1;
+    let pair = ethereum::Pair::from_secret(&secret).unwrap();
+    assert_eq!(
+        pair.address(),
+        ethereum::H160::from_slice(&hex_bytes("7e5f4552091a69125d5dfcb7b8c2659029395bdf"))
+    );
+
+    let payload = ethereum::app_state(Some(0), 1, &[2], 2, &[0xbb; 32]);
+    let signature = pair.sign(&payload);
+    assert!(signature.as_ref()[64] == 27 || signature.as_ref()[64] == 28);
+    assert_eq!(ethereum::recover_signer(&signature, &payload), Some(pair.address()));
+    assert!(ethereum::recover_signer(&signature, &payload[1..]) != Some(pair.address()));
+
+    // recovery ids 0 and 1 are accepted as well
+    let mut raw = *signature.as_ref();
+    raw[64] -= 27;
+    let signature = ethereum::EcdsaSignature::from_raw(raw);
+    assert_eq!(ethereum::recover_signer(&signature, &payload), Some(pair.address()));
+}
+
+#[test]
+fn test_pass_ethereum_app_id_depends_on_module_and_players() {
+    let mut secret = [0; 32];
+    secret[31] = 1;
+    let player_1 = ethereum::Pair::from_secret(&secret).unwrap().address();
+    secret[31] = 2;
+    let player_2 = ethereum::Pair::from_secret(&secret).unwrap().address();
+
+    let app_id = ethereum::app_id(*b"_single_", 0, &[player_1, player_2]);
+    assert!(app_id != ethereum::app_id(*b"_multi__", 0, &[player_1, player_2]));
+    assert!(app_id != ethereum::app_id(*b"_single_", 1, &[player_1, player_2]));
+    assert!(app_id != ethereum::app_id(*b"_single_", 0, &[player_2, player_1]));
+}
+
+fn hex_bytes(hex: &str) -> Vec<u8> {
+    (0..hex.len()).step_by(2)
+        .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).unwrap())
+        .collect()
+}
@@ -0,0 +1,188 @@
+//! Definitions of pallet types for SCALE clients such as polkadot.js
+//!
+//! Pallets list their encoded types with `type_defs!`, which checks every
+//! listed field and variant against the Rust type at compile time: a field
+//! or variant added, removed, renamed or retyped without updating the list
+//! is a compile error. Fields must be listed in declaration order, the order
+//! they are encoded in, which `TypeDef::encodes_in_order` checks at run time.
+//! Discriminants of fieldless enums are read from the enums themselves.
+
+#![no_std]
+
+#[doc(hidden)]
+pub use codec;
+
+use codec::{Decode, Error, Input};
+
+/// Type as SCALE clients see it
+pub struct TypeDef {
+    /// Name of the type in the pallet
+    pub name: &'static str,
+    /// Layout of the type
+    pub kind: TypeKind,
+    /// Whether a struct encodes its fields in the listed order, `None` for
+    /// other kinds
+    pub encodes_in_order: Option<fn() -> bool>,
+}
+
+/// Layout of a type, with field types as written in Rust
+pub enum TypeKind {
+    /// Struct of named fields in declaration order
+    Struct(&'static [(&'static str, &'static str)]),
+    /// Fieldless enum of variants and their discriminants
+    Enum(&'static [(&'static str, u8)]),
+    /// Enum of variants with one field each, in declaration order
+    DataEnum(&'static [(&'static str, &'static str)]),
+    /// Another name of a type
+    Alias(&'static str),
+}
+
+/// Endless input of one byte after an optional prefix byte
+struct SampleInput {
+    prefix: Option<u8>,
+    byte: u8,
+}
+
+impl Input for SampleInput {
+    fn remaining_len(&mut self) -> Result<Option<usize>, Error> {
+        Ok(None)
+    }
+
+    fn read(&mut self, into: &mut [u8]) -> Result<(), Error> {
+        for byte in into.iter_mut() {
+            *byte = self.prefix.take().unwrap_or(self.byte);
+        }
+        Ok(())
+    }
+}
+
+/// Sample value of a field, distinct from the samples of other fields
+///
+/// The value is decoded from bytes of `4 * seed`, so integers, hashes and
+/// vector lengths differ between fields. Options, bools and enums rejecting
+/// such bytes are decoded after a `1`, then a `0` prefix, and structs nesting
+/// several of them from bytes of `1`, which decode as `Some`, `true`, the
+/// second variant and 64 elements.
+///
+/// Parameter:
+/// `seed`: Seed of the previous field, incremented for this one
+#[doc(hidden)]
+pub fn sample<T: Decode>(seed: &mut u8) -> T {
+    *seed += 1;
+    let byte = *seed << 2;
+    let inputs = [(None, byte), (Some(1), byte), (Some(0), byte), (None, 1)];
+    for (prefix, byte) in inputs.iter() {
+        if let Ok(value) = T::decode(&mut SampleInput { prefix: *prefix, byte: *byte }) {
+            return value;
+        }
+    }
+    panic!("no sample value of field {}", seed)
+}
+
+/// List types of a pallet as `TYPE_DEFS`
+///
+/// Accepts structs, fieldless enums, enums with one field per variant and
+/// aliases. A struct whose generic parameters are not all type parameters is
+/// listed with the instance clients see, as in
+/// `struct BoardState<Hash> as BoardState<BoardRecord<Hash>> { .. }`.
+/// Field order is checked on a sample value with `u64` for every generic
+/// parameter. Aliases are not checked.
+#[macro_export]
+macro_rules! type_defs {
+    (@munch [$($defs:expr,)*]) => {
+        /// Encoded types of this pallet
+        pub const TYPE_DEFS: &[$crate::TypeDef] = &[$($defs,)*];
+    };
+    (@munch [$($defs:expr,)*]
+        struct $name:ident <$($gen:ident),*> as $inst:ty {
+            $($field:ident : $ty:ty),* $(,)?
+        }
+        $($rest:tt)*
+    ) => {
+        const _: () = {
+            #[allow(dead_code)]
+            fn check<$($gen),*>(value: $inst) {
+                let $name { $($field),* } = value;
+                $(let _: $ty = $field;)*
+            }
+        };
+        $crate::type_defs!(@munch [$($defs,)* $crate::TypeDef {
+            name: stringify!($name),
+            kind: $crate::TypeKind::Struct(&[$((stringify!($field), stringify!($ty))),*]),
+            encodes_in_order: Some({
+                fn encodes_in_order() -> bool {
+                    $(#[allow(dead_code)] type $gen = u64;)*
+                    let mut seed = 0;
+                    let value: $inst = $name { $($field: $crate::sample(&mut seed)),* };
+                    $crate::codec::Encode::encode(&value)
+                        == $crate::codec::Encode::encode(&($(&value.$field,)*))
+                }
+                encodes_in_order
+            }),
+        },] $($rest)*);
+    };
+    (@munch [$($defs:expr,)*]
+        struct $name:ident $(<$($gen:ident),*>)? {
+            $($field:ident : $ty:ty),* $(,)?
+        }
+        $($rest:tt)*
+    ) => {
+        $crate::type_defs!(@munch [$($defs,)*]
+            struct $name <$($($gen),*)?> as $name $(<$($gen),*>)? { $($field: $ty),* }
+            $($rest)*
+        );
+    };
+    (@munch [$($defs:expr,)*]
+        enum $name:ident $(<$($gen:ident),*>)? {
+            $($variant:ident ($vty:ty)),* $(,)?
+        }
+        $($rest:tt)*
+    ) => {
+        const _: () = {
+            #[allow(dead_code)]
+            fn check<$($($gen),*)?>(value: $name $(<$($gen),*>)?) {
+                match value {
+                    $($name::$variant(field) => { let _: $vty = field; },)*
+                }
+            }
+        };
+        $crate::type_defs!(@munch [$($defs,)* $crate::TypeDef {
+            name: stringify!($name),
+            kind: $crate::TypeKind::DataEnum(&[$((stringify!($variant), stringify!($vty))),*]),
+            encodes_in_order: None,
+        },] $($rest)*);
+    };
+    (@munch [$($defs:expr,)*]
+        enum $name:ident {
+            $($variant:ident),* $(,)?
+        }
+        $($rest:tt)*
+    ) => {
+        const _: () = {
+            #[allow(dead_code)]
+            fn check(value: $name) {
+                match value {
+                    $($name::$variant => {},)*
+                }
+            }
+        };
+        $crate::type_defs!(@munch [$($defs,)* $crate::TypeDef {
+            name: stringify!($name),
+            kind: $crate::TypeKind::Enum(&[$((stringify!($variant), $name::$variant as u8)),*]),
+            encodes_in_order: None,
+        },] $($rest)*);
+    };
+    (@munch [$($defs:expr,)*]
+        type $name:ident = $target:ty;
+        $($rest:tt)*
+    ) => {
+        $crate::type_defs!(@munch [$($defs,)* $crate::TypeDef {
+            name: stringify!($name),
+            kind: $crate::TypeKind::Alias(stringify!($target)),
+            encodes_in_order: None,
+        },] $($rest)*);
+    };
+    ($($body:tt)*) => {
+        $crate::type_defs!(@munch [] $($body)*);
+    };
+}
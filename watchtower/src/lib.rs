@@ -0,0 +1,339 @@
+//! Off-chain worker answering stale settlements on behalf of players
+//!
+//! A player registers the latest co-signed state proof of an app or session
+//! in the offchain local storage of a node, either through `watch_state_proof`
+//! or by writing the `WATCHED_IDS_KEY` and `watched_proof_key` of the pallet
+//! with the `offchain_localStorageSet` RPC. Each block, the worker of the node
+//! reads the `IntendSettle` events of the block and, when a watched id is
+//! settled with a lower `seq_num` than the watched proof, hands the proof to
+//! the `Submitter` of the pallet. `SignedSubmitter` submits it by
+//! `update_by_state`, or `update_by_eth_state` for proofs signed by Ethereum
+//! accounts, signed by a watchtower key of `KEY_TYPE` in the node keystore.
+//! Runtimes setting `()` as submitter, and nodes without watched ids or
+//! without a watchtower key, submit nothing.
+//!
+//! The worker records the on-chain `seq_num` it last answered for each id and
+//! does not answer it again until the chain state changes. Only newly watched
+//! proofs, and every id once per `PRUNE_PERIOD` blocks, are checked against
+//! the on-chain status, so that settlements intended before a proof was
+//! watched are answered and finalized ids are dropped with their proofs.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+#[cfg(test)]
+mod tests;
+
+use codec::{Decode, Encode};
+use frame_support::debug;
+use frame_system::offchain::{
+    AppCrypto, CreateSignedTransaction, SendSignedTransaction, Signer, SigningTypes,
+};
+use sp_runtime::{offchain::storage::StorageValueRef, KeyTypeId, RuntimeDebug};
+use sp_std::{fmt::Debug, marker::PhantomData, prelude::*};
+
+/// Key type of watchtower accounts
+pub const KEY_TYPE: KeyTypeId = KeyTypeId(*b"capw");
+
+/// sr25519 watchtower keys of `KEY_TYPE`
+pub mod crypto {
+    use super::KEY_TYPE;
+    use sp_core::sr25519::Signature as Sr25519Signature;
+    use sp_runtime::app_crypto::{app_crypto, sr25519};
+    use sp_runtime::{traits::Verify, MultiSignature, MultiSigner};
+
+    app_crypto!(sr25519, KEY_TYPE);
+
+    /// Watchtower account signing `update_by_state` transactions
+    pub struct WatchtowerId;
+
+    impl frame_system::offchain::AppCrypto<MultiSigner, MultiSignature> for WatchtowerId {
+        type RuntimeAppPublic = Public;
+        type GenericSignature = sp_core::sr25519::Signature;
+        type GenericPublic = sp_core::sr25519::Public;
+    }
+
+    impl frame_system::offchain::AppCrypto<<Sr25519Signature as Verify>::Signer, Sr25519Signature>
+        for WatchtowerId
+    {
+        type RuntimeAppPublic = Public;
+        type GenericSignature = sp_core::sr25519::Signature;
+        type GenericPublic = sp_core::sr25519::Public;
+    }
+}
+
+/// Status of a watched app or session
+#[derive(Clone, Copy, Eq, PartialEq, RuntimeDebug)]
+pub enum WatchedStatus {
+    Settle,
+    Finalized,
+    Other,
+}
+
+/// Pallet whose settlements are answered by the watchtower
+pub trait Watched {
+    /// Id of app or session
+    type Id: Encode + Decode + Eq + Copy + Debug;
+    /// Co-signed state proof accepted by `update_by_state`
+    type StateProof: Encode + Decode + Clone;
+    /// Submitter of state proofs answering stale settlements
+    type Submitter: SubmitProof<Self::StateProof>;
+
+    /// Offchain local storage key of the list of watched ids
+    const WATCHED_IDS_KEY: &'static [u8];
+    /// Prefix of the offchain local storage key of the latest state proof of an id
+    const PROOF_KEY_PREFIX: &'static [u8];
+
+    /// Get id of app or session of a state proof
+    ///
+    /// Parameter:
+    /// `state_proof`: Signed off-chain app state
+    fn proof_id(state_proof: &Self::StateProof) -> Self::Id;
+
+    /// Get sequence number of a state proof
+    ///
+    /// Parameter:
+    /// `state_proof`: Signed off-chain app state
+    fn proof_seq_num(state_proof: &Self::StateProof) -> u128;
+
+    /// Get status of app or session
+    ///
+    /// Parameter:
+    /// `id`: Id of app or session
+    fn status(id: Self::Id) -> WatchedStatus;
+
+    /// Get sequence number settled on-chain, 0 if nothing is settled
+    ///
+    /// Parameter:
+    /// `id`: Id of app or session
+    fn seq_num(id: Self::Id) -> u128;
+
+    /// Get ids and sequence numbers of the `IntendSettle` events of the current block
+    fn settle_events() -> Vec<(Self::Id, u128)>;
+}
+
+/// Number of blocks between checks of all watched ids against the on-chain status
+pub const PRUNE_PERIOD: u64 = 100;
+
+/// State proof of a watched app or session, signed by players or by
+/// Ethereum accounts of players
+#[derive(Clone, Eq, PartialEq, Encode, Decode, RuntimeDebug)]
+pub enum WatchedProof<StateProof, EthStateProof> {
+    Players(StateProof),
+    Ethereum(EthStateProof),
+}
+
+/// Submitter of state proofs from the off-chain worker
+pub trait SubmitProof<StateProof> {
+    /// Whether the off-chain worker answers stale settlements
+    const ENABLED: bool = true;
+
+    /// Submit a state proof
+    ///
+    /// Parameter:
+    /// `state_proof`: Signed off-chain app state
+    fn submit(state_proof: StateProof) -> Result<(), &'static str>;
+}
+
+/// No watchtower, the off-chain worker submits nothing
+impl<StateProof> SubmitProof<StateProof> for () {
+    const ENABLED: bool = false;
+
+    fn submit(_state_proof: StateProof) -> Result<(), &'static str> {
+        Err("watchtower is disabled")
+    }
+}
+
+/// Call of a pallet settling a state proof
+pub trait SettleCall<StateProof> {
+    /// Build the `update_by_state` or `update_by_eth_state` call
+    ///
+    /// Parameter:
+    /// `state_proof`: Signed off-chain app state
+    fn settle(state_proof: StateProof) -> Self;
+}
+
+/// Watchtower submitting settlement transactions signed by an `AuthorityId`
+/// key in the node keystore
+pub struct SignedSubmitter<T, Call, AuthorityId>(PhantomData<(T, Call, AuthorityId)>);
+
+impl<T, Call, AuthorityId, StateProof> SubmitProof<StateProof> for SignedSubmitter<T, Call, AuthorityId>
+where
+    T: CreateSignedTransaction<Call>,
+    Call: SettleCall<StateProof>,
+    AuthorityId: AppCrypto<<T as SigningTypes>::Public, <T as SigningTypes>::Signature>,
+    StateProof: Clone,
+{
+    fn submit(state_proof: StateProof) -> Result<(), &'static str> {
+        let signer = Signer::<T, AuthorityId>::any_account();
+        match signer.send_signed_transaction(|_account| Call::settle(state_proof.clone())) {
+            Some((_, Ok(()))) => Ok(()),
+            Some((_, Err(()))) => Err("failed to submit state proof"),
+            None => Err("no watchtower account in keystore"),
+        }
+    }
+}
+
+/// Offchain local storage key of the latest state proof of an app or session
+///
+/// Parameters:
+/// `prefix`: Prefix of the key in the pallet
+/// `id`: Id of app or session
+pub fn proof_key<Id: Encode>(prefix: &[u8], id: &Id) -> Vec<u8> {
+    let mut key = prefix.to_vec();
+    key.extend(id.encode());
+
+    key
+}
+
+/// Offchain local storage key of the latest state proof watched in a pallet
+///
+/// Parameter:
+/// `id`: Id of app or session
+pub fn watched_proof_key<W: Watched>(id: &W::Id) -> Vec<u8> {
+    proof_key(W::PROOF_KEY_PREFIX, id)
+}
+
+/// Offchain local storage key of the on-chain sequence number the watched
+/// proof of an id was last checked against
+///
+/// Parameter:
+/// `id`: Id of app or session
+fn answered_key<W: Watched>(id: &W::Id) -> Vec<u8> {
+    let mut key = b"answered::".to_vec();
+    key.extend(watched_proof_key::<W>(id));
+
+    key
+}
+
+/// Get the fields of the events of the current block of the same pallet and
+/// variant as `probe`
+///
+/// A runtime event is encoded as the index of its pallet, the index of its
+/// variant and then its fields, so the events are selected by the first two
+/// bytes of the encoded `probe` and their remaining bytes decode as `Fields`.
+///
+/// Parameter:
+/// `probe`: Runtime event of the pallet and variant to select
+pub fn block_events<T: frame_system::Trait, Fields: Decode>(
+    probe: <T as frame_system::Trait>::Event,
+) -> Vec<Fields> {
+    let encoded_probe = probe.encode();
+    let prefix = &encoded_probe[..2];
+    frame_system::Module::<T>::events()
+        .into_iter()
+        .filter_map(|record| {
+            let encoded = record.event.encode();
+            if encoded.starts_with(prefix) {
+                Fields::decode(&mut &encoded[2..]).ok()
+            } else {
+                None
+            }
+        })
+        .collect()
+}
+
+/// Watch an app or session with a co-signed state proof, keeping the proof of
+/// the highest sequence number
+///
+/// Parameter:
+/// `state_proof`: Signed off-chain app state
+pub fn watch_state_proof<W: Watched>(state_proof: W::StateProof) {
+    let id = W::proof_id(&state_proof);
+    let proof_key = watched_proof_key::<W>(&id);
+    let proof_ref = StorageValueRef::persistent(&proof_key);
+    if let Some(Some(watched)) = proof_ref.get::<W::StateProof>() {
+        if W::proof_seq_num(&watched) >= W::proof_seq_num(&state_proof) {
+            return;
+        }
+    }
+    proof_ref.set(&state_proof);
+    // the new proof is checked against the on-chain status by the next worker
+    StorageValueRef::persistent(&answered_key::<W>(&id)).clear();
+
+    let ids_ref = StorageValueRef::persistent(W::WATCHED_IDS_KEY);
+    let mut ids = match ids_ref.get::<Vec<W::Id>>() {
+        Some(Some(ids)) => ids,
+        _ => vec![],
+    };
+    if !ids.contains(&id) {
+        ids.push(id);
+        ids_ref.set(&ids);
+    }
+}
+
+/// Answer stale settlements of watched apps or sessions and stop watching
+/// finalized ones
+///
+/// Parameter:
+/// `block_number`: Number of the block the worker runs on
+pub fn answer_stale_settles<W: Watched>(block_number: u64) {
+    if !<W::Submitter as SubmitProof<W::StateProof>>::ENABLED {
+        return;
+    }
+    let ids_ref = StorageValueRef::persistent(W::WATCHED_IDS_KEY);
+    let ids = match ids_ref.get::<Vec<W::Id>>() {
+        Some(Some(ids)) => ids,
+        _ => return,
+    };
+    let settles = W::settle_events();
+    let prune = block_number % PRUNE_PERIOD == 0;
+
+    let mut watched = vec![];
+    for id in ids {
+        let mut answered_ref = StorageValueRef::persistent(&answered_key::<W>(&id));
+        let answered = match answered_ref.get::<u128>() {
+            Some(Some(seq_num)) => Some(seq_num),
+            _ => None,
+        };
+        // the latest settlement of the block has the highest sequence number
+        let mut settled = settles
+            .iter()
+            .rev()
+            .find(|(settled_id, _)| settled_id == &id)
+            .map(|(_, seq_num)| *seq_num);
+        if settled.is_none() && (answered.is_none() || prune) {
+            match W::status(id) {
+                WatchedStatus::Finalized => {
+                    StorageValueRef::persistent(&watched_proof_key::<W>(&id)).clear();
+                    answered_ref.clear();
+                    continue;
+                },
+                WatchedStatus::Settle => settled = Some(W::seq_num(id)),
+                WatchedStatus::Other => {
+                    if answered.is_none() {
+                        answered_ref.set(&W::seq_num(id));
+                    }
+                },
+            }
+        }
+        watched.push(id);
+
+        let seq_num = match settled {
+            Some(seq_num) if answered != Some(seq_num) => seq_num,
+            _ => continue,
+        };
+        if let Err(e) = submit_watched_proof::<W>(id, seq_num) {
+            debug::warn!("watchtower failed to answer settlement of {:?}: {}", id, e);
+        }
+        answered_ref.set(&seq_num);
+    }
+    ids_ref.set(&watched);
+}
+
+/// Submit the watched state proof if it is newer than the settled one
+///
+/// Parameters:
+/// `id`: Id of app or session
+/// `seq_num`: Sequence number settled on-chain
+fn submit_watched_proof<W: Watched>(id: W::Id, seq_num: u128) -> Result<(), &'static str> {
+    let proof_key = watched_proof_key::<W>(&id);
+    let state_proof = match StorageValueRef::persistent(&proof_key).get::<W::StateProof>() {
+        Some(Some(proof)) => proof,
+        _ => return Err("watched state proof not exist"),
+    };
+    if W::proof_seq_num(&state_proof) <= seq_num {
+        return Ok(());
+    }
+
+    W::Submitter::submit(state_proof)
+}
@@ -0,0 +1,224 @@
+use super::*;
+use sp_core::offchain::{testing, OffchainExt};
+use std::cell::RefCell;
+
+#[derive(Clone, Eq, PartialEq, Encode, Decode, RuntimeDebug)]
+struct Proof {
+    id: u32,
+    seq_num: u128,
+}
+
+thread_local! {
+    static ON_CHAIN: RefCell<Vec<(u32, WatchedStatus, u128)>> = RefCell::new(vec![]);
+    static SETTLES: RefCell<Vec<(u32, u128)>> = RefCell::new(vec![]);
+    static SUBMITTED: RefCell<Vec<Proof>> = RefCell::new(vec![]);
+}
+
+struct Recorder;
+
+impl SubmitProof<Proof> for Recorder {
+    fn submit(state_proof: Proof) -> Result<(), &'static str> {
+        SUBMITTED.with(|submitted| submitted.borrow_mut().push(state_proof));
+        Ok(())
+    }
+}
+
+fn on_chain(id: u32) -> (WatchedStatus, u128) {
+    ON_CHAIN.with(|apps| {
+        apps.borrow()
+            .iter()
+            .find(|app| app.0 == id)
+            .map(|app| (app.1, app.2))
+            .unwrap_or((WatchedStatus::Other, 0))
+    })
+}
+
+struct Pallet;
+
+impl Watched for Pallet {
+    type Id = u32;
+    type StateProof = Proof;
+    type Submitter = Recorder;
+
+    const WATCHED_IDS_KEY: &'static [u8] = b"test::watchtower::apps";
+    const PROOF_KEY_PREFIX: &'static [u8] = b"test::watchtower::proof::";
+
+    fn proof_id(state_proof: &Proof) -> u32 {
+        state_proof.id
+    }
+
+    fn proof_seq_num(state_proof: &Proof) -> u128 {
+        state_proof.seq_num
+    }
+
+    fn status(id: u32) -> WatchedStatus {
+        on_chain(id).0
+    }
+
+    fn seq_num(id: u32) -> u128 {
+        on_chain(id).1
+    }
+
+    fn settle_events() -> Vec<(u32, u128)> {
+        SETTLES.with(|settles| settles.borrow().clone())
+    }
+}
+
+struct DisabledPallet;
+
+impl Watched for DisabledPallet {
+    type Id = u32;
+    type StateProof = Proof;
+    type Submitter = ();
+
+    const WATCHED_IDS_KEY: &'static [u8] = b"test::watchtower::apps";
+    const PROOF_KEY_PREFIX: &'static [u8] = b"test::watchtower::proof::";
+
+    fn proof_id(state_proof: &Proof) -> u32 {
+        state_proof.id
+    }
+
+    fn proof_seq_num(state_proof: &Proof) -> u128 {
+        state_proof.seq_num
+    }
+
+    fn status(id: u32) -> WatchedStatus {
+        on_chain(id).0
+    }
+
+    fn seq_num(id: u32) -> u128 {
+        on_chain(id).1
+    }
+
+    fn settle_events() -> Vec<(u32, u128)> {
+        SETTLES.with(|settles| settles.borrow().clone())
+    }
+}
+
+fn new_test_ext() -> sp_io::TestExternalities {
+    ON_CHAIN.with(|apps| apps.borrow_mut().clear());
+    SETTLES.with(|settles| settles.borrow_mut().clear());
+    SUBMITTED.with(|submitted| submitted.borrow_mut().clear());
+    let (offchain, _state) = testing::TestOffchainExt::new();
+    let mut ext = sp_io::TestExternalities::default();
+    ext.register_extension(OffchainExt::new(offchain));
+    ext
+}
+
+fn watched_ids() -> Vec<u32> {
+    match StorageValueRef::persistent(Pallet::WATCHED_IDS_KEY).get::<Vec<u32>>() {
+        Some(Some(ids)) => ids,
+        _ => vec![],
+    }
+}
+
+fn submitted() -> Vec<Proof> {
+    SUBMITTED.with(|submitted| submitted.borrow().clone())
+}
+
+/// Settle an app on-chain and emit its `IntendSettle` event in the current block
+fn settle(id: u32, seq_num: u128) {
+    ON_CHAIN.with(|apps| {
+        let mut apps = apps.borrow_mut();
+        apps.retain(|app| app.0 != id);
+        apps.push((id, WatchedStatus::Settle, seq_num));
+    });
+    SETTLES.with(|settles| settles.borrow_mut().push((id, seq_num)));
+}
+
+fn new_block() {
+    SETTLES.with(|settles| settles.borrow_mut().clear());
+}
+
+#[test]
+fn test_pass_watch_state_proof_keeps_highest_seq_num() {
+    new_test_ext().execute_with(|| {
+        watch_state_proof::<Pallet>(Proof { id: 1, seq_num: 2 });
+        watch_state_proof::<Pallet>(Proof { id: 1, seq_num: 1 });
+        watch_state_proof::<Pallet>(Proof { id: 2, seq_num: 1 });
+
+        assert_eq!(watched_ids(), vec![1, 2]);
+        let watched = StorageValueRef::persistent(&watched_proof_key::<Pallet>(&1)).get::<Proof>();
+        assert_eq!(watched, Some(Some(Proof { id: 1, seq_num: 2 })));
+    })
+}
+
+#[test]
+fn test_pass_answer_stale_settles() {
+    new_test_ext().execute_with(|| {
+        watch_state_proof::<Pallet>(Proof { id: 1, seq_num: 3 });
+        watch_state_proof::<Pallet>(Proof { id: 2, seq_num: 3 });
+        watch_state_proof::<Pallet>(Proof { id: 3, seq_num: 3 });
+        watch_state_proof::<Pallet>(Proof { id: 4, seq_num: 3 });
+        ON_CHAIN.with(|apps| {
+            *apps.borrow_mut() = vec![
+                (1, WatchedStatus::Settle, 2),
+                (2, WatchedStatus::Settle, 3),
+                (3, WatchedStatus::Finalized, 2),
+                (4, WatchedStatus::Other, 0),
+            ];
+        });
+
+        answer_stale_settles::<Pallet>(1);
+
+        // only the stale settlement is answered and finalized apps are dropped with their proofs
+        assert_eq!(submitted(), vec![Proof { id: 1, seq_num: 3 }]);
+        assert_eq!(watched_ids(), vec![1, 2, 4]);
+        let finalized = StorageValueRef::persistent(&watched_proof_key::<Pallet>(&3)).get::<Proof>();
+        assert_eq!(finalized, None);
+    })
+}
+
+#[test]
+fn test_pass_answer_stale_settles_once_per_chain_state() {
+    new_test_ext().execute_with(|| {
+        watch_state_proof::<Pallet>(Proof { id: 1, seq_num: 3 });
+        answer_stale_settles::<Pallet>(1);
+        assert_eq!(submitted(), vec![]);
+
+        settle(1, 1);
+        answer_stale_settles::<Pallet>(2);
+        assert_eq!(submitted(), vec![Proof { id: 1, seq_num: 3 }]);
+
+        // the answer is not repeated while it is in flight
+        new_block();
+        answer_stale_settles::<Pallet>(3);
+        answer_stale_settles::<Pallet>(PRUNE_PERIOD);
+        assert_eq!(submitted(), vec![Proof { id: 1, seq_num: 3 }]);
+
+        // another stale settlement changes the chain state and is answered again
+        new_block();
+        settle(1, 2);
+        answer_stale_settles::<Pallet>(PRUNE_PERIOD + 1);
+        assert_eq!(submitted(), vec![Proof { id: 1, seq_num: 3 }; 2]);
+
+        // the watched proof is settled, nothing left to answer
+        new_block();
+        settle(1, 3);
+        answer_stale_settles::<Pallet>(PRUNE_PERIOD + 2);
+        assert_eq!(submitted(), vec![Proof { id: 1, seq_num: 3 }; 2]);
+
+        // finalized apps are dropped when all ids are checked
+        new_block();
+        ON_CHAIN.with(|apps| *apps.borrow_mut() = vec![(1, WatchedStatus::Finalized, 3)]);
+        answer_stale_settles::<Pallet>(PRUNE_PERIOD + 3);
+        assert_eq!(watched_ids(), vec![1]);
+        answer_stale_settles::<Pallet>(2 * PRUNE_PERIOD);
+        assert_eq!(watched_ids(), vec![]);
+    })
+}
+
+#[test]
+fn test_pass_answer_stale_settles_without_watchtower() {
+    new_test_ext().execute_with(|| {
+        watch_state_proof::<DisabledPallet>(Proof { id: 1, seq_num: 3 });
+        ON_CHAIN.with(|apps| {
+            *apps.borrow_mut() = vec![(1, WatchedStatus::Finalized, 2)];
+        });
+
+        answer_stale_settles::<DisabledPallet>(PRUNE_PERIOD);
+
+        assert_eq!(submitted(), vec![]);
+        assert_eq!(watched_ids(), vec![1]);
+    })
+}